@@ -0,0 +1,36 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy::{
+    primitives::{keccak256, B256, U256},
+    sol_types::SolValue,
+};
+use pool_lib::{compute_merkle_root, AggregateData};
+use sha2::{Digest, Sha256};
+
+pub fn main() {
+    let vkey = sp1_zkvm::io::read::<[u32; 8]>();
+    let public_values = sp1_zkvm::io::read::<Vec<Vec<u8>>>();
+
+    // Each child proof is provided by the host and checked against its public values here.
+    let leaves: Vec<B256> = public_values
+        .iter()
+        .map(|values| {
+            let digest: [u8; 32] = Sha256::digest(values).into();
+            sp1_zkvm::lib::verify::verify_sp1_proof(&vkey, &digest);
+            keccak256(values)
+        })
+        .collect();
+
+    let mut digest = [0u8; 32];
+    for (chunk, word) in digest.chunks_mut(4).zip(vkey) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    let data = AggregateData {
+        childVkeyDigest: digest.into(),
+        publicValuesRoot: compute_merkle_root(&leaves),
+        count: U256::from(leaves.len()),
+    };
+
+    sp1_zkvm::io::commit_slice(&data.abi_encode());
+}
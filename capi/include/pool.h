@@ -0,0 +1,129 @@
+#ifndef POOL_H
+#define POOL_H
+
+/* Generated by cbindgen from capi/src/lib.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of a call.
+ */
+typedef enum PoolStatus {
+  POOL_STATUS_OK = 0,
+  /**
+   * A required pointer was null.
+   */
+  POOL_STATUS_NULL_POINTER = 1,
+  /**
+   * An argument could not be decoded or failed validation.
+   */
+  POOL_STATUS_INVALID = 2,
+  /**
+   * The library panicked. This is a bug.
+   */
+  POOL_STATUS_PANIC = 3,
+} PoolStatus;
+
+/**
+ * Bytes allocated by the library.
+ */
+typedef struct PoolBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} PoolBuffer;
+
+/**
+ * Public values a withdrawal proof commits to. Integers wider than 64 bits are big-endian.
+ */
+typedef struct PoolPublicValues {
+  uint8_t nullifier[32];
+  uint8_t block_hash[32];
+  uint8_t exclusion_set_root[32];
+  uint8_t relayer_fee[32];
+  uint8_t recipient[20];
+  uint8_t relayer[20];
+  uint8_t contract_address[20];
+  uint64_t block_number;
+  uint8_t fee_token[20];
+  uint8_t fee_token_amount[32];
+} PoolPublicValues;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Why the last call on this thread failed, or null if it succeeded. The string is valid until
+ * the next call on this thread.
+ */
+const char *pool_last_error(void);
+
+/**
+ * Release a buffer returned by the library. Freeing an empty buffer does nothing.
+ *
+ * # Safety
+ *
+ * `buffer` must have been returned by the library and not freed already.
+ */
+void pool_buffer_free(PoolBuffer buffer);
+
+/**
+ * Write the commitment to deposit for a 32-byte `secret` and the nullifier revealed on
+ * withdrawal.
+ *
+ * # Safety
+ *
+ * `secret` must point to 32 readable bytes, and `commitment` and `nullifier` to 32 writable
+ * bytes each.
+ */
+PoolStatus pool_compute_commitment(const uint8_t *secret, uint8_t *commitment, uint8_t *nullifier);
+
+/**
+ * Encode a withdrawal input, given as NUL-terminated JSON, as the CBOR the program reads.
+ *
+ * # Safety
+ *
+ * `json` must be a NUL-terminated string and `cbor` must be writable.
+ */
+PoolStatus pool_withdrawal_input_encode(const char *json, PoolBuffer *cbor);
+
+/**
+ * Decode a CBOR withdrawal input into UTF-8 JSON, without a NUL terminator.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `json` must be writable.
+ */
+PoolStatus pool_withdrawal_input_decode(const uint8_t *data, uintptr_t len, PoolBuffer *json);
+
+/**
+ * Check a CBOR withdrawal input the way the program does, writing the public values it would
+ * commit.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `public_values` must be writable.
+ */
+PoolStatus pool_validate_withdrawal_input(const uint8_t *data,
+                                          uintptr_t len,
+                                          PoolPublicValues *public_values);
+
+/**
+ * Decode the ABI-encoded public values of a withdrawal proof.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `public_values` must be writable.
+ */
+PoolStatus pool_decode_public_values(const uint8_t *data,
+                                     uintptr_t len,
+                                     PoolPublicValues *public_values);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* POOL_H */
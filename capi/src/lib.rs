@@ -0,0 +1,266 @@
+//! C bindings to pool-lib, for node software and hardware wallet firmware that links against the
+//! reference implementation instead of reimplementing it. The header is `include/pool.h`.
+//!
+//! Every function returns a [`PoolStatus`]. On failure, [`pool_last_error`] describes what went
+//! wrong on the calling thread. Buffers returned through [`PoolBuffer`] belong to the caller and
+//! are released with [`pool_buffer_free`].
+
+use pool_lib::{
+    compute_commitment, decode_any_public_values, process_any_withdrawal, WithdrawalData,
+    WithdrawalInput,
+};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// Outcome of a call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolStatus {
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// An argument could not be decoded or failed validation.
+    Invalid = 2,
+    /// The library panicked. This is a bug.
+    Panic = 3,
+}
+
+/// Bytes allocated by the library.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PoolBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Public values a withdrawal proof commits to. Integers wider than 64 bits are big-endian.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PoolPublicValues {
+    pub nullifier: [u8; 32],
+    pub block_hash: [u8; 32],
+    pub exclusion_set_root: [u8; 32],
+    pub relayer_fee: [u8; 32],
+    pub recipient: [u8; 20],
+    pub relayer: [u8; 20],
+    pub contract_address: [u8; 20],
+    pub block_number: u64,
+    pub fee_token: [u8; 20],
+    pub fee_token_amount: [u8; 32],
+}
+
+impl From<WithdrawalData> for PoolPublicValues {
+    fn from(data: WithdrawalData) -> Self {
+        Self {
+            nullifier: data.nullifier.0,
+            block_hash: data.blockHash.0,
+            exclusion_set_root: data.exclusionSetRoot.0,
+            relayer_fee: data.relayerFee.to_be_bytes(),
+            recipient: data.recipient.into_array(),
+            relayer: data.relayer.into_array(),
+            contract_address: data.contractAddress.into_array(),
+            block_number: data.blockNumber,
+            fee_token: data.feeToken.into_array(),
+            fee_token_amount: data.feeTokenAmount.to_be_bytes(),
+        }
+    }
+}
+
+enum Failure {
+    NullPointer(&'static str),
+    Invalid(String),
+}
+
+impl<E: std::fmt::Display> From<E> for Failure {
+    fn from(e: E) -> Self {
+        Self::Invalid(e.to_string())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `f`, recording why it failed and keeping panics from unwinding into C.
+fn run(f: impl FnOnce() -> Result<(), Failure>) -> PoolStatus {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => PoolStatus::Ok,
+        Ok(Err(Failure::NullPointer(name))) => {
+            set_last_error(format!("{name} is null"));
+            PoolStatus::NullPointer
+        }
+        Ok(Err(Failure::Invalid(message))) => {
+            set_last_error(message);
+            PoolStatus::Invalid
+        }
+        Err(_) => {
+            set_last_error("panicked".to_string());
+            PoolStatus::Panic
+        }
+    }
+}
+
+unsafe fn bytes<'a>(name: &'static str, data: *const u8, len: usize) -> Result<&'a [u8], Failure> {
+    if data.is_null() {
+        return Err(Failure::NullPointer(name));
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn out<'a, T>(name: &'static str, out: *mut T) -> Result<&'a mut T, Failure> {
+    out.as_mut().ok_or(Failure::NullPointer(name))
+}
+
+fn buffer(bytes: Vec<u8>) -> PoolBuffer {
+    let bytes = bytes.into_boxed_slice();
+    let len = bytes.len();
+    PoolBuffer {
+        data: Box::into_raw(bytes) as *mut u8,
+        len,
+    }
+}
+
+/// Why the last call on this thread failed, or null if it succeeded. The string is valid until
+/// the next call on this thread.
+#[no_mangle]
+pub extern "C" fn pool_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Release a buffer returned by the library. Freeing an empty buffer does nothing.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by the library and not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn pool_buffer_free(buffer: PoolBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// Write the commitment to deposit for a 32-byte `secret` and the nullifier revealed on
+/// withdrawal.
+///
+/// # Safety
+///
+/// `secret` must point to 32 readable bytes, and `commitment` and `nullifier` to 32 writable
+/// bytes each.
+#[no_mangle]
+pub unsafe extern "C" fn pool_compute_commitment(
+    secret: *const u8,
+    commitment: *mut u8,
+    nullifier: *mut u8,
+) -> PoolStatus {
+    run(|| {
+        let secret = bytes("secret", secret, 32)?;
+        if commitment.is_null() {
+            return Err(Failure::NullPointer("commitment"));
+        }
+        if nullifier.is_null() {
+            return Err(Failure::NullPointer("nullifier"));
+        }
+        let (c, n) = compute_commitment(&secret.try_into()?);
+        ptr::copy_nonoverlapping(c.as_ptr(), commitment, 32);
+        ptr::copy_nonoverlapping(n.as_ptr(), nullifier, 32);
+        Ok(())
+    })
+}
+
+/// Encode a withdrawal input, given as NUL-terminated JSON, as the CBOR the program reads.
+///
+/// # Safety
+///
+/// `json` must be a NUL-terminated string and `cbor` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pool_withdrawal_input_encode(
+    json: *const c_char,
+    cbor: *mut PoolBuffer,
+) -> PoolStatus {
+    run(|| {
+        if json.is_null() {
+            return Err(Failure::NullPointer("json"));
+        }
+        let cbor = out("cbor", cbor)?;
+        let input: WithdrawalInput = serde_json::from_str(CStr::from_ptr(json).to_str()?)?;
+        *cbor = buffer(serde_cbor::to_vec(&input)?);
+        Ok(())
+    })
+}
+
+/// Decode a CBOR withdrawal input into UTF-8 JSON, without a NUL terminator.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `json` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pool_withdrawal_input_decode(
+    data: *const u8,
+    len: usize,
+    json: *mut PoolBuffer,
+) -> PoolStatus {
+    run(|| {
+        let data = bytes("data", data, len)?;
+        let json = out("json", json)?;
+        let input: WithdrawalInput = serde_cbor::from_slice(data)?;
+        *json = buffer(serde_json::to_vec(&input)?);
+        Ok(())
+    })
+}
+
+/// Check a CBOR withdrawal input the way the program does, writing the public values it would
+/// commit.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `public_values` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pool_validate_withdrawal_input(
+    data: *const u8,
+    len: usize,
+    public_values: *mut PoolPublicValues,
+) -> PoolStatus {
+    run(|| {
+        let data = bytes("data", data, len)?;
+        let public_values = out("public_values", public_values)?;
+        let input: WithdrawalInput = serde_cbor::from_slice(data)?;
+        *public_values = process_any_withdrawal(&input)?.withdrawal.into();
+        Ok(())
+    })
+}
+
+/// Decode the ABI-encoded public values of a withdrawal proof.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `public_values` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pool_decode_public_values(
+    data: *const u8,
+    len: usize,
+    public_values: *mut PoolPublicValues,
+) -> PoolStatus {
+    run(|| {
+        let data = bytes("data", data, len)?;
+        let public_values = out("public_values", public_values)?;
+        *public_values = decode_any_public_values(data)?.withdrawal.into();
+        Ok(())
+    })
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Pool} from "./Pool.sol";
+
+// @title Pool that also pays out withdrawals proving their deposit is in an association set,
+// whose root one of the providers it was deployed with signed.
+contract AssociationPool is Pool {
+    uint8 internal constant VARIANT_ASSOCIATION = 7;
+
+    // Length of the public values of an association withdrawal, a `WithdrawalData` and 5 more
+    // words.
+    uint256 internal constant ASSOCIATION_WITHDRAWAL_DATA_LENGTH = 16 * 32;
+
+    struct AssociationWithdrawalData {
+        WithdrawalData withdrawal;
+        address aspSigner;
+        bytes32 setId;
+        uint64 leafCount;
+        uint64 issuedAt;
+        uint256 chainId;
+    }
+
+    event AssociatedWithdrawal(bytes32 indexed nullifier, address indexed provider, bytes32 indexed setId);
+
+    // The program recovers whoever signed the root, so the pool only pays out proofs of roots
+    // signed by these. They are fixed at deployment, and no one can add to them later.
+    mapping(address => bool) public trustedProviders;
+
+    constructor(address _verifier, bytes32 _vkey, uint256 _amount, address[] memory _providers)
+        Pool(_verifier, _vkey, _amount)
+    {
+        for (uint256 i = 0; i < _providers.length; i++) {
+            require(_providers[i] != address(0), "Invalid provider");
+            trustedProviders[_providers[i]] = true;
+        }
+    }
+
+    // @notice Withdraw funds from the pool using an SP1 proof that the deposit is in an
+    // association set, whose root a trusted provider signed for this pool.
+    function withdrawAssociated(bytes calldata _publicValues, bytes calldata _proofBytes) public {
+        require(_publicValues.length == ASSOCIATION_WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        AssociationWithdrawalData memory data = abi.decode(_publicValues, (AssociationWithdrawalData));
+        WithdrawalData memory withdrawal = data.withdrawal;
+        require(withdrawal.variant == VARIANT_ASSOCIATION, "Invalid withdrawal variant");
+        require(trustedProviders[data.aspSigner], "Untrusted association set provider");
+        // The root was signed for this pool, which `_spend` checks is the contract address.
+        require(data.chainId == block.chainid, "Invalid chain id");
+        _spend(_publicValues, _proofBytes, withdrawal);
+        emit AssociatedWithdrawal(withdrawal.nullifier, data.aspSigner, data.setId);
+
+        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee}("");
+        require(success, "Failed to send withdrawal");
+
+        _payRelayer(withdrawal);
+    }
+}
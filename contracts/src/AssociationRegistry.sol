@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// @title Latest association set roots published by association set providers.
+contract AssociationRegistry {
+    event RootPublished(address indexed provider, bytes32 indexed setId, bytes32 root, uint64 leafCount);
+
+    mapping(address => mapping(bytes32 => bytes32)) public roots;
+
+    // @notice Publish the root of the caller's association set `setId`.
+    function publishRoot(bytes32 setId, bytes32 root, uint64 leafCount) external {
+        roots[msg.sender][setId] = root;
+        emit RootPublished(msg.sender, setId, root, leafCount);
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Pool} from "./Pool.sol";
+
+interface IL1StandardBridge {
+    function depositETHTo(address _to, uint32 _minGasLimit, bytes calldata _extraData) external payable;
+}
+
+// @title Pool that also pays out withdrawals to an L2 address, depositing them through the
+// canonical bridges it was deployed with.
+contract BridgePool is Pool {
+    uint8 internal constant VARIANT_BRIDGE = 3;
+
+    // Length of the public values of a bridge withdrawal, a `WithdrawalData` and 3 more words.
+    uint256 internal constant BRIDGE_WITHDRAWAL_DATA_LENGTH = 14 * 32;
+
+    struct BridgeWithdrawalData {
+        WithdrawalData withdrawal;
+        uint256 l2ChainId;
+        address l2Recipient;
+        bytes32 bridgeMessageHash;
+    }
+
+    // A withdrawal to an L2 address has the bridge as its recipient, and a contract that is no
+    // bridge would keep the funds, so the pool only deposits through these, each to the L2 chain
+    // it serves. They are fixed at deployment, and no one can add to them later.
+    mapping(address => uint256) public bridgeChainIds;
+
+    constructor(address _verifier, bytes32 _vkey, uint256 _amount, address[] memory _bridges, uint256[] memory _chainIds)
+        Pool(_verifier, _vkey, _amount)
+    {
+        require(_bridges.length == _chainIds.length, "Invalid bridges");
+        for (uint256 i = 0; i < _bridges.length; i++) {
+            require(_chainIds[i] != 0, "Invalid chain id");
+            bridgeChainIds[_bridges[i]] = _chainIds[i];
+        }
+    }
+
+    // @notice Withdraw funds from the pool to an L2 address using an SP1 proof, which commits to
+    // the hash of the bridge deposit the pool makes.
+    function withdrawToL2(
+        bytes calldata _publicValues,
+        bytes calldata _proofBytes,
+        uint32 _minGasLimit,
+        bytes calldata _extraData
+    ) public {
+        require(_publicValues.length == BRIDGE_WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        BridgeWithdrawalData memory data = abi.decode(_publicValues, (BridgeWithdrawalData));
+        WithdrawalData memory withdrawal = data.withdrawal;
+        require(withdrawal.variant == VARIANT_BRIDGE, "Invalid withdrawal variant");
+        require(bridgeChainIds[withdrawal.recipient] == data.l2ChainId, "Untrusted bridge");
+        // The gas limit and extra data are not in the public values, only their hash is.
+        require(
+            keccak256(abi.encode(data.l2ChainId, data.l2Recipient, _minGasLimit, _extraData)) == data.bridgeMessageHash,
+            "Invalid bridge message"
+        );
+        _spend(_publicValues, _proofBytes, withdrawal);
+
+        IL1StandardBridge(withdrawal.recipient).depositETHTo{value: amount - withdrawal.relayerFee}(
+            data.l2Recipient, _minGasLimit, _extraData
+        );
+
+        _payRelayer(withdrawal);
+    }
+}
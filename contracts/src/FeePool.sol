@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Pool} from "./Pool.sol";
+
+// @title Pool whose owner charges a protocol fee on withdrawals, at the rate in force at the
+// block each withdrawal was proven against.
+contract FeePool is Pool {
+    uint8 internal constant VARIANT_PROTOCOL_FEE = 2;
+
+    // Length of the public values of a withdrawal with a protocol fee, a `WithdrawalData` and 1
+    // more word.
+    uint256 internal constant FEE_WITHDRAWAL_DATA_LENGTH = 12 * 32;
+
+    struct FeeWithdrawalData {
+        WithdrawalData withdrawal;
+        uint256 protocolFee;
+    }
+
+    event ProtocolFeeChanged(uint256 protocolFee);
+
+    // Storage slot 2, after `deposits` and `nullifiers`, as `pool_lib::protocol_fee` proves it.
+    // Its value at the proof block is in the public values, so changing it does not touch proofs
+    // already in flight.
+    uint256 public protocolFee;
+
+    address public owner;
+
+    constructor(address _verifier, bytes32 _vkey, uint256 _amount, uint256 _protocolFee)
+        Pool(_verifier, _vkey, _amount)
+    {
+        require(_protocolFee <= _amount, "Invalid protocol fee");
+        owner = msg.sender;
+        protocolFee = _protocolFee;
+    }
+
+    function setProtocolFee(uint256 _protocolFee) external {
+        require(msg.sender == owner, "Not the owner");
+        require(_protocolFee <= amount, "Invalid protocol fee");
+        protocolFee = _protocolFee;
+        emit ProtocolFeeChanged(_protocolFee);
+    }
+
+    // @notice Withdraw funds from the pool using an SP1 proof, which commits to the protocol fee at
+    // the proof block, and pay that fee to the owner.
+    function withdrawWithProtocolFee(bytes calldata _publicValues, bytes calldata _proofBytes) public {
+        require(_publicValues.length == FEE_WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        FeeWithdrawalData memory data = abi.decode(_publicValues, (FeeWithdrawalData));
+        WithdrawalData memory withdrawal = data.withdrawal;
+        require(withdrawal.variant == VARIANT_PROTOCOL_FEE, "Invalid withdrawal variant");
+        require(withdrawal.relayerFee + data.protocolFee <= amount, "Invalid protocol fee");
+        _spend(_publicValues, _proofBytes, withdrawal);
+
+        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee - data.protocolFee}("");
+        require(success, "Failed to send withdrawal");
+
+        if (data.protocolFee > 0) {
+            (bool paid,) = owner.call{value: data.protocolFee}("");
+            require(paid, "Failed to send protocol fee");
+        }
+
+        _payRelayer(withdrawal);
+    }
+
+    // Every withdrawal pays the protocol fee, so the other entry points of `Pool` are closed.
+    function withdraw(bytes calldata, bytes calldata) public pure override {
+        revert("Withdrawals pay the protocol fee");
+    }
+
+    function withdrawWithTokenFee(bytes calldata, bytes calldata, TokenFeePermit calldata) public pure override {
+        revert("Withdrawals pay the protocol fee");
+    }
+
+    function withdrawSplit(bytes calldata, bytes calldata, address[] calldata, uint256[] calldata)
+        public
+        pure
+        override
+    {
+        revert("Withdrawals pay the protocol fee");
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Pool} from "./Pool.sol";
+
+// @title Pool that also pays out withdrawals proven against blocks older than `BLOCKHASH` reaches,
+// anchored through the block hash oracles it was deployed with.
+contract OraclePool is Pool {
+    uint8 internal constant VARIANT_ORACLE = 6;
+
+    // Length of the public values of an oracle withdrawal, a `WithdrawalData` and 5 more words.
+    uint256 internal constant ORACLE_WITHDRAWAL_DATA_LENGTH = 16 * 32;
+
+    struct OracleWithdrawalData {
+        WithdrawalData withdrawal;
+        address blockHashOracle;
+        uint256 oracleSlot;
+        bytes32 oracleCodeHash;
+        bytes32 provenBlockHash;
+        uint64 provenBlockNumber;
+    }
+
+    // A block hash oracle by its address, the slot of its `blockHashes` mapping and the hash of
+    // its runtime bytecode.
+    struct TrustedOracle {
+        address oracle;
+        uint256 slot;
+        bytes32 codeHash;
+    }
+
+    // The program takes any contract as the oracle, and one that stores made-up hashes would prove
+    // deposits that never happened, so the pool only pays out proofs through these. They are fixed
+    // at deployment, and no one can add to them later.
+    mapping(address => TrustedOracle) public trustedOracles;
+
+    constructor(address _verifier, bytes32 _vkey, uint256 _amount, TrustedOracle[] memory _oracles)
+        Pool(_verifier, _vkey, _amount)
+    {
+        for (uint256 i = 0; i < _oracles.length; i++) {
+            require(_oracles[i].codeHash != bytes32(0), "Invalid oracle code hash");
+            trustedOracles[_oracles[i].oracle] = _oracles[i];
+        }
+    }
+
+    // @notice Withdraw funds from the pool using an SP1 proof of a deposit at an old block, whose
+    // hash a trusted oracle holds at the anchor block the proof names.
+    function withdrawWithOracle(bytes calldata _publicValues, bytes calldata _proofBytes) public {
+        require(_publicValues.length == ORACLE_WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        OracleWithdrawalData memory data = abi.decode(_publicValues, (OracleWithdrawalData));
+        WithdrawalData memory withdrawal = data.withdrawal;
+        require(withdrawal.variant == VARIANT_ORACLE, "Invalid withdrawal variant");
+
+        TrustedOracle memory trusted = trustedOracles[data.blockHashOracle];
+        require(trusted.codeHash != bytes32(0), "Untrusted block hash oracle");
+        require(trusted.codeHash == data.oracleCodeHash, "Invalid oracle code hash");
+        require(trusted.slot == data.oracleSlot, "Invalid oracle slot");
+
+        // The anchor is checked with `BLOCKHASH` like the proof block of any other withdrawal.
+        _spend(_publicValues, _proofBytes, withdrawal);
+
+        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee}("");
+        require(success, "Failed to send withdrawal");
+
+        _payRelayer(withdrawal);
+    }
+}
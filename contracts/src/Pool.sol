@@ -3,9 +3,33 @@ pragma solidity ^0.8.20;
 
 import {ISP1Verifier} from "@sp1-contracts/ISP1Verifier.sol";
 
+interface IERC20 {
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+}
+
+interface IERC20Permit {
+    function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)
+        external;
+}
+
 // @title Privacy Pool using an SP1 program for withdrawals.
 contract Pool {
+    // Tag of a base withdrawal in the public values. The program commits the other variants of
+    // withdrawal with other tags, and this pool pays none of them out.
+    uint8 internal constant VARIANT_BASE = 0;
+
+    // Length of the public values of a base withdrawal, the 11 words of a `WithdrawalData`.
+    uint256 internal constant WITHDRAWAL_DATA_LENGTH = 11 * 32;
+
+    uint8 internal constant VARIANT_SPLIT = 5;
+
+    // Length of the public values of a split withdrawal, a `WithdrawalData` and 2 more words.
+    uint256 internal constant SPLIT_WITHDRAWAL_DATA_LENGTH = 13 * 32;
+
+    uint256 internal constant MAX_SPLIT_RECIPIENTS = 8;
+
     struct WithdrawalData {
+        uint8 variant;
         bytes32 nullifier;
         bytes32 blockHash;
         bytes32 exclusionSetRoot;
@@ -14,8 +38,35 @@ contract Pool {
         address relayer;
         address contractAddress;
         uint64 blockNumber;
+        address feeToken;
+        uint256 feeTokenAmount;
+    }
+
+    struct SplitWithdrawalData {
+        WithdrawalData withdrawal;
+        bytes32 distributionHash;
+        uint256 noteValue;
     }
 
+    // The recipient's consent to pay a withdrawal's token fee: their EIP-712 signature of its
+    // `TokenFeeAuthorization`, and optionally an EIP-2612 permit of the fee to this pool, so they
+    // need not approve the pool beforehand. The permit is skipped if its deadline is zero.
+    struct TokenFeePermit {
+        bytes authorization;
+        uint256 deadline;
+        uint8 v;
+        bytes32 r;
+        bytes32 s;
+    }
+
+    bytes32 internal constant DOMAIN_TYPEHASH =
+        keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)");
+
+    bytes32 internal constant TOKEN_FEE_AUTHORIZATION_TYPEHASH =
+        keccak256("TokenFeeAuthorization(bytes32 nullifier,address relayer,address feeToken,uint256 feeTokenAmount)");
+
+    event Deposit(bytes32 indexed commitment, uint256 leafIndex);
+
     event Withdrawal(
         bytes32 indexed nullifier, bytes32 exclusionSetRoot, address recipient, address relayer, uint256 relayerFee
     );
@@ -37,9 +88,74 @@ contract Pool {
     }
 
     // @notice Withdraw funds from the pool using an SP1 proof.
-    function withdraw(bytes calldata _publicValues, bytes calldata _proofBytes) public {
-        ISP1Verifier(verifier).verifyProof(programVkey, _publicValues, _proofBytes);
+    function withdraw(bytes calldata _publicValues, bytes calldata _proofBytes) public virtual {
+        // Every variant starts with a `WithdrawalData`, so only the exact length and the tag tell
+        // a base withdrawal from a longer one.
+        require(_publicValues.length == WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
         WithdrawalData memory withdrawal = abi.decode(_publicValues, (WithdrawalData));
+        require(withdrawal.variant == VARIANT_BASE, "Invalid withdrawal variant");
+        _spend(_publicValues, _proofBytes, withdrawal);
+
+        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee}("");
+        require(success, "Failed to send withdrawal");
+
+        _payRelayer(withdrawal);
+    }
+
+    // @notice Withdraw funds from the pool using an SP1 proof of a withdrawal paying its relayer in
+    // a token, which the recipient authorized.
+    function withdrawWithTokenFee(bytes calldata _publicValues, bytes calldata _proofBytes, TokenFeePermit calldata _fee)
+        public
+        virtual
+    {
+        require(_publicValues.length == WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        WithdrawalData memory withdrawal = abi.decode(_publicValues, (WithdrawalData));
+        require(withdrawal.variant == VARIANT_BASE, "Invalid withdrawal variant");
+        require(withdrawal.feeToken != address(0), "Invalid fee token");
+        _spend(_publicValues, _proofBytes, withdrawal);
+
+        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee}("");
+        require(success, "Failed to send withdrawal");
+
+        _payTokenFee(withdrawal, _fee);
+    }
+
+    // @notice Withdraw funds from the pool to several recipients using an SP1 proof, which commits
+    // to the hash of the recipients and the amounts each is paid.
+    function withdrawSplit(
+        bytes calldata _publicValues,
+        bytes calldata _proofBytes,
+        address[] calldata _recipients,
+        uint256[] calldata _amounts
+    ) public virtual {
+        require(_publicValues.length == SPLIT_WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        SplitWithdrawalData memory data = abi.decode(_publicValues, (SplitWithdrawalData));
+        WithdrawalData memory withdrawal = data.withdrawal;
+        require(withdrawal.variant == VARIANT_SPLIT, "Invalid withdrawal variant");
+        require(data.noteValue == amount, "Invalid note value");
+        // There is no recipient to pay a fee in a token.
+        require(withdrawal.feeToken == address(0), "Invalid fee token");
+        require(
+            _recipients.length == _amounts.length && _recipients.length <= MAX_SPLIT_RECIPIENTS,
+            "Invalid distribution"
+        );
+        // The program checked the amounts and the relayer fee add up to the note value.
+        require(keccak256(abi.encode(_recipients, _amounts)) == data.distributionHash, "Invalid distribution");
+        _spend(_publicValues, _proofBytes, withdrawal);
+
+        for (uint256 i = 0; i < _recipients.length; i++) {
+            (bool success,) = _recipients[i].call{value: _amounts[i]}("");
+            require(success, "Failed to send withdrawal");
+        }
+
+        _payRelayer(withdrawal);
+    }
+
+    // Verify the proof of a withdrawal anchored at a block `BLOCKHASH` reaches, and spend its note.
+    function _spend(bytes calldata _publicValues, bytes calldata _proofBytes, WithdrawalData memory withdrawal)
+        internal
+    {
+        ISP1Verifier(verifier).verifyProof(programVkey, _publicValues, _proofBytes);
         require(!nullifiers[withdrawal.nullifier], "Already withdrawn");
         require(blockhash(withdrawal.blockNumber) == withdrawal.blockHash, "Invalid block hash");
         require(withdrawal.contractAddress == address(this), "Invalid contract address");
@@ -52,19 +168,71 @@ contract Pool {
             withdrawal.relayer,
             withdrawal.relayerFee
         );
+    }
 
-        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee}("");
-        require(success, "Failed to send withdrawal");
-
+    function _payRelayer(WithdrawalData memory withdrawal) internal {
+        // Anyone can prove a withdrawal to any recipient, so a fee in a token, which the recipient
+        // pays, is only taken through `withdrawWithTokenFee` with their authorization.
+        require(withdrawal.feeToken == address(0), "Token fee needs the recipient's authorization");
         if (withdrawal.relayerFee > 0) {
-            (bool success2,) = withdrawal.relayer.call{value: withdrawal.relayerFee}("");
-            require(success2, "Failed to send relayer fee");
+            (bool success,) = withdrawal.relayer.call{value: withdrawal.relayerFee}("");
+            require(success, "Failed to send relayer fee");
+        }
+    }
+
+    function _payTokenFee(WithdrawalData memory withdrawal, TokenFeePermit calldata _fee) internal {
+        bytes32 structHash = keccak256(
+            abi.encode(
+                TOKEN_FEE_AUTHORIZATION_TYPEHASH,
+                withdrawal.nullifier,
+                withdrawal.relayer,
+                withdrawal.feeToken,
+                withdrawal.feeTokenAmount
+            )
+        );
+        bytes32 digest = keccak256(abi.encodePacked("\x19\x01", _domainSeparator(), structHash));
+        address signer = _recover(digest, _fee.authorization);
+        require(signer != address(0) && signer == withdrawal.recipient, "Invalid token fee authorization");
+
+        // A permit sent ahead of this call by someone else has already set the allowance, which
+        // the transfer still requires.
+        if (_fee.deadline != 0) {
+            try IERC20Permit(withdrawal.feeToken).permit(
+                withdrawal.recipient, address(this), withdrawal.feeTokenAmount, _fee.deadline, _fee.v, _fee.r, _fee.s
+            ) {} catch {}
+        }
+        bool paid =
+            IERC20(withdrawal.feeToken).transferFrom(withdrawal.recipient, withdrawal.relayer, withdrawal.feeTokenAmount);
+        require(paid, "Failed to send relayer fee");
+    }
+
+    // EIP-712 domain of token fee authorizations and withdrawal intents against this pool.
+    function _domainSeparator() internal view returns (bytes32) {
+        return keccak256(abi.encode(DOMAIN_TYPEHASH, keccak256("Pool"), keccak256("1"), block.chainid, address(this)));
+    }
+
+    // Signer of `digest` by a 65-byte signature, or the zero address if it is malformed.
+    function _recover(bytes32 digest, bytes calldata signature) internal pure returns (address) {
+        if (signature.length != 65) {
+            return address(0);
+        }
+        bytes32 r = bytes32(signature[0:32]);
+        bytes32 s = bytes32(signature[32:64]);
+        uint8 v = uint8(signature[64]);
+        if (v < 27) {
+            v += 27;
+        }
+        // Signatures with a high `s` are the malleated twins of low ones.
+        if (uint256(s) > 0x7FFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0) {
+            return address(0);
         }
+        return ecrecover(digest, v, r, s);
     }
 
     // @notice Deposit funds into the pool. The commitment should be the keccak256 of a known and unused bytes32 secret.
     function deposit(bytes32 _commitment) public payable {
         require(msg.value == amount, "Invalid deposit amount");
         deposits.push(_commitment);
+        emit Deposit(_commitment, deposits.length - 1);
     }
 }
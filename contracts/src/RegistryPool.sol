@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Pool} from "./Pool.sol";
+
+// @title Pool that also pays out withdrawals proving the registry it was deployed with maps their
+// chain, denomination and version to it.
+contract RegistryPool is Pool {
+    uint8 internal constant VARIANT_REGISTRY = 4;
+
+    // Length of the public values of a registry withdrawal, a `WithdrawalData` and 4 more words.
+    uint256 internal constant REGISTRY_WITHDRAWAL_DATA_LENGTH = 15 * 32;
+
+    struct RegistryWithdrawalData {
+        WithdrawalData withdrawal;
+        address registry;
+        uint256 chainId;
+        uint256 denomination;
+        uint256 version;
+    }
+
+    event RegisteredWithdrawal(bytes32 indexed nullifier, uint256 version);
+
+    // The program proves an entry of whichever registry the withdrawal names, so the pool only
+    // pays out proofs of entries in this one.
+    address public immutable registry;
+
+    constructor(address _verifier, bytes32 _vkey, uint256 _amount, address _registry)
+        Pool(_verifier, _vkey, _amount)
+    {
+        require(_registry != address(0), "Invalid registry");
+        registry = _registry;
+    }
+
+    // @notice Withdraw funds from the pool using an SP1 proof that the pool's registry mapped its
+    // chain, denomination and version to it at the proof block.
+    function withdrawRegistered(bytes calldata _publicValues, bytes calldata _proofBytes) public {
+        require(_publicValues.length == REGISTRY_WITHDRAWAL_DATA_LENGTH, "Invalid public values length");
+        RegistryWithdrawalData memory data = abi.decode(_publicValues, (RegistryWithdrawalData));
+        WithdrawalData memory withdrawal = data.withdrawal;
+        require(withdrawal.variant == VARIANT_REGISTRY, "Invalid withdrawal variant");
+        require(data.registry == registry, "Untrusted registry");
+        require(data.chainId == block.chainid, "Invalid chain id");
+        require(data.denomination == amount, "Invalid denomination");
+        // The program checked the entry maps to the pool, which `_spend` checks is this one.
+        _spend(_publicValues, _proofBytes, withdrawal);
+        emit RegisteredWithdrawal(withdrawal.nullifier, data.version);
+
+        (bool success,) = withdrawal.recipient.call{value: amount - withdrawal.relayerFee}("");
+        require(success, "Failed to send withdrawal");
+
+        _payRelayer(withdrawal);
+    }
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {AssociationPool} from "../src/AssociationPool.sol";
+import {Pool} from "../src/Pool.sol";
+import {MockVerifier} from "./Mocks.sol";
+
+contract AssociationPoolTest is Test {
+    uint256 internal constant AMOUNT = 1 ether;
+    uint256 internal constant FEE = 0.01 ether;
+    uint64 internal constant PROOF_BLOCK = 99;
+    bytes32 internal constant PROOF_BLOCK_HASH = bytes32(uint256(0xb10c));
+    bytes32 internal constant SET_ID = bytes32(uint256(0x5e7));
+
+    event AssociatedWithdrawal(bytes32 indexed nullifier, address indexed provider, bytes32 indexed setId);
+
+    AssociationPool internal pool;
+    address internal verifier;
+    address internal provider = address(0xa5b);
+    address internal recipient = address(0x12);
+    address internal relayer = address(0x7e1a);
+
+    function setUp() public {
+        verifier = address(new MockVerifier());
+        pool = new AssociationPool(verifier, bytes32(uint256(1)), AMOUNT, _providers(provider));
+
+        vm.roll(PROOF_BLOCK + 1);
+        vm.setBlockhash(PROOF_BLOCK, PROOF_BLOCK_HASH);
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc0)));
+    }
+
+    function _providers(address trusted) internal pure returns (address[] memory providers) {
+        providers = new address[](1);
+        providers[0] = trusted;
+    }
+
+    function _withdrawal() internal view returns (AssociationPool.AssociationWithdrawalData memory) {
+        return AssociationPool.AssociationWithdrawalData({
+            withdrawal: Pool.WithdrawalData({
+                variant: 7,
+                nullifier: bytes32(uint256(1)),
+                blockHash: PROOF_BLOCK_HASH,
+                exclusionSetRoot: bytes32(uint256(0xa55e7)),
+                relayerFee: FEE,
+                recipient: recipient,
+                relayer: relayer,
+                contractAddress: address(pool),
+                blockNumber: PROOF_BLOCK,
+                feeToken: address(0),
+                feeTokenAmount: 0
+            }),
+            aspSigner: provider,
+            setId: SET_ID,
+            leafCount: 4,
+            issuedAt: 1,
+            chainId: block.chainid
+        });
+    }
+
+    function test_WithdrawAssociatedPaysOut() public {
+        vm.expectEmit(address(pool));
+        emit AssociatedWithdrawal(bytes32(uint256(1)), provider, SET_ID);
+        pool.withdrawAssociated(abi.encode(_withdrawal()), "");
+
+        assertEq(recipient.balance, AMOUNT - FEE);
+        assertEq(relayer.balance, FEE);
+    }
+
+    // Anyone can sign a root of a set holding their own deposits and nothing else.
+    function test_WithdrawAssociatedRejectsAnotherProvider() public {
+        AssociationPool.AssociationWithdrawalData memory data = _withdrawal();
+        data.aspSigner = address(0xbad);
+
+        vm.expectRevert("Untrusted association set provider");
+        pool.withdrawAssociated(abi.encode(data), "");
+    }
+
+    // A root signed for the pool at this address on another chain says nothing of this one.
+    function test_WithdrawAssociatedRejectsAnotherChain() public {
+        AssociationPool.AssociationWithdrawalData memory data = _withdrawal();
+        data.chainId = block.chainid + 1;
+
+        vm.expectRevert("Invalid chain id");
+        pool.withdrawAssociated(abi.encode(data), "");
+    }
+
+    function test_WithdrawAssociatedRejectsOtherVariants() public {
+        AssociationPool.AssociationWithdrawalData memory data = _withdrawal();
+        data.withdrawal.variant = 0;
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdrawAssociated(abi.encode(data), "");
+
+        vm.expectRevert("Invalid public values length");
+        pool.withdrawAssociated(abi.encode(data.withdrawal), "");
+    }
+
+    // The zero address is what a malformed signature recovers to.
+    function test_ConstructorRejectsTheZeroProvider() public {
+        vm.expectRevert("Invalid provider");
+        new AssociationPool(verifier, bytes32(uint256(1)), AMOUNT, _providers(address(0)));
+    }
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {BridgePool} from "../src/BridgePool.sol";
+import {Pool} from "../src/Pool.sol";
+import {MockBridge, MockVerifier} from "./Mocks.sol";
+
+contract BridgePoolTest is Test {
+    uint256 internal constant AMOUNT = 1 ether;
+    uint256 internal constant FEE = 0.01 ether;
+    uint256 internal constant L2_CHAIN_ID = 10;
+    uint64 internal constant PROOF_BLOCK = 99;
+    bytes32 internal constant PROOF_BLOCK_HASH = bytes32(uint256(0xb10c));
+
+    BridgePool internal pool;
+    MockBridge internal bridge;
+    address internal l2Recipient = address(0x12);
+    address internal relayer = address(0x7e1a);
+
+    function setUp() public {
+        bridge = new MockBridge();
+        address[] memory bridges = new address[](1);
+        bridges[0] = address(bridge);
+        uint256[] memory chainIds = new uint256[](1);
+        chainIds[0] = L2_CHAIN_ID;
+        pool = new BridgePool(address(new MockVerifier()), bytes32(uint256(1)), AMOUNT, bridges, chainIds);
+
+        vm.roll(PROOF_BLOCK + 1);
+        vm.setBlockhash(PROOF_BLOCK, PROOF_BLOCK_HASH);
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc0)));
+    }
+
+    function _withdrawal(address to, uint32 minGasLimit, bytes memory extraData)
+        internal
+        view
+        returns (BridgePool.BridgeWithdrawalData memory)
+    {
+        return BridgePool.BridgeWithdrawalData({
+            withdrawal: Pool.WithdrawalData({
+                variant: 3,
+                nullifier: bytes32(uint256(1)),
+                blockHash: PROOF_BLOCK_HASH,
+                exclusionSetRoot: bytes32(0),
+                relayerFee: FEE,
+                recipient: to,
+                relayer: relayer,
+                contractAddress: address(pool),
+                blockNumber: PROOF_BLOCK,
+                feeToken: address(0),
+                feeTokenAmount: 0
+            }),
+            l2ChainId: L2_CHAIN_ID,
+            l2Recipient: l2Recipient,
+            bridgeMessageHash: keccak256(abi.encode(L2_CHAIN_ID, l2Recipient, minGasLimit, extraData))
+        });
+    }
+
+    function test_WithdrawToL2DepositsThroughTheBridge() public {
+        bytes memory publicValues = abi.encode(_withdrawal(address(bridge), 200_000, hex"beef"));
+
+        pool.withdrawToL2(publicValues, "", 200_000, hex"beef");
+
+        assertEq(bridge.to(), l2Recipient);
+        assertEq(bridge.minGasLimit(), 200_000);
+        assertEq(bridge.extraData(), hex"beef");
+        assertEq(bridge.value(), AMOUNT - FEE);
+        assertEq(relayer.balance, FEE);
+    }
+
+    // The submitter cannot starve the deposit of gas or change what the L2 recipient is sent.
+    function test_WithdrawToL2RejectsAnotherMessage() public {
+        bytes memory publicValues = abi.encode(_withdrawal(address(bridge), 200_000, hex"beef"));
+
+        vm.expectRevert("Invalid bridge message");
+        pool.withdrawToL2(publicValues, "", 21_000, hex"beef");
+        vm.expectRevert("Invalid bridge message");
+        pool.withdrawToL2(publicValues, "", 200_000, hex"");
+    }
+
+    // The program takes any recipient as the bridge, which would keep the funds if it were none.
+    function test_WithdrawToL2RejectsAnUntrustedBridge() public {
+        bytes memory publicValues = abi.encode(_withdrawal(address(0xbad), 200_000, hex""));
+
+        vm.expectRevert("Untrusted bridge");
+        pool.withdrawToL2(publicValues, "", 200_000, hex"");
+    }
+
+    function test_WithdrawToL2RejectsOtherVariants() public {
+        BridgePool.BridgeWithdrawalData memory data = _withdrawal(address(bridge), 200_000, hex"");
+        data.withdrawal.variant = 0;
+
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdrawToL2(abi.encode(data), "", 200_000, hex"");
+        // Nor does `withdraw` take the public values of a withdrawal to an L2 address.
+        vm.expectRevert("Invalid public values length");
+        pool.withdraw(abi.encode(_withdrawal(address(bridge), 200_000, hex"")), "");
+    }
+}
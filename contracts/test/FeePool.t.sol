@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {FeePool} from "../src/FeePool.sol";
+import {Pool} from "../src/Pool.sol";
+import {MockVerifier} from "./Mocks.sol";
+
+contract FeePoolTest is Test {
+    uint256 internal constant AMOUNT = 1 ether;
+    uint256 internal constant RELAYER_FEE = 0.01 ether;
+    uint256 internal constant PROTOCOL_FEE = 0.003 ether;
+    uint64 internal constant PROOF_BLOCK = 99;
+    bytes32 internal constant PROOF_BLOCK_HASH = bytes32(uint256(0xb10c));
+
+    FeePool internal pool;
+    address internal owner = address(0x0e);
+    address internal recipient = address(0x12);
+    address internal relayer = address(0x7e1a);
+
+    function setUp() public {
+        vm.prank(owner);
+        pool = new FeePool(address(new MockVerifier()), bytes32(uint256(1)), AMOUNT, PROTOCOL_FEE);
+
+        vm.roll(PROOF_BLOCK + 1);
+        vm.setBlockhash(PROOF_BLOCK, PROOF_BLOCK_HASH);
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc0)));
+    }
+
+    function _withdrawal(uint8 variant) internal view returns (Pool.WithdrawalData memory) {
+        return Pool.WithdrawalData({
+            variant: variant,
+            nullifier: bytes32(uint256(1)),
+            blockHash: PROOF_BLOCK_HASH,
+            exclusionSetRoot: bytes32(0),
+            relayerFee: RELAYER_FEE,
+            recipient: recipient,
+            relayer: relayer,
+            contractAddress: address(pool),
+            blockNumber: PROOF_BLOCK,
+            feeToken: address(0),
+            feeTokenAmount: 0
+        });
+    }
+
+    function _feeWithdrawal(uint256 protocolFee) internal view returns (bytes memory) {
+        return abi.encode(FeePool.FeeWithdrawalData({withdrawal: _withdrawal(2), protocolFee: protocolFee}));
+    }
+
+    // The program proves the fee from this slot, so it must be where `pool_lib` looks.
+    function test_ProtocolFeeIsInSlot2() public view {
+        assertEq(uint256(vm.load(address(pool), bytes32(uint256(2)))), PROTOCOL_FEE);
+    }
+
+    function test_WithdrawChargesTheProvenFee() public {
+        pool.withdrawWithProtocolFee(_feeWithdrawal(PROTOCOL_FEE), "");
+
+        assertEq(recipient.balance, AMOUNT - RELAYER_FEE - PROTOCOL_FEE);
+        assertEq(owner.balance, PROTOCOL_FEE);
+        assertEq(relayer.balance, RELAYER_FEE);
+    }
+
+    // A fee raised while the proof was in flight does not apply to it.
+    function test_RaisingTheFeeSparesProofsInFlight() public {
+        vm.prank(owner);
+        pool.setProtocolFee(2 * PROTOCOL_FEE);
+
+        pool.withdrawWithProtocolFee(_feeWithdrawal(PROTOCOL_FEE), "");
+
+        assertEq(owner.balance, PROTOCOL_FEE);
+    }
+
+    function test_WithdrawWithAZeroFee() public {
+        pool.withdrawWithProtocolFee(_feeWithdrawal(0), "");
+
+        assertEq(recipient.balance, AMOUNT - RELAYER_FEE);
+        assertEq(owner.balance, 0);
+    }
+
+    function test_WithdrawRejectsAFeeOverTheNote() public {
+        vm.expectRevert("Invalid protocol fee");
+        pool.withdrawWithProtocolFee(_feeWithdrawal(AMOUNT), "");
+    }
+
+    // Withdrawals that commit to no fee cannot skip it through the entry points of `Pool`.
+    function test_OtherEntryPointsAreClosed() public {
+        vm.expectRevert("Withdrawals pay the protocol fee");
+        pool.withdraw(abi.encode(_withdrawal(0)), "");
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdrawWithProtocolFee(abi.encode(FeePool.FeeWithdrawalData({withdrawal: _withdrawal(0), protocolFee: 0})), "");
+    }
+
+    function test_OnlyTheOwnerSetsTheFee() public {
+        vm.expectRevert("Not the owner");
+        pool.setProtocolFee(0);
+        vm.prank(owner);
+        vm.expectRevert("Invalid protocol fee");
+        pool.setProtocolFee(AMOUNT + 1);
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {OraclePool} from "../src/OraclePool.sol";
+import {Pool} from "../src/Pool.sol";
+import {MockVerifier} from "./Mocks.sol";
+
+contract OraclePoolTest is Test {
+    uint256 internal constant AMOUNT = 1 ether;
+    uint256 internal constant FEE = 0.01 ether;
+    uint64 internal constant PROOF_BLOCK = 99;
+    bytes32 internal constant PROOF_BLOCK_HASH = bytes32(uint256(0xb10c));
+    bytes32 internal constant ORACLE_CODE_HASH = bytes32(uint256(0xc0de));
+
+    OraclePool internal pool;
+    address internal verifier;
+    address internal oracle = address(0x0a11);
+    address internal recipient = address(0x12);
+    address internal relayer = address(0x7e1a);
+
+    function setUp() public {
+        verifier = address(new MockVerifier());
+        pool = new OraclePool(verifier, bytes32(uint256(1)), AMOUNT, _oracles(ORACLE_CODE_HASH));
+
+        vm.roll(PROOF_BLOCK + 1);
+        vm.setBlockhash(PROOF_BLOCK, PROOF_BLOCK_HASH);
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc0)));
+    }
+
+    function _oracles(bytes32 codeHash) internal view returns (OraclePool.TrustedOracle[] memory oracles) {
+        oracles = new OraclePool.TrustedOracle[](1);
+        oracles[0] = OraclePool.TrustedOracle({oracle: oracle, slot: 3, codeHash: codeHash});
+    }
+
+    function _withdrawal() internal view returns (OraclePool.OracleWithdrawalData memory) {
+        return OraclePool.OracleWithdrawalData({
+            withdrawal: Pool.WithdrawalData({
+                variant: 6,
+                nullifier: bytes32(uint256(1)),
+                blockHash: PROOF_BLOCK_HASH,
+                exclusionSetRoot: bytes32(0),
+                relayerFee: FEE,
+                recipient: recipient,
+                relayer: relayer,
+                contractAddress: address(pool),
+                blockNumber: PROOF_BLOCK,
+                feeToken: address(0),
+                feeTokenAmount: 0
+            }),
+            blockHashOracle: oracle,
+            oracleSlot: 3,
+            oracleCodeHash: ORACLE_CODE_HASH,
+            provenBlockHash: bytes32(uint256(0x01d)),
+            provenBlockNumber: 1
+        });
+    }
+
+    function test_WithdrawWithOraclePaysOut() public {
+        pool.withdrawWithOracle(abi.encode(_withdrawal()), "");
+
+        assertEq(recipient.balance, AMOUNT - FEE);
+        assertEq(relayer.balance, FEE);
+    }
+
+    // An oracle anyone deployed could store the hash of a block that never happened.
+    function test_WithdrawWithOracleRejectsAnotherOracle() public {
+        OraclePool.OracleWithdrawalData memory data = _withdrawal();
+        data.blockHashOracle = address(0xbad);
+        vm.expectRevert("Untrusted block hash oracle");
+        pool.withdrawWithOracle(abi.encode(data), "");
+
+        data = _withdrawal();
+        data.oracleCodeHash = bytes32(uint256(0xbad));
+        vm.expectRevert("Invalid oracle code hash");
+        pool.withdrawWithOracle(abi.encode(data), "");
+
+        data = _withdrawal();
+        data.oracleSlot = 4;
+        vm.expectRevert("Invalid oracle slot");
+        pool.withdrawWithOracle(abi.encode(data), "");
+    }
+
+    // The proof is anchored at a block `BLOCKHASH` reaches, whatever old block it proves.
+    function test_WithdrawWithOracleChecksTheAnchor() public {
+        OraclePool.OracleWithdrawalData memory data = _withdrawal();
+        data.withdrawal.blockHash = bytes32(uint256(0xbad));
+
+        vm.expectRevert("Invalid block hash");
+        pool.withdrawWithOracle(abi.encode(data), "");
+    }
+
+    function test_WithdrawWithOracleRejectsOtherVariants() public {
+        OraclePool.OracleWithdrawalData memory data = _withdrawal();
+        data.withdrawal.variant = 0;
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdrawWithOracle(abi.encode(data), "");
+
+        vm.expectRevert("Invalid public values length");
+        pool.withdrawWithOracle(abi.encode(data.withdrawal), "");
+    }
+
+    // A zero code hash is what an untrusted oracle reads as, so it cannot be trusted.
+    function test_ConstructorRejectsAZeroCodeHash() public {
+        vm.expectRevert("Invalid oracle code hash");
+        new OraclePool(verifier, bytes32(uint256(1)), AMOUNT, _oracles(bytes32(0)));
+    }
+}
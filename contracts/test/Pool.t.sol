@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {Pool} from "../src/Pool.sol";
+import {MockToken, MockVerifier} from "./Mocks.sol";
+
+contract PoolTest is Test {
+    uint256 internal constant AMOUNT = 1 ether;
+    uint256 internal constant FEE = 1000;
+    uint64 internal constant PROOF_BLOCK = 99;
+    bytes32 internal constant PROOF_BLOCK_HASH = bytes32(uint256(0xb10c));
+
+    Pool internal pool;
+    MockToken internal token;
+
+    uint256 internal recipientKey = 0xa11ce;
+    address internal recipient;
+    address internal relayer = address(0x7e1a);
+
+    function setUp() public {
+        pool = new Pool(address(new MockVerifier()), bytes32(uint256(1)), AMOUNT);
+        token = new MockToken();
+        recipient = vm.addr(recipientKey);
+        token.mint(recipient, 10 * FEE);
+
+        vm.roll(PROOF_BLOCK + 1);
+        vm.setBlockhash(PROOF_BLOCK, PROOF_BLOCK_HASH);
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc0)));
+    }
+
+    function _withdrawal(bytes32 nullifier, address to) internal view returns (Pool.WithdrawalData memory) {
+        return Pool.WithdrawalData({
+            variant: 0,
+            nullifier: nullifier,
+            blockHash: PROOF_BLOCK_HASH,
+            exclusionSetRoot: bytes32(0),
+            relayerFee: 0,
+            recipient: to,
+            relayer: relayer,
+            contractAddress: address(pool),
+            blockNumber: PROOF_BLOCK,
+            feeToken: address(token),
+            feeTokenAmount: FEE
+        });
+    }
+
+    // A withdrawal of `_withdrawal(nullifier, to)` paying its relayer `FEE` wei instead of a token.
+    function _ethWithdrawal(bytes32 nullifier, address to) internal view returns (Pool.WithdrawalData memory) {
+        Pool.WithdrawalData memory data = _withdrawal(nullifier, to);
+        data.relayerFee = FEE;
+        data.feeToken = address(0);
+        data.feeTokenAmount = 0;
+        return data;
+    }
+
+    // The recipient's signature of the `TokenFeeAuthorization` of `data`, as `pool_lib` signs it.
+    function _authorize(uint256 key, Pool.WithdrawalData memory data) internal view returns (bytes memory) {
+        bytes32 domainSeparator = keccak256(
+            abi.encode(
+                keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"),
+                keccak256("Pool"),
+                keccak256("1"),
+                block.chainid,
+                address(pool)
+            )
+        );
+        bytes32 structHash = keccak256(
+            abi.encode(
+                keccak256(
+                    "TokenFeeAuthorization(bytes32 nullifier,address relayer,address feeToken,uint256 feeTokenAmount)"
+                ),
+                data.nullifier,
+                data.relayer,
+                data.feeToken,
+                data.feeTokenAmount
+            )
+        );
+        (uint8 v, bytes32 r, bytes32 s) =
+            vm.sign(key, keccak256(abi.encodePacked("\x19\x01", domainSeparator, structHash)));
+        return abi.encodePacked(r, s, v);
+    }
+
+    function _permit(uint256 key, uint256 deadline) internal view returns (uint8 v, bytes32 r, bytes32 s) {
+        address owner = vm.addr(key);
+        bytes32 structHash = keccak256(
+            abi.encode(
+                keccak256("Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)"),
+                owner,
+                address(pool),
+                FEE,
+                token.nonces(owner),
+                deadline
+            )
+        );
+        return vm.sign(key, keccak256(abi.encodePacked("\x19\x01", token.DOMAIN_SEPARATOR(), structHash)));
+    }
+
+    function _noPermit(bytes memory authorization) internal pure returns (Pool.TokenFeePermit memory) {
+        return Pool.TokenFeePermit({authorization: authorization, deadline: 0, v: 0, r: bytes32(0), s: bytes32(0)});
+    }
+
+    function test_WithdrawPaysTheRecipientAndRelayer() public {
+        Pool.WithdrawalData memory data = _ethWithdrawal(bytes32(uint256(1)), recipient);
+
+        pool.withdraw(abi.encode(data), "");
+
+        assertEq(recipient.balance, AMOUNT - FEE);
+        assertEq(relayer.balance, FEE);
+        assertTrue(pool.nullifiers(data.nullifier));
+    }
+
+    function test_WithdrawSpendsANoteOnce() public {
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc1)));
+        bytes memory publicValues = abi.encode(_ethWithdrawal(bytes32(uint256(1)), recipient));
+        pool.withdraw(publicValues, "");
+
+        vm.expectRevert("Already withdrawn");
+        pool.withdraw(publicValues, "");
+    }
+
+    function test_WithdrawRejectsAnotherBlockOrPool() public {
+        Pool.WithdrawalData memory data = _ethWithdrawal(bytes32(uint256(1)), recipient);
+        data.blockHash = bytes32(uint256(0xbad));
+        vm.expectRevert("Invalid block hash");
+        pool.withdraw(abi.encode(data), "");
+
+        // `BLOCKHASH` is zero for blocks it no longer reaches.
+        data = _ethWithdrawal(bytes32(uint256(1)), recipient);
+        vm.roll(PROOF_BLOCK + 257);
+        vm.expectRevert("Invalid block hash");
+        pool.withdraw(abi.encode(data), "");
+        vm.roll(PROOF_BLOCK + 1);
+
+        data.contractAddress = address(0xbad);
+        vm.expectRevert("Invalid contract address");
+        pool.withdraw(abi.encode(data), "");
+    }
+
+    // Every variant starts with a `WithdrawalData`, so the base pool must not pay out the others.
+    function test_WithdrawRejectsOtherVariants() public {
+        Pool.WithdrawalData memory data = _ethWithdrawal(bytes32(uint256(1)), recipient);
+        data.variant = 2;
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdraw(abi.encode(data), "");
+
+        data.variant = 0;
+        vm.expectRevert("Invalid public values length");
+        pool.withdraw(abi.encode(data, uint256(0)), "");
+    }
+
+    // A split withdrawal of the note paying `amounts` to `recipients` and `FEE` to the relayer.
+    function _split(address[] memory recipients, uint256[] memory amounts) internal view returns (bytes memory) {
+        Pool.WithdrawalData memory data = _ethWithdrawal(bytes32(uint256(1)), address(0));
+        data.variant = 5;
+        return abi.encode(
+            Pool.SplitWithdrawalData({
+                withdrawal: data,
+                distributionHash: keccak256(abi.encode(recipients, amounts)),
+                noteValue: AMOUNT
+            })
+        );
+    }
+
+    function _distribution() internal pure returns (address[] memory recipients, uint256[] memory amounts) {
+        recipients = new address[](2);
+        recipients[0] = address(0x5a1);
+        recipients[1] = address(0x5a2);
+        amounts = new uint256[](2);
+        amounts[0] = AMOUNT / 4;
+        amounts[1] = AMOUNT - AMOUNT / 4 - FEE;
+    }
+
+    function test_WithdrawSplitPaysEveryRecipient() public {
+        (address[] memory recipients, uint256[] memory amounts) = _distribution();
+
+        pool.withdrawSplit(_split(recipients, amounts), "", recipients, amounts);
+
+        assertEq(recipients[0].balance, amounts[0]);
+        assertEq(recipients[1].balance, amounts[1]);
+        assertEq(relayer.balance, FEE);
+        assertEq(address(pool).balance, 0);
+    }
+
+    // The relayer submits the distribution, so it must be the one the proof committed to.
+    function test_WithdrawSplitRejectsAnotherDistribution() public {
+        (address[] memory recipients, uint256[] memory amounts) = _distribution();
+        bytes memory publicValues = _split(recipients, amounts);
+        recipients[1] = address(0xbad);
+
+        vm.expectRevert("Invalid distribution");
+        pool.withdrawSplit(publicValues, "", recipients, amounts);
+    }
+
+    function test_WithdrawSplitRejectsAnotherNote() public {
+        (address[] memory recipients, uint256[] memory amounts) = _distribution();
+        Pool.SplitWithdrawalData memory data = abi.decode(_split(recipients, amounts), (Pool.SplitWithdrawalData));
+        data.noteValue = AMOUNT * 2;
+        vm.expectRevert("Invalid note value");
+        pool.withdrawSplit(abi.encode(data), "", recipients, amounts);
+
+        data.noteValue = AMOUNT;
+        data.withdrawal.feeToken = address(token);
+        vm.expectRevert("Invalid fee token");
+        pool.withdrawSplit(abi.encode(data), "", recipients, amounts);
+
+        // The public values of a base withdrawal are not a split's.
+        data.withdrawal = _ethWithdrawal(bytes32(uint256(1)), recipient);
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdrawSplit(abi.encode(data), "", recipients, amounts);
+        vm.expectRevert("Invalid public values length");
+        pool.withdrawSplit(abi.encode(data.withdrawal), "", recipients, amounts);
+    }
+
+    // Before token fees needed the recipient's consent, anyone could prove a withdrawal of their
+    // own note to a victim who had approved the pool, and take the victim's allowance as the fee.
+    function test_TokenFeeCannotDrainAnApprovedRecipient() public {
+        vm.prank(recipient);
+        token.approve(address(pool), FEE);
+        Pool.WithdrawalData memory data = _withdrawal(bytes32(uint256(1)), recipient);
+        bytes memory publicValues = abi.encode(data);
+
+        vm.expectRevert("Token fee needs the recipient's authorization");
+        pool.withdraw(publicValues, "");
+
+        uint256 attackerKey = 0xbad;
+        vm.expectRevert("Invalid token fee authorization");
+        pool.withdrawWithTokenFee(publicValues, "", _noPermit(_authorize(attackerKey, data)));
+
+        // An authorization the recipient gave for a different relayer does not carry over.
+        Pool.WithdrawalData memory other = _withdrawal(bytes32(uint256(1)), recipient);
+        other.relayer = address(0xbad);
+        vm.expectRevert("Invalid token fee authorization");
+        pool.withdrawWithTokenFee(publicValues, "", _noPermit(_authorize(recipientKey, other)));
+
+        assertEq(token.balanceOf(recipient), 10 * FEE);
+        assertEq(token.balanceOf(relayer), 0);
+    }
+
+    function test_WithdrawWithTokenFeeTakesTheAuthorizedFee() public {
+        vm.prank(recipient);
+        token.approve(address(pool), FEE);
+        Pool.WithdrawalData memory data = _withdrawal(bytes32(uint256(1)), recipient);
+
+        pool.withdrawWithTokenFee(abi.encode(data), "", _noPermit(_authorize(recipientKey, data)));
+
+        assertEq(recipient.balance, AMOUNT);
+        assertEq(token.balanceOf(relayer), FEE);
+        assertEq(token.balanceOf(recipient), 9 * FEE);
+        assertTrue(pool.nullifiers(data.nullifier));
+    }
+
+    // With a permit, the recipient never sends an approval of the pool that would link them to it.
+    function test_WithdrawWithTokenFeeTakesAPermit() public {
+        Pool.WithdrawalData memory data = _withdrawal(bytes32(uint256(1)), recipient);
+        uint256 deadline = block.timestamp + 1 days;
+        (uint8 v, bytes32 r, bytes32 s) = _permit(recipientKey, deadline);
+        Pool.TokenFeePermit memory fee =
+            Pool.TokenFeePermit({authorization: _authorize(recipientKey, data), deadline: deadline, v: v, r: r, s: s});
+
+        pool.withdrawWithTokenFee(abi.encode(data), "", fee);
+
+        assertEq(token.balanceOf(relayer), FEE);
+        assertEq(token.allowance(recipient, address(pool)), 0);
+    }
+
+    // Someone copying the permit out of the mempool only sets the allowance the withdrawal uses.
+    function test_FrontRunPermitDoesNotBlockTheWithdrawal() public {
+        Pool.WithdrawalData memory data = _withdrawal(bytes32(uint256(1)), recipient);
+        uint256 deadline = block.timestamp + 1 days;
+        (uint8 v, bytes32 r, bytes32 s) = _permit(recipientKey, deadline);
+        token.permit(recipient, address(pool), FEE, deadline, v, r, s);
+        Pool.TokenFeePermit memory fee =
+            Pool.TokenFeePermit({authorization: _authorize(recipientKey, data), deadline: deadline, v: v, r: r, s: s});
+
+        pool.withdrawWithTokenFee(abi.encode(data), "", fee);
+
+        assertEq(token.balanceOf(relayer), FEE);
+    }
+
+    function test_WithdrawWithTokenFeeRejectsAnEthFee() public {
+        Pool.WithdrawalData memory data = _withdrawal(bytes32(uint256(1)), recipient);
+        data.feeToken = address(0);
+        data.feeTokenAmount = 0;
+
+        vm.expectRevert("Invalid fee token");
+        pool.withdrawWithTokenFee(abi.encode(data), "", _noPermit(_authorize(recipientKey, data)));
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {Pool} from "../src/Pool.sol";
+import {RegistryPool} from "../src/RegistryPool.sol";
+import {MockVerifier} from "./Mocks.sol";
+
+contract RegistryPoolTest is Test {
+    uint256 internal constant AMOUNT = 1 ether;
+    uint256 internal constant FEE = 0.01 ether;
+    uint64 internal constant PROOF_BLOCK = 99;
+    bytes32 internal constant PROOF_BLOCK_HASH = bytes32(uint256(0xb10c));
+
+    RegistryPool internal pool;
+    address internal registry = address(0x4e9);
+    address internal recipient = address(0x12);
+    address internal relayer = address(0x7e1a);
+
+    function setUp() public {
+        pool = new RegistryPool(address(new MockVerifier()), bytes32(uint256(1)), AMOUNT, registry);
+
+        vm.roll(PROOF_BLOCK + 1);
+        vm.setBlockhash(PROOF_BLOCK, PROOF_BLOCK_HASH);
+        pool.deposit{value: AMOUNT}(bytes32(uint256(0xc0)));
+    }
+
+    function _withdrawal() internal view returns (RegistryPool.RegistryWithdrawalData memory) {
+        return RegistryPool.RegistryWithdrawalData({
+            withdrawal: Pool.WithdrawalData({
+                variant: 4,
+                nullifier: bytes32(uint256(1)),
+                blockHash: PROOF_BLOCK_HASH,
+                exclusionSetRoot: bytes32(0),
+                relayerFee: FEE,
+                recipient: recipient,
+                relayer: relayer,
+                contractAddress: address(pool),
+                blockNumber: PROOF_BLOCK,
+                feeToken: address(0),
+                feeTokenAmount: 0
+            }),
+            registry: registry,
+            chainId: block.chainid,
+            denomination: AMOUNT,
+            version: 1
+        });
+    }
+
+    function test_WithdrawRegisteredPaysOut() public {
+        pool.withdrawRegistered(abi.encode(_withdrawal()), "");
+
+        assertEq(recipient.balance, AMOUNT - FEE);
+        assertEq(relayer.balance, FEE);
+    }
+
+    // An entry in a registry anyone deployed, mapping anything to the pool, proves nothing.
+    function test_WithdrawRegisteredRejectsAnotherRegistry() public {
+        RegistryPool.RegistryWithdrawalData memory data = _withdrawal();
+        data.registry = address(0xbad);
+
+        vm.expectRevert("Untrusted registry");
+        pool.withdrawRegistered(abi.encode(data), "");
+    }
+
+    function test_WithdrawRegisteredRejectsAnotherEntry() public {
+        RegistryPool.RegistryWithdrawalData memory data = _withdrawal();
+        data.chainId = block.chainid + 1;
+        vm.expectRevert("Invalid chain id");
+        pool.withdrawRegistered(abi.encode(data), "");
+
+        data = _withdrawal();
+        data.denomination = AMOUNT / 10;
+        vm.expectRevert("Invalid denomination");
+        pool.withdrawRegistered(abi.encode(data), "");
+    }
+
+    function test_WithdrawRegisteredRejectsOtherVariants() public {
+        RegistryPool.RegistryWithdrawalData memory data = _withdrawal();
+        data.withdrawal.variant = 0;
+
+        vm.expectRevert("Invalid withdrawal variant");
+        pool.withdrawRegistered(abi.encode(data), "");
+    }
+}
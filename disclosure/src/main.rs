@@ -0,0 +1,14 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy::sol_types::SolValue;
+use pool_lib::disclosure::{process_disclosure, DisclosureInput};
+
+pub fn main() {
+    let bytes = sp1_zkvm::io::read_vec();
+    let input = serde_cbor::from_slice::<DisclosureInput>(&bytes).unwrap();
+
+    let data = process_disclosure(&input).unwrap();
+
+    sp1_zkvm::io::commit_slice(&data.abi_encode());
+}
@@ -0,0 +1,231 @@
+//! UniFFI bindings to pool-lib, so iOS and Android wallets can derive notes and assemble
+//! withdrawal inputs with the same code the program runs.
+//!
+//! Hashes, addresses and amounts cross the boundary as `0x`-prefixed hex strings. Generate the
+//! Swift or Kotlin bindings from the built library with
+//! `cargo run -p pool-ffi --bin uniffi-bindgen -- generate --library <lib> --language swift`.
+
+use alloy::primitives::{Address, U256};
+use pool_lib::{
+    compute_commitment, compute_inclusion_root, decode_any_public_values, note::Note,
+    process_any_withdrawal, InclusionBranches, WithdrawalData, WithdrawalInput,
+};
+use rand::Rng;
+use std::{fmt, str::FromStr};
+
+uniffi::setup_scaffolding!();
+
+/// Error thrown by every fallible binding.
+#[derive(Debug, uniffi::Error)]
+pub enum PoolError {
+    /// An argument could not be parsed or failed validation.
+    Invalid { message: String },
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for PoolError {}
+
+fn invalid(e: impl fmt::Display) -> PoolError {
+    PoolError::Invalid {
+        message: e.to_string(),
+    }
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, PoolError> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("invalid {name} {value}")))
+}
+
+/// A deposit secret, where it is deposited and what it commits to.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct NoteInfo {
+    pub chain_id: u64,
+    pub pool: String,
+    pub secret: String,
+    /// Commitment to deposit.
+    pub commitment: String,
+    /// Nullifier revealed on withdrawal.
+    pub nullifier: String,
+}
+
+impl From<Note> for NoteInfo {
+    fn from(note: Note) -> Self {
+        let (commitment, nullifier) = compute_commitment(&note.secret);
+        Self {
+            chain_id: note.chain_id,
+            pool: note.pool.to_string(),
+            secret: note.secret.to_string(),
+            commitment: commitment.to_string(),
+            nullifier: nullifier.to_string(),
+        }
+    }
+}
+
+/// Branches proving a commitment is in an association set.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Branches {
+    pub index: u32,
+    pub proof: Vec<String>,
+}
+
+/// Everything needed to build a [`WithdrawalInput`]. The account proof and block header are the
+/// JSON `eth_getProof` and `eth_getBlockByNumber` return.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct WithdrawalRequest {
+    pub note: String,
+    pub array_index: u64,
+    pub array_slot: String,
+    pub account_proof_json: String,
+    pub block_header_json: String,
+    pub inclusion_branches: Option<Branches>,
+    pub relayer_fee: String,
+    pub recipient: String,
+    pub relayer: String,
+}
+
+/// Public values a withdrawal proof commits to.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct PublicValues {
+    pub nullifier: String,
+    pub block_hash: String,
+    pub exclusion_set_root: String,
+    pub relayer_fee: String,
+    pub recipient: String,
+    pub relayer: String,
+    pub contract_address: String,
+    pub block_number: u64,
+    pub fee_token: String,
+    pub fee_token_amount: String,
+}
+
+impl From<WithdrawalData> for PublicValues {
+    fn from(data: WithdrawalData) -> Self {
+        Self {
+            nullifier: data.nullifier.to_string(),
+            block_hash: data.blockHash.to_string(),
+            exclusion_set_root: data.exclusionSetRoot.to_string(),
+            relayer_fee: data.relayerFee.to_string(),
+            recipient: data.recipient.to_string(),
+            relayer: data.relayer.to_string(),
+            contract_address: data.contractAddress.to_string(),
+            block_number: data.blockNumber,
+            fee_token: data.feeToken.to_string(),
+            fee_token_amount: data.feeTokenAmount.to_string(),
+        }
+    }
+}
+
+fn branches(branches: Branches) -> Result<InclusionBranches, PoolError> {
+    Ok(InclusionBranches {
+        index: branches.index,
+        proof: branches
+            .proof
+            .iter()
+            .map(|node| parse("branch", node))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Generate a fresh secret for depositing into `pool` on `chain_id`.
+#[uniffi::export]
+pub fn new_note(chain_id: u64, pool: String) -> Result<NoteInfo, PoolError> {
+    Ok(Note {
+        chain_id,
+        pool: parse("pool", &pool)?,
+        secret: rand::rng().random::<[u8; 32]>().into(),
+    }
+    .into())
+}
+
+/// Commitment and nullifier of `secret`, for a note kept some other way.
+#[uniffi::export]
+pub fn derive_note(chain_id: u64, pool: String, secret: String) -> Result<NoteInfo, PoolError> {
+    Ok(Note {
+        chain_id,
+        pool: parse("pool", &pool)?,
+        secret: parse("secret", &secret)?,
+    }
+    .into())
+}
+
+/// Encode a note as a `pool-note-v1` string.
+#[uniffi::export]
+pub fn encode_note(note: NoteInfo) -> Result<String, PoolError> {
+    Ok(Note {
+        chain_id: note.chain_id,
+        pool: parse("pool", &note.pool)?,
+        secret: parse("secret", &note.secret)?,
+    }
+    .to_string())
+}
+
+/// Decode a `pool-note-v1` string.
+#[uniffi::export]
+pub fn decode_note(note: String) -> Result<NoteInfo, PoolError> {
+    Ok(note.parse::<Note>().map_err(invalid)?.into())
+}
+
+/// Root a commitment proves membership of with `branches`, as committed in a withdrawal's
+/// `exclusionSetRoot`.
+#[uniffi::export]
+pub fn inclusion_root(commitment: String, branches: Branches) -> Result<String, PoolError> {
+    let commitment = parse("commitment", &commitment)?;
+    Ok(compute_inclusion_root(&commitment, &self::branches(branches)?).to_string())
+}
+
+/// Build a withdrawal input, check it the way the program does and encode it as the CBOR the
+/// program reads.
+#[uniffi::export]
+pub fn build_withdrawal_input(request: WithdrawalRequest) -> Result<Vec<u8>, PoolError> {
+    let note: Note = request.note.parse().map_err(invalid)?;
+    let input = WithdrawalInput {
+        secret: note.secret,
+        array_index: U256::from(request.array_index),
+        account_proof: serde_json::from_str(&request.account_proof_json).map_err(invalid)?,
+        block_header: serde_json::from_str(&request.block_header_json).map_err(invalid)?,
+        inclusion_set_branches: request.inclusion_branches.map(branches).transpose()?,
+        contract_address: note.pool,
+        array_slot: parse("array slot", &request.array_slot)?,
+        relayer_fee: parse("relayer fee", &request.relayer_fee)?,
+        recipient: parse::<Address>("recipient", &request.recipient)?,
+        relayer: parse::<Address>("relayer", &request.relayer)?,
+        nft: None,
+        protocol_fee: false,
+        bridge: None,
+        registry: None,
+        oracle: None,
+        split: None,
+        token_fee: None,
+        association: None,
+    };
+    process_any_withdrawal(&input).map_err(invalid)?;
+    serde_cbor::to_vec(&input).map_err(invalid)
+}
+
+/// Check CBOR written by [`build_withdrawal_input`], returning the public values the program
+/// would commit.
+#[uniffi::export]
+pub fn validate_withdrawal_input(input: Vec<u8>) -> Result<PublicValues, PoolError> {
+    let input: WithdrawalInput = serde_cbor::from_slice(&input).map_err(invalid)?;
+    Ok(process_any_withdrawal(&input)
+        .map_err(invalid)?
+        .withdrawal
+        .into())
+}
+
+/// Decode the public values of a withdrawal proof.
+#[uniffi::export]
+pub fn decode_public_values(public_values: Vec<u8>) -> Result<PublicValues, PoolError> {
+    Ok(decode_any_public_values(&public_values)
+        .map_err(invalid)?
+        .withdrawal
+        .into())
+}
@@ -0,0 +1,114 @@
+//! Host-side benchmarks of pool-lib. Run with `cargo bench -p pool-lib`; criterion compares each
+//! run against the last one saved in `target/criterion`.
+//!
+//! Proofs come from [`fixture::withdrawal`]: a pool whose `deposits` array holds `DEPOSITS`
+//! commitments, in a state trie with `ACCOUNTS` other accounts, which gives proofs as deep as
+//! mainnet's.
+
+use alloy::primitives::B256;
+use alloy_trie::TrieAccount;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pool_lib::{
+    compute_commitment, compute_inclusion_root, compute_merkle_root,
+    fixture::{self, secret},
+    fork::Fork,
+    hash_block_header, process_withdrawal, verify_mpt_proof, InclusionBranches,
+};
+use std::hint::black_box;
+
+const DEPOSITS: u64 = 100_000;
+const ACCOUNTS: u64 = 1_000_000;
+
+fn commitment(c: &mut Criterion) {
+    c.bench_function("compute_commitment", |b| {
+        b.iter(|| compute_commitment(black_box(&secret(7))))
+    });
+}
+
+fn inclusion_root(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compute_inclusion_root");
+    for depth in [16u32, 20, 24, 28, 32] {
+        let branches = InclusionBranches {
+            index: 0x5555_5555 & (u32::MAX >> (32 - depth)),
+            proof: (0..depth).map(|i| secret(i.into())).collect(),
+        };
+        let commitment = secret(u64::MAX);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(depth),
+            &branches,
+            |b, branches| {
+                b.iter(|| compute_inclusion_root(black_box(&commitment), black_box(branches)))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn merkle_root(c: &mut Criterion) {
+    let leaves: Vec<B256> = (0..1 << 20).map(secret).collect();
+    let mut group = c.benchmark_group("compute_merkle_root");
+    group.sample_size(10);
+    group.bench_function("1m", |b| b.iter(|| compute_merkle_root(black_box(&leaves))));
+    group.finish();
+}
+
+fn header_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_block_header");
+    for fork in Fork::ALL {
+        let header = fixture::header(fork, secret(0), 1);
+        // Every fork level's withdrawals verify, not only the default fixture's.
+        process_withdrawal(&fixture::withdrawal_at(fork, 16, 16)).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(fork), &header, |b, header| {
+            b.iter(|| hash_block_header(black_box(header)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn mpt(c: &mut Criterion) {
+    let input = fixture::withdrawal(DEPOSITS, ACCOUNTS);
+    let proof = &input.account_proof;
+    let account = TrieAccount {
+        nonce: proof.nonce,
+        balance: proof.balance,
+        code_hash: proof.code_hash,
+        storage_root: proof.storage_hash,
+    };
+    c.bench_function("verify_mpt_proof/account", |b| {
+        b.iter(|| {
+            verify_mpt_proof(
+                &input.block_header.state_root,
+                proof.address,
+                &account,
+                black_box(&proof.account_proof),
+            )
+            .unwrap()
+        })
+    });
+    let slot = &proof.storage_proof[1];
+    let commitment = B256::from(slot.value);
+    c.bench_function("verify_mpt_proof/storage", |b| {
+        b.iter(|| {
+            verify_mpt_proof(
+                &proof.storage_hash,
+                slot.key.as_b256(),
+                commitment,
+                black_box(&slot.proof),
+            )
+            .unwrap()
+        })
+    });
+    c.bench_function("process_withdrawal", |b| {
+        b.iter(|| process_withdrawal(black_box(&input)).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    commitment,
+    inclusion_root,
+    merkle_root,
+    header_hash,
+    mpt
+);
+criterion_main!(benches);
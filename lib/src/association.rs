@@ -0,0 +1,198 @@
+use crate::{
+    compute_merkle_branch, compute_merkle_root, intent::recover_typed_signer, process_withdrawal,
+    AssociationWithdrawalData, InclusionBranches, Variant, WithdrawalData, WithdrawalInput,
+};
+use alloy::{
+    primitives::{Address, PrimitiveSignature, B256, U256},
+    sol,
+    sol_types::{eip712_domain, Eip712Domain},
+};
+use eyre::{ensure, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+
+sol! {
+    /// The root of an association set: the deposits of a pool an association set provider
+    /// vouches for, built with `compute_merkle_root`.
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct AssociationRoot {
+        bytes32 setId;
+        bytes32 root;
+        uint64 leafCount;
+        uint64 issuedAt;
+    }
+
+    /// An allow-list of deposits distributed out of band, whose root withdrawals prove their
+    /// deposit is in and commit to as `exclusionSetRoot`, valid from `validFrom` until
+    /// `validUntil`.
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct AllowList {
+        bytes32 setId;
+        bytes32 root;
+        uint64 leafCount;
+        uint64 validFrom;
+        uint64 validUntil;
+    }
+}
+
+/// Version of the [`AllowListBundle`] format. Readers reject versions they do not know. Version 1
+/// bundles were signed as a `Blocklist`, which the set never was.
+pub const ALLOW_LIST_VERSION: u16 = 2;
+
+/// An association root with the provider's signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRoot {
+    pub root: AssociationRoot,
+    pub chain_id: u64,
+    pub pool: Address,
+    pub signature: PrimitiveSignature,
+}
+
+/// EIP-712 domain for association roots over the deposits of `pool` on chain `chain_id`.
+pub fn association_domain(chain_id: u64, pool: Address) -> Eip712Domain {
+    eip712_domain! {
+        name: "Pool Association Set",
+        version: "1",
+        chain_id: chain_id,
+        verifying_contract: pool,
+    }
+}
+
+impl SignedRoot {
+    /// The address that signed this root.
+    pub fn signer(&self) -> Result<Address> {
+        recover_typed_signer(
+            &self.root,
+            &association_domain(self.chain_id, self.pool),
+            &self.signature,
+        )
+    }
+
+    /// Check that this root was signed by `provider`.
+    pub fn verify(&self, provider: Address) -> Result<()> {
+        ensure!(
+            self.signer()? == provider,
+            "association root not signed by {provider}"
+        );
+        Ok(())
+    }
+}
+
+/// Process a withdrawal whose deposit is in the association set of `input.association`,
+/// recovering the provider that signed its root so the pool can tell whether it trusts it.
+pub fn process_association_withdrawal(
+    input: &WithdrawalInput,
+) -> Result<AssociationWithdrawalData> {
+    let signed = input
+        .association
+        .as_ref()
+        .ok_or_eyre("withdrawal is not from an association set")?;
+    ensure!(
+        input.inclusion_set_branches.is_some(),
+        "withdrawal has no branch into the association set"
+    );
+    ensure!(
+        signed.pool == input.contract_address,
+        "association root is for pool {}",
+        signed.pool
+    );
+    let data = process_withdrawal(input)?;
+    ensure!(
+        data.exclusionSetRoot == signed.root.root,
+        "deposit is not in the signed association set"
+    );
+    let signer = signed.signer()?;
+    Ok(AssociationWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::Association as u8,
+            ..data
+        },
+        aspSigner: signer,
+        setId: signed.root.setId,
+        leafCount: signed.root.leafCount,
+        issuedAt: signed.root.issuedAt,
+        chainId: U256::from(signed.chain_id),
+    })
+}
+
+/// An [`AllowList`] signed by its issuer, as a JSON file anyone can mirror.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowListBundle {
+    pub version: u16,
+    pub chain_id: u64,
+    pub pool: Address,
+    pub issuer: Address,
+    pub allow_list: AllowList,
+    pub signature: PrimitiveSignature,
+    /// Members of the set, so withdrawers can build branches without asking the issuer. Empty
+    /// when only the root is distributed.
+    #[serde(default)]
+    pub leaves: Vec<B256>,
+}
+
+impl AllowListBundle {
+    /// Check the bundle is a known version signed by its issuer, and that its leaves, if
+    /// included, build its root. Says nothing about whether the issuer is trusted.
+    pub fn verify_signature(&self) -> Result<()> {
+        ensure!(
+            self.version == ALLOW_LIST_VERSION,
+            "unsupported allow-list version {}, expected {ALLOW_LIST_VERSION}",
+            self.version
+        );
+        let signer = recover_typed_signer(
+            &self.allow_list,
+            &association_domain(self.chain_id, self.pool),
+            &self.signature,
+        )?;
+        ensure!(signer == self.issuer, "allow-list not signed by its issuer");
+        ensure!(
+            self.allow_list.validFrom <= self.allow_list.validUntil,
+            "allow-list validity window is empty"
+        );
+        if !self.leaves.is_empty() {
+            ensure!(
+                self.leaves.len() as u64 == self.allow_list.leafCount,
+                "allow-list has {} leaves, signed for {}",
+                self.leaves.len(),
+                self.allow_list.leafCount
+            );
+            ensure!(
+                compute_merkle_root(&self.leaves) == self.allow_list.root,
+                "allow-list leaves do not match its root"
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether `now` is within the validity window.
+    pub fn is_valid_at(&self, now: u64) -> bool {
+        (self.allow_list.validFrom..=self.allow_list.validUntil).contains(&now)
+    }
+
+    /// Check the bundle is authentic, issued by one of `trusted`, and valid at `now`.
+    pub fn verify(&self, trusted: &[Address], now: u64) -> Result<()> {
+        self.verify_signature()?;
+        ensure!(
+            trusted.contains(&self.issuer),
+            "allow-list issuer {} is not trusted",
+            self.issuer
+        );
+        ensure!(
+            self.is_valid_at(now),
+            "allow-list is only valid from {} to {}",
+            self.allow_list.validFrom,
+            self.allow_list.validUntil
+        );
+        Ok(())
+    }
+
+    /// Branch proving `commitment` is in the set, for [`crate::compute_inclusion_root`].
+    pub fn branch(&self, commitment: B256) -> Result<InclusionBranches> {
+        ensure!(!self.leaves.is_empty(), "allow-list bundle has no leaves");
+        let index = self
+            .leaves
+            .iter()
+            .position(|leaf| *leaf == commitment)
+            .ok_or_else(|| eyre::eyre!("{commitment} is not in the allow-list"))?;
+        Ok(compute_merkle_branch(&self.leaves, index))
+    }
+}
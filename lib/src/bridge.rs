@@ -0,0 +1,73 @@
+//! Withdrawals to an L2 address. The recipient is a canonical bridge, and the withdrawal commits
+//! in [`BridgeWithdrawalData`] to the deposit the pool is to make through it, so whoever submits
+//! the proof cannot change where on L2 the funds go or starve the deposit of gas.
+
+use crate::{
+    compute_bridge_message_hash, process_withdrawal, BridgeWithdrawalData, Variant, WithdrawalData,
+    WithdrawalInput,
+};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use eyre::{ensure, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+
+/// Gas limit of a bridge deposit's execution on L2 unless one is given, enough for an ETH
+/// transfer to a contract.
+pub const DEFAULT_L2_GAS_LIMIT: u32 = 200_000;
+
+/// The deposit a withdrawal to an L2 address has the pool make through the bridge it pays.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BridgeMessage {
+    pub l2_chain_id: u64,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::address"))]
+    pub l2_recipient: Address,
+    /// Gas limit of the deposit's execution on L2.
+    pub min_gas_limit: u32,
+    /// Data passed along with the deposit to the L2 recipient.
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::bytes"))]
+    pub extra_data: Bytes,
+}
+
+impl BridgeMessage {
+    pub fn hash(&self) -> B256 {
+        compute_bridge_message_hash(
+            self.l2_chain_id,
+            &self.l2_recipient,
+            self.min_gas_limit,
+            &self.extra_data,
+        )
+    }
+
+    /// Check that the withdrawal with public values `data` commits to this message.
+    pub fn check(&self, data: &BridgeWithdrawalData) -> Result<()> {
+        ensure!(
+            data.l2ChainId == U256::from(self.l2_chain_id)
+                && data.l2Recipient == self.l2_recipient
+                && data.bridgeMessageHash == self.hash(),
+            "withdrawal commits to a different bridge message"
+        );
+        Ok(())
+    }
+}
+
+/// Process a withdrawal to an L2 address, fully verifying it and returning public data.
+pub fn process_bridge_withdrawal(input: &WithdrawalInput) -> Result<BridgeWithdrawalData> {
+    let message = input
+        .bridge
+        .as_ref()
+        .ok_or_eyre("withdrawal is not to an L2 address")?;
+    ensure!(
+        input.recipient != Address::ZERO,
+        "withdrawals to an L2 address pay a bridge"
+    );
+    let data = process_withdrawal(input)?;
+    Ok(BridgeWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::Bridge as u8,
+            ..data
+        },
+        l2ChainId: U256::from(message.l2_chain_id),
+        l2Recipient: message.l2_recipient,
+        bridgeMessageHash: message.hash(),
+    })
+}
@@ -0,0 +1,62 @@
+//! Deposits into pools that take them in two phases, so a commitment cannot be front-run into a
+//! different leaf. The depositor first commits to [`deposit_hash`], which binds the commitment
+//! to their address, and pays; the pool records the depositor under the hash in its
+//! `pendingDeposits` mapping. Revealing the commitment and salt later appends the commitment, and
+//! only the recorded depositor can reveal it.
+
+use crate::verify_mpt_proof;
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    rpc::types::EIP1186AccountProofResponse,
+    sol_types::SolValue,
+};
+use alloy_trie::TrieAccount;
+use eyre::{ensure, Result};
+
+/// Storage slot of the `pendingDeposits` mapping of commit-reveal pools, after the `deposits`
+/// array and `nullifiers` mapping.
+pub const PENDING_DEPOSITS_SLOT: u64 = 2;
+
+/// The hash a depositor commits to: `keccak256(abi.encode(commitment, depositor, salt))`.
+pub fn deposit_hash(commitment: &B256, depositor: &Address, salt: &B256) -> B256 {
+    keccak256((*commitment, *depositor, *salt).abi_encode())
+}
+
+/// Storage key of `mapping[key]` for a mapping stored at `slot`.
+pub fn mapping_key(slot: U256, key: &B256) -> B256 {
+    keccak256((*key, slot).abi_encode())
+}
+
+/// Verify that `pendingDeposits[hash]`, a mapping at `slot` in `pool`, holds `depositor` at
+/// `state_root`.
+pub fn verify_pending_deposit(
+    pool: &Address,
+    slot: &U256,
+    hash: &B256,
+    depositor: &Address,
+    state_root: &B256,
+    proof: &EIP1186AccountProofResponse,
+) -> Result<()> {
+    ensure!(*pool == proof.address, "invalid contract address");
+    let account = TrieAccount {
+        nonce: proof.nonce,
+        balance: proof.balance,
+        code_hash: proof.code_hash,
+        storage_root: proof.storage_hash,
+    };
+    verify_mpt_proof(state_root, proof.address, account, &proof.account_proof)?;
+
+    ensure!(proof.storage_proof.len() == 1, "invalid storage proof");
+    let pending = &proof.storage_proof[0];
+    let value = U256::from_be_slice(depositor.as_slice());
+    ensure!(
+        pending.value == value,
+        "deposit is pending for another address"
+    );
+    verify_mpt_proof(
+        &proof.storage_hash,
+        mapping_key(*slot, hash),
+        value,
+        &pending.proof,
+    )
+}
@@ -0,0 +1,286 @@
+//! Shrinking the withdrawal input the guest reads. Proof nodes off the paths being proven are
+//! dropped, and nodes shared between proofs are stored once. Neither changes what
+//! [`process_withdrawal`](crate::process_withdrawal) checks.
+
+use crate::WithdrawalInput;
+use alloy::{
+    primitives::{keccak256, Bytes, B256},
+    rlp::Header,
+    rpc::types::EIP1186AccountProofResponse,
+};
+use alloy_trie::Nibbles;
+use eyre::{ensure, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A [`WithdrawalInput`] whose proof nodes are listed once in `nodes` and referenced by index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactInput {
+    /// The input with the nodes of its account and storage proofs removed.
+    pub input: WithdrawalInput,
+    pub nodes: Vec<Bytes>,
+    pub account_proof: Vec<u32>,
+    /// Node indices of each storage proof, in the order of `storage_proof`.
+    pub storage_proofs: Vec<Vec<u32>>,
+}
+
+impl CompactInput {
+    pub fn new(input: &WithdrawalInput) -> Self {
+        let mut input = input.clone();
+        let mut nodes = Vec::new();
+        let mut indices = HashMap::new();
+        let mut index = |proof: Vec<Bytes>| -> Vec<u32> {
+            proof
+                .into_iter()
+                .map(|node| {
+                    *indices.entry(node.clone()).or_insert_with(|| {
+                        nodes.push(node);
+                        nodes.len() as u32 - 1
+                    })
+                })
+                .collect()
+        };
+        let account_proof = index(std::mem::take(&mut input.account_proof.account_proof));
+        let storage_proofs = input
+            .account_proof
+            .storage_proof
+            .iter_mut()
+            .map(|slot| index(std::mem::take(&mut slot.proof)))
+            .collect();
+        Self {
+            input,
+            nodes,
+            account_proof,
+            storage_proofs,
+        }
+    }
+
+    /// Put the proof nodes back in place.
+    pub fn expand(self) -> Result<WithdrawalInput> {
+        let Self {
+            mut input,
+            nodes,
+            account_proof,
+            storage_proofs,
+        } = self;
+        let resolve = |proof: &[u32]| -> Result<Vec<Bytes>> {
+            proof
+                .iter()
+                .map(|&i| {
+                    nodes
+                        .get(i as usize)
+                        .cloned()
+                        .ok_or_eyre("invalid node index")
+                })
+                .collect()
+        };
+        input.account_proof.account_proof = resolve(&account_proof)?;
+        ensure!(
+            storage_proofs.len() == input.account_proof.storage_proof.len(),
+            "invalid storage proof count"
+        );
+        for (slot, proof) in input
+            .account_proof
+            .storage_proof
+            .iter_mut()
+            .zip(&storage_proofs)
+        {
+            slot.proof = resolve(proof)?;
+        }
+        Ok(input)
+    }
+}
+
+/// Drop the nodes of `proof` that are not on the paths from `state_root` to the account and
+/// from its storage root to each slot. Proofs whose path cannot be followed are kept whole, so
+/// verification fails the same way it would have.
+pub fn prune_proof(proof: &mut EIP1186AccountProofResponse, state_root: B256) {
+    prune_path(
+        &mut proof.account_proof,
+        state_root,
+        keccak256(proof.address),
+    );
+    for slot in &mut proof.storage_proof {
+        prune_path(
+            &mut slot.proof,
+            proof.storage_hash,
+            keccak256(slot.key.as_b256()),
+        );
+    }
+}
+
+/// A child reference in a trie node: the hash of a node in the proof, or a node under 32 bytes
+/// embedded in its parent.
+enum Child<'a> {
+    Hash(B256),
+    Inline(&'a [u8]),
+}
+
+fn prune_path(nodes: &mut Vec<Bytes>, root: B256, key: B256) {
+    if let Some(path) = follow_path(nodes, root, key) {
+        *nodes = path;
+    }
+}
+
+/// The nodes from `root` down the path of `key`, ending where the path leaves the trie.
+fn follow_path(nodes: &[Bytes], root: B256, key: B256) -> Option<Vec<Bytes>> {
+    let by_hash: HashMap<B256, &Bytes> = nodes.iter().map(|node| (keccak256(node), node)).collect();
+    let key = Nibbles::unpack(key);
+    let mut path = &key[..];
+    let mut kept = Vec::new();
+    let mut next = Child::Hash(root);
+    loop {
+        let node: &[u8] = match next {
+            Child::Hash(hash) => {
+                let node = by_hash.get(&hash)?;
+                kept.push((*node).clone());
+                node
+            }
+            Child::Inline(node) => node,
+        };
+        let items = list_items(node)?;
+        let child = match items.len() {
+            17 => {
+                let Some((&nibble, rest)) = path.split_first() else {
+                    break;
+                };
+                path = rest;
+                items[nibble as usize]
+            }
+            2 => {
+                let (prefix, leaf) = hex_prefix(string_payload(items[0])?)?;
+                if leaf || !path.starts_with(&prefix) {
+                    break;
+                }
+                path = &path[prefix.len()..];
+                items[1]
+            }
+            _ => return None,
+        };
+        next = match child_ref(child)? {
+            Some(child) => child,
+            None => break,
+        };
+    }
+    Some(kept)
+}
+
+/// The encoded items of the RLP list `node`.
+fn list_items(node: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut buf = node;
+    let header = Header::decode(&mut buf).ok()?;
+    if !header.list || buf.len() < header.payload_length {
+        return None;
+    }
+    let mut payload = &buf[..header.payload_length];
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let mut rest = payload;
+        let header = Header::decode(&mut rest).ok()?;
+        let len = payload.len() - rest.len() + header.payload_length;
+        if len > payload.len() {
+            return None;
+        }
+        items.push(&payload[..len]);
+        payload = &payload[len..];
+    }
+    Some(items)
+}
+
+fn string_payload(item: &[u8]) -> Option<&[u8]> {
+    let mut buf = item;
+    let header = Header::decode(&mut buf).ok()?;
+    (!header.list).then(|| &buf[..header.payload_length])
+}
+
+fn child_ref(item: &[u8]) -> Option<Option<Child<'_>>> {
+    let mut buf = item;
+    let header = Header::decode(&mut buf).ok()?;
+    if header.list {
+        return Some(Some(Child::Inline(item)));
+    }
+    match header.payload_length {
+        0 => Some(None),
+        32 => Some(Some(Child::Hash(B256::from_slice(&buf[..32])))),
+        _ => None,
+    }
+}
+
+/// Decode the hex-prefix encoded path of a leaf or extension node, returning its nibbles and
+/// whether the node is a leaf.
+fn hex_prefix(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let (&first, rest) = encoded.split_first()?;
+    let flag = first >> 4;
+    let leaf = flag & 2 != 0;
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 != 0 {
+        nibbles.push(first & 0x0f);
+    }
+    for byte in rest {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    Some((nibbles, leaf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture, process_withdrawal};
+    use alloy::sol_types::SolValue;
+
+    #[test]
+    fn pruning_drops_nodes_off_the_paths() {
+        let input = fixture::withdrawal(64, 64);
+        let mut pruned = input.clone();
+        // Nodes of the other storage proof are off the account's path.
+        let stray = input.account_proof.storage_proof[1].proof.clone();
+        pruned.account_proof.account_proof.extend(stray);
+        prune_proof(&mut pruned.account_proof, input.block_header.state_root);
+        assert_eq!(
+            pruned.account_proof.account_proof,
+            input.account_proof.account_proof
+        );
+        assert_eq!(
+            process_withdrawal(&pruned).unwrap().abi_encode(),
+            process_withdrawal(&input).unwrap().abi_encode()
+        );
+    }
+
+    #[test]
+    fn pruning_keeps_a_proof_it_cannot_follow() {
+        let mut input = fixture::withdrawal(64, 64);
+        let proof = input.account_proof.account_proof.clone();
+        prune_proof(&mut input.account_proof, B256::repeat_byte(1));
+        assert_eq!(input.account_proof.account_proof, proof);
+    }
+
+    #[test]
+    fn compact_input_stores_shared_nodes_once() {
+        let input = fixture::withdrawal(64, 64);
+        let total: usize = input
+            .account_proof
+            .storage_proof
+            .iter()
+            .map(|slot| slot.proof.len())
+            .sum::<usize>()
+            + input.account_proof.account_proof.len();
+        let compact = CompactInput::new(&input);
+        // Both storage proofs start at the pool's storage root.
+        assert!(compact.nodes.len() < total);
+
+        let expanded = compact.expand().unwrap();
+        assert_eq!(expanded.account_proof, input.account_proof);
+        assert_eq!(
+            process_withdrawal(&expanded).unwrap().abi_encode(),
+            process_withdrawal(&input).unwrap().abi_encode()
+        );
+    }
+
+    #[test]
+    fn expanding_rejects_unknown_nodes() {
+        let mut compact = CompactInput::new(&fixture::withdrawal(16, 16));
+        compact.account_proof.push(compact.nodes.len() as u32);
+        assert!(compact.expand().is_err());
+    }
+}
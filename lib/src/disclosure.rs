@@ -0,0 +1,130 @@
+//! Proving which deposit a withdrawal spent, for a holder who chooses to tell an auditor. The
+//! disclosure program checks that the secret behind a nullifier was deposited by a given
+//! transaction, using the block's transaction and receipt tries, and commits to both ends along
+//! with the auditor the disclosure is addressed to.
+
+use crate::{compute_commitment, hash_block_header, redact::Redacted};
+use alloy::{
+    consensus::{Header, ReceiptEnvelope, TxEnvelope, TxReceipt},
+    eips::eip2718::Decodable2718,
+    primitives::{keccak256, Address, Bytes, B256},
+    rlp, sol,
+    sol_types::SolEvent,
+};
+use alloy_trie::{proof::verify_proof, Nibbles};
+use eyre::{ensure, eyre, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+sol! {
+    /// Public values of a disclosure proof.
+    #[derive(Debug)]
+    struct DisclosureData {
+        bytes32 nullifier;
+        bytes32 commitment;
+        address pool;
+        /// Sender of the deposit transaction.
+        address depositor;
+        bytes32 txHash;
+        bytes32 blockHash;
+        uint64 blockNumber;
+        /// Who the holder disclosed to.
+        address auditor;
+    }
+
+    /// Event of pools taking batched deposits.
+    event DepositMany(bytes32[] commitments, uint256 firstLeafIndex);
+}
+
+/// The private inputs for the disclosure proof.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DisclosureInput {
+    pub secret: B256,
+    pub pool: Address,
+    pub auditor: Address,
+    /// Header of the block the deposit transaction is in.
+    pub block_header: Header,
+    pub tx_index: u64,
+    /// The deposit transaction, EIP-2718 encoded.
+    pub transaction: Bytes,
+    pub transaction_proof: Vec<Bytes>,
+    /// Its receipt, EIP-2718 encoded.
+    pub receipt: Bytes,
+    pub receipt_proof: Vec<Bytes>,
+}
+
+impl fmt::Debug for DisclosureInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DisclosureInput")
+            .field("secret", &Redacted(self.secret))
+            .field("pool", &self.pool)
+            .field("auditor", &self.auditor)
+            .field("block_header", &self.block_header)
+            .field("tx_index", &self.tx_index)
+            .field("transaction", &self.transaction)
+            .field("transaction_proof", &self.transaction_proof)
+            .field("receipt", &self.receipt)
+            .field("receipt_proof", &self.receipt_proof)
+            .finish()
+    }
+}
+
+/// Verify a proof of `value` at `index` in a transaction or receipt trie, whose keys are the
+/// RLP-encoded indices rather than their hashes.
+fn verify_ordered_proof(root: &B256, index: u64, value: &[u8], proof: &[Bytes]) -> Result<()> {
+    let key = Nibbles::unpack(rlp::encode(index));
+    verify_proof(*root, key, Some(value.to_vec()), proof).map_err(|_| eyre!("invalid proof"))
+}
+
+/// Process a disclosure, fully verifying it and returning public data.
+pub fn process_disclosure(input: &DisclosureInput) -> Result<DisclosureData> {
+    let (commitment, nullifier) = compute_commitment(&input.secret);
+    let header = &input.block_header;
+    let block = hash_block_header(header)?;
+
+    verify_ordered_proof(
+        &header.transactions_root,
+        input.tx_index,
+        &input.transaction,
+        &input.transaction_proof,
+    )?;
+    verify_ordered_proof(
+        &header.receipts_root,
+        input.tx_index,
+        &input.receipt,
+        &input.receipt_proof,
+    )?;
+
+    let transaction = TxEnvelope::decode_2718(&mut input.transaction.as_ref())?;
+    let depositor = transaction
+        .recover_signer()
+        .map_err(|_| eyre!("invalid transaction signature"))?;
+    let receipt = ReceiptEnvelope::decode_2718(&mut input.receipt.as_ref())?;
+    ensure!(receipt.status(), "deposit transaction reverted");
+
+    let deposit = keccak256("Deposit(bytes32,uint256)");
+    receipt
+        .logs()
+        .iter()
+        .filter(|log| log.address == input.pool)
+        .find(|log| match log.topics().first() {
+            Some(topic) if *topic == deposit => log.topics().get(1) == Some(&commitment),
+            Some(topic) if *topic == DepositMany::SIGNATURE_HASH => {
+                DepositMany::decode_log_data(&log.data, true)
+                    .is_ok_and(|event| event.commitments.contains(&commitment))
+            }
+            _ => false,
+        })
+        .ok_or_eyre("transaction did not deposit the commitment")?;
+
+    Ok(DisclosureData {
+        nullifier,
+        commitment,
+        pool: input.pool,
+        depositor,
+        txHash: keccak256(&input.transaction),
+        blockHash: block.hash,
+        blockNumber: block.number,
+        auditor: input.auditor,
+    })
+}
@@ -0,0 +1,171 @@
+//! Synthetic withdrawals with proofs from tries built in memory, for benchmarks and
+//! `pool cycles-check`. The same arguments always give the same input.
+
+use crate::{
+    compute_commitment, compute_storage_keys, fork::Fork, Variant, WithdrawalData, WithdrawalInput,
+};
+use alloy::{
+    consensus::Header,
+    primitives::{keccak256, Address, Bytes, B256, U256},
+    rlp,
+    rpc::types::{EIP1186AccountProofResponse, EIP1186StorageProof},
+    serde::JsonStorageKey,
+};
+use alloy_trie::{
+    proof::ProofRetainer, HashBuilder, Nibbles, TrieAccount, EMPTY_ROOT_HASH, KECCAK_EMPTY,
+};
+
+/// Address of the fixture pool.
+pub const POOL: Address = Address::repeat_byte(0x42);
+
+/// Secret of the fixture deposit at leaf `i`.
+pub fn secret(i: u64) -> B256 {
+    keccak256(i.to_be_bytes())
+}
+
+/// Root of a trie holding `leaves`, keyed by the keccak256 of their keys, and the proofs of
+/// `targets`.
+pub(crate) fn trie(mut leaves: Vec<(B256, Vec<u8>)>, targets: &[B256]) -> (B256, Vec<Vec<Bytes>>) {
+    leaves.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let targets: Vec<Nibbles> = targets.iter().map(|key| Nibbles::unpack(key)).collect();
+    let mut builder =
+        HashBuilder::default().with_proof_retainer(ProofRetainer::new(targets.clone()));
+    for (key, value) in &leaves {
+        builder.add_leaf(Nibbles::unpack(key), value);
+    }
+    let root = builder.root();
+    let nodes = builder.take_proof_nodes();
+    let proofs = targets
+        .iter()
+        .map(|target| {
+            nodes
+                .matching_nodes_sorted(target)
+                .into_iter()
+                .map(|(_, node)| node)
+                .collect()
+        })
+        .collect();
+    (root, proofs)
+}
+
+/// Header of block `number` with `state_root`, carrying the fields of every fork up to `fork`.
+/// Fields other than the state root and number hold arbitrary values.
+pub fn header(fork: Fork, state_root: B256, number: u64) -> Header {
+    let mut header = Header {
+        state_root,
+        number,
+        ..Default::default()
+    };
+    if fork >= Fork::London {
+        header.base_fee_per_gas = Some(7);
+    }
+    if fork >= Fork::Shanghai {
+        header.withdrawals_root = Some(EMPTY_ROOT_HASH);
+    }
+    if fork >= Fork::Cancun {
+        header.blob_gas_used = Some(0);
+        header.excess_blob_gas = Some(0);
+        header.parent_beacon_block_root = Some(B256::repeat_byte(0xbe));
+    }
+    if fork >= Fork::Prague {
+        header.requests_hash = Some(B256::repeat_byte(0x7e));
+    }
+    header
+}
+
+/// A withdrawal of the deposit in the middle of a pool of `deposits` deposits, in a state trie
+/// with `accounts` other accounts, at a block without the header fields of London and later.
+pub fn withdrawal(deposits: u64, accounts: u64) -> WithdrawalInput {
+    withdrawal_at(Fork::Frontier, deposits, accounts)
+}
+
+/// [`withdrawal`] at a block of a chain that has activated `fork`.
+pub fn withdrawal_at(fork: Fork, deposits: u64, accounts: u64) -> WithdrawalInput {
+    let index = deposits / 2;
+    let (length_key, index_key) = compute_storage_keys(U256::ZERO, U256::from(index));
+    let mut storage = vec![(keccak256(length_key), rlp::encode(U256::from(deposits)))];
+    let base = U256::from_be_bytes(keccak256(length_key).0);
+    for i in 0..deposits {
+        let key = B256::from(base + U256::from(i));
+        let (commitment, _) = compute_commitment(&secret(i));
+        storage.push((keccak256(key), rlp::encode(commitment)));
+    }
+    let (storage_root, storage_proofs) =
+        trie(storage, &[keccak256(length_key), keccak256(index_key)]);
+
+    let account = TrieAccount {
+        nonce: 1,
+        balance: U256::ZERO,
+        storage_root,
+        code_hash: KECCAK_EMPTY,
+    };
+    let mut accounts = vec![(keccak256(POOL), rlp::encode(&account))];
+    for i in 0..accounts {
+        let filler = TrieAccount {
+            nonce: i,
+            ..TrieAccount::default()
+        };
+        accounts.push((keccak256((i + 1).to_le_bytes()), rlp::encode(&filler)));
+    }
+    let (state_root, account_proofs) = trie(accounts, &[keccak256(POOL)]);
+
+    let (commitment, _) = compute_commitment(&secret(index));
+    let [length_proof, index_proof] = <[Vec<Bytes>; 2]>::try_from(storage_proofs).unwrap();
+    WithdrawalInput {
+        secret: secret(index),
+        array_index: U256::from(index),
+        account_proof: EIP1186AccountProofResponse {
+            address: POOL,
+            balance: account.balance,
+            code_hash: account.code_hash,
+            nonce: account.nonce,
+            storage_hash: storage_root,
+            account_proof: account_proofs.into_iter().next().unwrap(),
+            storage_proof: vec![
+                EIP1186StorageProof {
+                    key: JsonStorageKey::from(length_key),
+                    value: U256::from(deposits),
+                    proof: length_proof,
+                },
+                EIP1186StorageProof {
+                    key: JsonStorageKey::from(index_key),
+                    value: U256::from_be_bytes(commitment.0),
+                    proof: index_proof,
+                },
+            ],
+        },
+        block_header: header(fork, state_root, 1),
+        inclusion_set_branches: None,
+        contract_address: POOL,
+        array_slot: U256::ZERO,
+        relayer_fee: U256::ZERO,
+        recipient: Address::repeat_byte(1),
+        relayer: Address::ZERO,
+        nft: None,
+        protocol_fee: false,
+        bridge: None,
+        registry: None,
+        oracle: None,
+        split: None,
+        token_fee: None,
+        association: None,
+    }
+}
+
+/// Public values of a base withdrawal from the fixture pool at block 16, paying a relayer fee of
+/// 1000 wei, for tests of what checks them.
+pub fn withdrawal_data() -> WithdrawalData {
+    WithdrawalData {
+        variant: Variant::Base as u8,
+        nullifier: B256::with_last_byte(1),
+        blockHash: B256::with_last_byte(2),
+        exclusionSetRoot: B256::ZERO,
+        relayerFee: U256::from(1000),
+        recipient: Address::with_last_byte(1),
+        relayer: Address::with_last_byte(2),
+        contractAddress: POOL,
+        blockNumber: 16,
+        feeToken: Address::ZERO,
+        feeTokenAmount: U256::ZERO,
+    }
+}
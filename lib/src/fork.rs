@@ -0,0 +1,148 @@
+//! Block headers of chains at different fork levels. Each fork from London on appends fields to
+//! the header, so a chain's headers carry the fields of every fork it has activated and none of
+//! the later ones. [`Fork::of`] checks a header is shaped like that before it is hashed: one
+//! that is not, like a header with a beacon root but no withdrawals root, has no block hash on
+//! any chain, and hashing it anyway would commit to a block that does not exist.
+
+use alloy::consensus::Header;
+use eyre::{ensure, Result};
+use std::fmt;
+
+/// The latest fork whose header fields a header has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fork {
+    /// Before London: no base fee.
+    Frontier,
+    /// Adds `base_fee_per_gas`.
+    London,
+    /// Adds `withdrawals_root`.
+    Shanghai,
+    /// Adds `blob_gas_used`, `excess_blob_gas` and `parent_beacon_block_root`.
+    Cancun,
+    /// Adds `requests_hash`.
+    Prague,
+}
+
+impl Fork {
+    pub const ALL: [Self; 5] = [
+        Self::Frontier,
+        Self::London,
+        Self::Shanghai,
+        Self::Cancun,
+        Self::Prague,
+    ];
+
+    /// The fork of `header`, failing if it has the fields of a fork without those of every fork
+    /// before it, or only some of a fork's fields.
+    pub fn of(header: &Header) -> Result<Self> {
+        let cancun = [
+            header.blob_gas_used.is_some(),
+            header.excess_blob_gas.is_some(),
+            header.parent_beacon_block_root.is_some(),
+        ];
+        ensure!(
+            cancun.iter().all(|present| *present == cancun[0]),
+            "header has only some of the Cancun fields"
+        );
+        let present = [
+            (Self::London, header.base_fee_per_gas.is_some()),
+            (Self::Shanghai, header.withdrawals_root.is_some()),
+            (Self::Cancun, cancun[0]),
+            (Self::Prague, header.requests_hash.is_some()),
+        ];
+        let mut fork = Self::Frontier;
+        for (next, has_fields) in present {
+            if !has_fields {
+                continue;
+            }
+            let expected = fork.next().expect("a fork follows every one before Prague");
+            ensure!(
+                next == expected,
+                "header has the {next} fields without the {expected} ones"
+            );
+            fork = next;
+        }
+        Ok(fork)
+    }
+
+    fn next(self) -> Option<Self> {
+        Self::ALL.get(self as usize + 1).copied()
+    }
+}
+
+impl fmt::Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Frontier => "Frontier",
+            Self::London => "London",
+            Self::Shanghai => "Shanghai",
+            Self::Cancun => "Cancun",
+            Self::Prague => "Prague",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use alloy::{
+        consensus::EMPTY_OMMER_ROOT_HASH,
+        primitives::{b256, bytes, Address, Bloom, B64, U256},
+    };
+    use alloy_trie::EMPTY_ROOT_HASH;
+
+    /// The mainnet genesis header.
+    fn mainnet_genesis() -> Header {
+        Header {
+            parent_hash: B256::ZERO,
+            ommers_hash: EMPTY_OMMER_ROOT_HASH,
+            beneficiary: Address::ZERO,
+            state_root: b256!("d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544"),
+            transactions_root: EMPTY_ROOT_HASH,
+            receipts_root: EMPTY_ROOT_HASH,
+            logs_bloom: Bloom::ZERO,
+            difficulty: U256::from(0x400000000u64),
+            number: 0,
+            gas_limit: 5000,
+            gas_used: 0,
+            timestamp: 0,
+            extra_data: bytes!("11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa"),
+            mix_hash: B256::ZERO,
+            nonce: B64::with_last_byte(0x42),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mainnet_genesis_hash() {
+        let header = mainnet_genesis();
+        assert_eq!(Fork::of(&header).unwrap(), Fork::Frontier);
+        assert_eq!(
+            header.hash_slow(),
+            b256!("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3")
+        );
+    }
+
+    #[test]
+    fn fork_of_fixture_headers() {
+        for fork in Fork::ALL {
+            let header = fixture::header(fork, B256::ZERO, 1);
+            assert_eq!(Fork::of(&header).unwrap(), fork);
+        }
+    }
+
+    #[test]
+    fn rejects_skipped_fork_fields() {
+        let mut header = fixture::header(Fork::Cancun, B256::ZERO, 1);
+        header.withdrawals_root = None;
+        assert!(Fork::of(&header).is_err());
+    }
+
+    #[test]
+    fn rejects_partial_cancun_fields() {
+        let mut header = fixture::header(Fork::Cancun, B256::ZERO, 1);
+        header.excess_blob_gas = None;
+        assert!(Fork::of(&header).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{decode_any_public_values, WithdrawalData};
+use alloy::{
+    primitives::{keccak256, Address, PrimitiveSignature, B256, U256},
+    sol,
+    sol_types::{eip712_domain, Eip712Domain, SolStruct},
+};
+use eyre::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+sol! {
+    /// Terms under which a withdrawal may be relayed, signed by the withdrawer. The digest fixes
+    /// everything but the relayer and its fee in ETH, which any of `relayers`, or any relayer if
+    /// empty, may fill in up to `maxRelayerFee`.
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct WithdrawalIntent {
+        bytes32 publicValuesDigest;
+        uint256 maxRelayerFee;
+        uint64 deadline;
+        address[] relayers;
+    }
+
+    /// A relayer's offer to relay withdrawals from a pool for `fee` until `expiry`. The fee is
+    /// in wei, or in the smallest unit of `feeToken` unless that is zero.
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct FeeQuote {
+        address relayer;
+        uint256 fee;
+        uint64 expiry;
+        address feeToken;
+    }
+
+    /// A relayer's acceptance of a withdrawal intent.
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct RelayAcceptance {
+        bytes32 intentHash;
+        address relayer;
+    }
+}
+
+/// A withdrawal intent with the withdrawer's signature and, once accepted, the relayer's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedIntent {
+    pub intent: WithdrawalIntent,
+    pub chain_id: u64,
+    pub pool: Address,
+    pub signature: PrimitiveSignature,
+    pub relayer_signature: Option<PrimitiveSignature>,
+}
+
+/// EIP-712 domain for intents against the pool at `pool` on chain `chain_id`.
+pub fn intent_domain(chain_id: u64, pool: Address) -> Eip712Domain {
+    eip712_domain! {
+        name: "Pool",
+        version: "1",
+        chain_id: chain_id,
+        verifying_contract: pool,
+    }
+}
+
+/// Words of the `relayerFee` and `relayer` of a [`WithdrawalData`], which every variant's public
+/// values start with.
+const RELAYER_TERM_WORDS: [usize; 2] = [4, 6];
+
+/// Hash of the public values the withdrawal program commits, of any variant, with the relayer
+/// and relayer fee zeroed so an intent leaves them open.
+pub fn public_values_digest(public_values: &[u8]) -> Result<B256> {
+    decode_any_public_values(public_values)?;
+    let mut terms = public_values.to_vec();
+    for word in RELAYER_TERM_WORDS {
+        terms[word * 32..(word + 1) * 32].fill(0);
+    }
+    Ok(keccak256(terms))
+}
+
+impl WithdrawalIntent {
+    /// Build an intent for the withdrawal committing `public_values` that allows any of
+    /// `relayers` to relay it for at most `max_relayer_fee` until `deadline`.
+    pub fn new(
+        public_values: &[u8],
+        max_relayer_fee: U256,
+        deadline: u64,
+        relayers: Vec<Address>,
+    ) -> Result<Self> {
+        Ok(Self {
+            publicValuesDigest: public_values_digest(public_values)?,
+            maxRelayerFee: max_relayer_fee,
+            deadline,
+            relayers,
+        })
+    }
+
+    /// Check that the withdrawal committing `public_values` satisfies the terms of this intent at
+    /// time `now`.
+    pub fn check(&self, public_values: &[u8], now: u64) -> Result<()> {
+        ensure!(
+            public_values_digest(public_values)? == self.publicValuesDigest,
+            "public values do not match intent"
+        );
+        let data = decode_any_public_values(public_values)?.withdrawal;
+        ensure!(now <= self.deadline, "intent expired");
+        ensure!(
+            data.relayerFee <= self.maxRelayerFee,
+            "relayer fee exceeds intent maximum"
+        );
+        ensure!(
+            self.relayers.is_empty() || self.relayers.contains(&data.relayer),
+            "relayer not allowed by intent"
+        );
+        Ok(())
+    }
+}
+
+/// Recover the signer of an EIP-712 typed struct.
+pub fn recover_typed_signer<T: SolStruct>(
+    value: &T,
+    domain: &Eip712Domain,
+    signature: &PrimitiveSignature,
+) -> Result<Address> {
+    let hash = value.eip712_signing_hash(domain);
+    signature
+        .recover_address_from_prehash(&hash)
+        .map_err(|_| eyre::eyre!("invalid signature"))
+}
+
+impl FeeQuote {
+    /// Check that `data` takes this quote at time `now`.
+    pub fn check(&self, data: &WithdrawalData, now: u64) -> Result<()> {
+        ensure!(now <= self.expiry, "quote expired");
+        ensure!(data.relayer == self.relayer, "relayer does not match quote");
+        ensure!(
+            data.feeToken == self.feeToken,
+            "fee token does not match quote"
+        );
+        let fee = if self.feeToken.is_zero() {
+            data.relayerFee
+        } else {
+            data.feeTokenAmount
+        };
+        ensure!(fee == self.fee, "relayer fee does not match quote");
+        Ok(())
+    }
+}
+
+impl SignedIntent {
+    /// The EIP-712 domain this intent was signed under.
+    pub fn domain(&self) -> Eip712Domain {
+        intent_domain(self.chain_id, self.pool)
+    }
+
+    /// Recover the withdrawer that signed the intent.
+    pub fn signer(&self) -> Result<Address> {
+        recover_typed_signer(&self.intent, &self.domain(), &self.signature)
+    }
+
+    /// The acceptance message a relayer countersigns.
+    pub fn acceptance(&self, relayer: Address) -> RelayAcceptance {
+        RelayAcceptance {
+            intentHash: self.intent.eip712_signing_hash(&self.domain()),
+            relayer,
+        }
+    }
+
+    /// Verify that `relayer` countersigned the intent.
+    pub fn verify_relayer(&self, relayer: Address) -> Result<()> {
+        let signature = self
+            .relayer_signature
+            .ok_or_else(|| eyre::eyre!("intent not countersigned"))?;
+        let signer = recover_typed_signer(&self.acceptance(relayer), &self.domain(), &signature)?;
+        ensure!(signer == relayer, "invalid relayer signature");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture::withdrawal_data, FeeWithdrawalData, Variant};
+    use alloy::sol_types::SolValue;
+
+    fn public_values(data: &WithdrawalData) -> Vec<u8> {
+        data.abi_encode()
+    }
+
+    fn intent(max_relayer_fee: u64, relayers: Vec<Address>) -> WithdrawalIntent {
+        let public_values = public_values(&withdrawal_data());
+        WithdrawalIntent::new(&public_values, U256::from(max_relayer_fee), 50, relayers).unwrap()
+    }
+
+    #[test]
+    fn intent_accepts_its_withdrawal() {
+        let data = withdrawal_data();
+        intent(1000, vec![])
+            .check(&public_values(&data), 50)
+            .unwrap();
+        intent(2000, vec![data.relayer])
+            .check(&public_values(&data), 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn intent_leaves_the_relayer_terms_open() {
+        // Any allowed relayer may relay for up to the maximum fee, proving with its own terms.
+        let other = WithdrawalData {
+            relayer: Address::with_last_byte(8),
+            relayerFee: U256::from(500),
+            ..withdrawal_data()
+        };
+        intent(1000, vec![])
+            .check(&public_values(&other), 0)
+            .unwrap();
+        let relayers = vec![withdrawal_data().relayer, other.relayer];
+        intent(1000, relayers)
+            .check(&public_values(&other), 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn intent_rejects_other_public_values() {
+        let mut other = withdrawal_data();
+        other.recipient = Address::with_last_byte(9);
+        assert!(intent(1000, vec![])
+            .check(&public_values(&other), 0)
+            .is_err());
+        let mut other = withdrawal_data();
+        other.feeTokenAmount = U256::from(1);
+        assert!(intent(1000, vec![])
+            .check(&public_values(&other), 0)
+            .is_err());
+    }
+
+    #[test]
+    fn intent_covers_the_whole_public_values() {
+        let fee = |protocol_fee: u64| {
+            FeeWithdrawalData {
+                withdrawal: WithdrawalData {
+                    variant: Variant::ProtocolFee as u8,
+                    ..withdrawal_data()
+                },
+                protocolFee: U256::from(protocol_fee),
+            }
+            .abi_encode()
+        };
+        let intent = WithdrawalIntent::new(&fee(10), U256::from(1000), 50, vec![]).unwrap();
+        intent.check(&fee(10), 0).unwrap();
+        assert!(intent.check(&fee(11), 0).is_err());
+    }
+
+    #[test]
+    fn intent_rejects_expired() {
+        let data = public_values(&withdrawal_data());
+        assert!(intent(1000, vec![]).check(&data, 51).is_err());
+    }
+
+    #[test]
+    fn intent_rejects_fee_over_maximum() {
+        let data = public_values(&withdrawal_data());
+        assert!(intent(999, vec![]).check(&data, 0).is_err());
+    }
+
+    #[test]
+    fn intent_rejects_other_relayers() {
+        let data = public_values(&withdrawal_data());
+        assert!(intent(1000, vec![Address::with_last_byte(8)])
+            .check(&data, 0)
+            .is_err());
+    }
+
+    fn quote() -> FeeQuote {
+        let data = withdrawal_data();
+        FeeQuote {
+            relayer: data.relayer,
+            fee: data.relayerFee,
+            expiry: 50,
+            feeToken: Address::ZERO,
+        }
+    }
+
+    #[test]
+    fn quote_accepts_matching_withdrawal() {
+        quote().check(&withdrawal_data(), 50).unwrap();
+    }
+
+    #[test]
+    fn quote_rejects_mismatches() {
+        assert!(quote().check(&withdrawal_data(), 51).is_err());
+
+        let mut other = withdrawal_data();
+        other.relayer = Address::with_last_byte(8);
+        assert!(quote().check(&other, 0).is_err());
+
+        let mut other = withdrawal_data();
+        other.relayerFee = U256::from(999);
+        assert!(quote().check(&other, 0).is_err());
+    }
+
+    #[test]
+    fn quote_in_token_checks_token_amount() {
+        let token = Address::with_last_byte(7);
+        let quote = FeeQuote {
+            feeToken: token,
+            ..quote()
+        };
+        // A fee in ETH does not take a quote in a token.
+        assert!(quote.check(&withdrawal_data(), 0).is_err());
+
+        let mut data = withdrawal_data();
+        data.relayerFee = U256::ZERO;
+        data.feeToken = token;
+        data.feeTokenAmount = U256::from(1000);
+        quote.check(&data, 0).unwrap();
+        data.feeTokenAmount = U256::from(1001);
+        assert!(quote.check(&data, 0).is_err());
+    }
+}
@@ -1,86 +1,318 @@
+pub mod association;
+pub mod bridge;
+pub mod commit_reveal;
+pub mod compact;
+pub mod disclosure;
+pub mod fixture;
+pub mod fork;
+pub mod intent;
+pub mod nft;
+pub mod oracle;
+pub mod protocol_fee;
+pub mod registry;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod split;
+pub mod token_fee;
+
+pub use pool_types::{
+    compute_bridge_message_hash, compute_commitment, compute_distribution_hash,
+    compute_nft_commitment, decode_any_public_values, decode_public_values, encode_public_values,
+    note, redact, view, watch, AggregateData, AssociationWithdrawalData, BridgeWithdrawalData,
+    FeeWithdrawalData, NftWithdrawalData, OracleWithdrawalData, PublicValues,
+    RegistryWithdrawalData, SplitWithdrawalData, Variant, WithdrawalData,
+};
+
 use alloy::{
     consensus::Header,
     primitives::{keccak256, Address, Bytes, B256, U256},
     rlp,
     rpc::types::{BlockNumHash, EIP1186AccountProofResponse},
-    sol,
+    sol_types::SolValue,
 };
 use alloy_trie::{proof::verify_proof, Nibbles, TrieAccount};
+use association::SignedRoot;
+use bridge::BridgeMessage;
 use eyre::{ensure, Result};
+use fork::Fork;
+use nft::NftToken;
+use oracle::OracleAnchor;
+use protocol_fee::verify_fee_slot;
+use redact::Redacted;
+use registry::RegistryEntry;
 use serde::{Deserialize, Serialize};
+use split::Distribution;
+use std::fmt;
+use token_fee::TokenFee;
 
-sol! {
-    #[derive(Debug)]
-    struct WithdrawalData {
-        bytes32 nullifier;
-        bytes32 blockHash;
-        bytes32 exclusionSetRoot;
-        uint256 relayerFee;
-        address recipient;
-        address relayer;
-        address contractAddress;
-        uint64 blockNumber;
-    }
+/// Evaluate `$body`, counting its cycles as `$name` in SP1's execution report when run in the
+/// guest.
+macro_rules! region {
+    ($name:literal, $body:expr) => {{
+        #[cfg(target_os = "zkvm")]
+        println!("cycle-tracker-report-start: {}", $name);
+        let value = $body;
+        #[cfg(target_os = "zkvm")]
+        println!("cycle-tracker-report-end: {}", $name);
+        value
+    }};
 }
 
 /// Inclusion branches and an index for proving that a commitment is in an array of commitments.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InclusionBranches {
     pub index: u32,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::bytes32_array"))]
     pub proof: Vec<B256>,
 }
 
 /// The private inputs for the withdrawal proof.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WithdrawalInput {
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::bytes32"))]
     pub secret: B256,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::uint256"))]
     pub array_index: U256,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::account_proof"))]
     pub account_proof: EIP1186AccountProofResponse,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::header"))]
     pub block_header: Header,
     pub inclusion_set_branches: Option<InclusionBranches>,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::address"))]
     pub contract_address: Address,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::uint256"))]
     pub array_slot: U256,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::uint256"))]
     pub relayer_fee: U256,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::address"))]
     pub recipient: Address,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "schema::address"))]
     pub relayer: Address,
+    /// The token the note deposited, for withdrawals from ERC-721 pools.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nft: Option<NftToken>,
+    /// Whether the pool has a protocol fee its owner can change, at
+    /// [`PROTOCOL_FEE_SLOT`](protocol_fee::PROTOCOL_FEE_SLOT).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protocol_fee: bool,
+    /// The bridge deposit to make, for withdrawals to an L2 address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge: Option<BridgeMessage>,
+    /// The registry entry of the pool, for withdrawals that commit to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<RegistryEntry>,
+    /// The anchor block and oracle, for withdrawals proven against a block older than
+    /// `BLOCKHASH` reaches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle: Option<OracleAnchor>,
+    /// The recipients and their amounts, for withdrawals split between several recipients.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<Distribution>,
+    /// The token fee, for withdrawals paying the relayer in an ERC-20 token instead of ETH.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_fee: Option<TokenFee>,
+    /// The signed root of the association set `inclusion_set_branches` prove the deposit is in,
+    /// for withdrawals that commit to the provider that signed it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "schema::optional_signed_root")
+    )]
+    pub association: Option<SignedRoot>,
 }
 
-/// Compute commitment and nullifier from secret.
-pub fn compute_commitment(secret: &B256) -> (B256, B256) {
-    let u256 = U256::from_be_slice(&secret.0);
-    let commitment = keccak256(u256.to_be_bytes::<32>());
-    let nullifier = keccak256(u256.wrapping_add(U256::from(1)).to_be_bytes::<32>());
-    (commitment, nullifier)
+impl fmt::Debug for WithdrawalInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithdrawalInput")
+            .field("secret", &Redacted(self.secret))
+            .field("array_index", &self.array_index)
+            .field("account_proof", &self.account_proof)
+            .field("block_header", &self.block_header)
+            .field("inclusion_set_branches", &self.inclusion_set_branches)
+            .field("contract_address", &self.contract_address)
+            .field("array_slot", &self.array_slot)
+            .field("relayer_fee", &self.relayer_fee)
+            .field("recipient", &self.recipient)
+            .field("relayer", &self.relayer)
+            .field("nft", &self.nft)
+            .field("protocol_fee", &self.protocol_fee)
+            .field("bridge", &self.bridge)
+            .field("registry", &self.registry)
+            .field("oracle", &self.oracle)
+            .field("split", &self.split)
+            .field("token_fee", &self.token_fee)
+            .field("association", &self.association)
+            .finish()
+    }
+}
+
+/// keccak256 of `left` and `right` concatenated, the parent of two Merkle tree nodes.
+fn hash_pair(left: &B256, right: &B256) -> B256 {
+    let mut input = [0u8; 64];
+    input[..32].copy_from_slice(&left.0);
+    input[32..].copy_from_slice(&right.0);
+    keccak256(input)
 }
 
 /// Compute inclusion set root from commitment, index, and branches.
-pub fn compute_inclusion_root(commitment: B256, proof: InclusionBranches) -> B256 {
+pub fn compute_inclusion_root(commitment: &B256, proof: &InclusionBranches) -> B256 {
     let bits = proof.index;
 
-    let mut root = commitment;
+    let mut root = *commitment;
     for (i, hash) in proof.proof.iter().enumerate() {
-        if bits & (1 << i) == 0 {
-            let mut input = [0u8; 64];
-            input[..32].copy_from_slice(&root.0);
-            input[32..].copy_from_slice(&hash.0);
-            root = keccak256(input);
+        root = if bits & (1 << i) == 0 {
+            hash_pair(&root, hash)
         } else {
-            let mut input = [0u8; 64];
-            input[..32].copy_from_slice(&hash.0);
-            input[32..].copy_from_slice(&root.0);
-            root = keccak256(input);
-        }
+            hash_pair(hash, &root)
+        };
     }
 
     root
 }
 
-/// Hash block header.
-pub fn hash_block_header(header: &Header) -> BlockNumHash {
-    header.num_hash_slow()
+/// `leaves` padded with zero leaves to the next power of two, as the bottom level of a tree.
+fn padded_level(leaves: &[B256]) -> Vec<B256> {
+    let len = leaves.len().next_power_of_two();
+    let mut level = Vec::with_capacity(len);
+    level.extend_from_slice(leaves);
+    level.resize(len, B256::ZERO);
+    level
+}
+
+/// Pairs a level must have before the `parallel` feature hashes it on several threads.
+#[cfg(feature = "parallel")]
+const PARALLEL_PAIRS: usize = 1 << 12;
+
+/// Replace the first half of `level` with the parents of its pairs, returning the new length.
+fn hash_level(level: &mut [B256]) -> usize {
+    let len = level.len() / 2;
+    #[cfg(feature = "parallel")]
+    if len >= PARALLEL_PAIRS {
+        use rayon::prelude::*;
+        // Parents are hashed into a new buffer, since a parent overwrites a node another thread
+        // may still be reading.
+        let parents: Vec<B256> = level
+            .par_chunks(2)
+            .with_min_len(PARALLEL_PAIRS / 4)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        level[..len].copy_from_slice(&parents);
+        return len;
+    }
+    for i in 0..len {
+        level[i] = hash_pair(&level[2 * i], &level[2 * i + 1]);
+    }
+    len
+}
+
+/// Compute the Merkle root of `leaves`, padded with zero leaves to the next power of two. Branches
+/// for [`compute_inclusion_root`] against this root are built the same way.
+pub fn compute_merkle_root(leaves: &[B256]) -> B256 {
+    if leaves.is_empty() {
+        return B256::ZERO;
+    }
+
+    let mut level = padded_level(leaves);
+    let mut len = level.len();
+    while len > 1 {
+        len = hash_level(&mut level[..len]);
+    }
+
+    level[0]
+}
+
+/// Compute the branches proving `leaves[index]` against [`compute_merkle_root`] of `leaves`.
+pub fn compute_merkle_branch(leaves: &[B256], index: usize) -> InclusionBranches {
+    let mut level = padded_level(leaves);
+    let mut len = level.len();
+
+    let mut proof = Vec::with_capacity(len.trailing_zeros() as usize);
+    let mut position = index;
+    while len > 1 {
+        proof.push(level[position ^ 1]);
+        len = hash_level(&mut level[..len]);
+        position /= 2;
+    }
+
+    InclusionBranches {
+        index: index as u32,
+        proof,
+    }
+}
+
+/// The right edge of a [`compute_merkle_root`] tree, which takes leaves one at a time and gives the
+/// root of those so far without rehashing them: pushing `n` leaves hashes `n` pairs in all, and
+/// each root hashes one pair per level.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleFrontier {
+    /// At each level, the root of the last complete subtree whose right sibling is not yet.
+    branch: Vec<B256>,
+    len: u64,
+}
+
+impl MerkleFrontier {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, leaf: B256) {
+        self.len += 1;
+        let mut node = leaf;
+        let mut size = self.len;
+        let mut level = 0;
+        while size & 1 == 0 {
+            node = hash_pair(&self.branch[level], &node);
+            size >>= 1;
+            level += 1;
+        }
+        if level == self.branch.len() {
+            self.branch.push(node);
+        } else {
+            self.branch[level] = node;
+        }
+    }
+
+    /// [`compute_merkle_root`] of the leaves pushed so far.
+    pub fn root(&self) -> B256 {
+        if self.len == 0 {
+            return B256::ZERO;
+        }
+        let depth = self.len.next_power_of_two().trailing_zeros() as usize;
+        if self.len.is_power_of_two() {
+            return self.branch[depth];
+        }
+        // Climb from the zero padding after the last leaf, pairing with the complete subtrees
+        // to the left and with zero subtrees to the right.
+        let mut node = B256::ZERO;
+        let mut zero = B256::ZERO;
+        for level in 0..depth {
+            node = if (self.len >> level) & 1 == 1 {
+                hash_pair(&self.branch[level], &node)
+            } else {
+                hash_pair(&node, &zero)
+            };
+            zero = hash_pair(&zero, &zero);
+        }
+        node
+    }
+}
+
+/// Hash block header, after checking it has the fields of a chain at some fork level.
+pub fn hash_block_header(header: &Header) -> Result<BlockNumHash> {
+    Fork::of(header)?;
+    Ok(header.num_hash_slow())
 }
 
 /// Verify the commitment is in array[array_index] where array is stored in array_slot in contract_address.
+/// For ERC-721 pools, also verify the pool escrows `nft`, and for pools with a protocol fee, the
+/// value of the fee.
+#[allow(clippy::too_many_arguments)]
 pub fn verify_storage_slot(
     contract_address: &Address,
     array_slot: &U256,
@@ -88,6 +320,8 @@ pub fn verify_storage_slot(
     array_index: &U256,
     state_root: &B256,
     proof: &EIP1186AccountProofResponse,
+    nft: Option<&NftToken>,
+    protocol_fee: bool,
 ) -> Result<()> {
     // Verify contract address
     ensure!(
@@ -96,16 +330,13 @@ pub fn verify_storage_slot(
     );
 
     // Verify account proof from state_root
-    let account = TrieAccount {
-        nonce: proof.nonce,
-        balance: proof.balance,
-        code_hash: proof.code_hash,
-        storage_root: proof.storage_hash,
-    };
-    verify_mpt_proof(state_root, proof.address, account, &proof.account_proof)?;
+    verify_account_proof(state_root, proof)?;
 
     // Verify storage proofs
-    ensure!(proof.storage_proof.len() == 2, "invalid storage proof");
+    ensure!(
+        proof.storage_proof.len() == 2 + nft.is_some() as usize + protocol_fee as usize,
+        "invalid storage proof"
+    );
 
     // First storage proof: len of array, key is array_slot
     let array_len_proof = proof.storage_proof.first().unwrap();
@@ -131,9 +362,30 @@ pub fn verify_storage_slot(
         &commitment_proof.proof,
     )?;
 
+    // Third storage proof: escrowed[collection][token_id] is set
+    if let Some(nft) = nft {
+        nft.verify_escrow(&proof.storage_hash, proof.storage_proof.get(2).unwrap())?;
+    }
+
+    // Last storage proof: the protocol fee
+    if protocol_fee {
+        verify_fee_slot(&proof.storage_hash, proof.storage_proof.last().unwrap())?;
+    }
+
     Ok(())
 }
 
+/// Verify the account of `proof`, with its storage root, is in the state under `state_root`.
+pub fn verify_account_proof(state_root: &B256, proof: &EIP1186AccountProofResponse) -> Result<()> {
+    let account = TrieAccount {
+        nonce: proof.nonce,
+        balance: proof.balance,
+        code_hash: proof.code_hash,
+        storage_root: proof.storage_hash,
+    };
+    verify_mpt_proof(state_root, proof.address, account, &proof.account_proof)
+}
+
 /// Verify a Merkle Patricia Trie proof.
 pub fn verify_mpt_proof<K: AsRef<[u8]>, V: rlp::Encodable>(
     root: &B256,
@@ -141,7 +393,10 @@ pub fn verify_mpt_proof<K: AsRef<[u8]>, V: rlp::Encodable>(
     raw_value: V,
     proof: &[Bytes],
 ) -> Result<()> {
-    let key = Nibbles::unpack(keccak256(raw_key));
+    // Counted apart so `pool cycles-check` shows what hinting the keys to the guest could save:
+    // a hinted hash would still be checked by hashing, so at most the unpacking.
+    let hash = region!("trie_key_hash", keccak256(raw_key));
+    let key = region!("trie_key_nibbles", Nibbles::unpack(hash));
     let value = rlp::encode(raw_value);
 
     verify_proof(*root, key, Some(value), proof).map_err(|_| eyre::eyre!("invalid proof"))
@@ -168,28 +423,72 @@ pub fn process_withdrawal(input: &WithdrawalInput) -> Result<WithdrawalData> {
         relayer_fee,
         recipient,
         relayer,
+        nft,
+        protocol_fee,
+        bridge: _,
+        registry: _,
+        oracle: _,
+        split,
+        token_fee,
+        association: _,
     } = input;
 
-    let (commitment, nullifier) = compute_commitment(secret);
+    let (commitment, nullifier) = region!(
+        "commitment",
+        match nft {
+            Some(nft) => compute_nft_commitment(secret, &nft.collection, &nft.token_id),
+            None => compute_commitment(secret),
+        }
+    );
     let state_root = block_header.state_root;
-    let block_hash = hash_block_header(block_header);
+    let block_hash = region!("header_hash", hash_block_header(block_header))?;
 
     // Verify storage proofs
-    verify_storage_slot(
-        contract_address,
-        array_slot,
-        &commitment,
-        array_index,
-        &state_root,
-        account_proof,
+    region!(
+        "storage_proofs",
+        verify_storage_slot(
+            contract_address,
+            array_slot,
+            &commitment,
+            array_index,
+            &state_root,
+            account_proof,
+            nft.as_ref(),
+            *protocol_fee,
+        )
     )?;
 
-    let inclusion_root = inclusion_set_branches
-        .clone()
-        .map(|branches| compute_inclusion_root(commitment, branches))
-        .unwrap_or(B256::ZERO);
+    // A split withdrawal's recipients are in its distribution, which must pay out the note.
+    if let Some(split) = split {
+        ensure!(
+            recipient.is_zero(),
+            "split withdrawals name their recipients in the distribution"
+        );
+        ensure!(
+            token_fee.is_none(),
+            "split withdrawals pay the relayer in ETH, having no recipient to pay a token"
+        );
+        split.verify(relayer_fee)?;
+    }
+
+    if let Some(fee) = token_fee {
+        fee.verify(relayer_fee)?;
+    }
+    let (fee_token, fee_token_amount) = token_fee
+        .as_ref()
+        .map_or((Address::ZERO, U256::ZERO), |fee| (fee.token, fee.amount));
+
+    let inclusion_root = region!(
+        "inclusion_root",
+        inclusion_set_branches
+            .as_ref()
+            .map_or(B256::ZERO, |branches| {
+                compute_inclusion_root(&commitment, branches)
+            })
+    );
 
     Ok(WithdrawalData {
+        variant: Variant::Base as u8,
         nullifier,
         blockNumber: block_hash.number,
         blockHash: block_hash.hash,
@@ -198,5 +497,126 @@ pub fn process_withdrawal(input: &WithdrawalInput) -> Result<WithdrawalData> {
         relayerFee: *relayer_fee,
         recipient: *recipient,
         relayer: *relayer,
+        feeToken: fee_token,
+        feeTokenAmount: fee_token_amount,
     })
 }
+
+/// Process a withdrawal from any pool and ABI-encode the public values its proof commits to:
+/// a [`WithdrawalData`], an [`NftWithdrawalData`] for ERC-721 pools, a [`FeeWithdrawalData`]
+/// for pools with a protocol fee, a [`BridgeWithdrawalData`] for withdrawals to an L2 address or
+/// a [`RegistryWithdrawalData`] for withdrawals committing to the pool's registry entry, or an
+/// [`OracleWithdrawalData`] for withdrawals anchored through a block hash oracle, a
+/// [`SplitWithdrawalData`] for withdrawals split between several recipients, or an
+/// [`AssociationWithdrawalData`] for withdrawals from an association set.
+pub fn withdrawal_public_values(input: &WithdrawalInput) -> Result<Vec<u8>> {
+    let variants = [
+        input.nft.is_some(),
+        input.protocol_fee,
+        input.bridge.is_some(),
+        input.registry.is_some(),
+        input.oracle.is_some(),
+        input.split.is_some(),
+        input.association.is_some(),
+    ];
+    ensure!(
+        variants.iter().filter(|&&variant| variant).count() <= 1,
+        "a withdrawal is from an ERC-721 pool, from a pool with a protocol fee, to an L2 \
+         address, committing to a registry entry, anchored through a block hash oracle, split \
+         between recipients or from an association set, but not several"
+    );
+    Ok(match variants {
+        [true, ..] => nft::process_nft_withdrawal(input)?.abi_encode(),
+        [_, true, ..] => protocol_fee::process_fee_withdrawal(input)?.abi_encode(),
+        [_, _, true, ..] => bridge::process_bridge_withdrawal(input)?.abi_encode(),
+        [_, _, _, true, ..] => registry::process_registry_withdrawal(input)?.abi_encode(),
+        [.., true, _, _] => oracle::process_oracle_withdrawal(input)?.abi_encode(),
+        [.., true, _] => split::process_split_withdrawal(input)?.abi_encode(),
+        [.., true] => association::process_association_withdrawal(input)?.abi_encode(),
+        _ => process_withdrawal(input)?.abi_encode(),
+    })
+}
+
+/// Process a withdrawal from any pool as the program does, returning the public values its proof
+/// commits to, decoded.
+pub fn process_any_withdrawal(input: &WithdrawalInput) -> Result<PublicValues> {
+    Ok(decode_any_public_values(&withdrawal_public_values(input)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontier_matches_merkle_root() {
+        let leaves: Vec<B256> = (0..40u64).map(|i| keccak256(i.to_be_bytes())).collect();
+        let mut frontier = MerkleFrontier::default();
+        assert_eq!(frontier.root(), compute_merkle_root(&[]));
+        for (i, leaf) in leaves.iter().enumerate() {
+            frontier.push(*leaf);
+            assert_eq!(
+                frontier.root(),
+                compute_merkle_root(&leaves[..=i]),
+                "{} leaves",
+                i + 1
+            );
+        }
+    }
+
+    #[test]
+    fn branches_prove_leaves() {
+        let leaves: Vec<B256> = (0..13u64).map(|i| keccak256(i.to_be_bytes())).collect();
+        let root = compute_merkle_root(&leaves);
+        for (i, leaf) in leaves.iter().enumerate() {
+            let branches = compute_merkle_branch(&leaves, i);
+            assert_eq!(compute_inclusion_root(leaf, &branches), root);
+        }
+    }
+
+    #[test]
+    fn withdrawal_commits_the_root_of_its_inclusion_set() {
+        let mut input = fixture::withdrawal(16, 16);
+        assert_eq!(
+            process_withdrawal(&input).unwrap().exclusionSetRoot,
+            B256::ZERO
+        );
+
+        let (commitment, _) = compute_commitment(&input.secret);
+        let set: Vec<B256> = (0..5u64)
+            .map(|i| keccak256(i.to_be_bytes()))
+            .chain([commitment])
+            .collect();
+        input.inclusion_set_branches = Some(compute_merkle_branch(&set, 5));
+        assert_eq!(
+            process_withdrawal(&input).unwrap().exclusionSetRoot,
+            compute_merkle_root(&set)
+        );
+    }
+
+    #[test]
+    fn withdrawal_rejects_a_proof_of_another_deposit() {
+        let mut input = fixture::withdrawal(16, 16);
+        input.secret = fixture::secret(0);
+        assert!(process_withdrawal(&input).is_err());
+
+        let mut input = fixture::withdrawal(16, 16);
+        input.account_proof.storage_proof[1].proof.pop();
+        assert!(process_withdrawal(&input).is_err());
+    }
+
+    #[test]
+    fn any_withdrawal_decodes_what_the_program_commits() {
+        let input = fixture::withdrawal(16, 16);
+        let values = process_any_withdrawal(&input).unwrap();
+        assert_eq!(
+            values.withdrawal.abi_encode(),
+            process_withdrawal(&input).unwrap().abi_encode()
+        );
+        assert!(values.protocol_fee.is_none() && values.bridge.is_none());
+
+        // The fixture proves no fee slot, so it is no withdrawal from a pool with a protocol fee.
+        let mut input = input;
+        input.protocol_fee = true;
+        assert!(process_any_withdrawal(&input).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! ERC-721 pools. A note deposits one token and its commitment binds it, as
+//! [`compute_nft_commitment`](crate::compute_nft_commitment). Withdrawals also prove the pool
+//! still escrows the token, from its `escrowed[collection][tokenId]` mapping, and commit to it in
+//! [`NftWithdrawalData`].
+
+use crate::{
+    process_withdrawal, verify_mpt_proof, NftWithdrawalData, Variant, WithdrawalData,
+    WithdrawalInput,
+};
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    rpc::types::EIP1186StorageProof,
+    sol_types::SolValue,
+};
+use eyre::{OptionExt, Result};
+use serde::{Deserialize, Serialize};
+
+/// Storage slot of the `escrowed` mapping of ERC-721 pools. It is fixed rather than taken from
+/// the input, or a prover could show the token set in some other mapping of the pool.
+pub const ESCROW_SLOT: u64 = 2;
+
+/// The token a note of an ERC-721 pool deposited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NftToken {
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::address"))]
+    pub collection: Address,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub token_id: U256,
+}
+
+impl NftToken {
+    pub fn new(collection: Address, token_id: U256) -> Self {
+        Self {
+            collection,
+            token_id,
+        }
+    }
+
+    /// Storage key of `escrowed[collection][token_id]`.
+    pub fn escrow_key(&self) -> B256 {
+        let outer = keccak256((self.collection, U256::from(ESCROW_SLOT)).abi_encode());
+        keccak256((self.token_id, outer).abi_encode())
+    }
+
+    /// Verify `proof` shows the pool storage under `storage_root` escrows the token.
+    pub fn verify_escrow(&self, storage_root: &B256, proof: &EIP1186StorageProof) -> Result<()> {
+        verify_mpt_proof(storage_root, self.escrow_key(), U256::from(1), &proof.proof)
+    }
+}
+
+/// Process a withdrawal from an ERC-721 pool, fully verifying it and returning public data.
+pub fn process_nft_withdrawal(input: &WithdrawalInput) -> Result<NftWithdrawalData> {
+    let token = input
+        .nft
+        .ok_or_eyre("withdrawal is not from an ERC-721 pool")?;
+    let data = process_withdrawal(input)?;
+    Ok(NftWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::Nft as u8,
+            ..data
+        },
+        collection: token.collection,
+        tokenId: token.token_id,
+    })
+}
@@ -0,0 +1,97 @@
+//! Withdrawals proven against blocks older than the 256 the EVM's `BLOCKHASH` reaches back. A
+//! block hash oracle keeps the hashes of past blocks in a `mapping(uint256 => bytes32)` keyed by
+//! block number. The withdrawal proves the deposit at the old block as usual, and proves that the
+//! oracle holds that block's hash in the state of a recent anchor block, which the contract can
+//! still check with `BLOCKHASH`. [`OracleWithdrawalData`] commits to the anchor and to the
+//! oracle's address, slot and code hash. The program takes any contract as the oracle, and it is
+//! `OraclePool` that only pays out proofs through the oracles it was deployed trusting.
+
+use crate::{
+    hash_block_header, process_withdrawal, verify_account_proof, verify_mpt_proof,
+    OracleWithdrawalData, Variant, WithdrawalData, WithdrawalInput,
+};
+use alloy::{
+    consensus::Header,
+    primitives::{keccak256, Address, B256, U256},
+    rpc::types::{BlockNumHash, EIP1186AccountProofResponse},
+    sol_types::SolValue,
+};
+use eyre::{ensure, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+
+/// Storage key of `blockHashes[number]` for the oracle's mapping at `slot`.
+pub fn block_hash_entry_key(slot: U256, number: u64) -> B256 {
+    keccak256((U256::from(number), slot).abi_encode())
+}
+
+/// A recent block whose state shows a block hash oracle holding the hash of the proof block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OracleAnchor {
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::address"))]
+    pub oracle: Address,
+    /// Storage slot of the oracle's `blockHashes` mapping.
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub slot: U256,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::header"))]
+    pub header: Header,
+    /// Proof of the oracle account and its `blockHashes` entry for the proof block, under the
+    /// anchor's state root.
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::schema::account_proof")
+    )]
+    pub proof: EIP1186AccountProofResponse,
+}
+
+impl OracleAnchor {
+    /// Verify the oracle at the anchor block holds the hash of `block`, and return the anchor.
+    pub fn verify(&self, block: &BlockNumHash) -> Result<BlockNumHash> {
+        ensure!(self.proof.address == self.oracle, "invalid oracle address");
+        let anchor = hash_block_header(&self.header)?;
+        ensure!(
+            anchor.number > block.number,
+            "anchor block is not after the proof block"
+        );
+        verify_account_proof(&self.header.state_root, &self.proof)?;
+        ensure!(self.proof.storage_proof.len() == 1, "invalid oracle proof");
+        let proof = &self.proof.storage_proof[0];
+        ensure!(
+            proof.value == U256::from_be_bytes(block.hash.0),
+            "oracle does not hold the proof block's hash"
+        );
+        verify_mpt_proof(
+            &self.proof.storage_hash,
+            block_hash_entry_key(self.slot, block.number),
+            proof.value,
+            &proof.proof,
+        )?;
+        Ok(anchor)
+    }
+}
+
+/// Process a withdrawal proven against a block anchored through a block hash oracle, fully
+/// verifying it and returning public data. The block the public values name is the anchor, and
+/// the proof block follows the oracle.
+pub fn process_oracle_withdrawal(input: &WithdrawalInput) -> Result<OracleWithdrawalData> {
+    let anchor = input
+        .oracle
+        .as_ref()
+        .ok_or_eyre("withdrawal is not anchored through a block hash oracle")?;
+    let data = process_withdrawal(input)?;
+    let proven = BlockNumHash::new(data.blockNumber, data.blockHash);
+    let anchor_block = anchor.verify(&proven)?;
+    Ok(OracleWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::Oracle as u8,
+            blockHash: anchor_block.hash,
+            blockNumber: anchor_block.number,
+            ..data
+        },
+        blockHashOracle: anchor.oracle,
+        oracleSlot: anchor.slot,
+        oracleCodeHash: anchor.proof.code_hash,
+        provenBlockHash: proven.hash,
+        provenBlockNumber: proven.number,
+    })
+}
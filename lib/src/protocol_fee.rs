@@ -0,0 +1,96 @@
+//! Pools whose owner can change a protocol fee. A withdrawal proves the fee slot at the proof
+//! block and commits its value in [`FeeWithdrawalData`], so the contract charges the fee that was
+//! in force when the proof was anchored rather than one set while it was in flight.
+
+use crate::{process_withdrawal, FeeWithdrawalData, Variant, WithdrawalData, WithdrawalInput};
+use alloy::{
+    primitives::{keccak256, B256, U256},
+    rlp,
+    rpc::types::EIP1186StorageProof,
+};
+use alloy_trie::{proof::verify_proof, Nibbles};
+use eyre::{ensure, eyre, OptionExt, Result};
+
+/// Storage slot of the protocol fee of pools with one. It is fixed rather than taken from the
+/// input, as the fee is only committed by value and a prover could otherwise prove any slot that
+/// holds a lower one.
+pub const PROTOCOL_FEE_SLOT: u64 = 2;
+
+/// Storage key of the protocol fee.
+pub fn fee_key() -> B256 {
+    U256::from(PROTOCOL_FEE_SLOT).to_be_bytes::<32>().into()
+}
+
+/// Verify `proof` shows the value of the protocol fee in the pool storage under `storage_root`.
+/// Zero slots are not in the storage trie, so a zero fee is proven by the slot's absence.
+pub fn verify_fee_slot(storage_root: &B256, proof: &EIP1186StorageProof) -> Result<()> {
+    let key = Nibbles::unpack(keccak256(fee_key()));
+    let value = (!proof.value.is_zero()).then(|| rlp::encode(proof.value));
+    verify_proof(*storage_root, key, value, &proof.proof).map_err(|_| eyre!("invalid proof"))
+}
+
+/// Process a withdrawal from a pool with a protocol fee, fully verifying it and returning public
+/// data.
+pub fn process_fee_withdrawal(input: &WithdrawalInput) -> Result<FeeWithdrawalData> {
+    ensure!(
+        input.protocol_fee,
+        "withdrawal is not from a pool with a protocol fee"
+    );
+    let data = process_withdrawal(input)?;
+    // The fee slot is proven last, and `process_withdrawal` checked its proof with the others.
+    let protocol_fee = input
+        .account_proof
+        .storage_proof
+        .last()
+        .ok_or_eyre("invalid storage proof")?
+        .value;
+    Ok(FeeWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::ProtocolFee as u8,
+            ..data
+        },
+        protocolFee: protocol_fee,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::trie;
+    use alloy::serde::JsonStorageKey;
+
+    /// Proof of the fee slot in a storage trie holding `fee` in it, and a deposit count in slot 0.
+    fn fee_proof(fee: U256) -> (B256, EIP1186StorageProof) {
+        let mut storage = vec![(keccak256(B256::ZERO), rlp::encode(U256::from(3)))];
+        if !fee.is_zero() {
+            storage.push((keccak256(fee_key()), rlp::encode(fee)));
+        }
+        let (root, mut proofs) = trie(storage, &[keccak256(fee_key())]);
+        let proof = EIP1186StorageProof {
+            key: JsonStorageKey::from(fee_key()),
+            value: fee,
+            proof: proofs.remove(0),
+        };
+        (root, proof)
+    }
+
+    #[test]
+    fn proves_a_set_fee() {
+        let (root, mut proof) = fee_proof(U256::from(500));
+        verify_fee_slot(&root, &proof).unwrap();
+        proof.value = U256::from(499);
+        assert!(verify_fee_slot(&root, &proof).is_err());
+        // Nor does the inclusion proof pass for a fee of zero.
+        proof.value = U256::ZERO;
+        assert!(verify_fee_slot(&root, &proof).is_err());
+    }
+
+    #[test]
+    fn proves_a_zero_fee_by_exclusion() {
+        let (root, mut proof) = fee_proof(U256::ZERO);
+        verify_fee_slot(&root, &proof).unwrap();
+        // An absent slot proves no other fee.
+        proof.value = U256::from(1);
+        assert!(verify_fee_slot(&root, &proof).is_err());
+    }
+}
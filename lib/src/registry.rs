@@ -0,0 +1,111 @@
+//! Pools resolved through an on-chain registry, which maps a chain id, denomination and version
+//! to the pool deployed for them in its `mapping(bytes32 => address) pools`. A withdrawal can
+//! prove the registry entry at the proof block alongside the deposit, and commit to it in
+//! [`RegistryWithdrawalData`] so the contract knows the pool is the registered one.
+
+use crate::{
+    process_withdrawal, verify_account_proof, verify_mpt_proof, RegistryWithdrawalData, Variant,
+    WithdrawalData, WithdrawalInput,
+};
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    rpc::types::EIP1186AccountProofResponse,
+    sol_types::SolValue,
+};
+use eyre::{ensure, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+
+/// Storage slot of the registry's `pools` mapping.
+pub const POOLS_SLOT: u64 = 0;
+
+/// Key of the pool for `chain_id`, `denomination` and `version` in the registry,
+/// `keccak256(abi.encode(chainId, denomination, version))`.
+pub fn registry_key(chain_id: u64, denomination: U256, version: u64) -> B256 {
+    keccak256((U256::from(chain_id), denomination, U256::from(version)).abi_encode())
+}
+
+/// Storage key of `pools[key]`.
+pub fn pools_entry_key(key: B256) -> B256 {
+    keccak256((key, U256::from(POOLS_SLOT)).abi_encode())
+}
+
+/// A registry entry with the proof of the registry account and of its `pools` entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RegistryEntry {
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::address"))]
+    pub registry: Address,
+    pub chain_id: u64,
+    /// Amount each deposit of the pool is, in wei.
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub denomination: U256,
+    pub version: u64,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::schema::account_proof")
+    )]
+    pub proof: EIP1186AccountProofResponse,
+}
+
+impl RegistryEntry {
+    pub fn key(&self) -> B256 {
+        registry_key(self.chain_id, self.denomination, self.version)
+    }
+
+    /// The pool the entry's proof shows, without verifying it.
+    pub fn pool(&self) -> Result<Address> {
+        let value = self
+            .proof
+            .storage_proof
+            .first()
+            .ok_or_eyre("invalid registry proof")?
+            .value;
+        ensure!(value < U256::from(1) << 160, "invalid registry entry");
+        Ok(Address::from_word(B256::from(value)))
+    }
+
+    /// Verify the registry under `state_root` maps the entry to `pool`.
+    pub fn verify(&self, state_root: &B256, pool: &Address) -> Result<()> {
+        ensure!(
+            self.proof.address == self.registry,
+            "invalid registry address"
+        );
+        verify_account_proof(state_root, &self.proof)?;
+        ensure!(
+            self.proof.storage_proof.len() == 1,
+            "invalid registry proof"
+        );
+        ensure!(
+            !pool.is_zero() && self.pool()? == *pool,
+            "pool is not the registered one"
+        );
+        let proof = &self.proof.storage_proof[0];
+        verify_mpt_proof(
+            &self.proof.storage_hash,
+            pools_entry_key(self.key()),
+            proof.value,
+            &proof.proof,
+        )
+    }
+}
+
+/// Process a withdrawal from a pool resolved through a registry, fully verifying it and
+/// returning public data.
+pub fn process_registry_withdrawal(input: &WithdrawalInput) -> Result<RegistryWithdrawalData> {
+    let entry = input
+        .registry
+        .as_ref()
+        .ok_or_eyre("withdrawal does not prove a registry entry")?;
+    let data = process_withdrawal(input)?;
+    entry.verify(&input.block_header.state_root, &input.contract_address)?;
+    Ok(RegistryWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::Registry as u8,
+            ..data
+        },
+        registry: entry.registry,
+        chainId: U256::from(entry.chain_id),
+        denomination: entry.denomination,
+        version: U256::from(entry.version),
+    })
+}
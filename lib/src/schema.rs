@@ -0,0 +1,118 @@
+//! JSON Schemas for the alloy types wire types are built from, which do not implement
+//! `JsonSchema` themselves. Used with `#[schemars(schema_with = "...")]`.
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{
+        ArrayValidation, InstanceType, Metadata, Schema, SchemaObject, StringValidation,
+        SubschemaValidation,
+    },
+};
+
+fn described(mut schema: SchemaObject, description: &str) -> Schema {
+    schema.metadata = Some(Box::new(Metadata {
+        description: Some(description.to_string()),
+        ..Default::default()
+    }));
+    schema.into()
+}
+
+fn hex(pattern: &str, description: &str) -> Schema {
+    described(
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(pattern.to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        description,
+    )
+}
+
+fn object(description: &str) -> Schema {
+    described(
+        SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            ..Default::default()
+        },
+        description,
+    )
+}
+
+fn nullable(schema: Schema) -> Schema {
+    let null = SchemaObject {
+        instance_type: Some(InstanceType::Null.into()),
+        ..Default::default()
+    };
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![schema, null.into()]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+pub fn bytes32(_: &mut SchemaGenerator) -> Schema {
+    hex("^0x[0-9a-fA-F]{64}$", "32 bytes as 0x-prefixed hex.")
+}
+
+pub fn optional_bytes32(gen: &mut SchemaGenerator) -> Schema {
+    nullable(bytes32(gen))
+}
+
+pub fn bytes32_array(gen: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(bytes32(gen).into()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+pub fn address(_: &mut SchemaGenerator) -> Schema {
+    hex("^0x[0-9a-fA-F]{40}$", "20-byte address as 0x-prefixed hex.")
+}
+
+pub fn optional_address(gen: &mut SchemaGenerator) -> Schema {
+    nullable(address(gen))
+}
+
+pub fn uint256(_: &mut SchemaGenerator) -> Schema {
+    hex(
+        "^0x[0-9a-fA-F]{1,64}$",
+        "256-bit unsigned integer as 0x-prefixed hex.",
+    )
+}
+
+pub fn optional_uint256(gen: &mut SchemaGenerator) -> Schema {
+    nullable(uint256(gen))
+}
+
+pub fn bytes(_: &mut SchemaGenerator) -> Schema {
+    hex("^0x([0-9a-fA-F]{2})*$", "Bytes as 0x-prefixed hex.")
+}
+
+pub fn signature(_: &mut SchemaGenerator) -> Schema {
+    object("ECDSA signature as { r, s, yParity }.")
+}
+
+pub fn account_proof(_: &mut SchemaGenerator) -> Schema {
+    object("Account and storage proof as returned by eth_getProof.")
+}
+
+pub fn header(_: &mut SchemaGenerator) -> Schema {
+    object("Block header as returned by eth_getBlockByNumber.")
+}
+
+pub fn optional_signed_root(_: &mut SchemaGenerator) -> Schema {
+    nullable(object(
+        "Association root signed by its provider, as `asp` serves it.",
+    ))
+}
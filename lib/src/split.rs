@@ -0,0 +1,88 @@
+//! Withdrawals splitting one note between several recipients. The withdrawal names no recipient
+//! of its own and commits in [`SplitWithdrawalData`] to a hash of the distribution and to the
+//! note value its amounts and the relayer fee add up to. The contract checks the note value is
+//! its deposit amount and pays out a distribution with that hash, so whoever submits the proof
+//! cannot change who gets how much.
+
+use crate::{
+    compute_distribution_hash, process_withdrawal, SplitWithdrawalData, Variant, WithdrawalData,
+    WithdrawalInput,
+};
+use alloy::primitives::{Address, B256, U256};
+use eyre::{ensure, OptionExt, Result};
+use serde::{Deserialize, Serialize};
+
+/// Most recipients a withdrawal can be split between, bounding the contract's payout loop.
+pub const MAX_SPLIT_RECIPIENTS: usize = 8;
+
+/// A recipient of a split withdrawal and the amount they get, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Split {
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::address"))]
+    pub recipient: Address,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub amount: U256,
+}
+
+/// How a split withdrawal distributes the note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Distribution {
+    /// Value of the note, the pool's deposit amount, in wei.
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub note_value: U256,
+    pub splits: Vec<Split>,
+}
+
+impl Distribution {
+    pub fn hash(&self) -> B256 {
+        let (recipients, amounts): (Vec<Address>, Vec<U256>) = self
+            .splits
+            .iter()
+            .map(|split| (split.recipient, split.amount))
+            .unzip();
+        compute_distribution_hash(&recipients, &amounts)
+    }
+
+    /// Check the distribution pays between one and [`MAX_SPLIT_RECIPIENTS`] recipients, and
+    /// that their amounts and `relayer_fee` add up to the note value.
+    pub fn verify(&self, relayer_fee: &U256) -> Result<()> {
+        ensure!(
+            !self.splits.is_empty() && self.splits.len() <= MAX_SPLIT_RECIPIENTS,
+            "a split withdrawal pays between 1 and {MAX_SPLIT_RECIPIENTS} recipients"
+        );
+        let mut total = *relayer_fee;
+        for split in &self.splits {
+            ensure!(!split.recipient.is_zero(), "split to the zero address");
+            ensure!(!split.amount.is_zero(), "split of no value");
+            total = total
+                .checked_add(split.amount)
+                .ok_or_eyre("split amounts overflow")?;
+        }
+        ensure!(
+            total == self.note_value,
+            "split amounts and relayer fee add up to {total}, not the note value {}",
+            self.note_value
+        );
+        Ok(())
+    }
+}
+
+/// Process a withdrawal split between several recipients, fully verifying it and returning
+/// public data.
+pub fn process_split_withdrawal(input: &WithdrawalInput) -> Result<SplitWithdrawalData> {
+    let distribution = input
+        .split
+        .as_ref()
+        .ok_or_eyre("withdrawal is not split between recipients")?;
+    let data = process_withdrawal(input)?;
+    Ok(SplitWithdrawalData {
+        withdrawal: WithdrawalData {
+            variant: Variant::Split as u8,
+            ..data
+        },
+        distributionHash: distribution.hash(),
+        noteValue: distribution.note_value,
+    })
+}
@@ -0,0 +1,109 @@
+//! Relayer fees paid in an ERC-20 token instead of carved from the withdrawn ETH. The withdrawal
+//! commits to the token and amount in [`WithdrawalData`], with no ETH relayer fee, so the
+//! recipient gets the whole deposit and whoever submits the proof cannot change what the relayer
+//! is paid.
+//!
+//! The recipient pays the fee, and anyone can prove a withdrawal of their own note to anyone, so
+//! the pool only takes it with the recipient's [`TokenFeeConsent`].
+
+use crate::{
+    intent::{intent_domain, recover_typed_signer},
+    WithdrawalData,
+};
+use alloy::{
+    primitives::{Address, PrimitiveSignature, U256},
+    sol,
+};
+use eyre::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+sol! {
+    /// A recipient's agreement to pay the relayer of the withdrawal with `nullifier`
+    /// `feeTokenAmount` of `feeToken`, signed under the pool's
+    /// [`intent_domain`](crate::intent::intent_domain).
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct TokenFeeAuthorization {
+        bytes32 nullifier;
+        address relayer;
+        address feeToken;
+        uint256 feeTokenAmount;
+    }
+}
+
+impl TokenFeeAuthorization {
+    /// The authorization of the token fee `data` pays.
+    pub fn new(data: &WithdrawalData) -> Self {
+        Self {
+            nullifier: data.nullifier,
+            relayer: data.relayer,
+            feeToken: data.feeToken,
+            feeTokenAmount: data.feeTokenAmount,
+        }
+    }
+}
+
+/// What `Pool.withdrawWithTokenFee` takes from the recipient to pay a withdrawal's token fee.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TokenFeeConsent {
+    /// Signature of the withdrawal's [`TokenFeeAuthorization`].
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::signature"))]
+    pub authorization: PrimitiveSignature,
+    /// An EIP-2612 permit of the fee to the pool. Without one, the recipient must have approved
+    /// the pool, and that transaction links them to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permit: Option<TokenPermit>,
+}
+
+/// An EIP-2612 permit from the recipient to the pool, for exactly the fee.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TokenPermit {
+    /// Timestamp after which the permit is void.
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub deadline: U256,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::signature"))]
+    pub signature: PrimitiveSignature,
+}
+
+impl TokenFeeConsent {
+    /// Check the recipient of `data`, a withdrawal from a pool on chain `chain_id`, signed the
+    /// authorization of its token fee.
+    pub fn verify(&self, chain_id: u64, data: &WithdrawalData) -> Result<()> {
+        let signer = recover_typed_signer(
+            &TokenFeeAuthorization::new(data),
+            &intent_domain(chain_id, data.contractAddress),
+            &self.authorization,
+        )?;
+        ensure!(
+            signer == data.recipient,
+            "token fee not authorized by the recipient {}",
+            data.recipient
+        );
+        Ok(())
+    }
+}
+
+/// The token a withdrawal pays its relayer in, and how much of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TokenFee {
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::address"))]
+    pub token: Address,
+    /// Amount in the token's smallest unit.
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::uint256"))]
+    pub amount: U256,
+}
+
+impl TokenFee {
+    /// Check the fee names a token, and that the withdrawal's ETH relayer fee `relayer_fee` is
+    /// zero alongside it.
+    pub fn verify(&self, relayer_fee: &U256) -> Result<()> {
+        ensure!(!self.token.is_zero(), "token fee in the zero address");
+        ensure!(
+            relayer_fee.is_zero(),
+            "a withdrawal pays its relayer in ETH or in a token, not both"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+//! napi-rs bindings to pool-lib for TypeScript backends and Electron wallets, with helpers that
+//! hand inputs to a `pool serve --jsonrpc` proving service.
+//!
+//! Hashes, addresses and amounts cross the boundary as `0x`-prefixed hex strings, and withdrawal
+//! inputs as the CBOR the program reads.
+
+use alloy::primitives::Bytes;
+use napi::{bindgen_prelude::Buffer, Error, Result};
+use napi_derive::napi;
+use pool_lib::{
+    compute_commitment, decode_any_public_values, note::Note, process_any_withdrawal,
+    WithdrawalData, WithdrawalInput,
+};
+use rand::Rng;
+use serde_json::{json, Value};
+use std::{fmt, str::FromStr};
+
+fn error(e: impl fmt::Display) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| error(format!("invalid {name} {value}")))
+}
+
+fn chain_id(chain_id: i64) -> Result<u64> {
+    u64::try_from(chain_id).map_err(|_| error(format!("invalid chain id {chain_id}")))
+}
+
+/// A deposit secret, where it is deposited and what it commits to.
+#[napi(object)]
+pub struct NoteInfo {
+    pub chain_id: i64,
+    pub pool: String,
+    pub secret: String,
+    pub commitment: String,
+    pub nullifier: String,
+}
+
+impl From<Note> for NoteInfo {
+    fn from(note: Note) -> Self {
+        let (commitment, nullifier) = compute_commitment(&note.secret);
+        Self {
+            chain_id: note.chain_id as i64,
+            pool: note.pool.to_string(),
+            secret: note.secret.to_string(),
+            commitment: commitment.to_string(),
+            nullifier: nullifier.to_string(),
+        }
+    }
+}
+
+/// Public values a withdrawal proof commits to.
+#[napi(object)]
+pub struct PublicValues {
+    pub nullifier: String,
+    pub block_hash: String,
+    pub exclusion_set_root: String,
+    pub relayer_fee: String,
+    pub recipient: String,
+    pub relayer: String,
+    pub contract_address: String,
+    pub block_number: i64,
+    pub fee_token: String,
+    pub fee_token_amount: String,
+}
+
+impl From<WithdrawalData> for PublicValues {
+    fn from(data: WithdrawalData) -> Self {
+        Self {
+            nullifier: data.nullifier.to_string(),
+            block_hash: data.blockHash.to_string(),
+            exclusion_set_root: data.exclusionSetRoot.to_string(),
+            relayer_fee: data.relayerFee.to_string(),
+            recipient: data.recipient.to_string(),
+            relayer: data.relayer.to_string(),
+            contract_address: data.contractAddress.to_string(),
+            block_number: data.blockNumber as i64,
+            fee_token: data.feeToken.to_string(),
+            fee_token_amount: data.feeTokenAmount.to_string(),
+        }
+    }
+}
+
+/// A proof returned by the proving service.
+#[napi(object)]
+pub struct ProofResult {
+    /// Proof in the prover's own encoding, as `pool withdraw --prove` writes it.
+    pub proof: Buffer,
+    /// Proof to pass to `Pool.withdraw`, unless the proof is compressed.
+    pub proof_bytes: Option<Buffer>,
+    /// ABI-encoded public values.
+    pub public_values: Buffer,
+}
+
+/// Generate a fresh secret for depositing into `pool` on `chainId`.
+#[napi]
+pub fn new_note(chain_id: i64, pool: String) -> Result<NoteInfo> {
+    Ok(Note {
+        chain_id: self::chain_id(chain_id)?,
+        pool: parse("pool", &pool)?,
+        secret: rand::rng().random::<[u8; 32]>().into(),
+    }
+    .into())
+}
+
+/// Commitment and nullifier of `secret`, for a note kept some other way.
+#[napi]
+pub fn derive_note(chain_id: i64, pool: String, secret: String) -> Result<NoteInfo> {
+    Ok(Note {
+        chain_id: self::chain_id(chain_id)?,
+        pool: parse("pool", &pool)?,
+        secret: parse("secret", &secret)?,
+    }
+    .into())
+}
+
+/// Encode a note as a `pool-note-v1` string.
+#[napi]
+pub fn encode_note(note: NoteInfo) -> Result<String> {
+    Ok(Note {
+        chain_id: chain_id(note.chain_id)?,
+        pool: parse("pool", &note.pool)?,
+        secret: parse("secret", &note.secret)?,
+    }
+    .to_string())
+}
+
+/// Decode a `pool-note-v1` string.
+#[napi]
+pub fn decode_note(note: String) -> Result<NoteInfo> {
+    Ok(note.parse::<Note>().map_err(error)?.into())
+}
+
+fn decode_input(input: &[u8]) -> Result<WithdrawalInput> {
+    serde_cbor::from_slice(input).map_err(|e| error(format!("invalid input: {e}")))
+}
+
+/// Check a CBOR withdrawal input the way the program does, returning the public values it would
+/// commit.
+#[napi]
+pub fn validate_withdrawal_input(input: Buffer) -> Result<PublicValues> {
+    Ok(process_any_withdrawal(&decode_input(&input)?)
+        .map_err(error)?
+        .withdrawal
+        .into())
+}
+
+/// Decode the ABI-encoded public values of a withdrawal proof.
+#[napi]
+pub fn decode_public_values(public_values: Buffer) -> Result<PublicValues> {
+    Ok(decode_any_public_values(&public_values)
+        .map_err(error)?
+        .withdrawal
+        .into())
+}
+
+/// Call `method` on a `pool serve --jsonrpc` service.
+async fn call(url: &str, token: Option<&str>, method: &str, params: Value) -> Result<Value> {
+    let mut request = reqwest::Client::new().post(url).json(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    }));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response: Value = request
+        .send()
+        .await
+        .map_err(error)?
+        .error_for_status()
+        .map_err(error)?
+        .json()
+        .await
+        .map_err(error)?;
+    if let Some(e) = response.get("error") {
+        let message = e
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(error(format!("{method} failed: {message}")));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| error(format!("{method} returned no result")))
+}
+
+fn hex_field(result: &Value, name: &str) -> Result<Option<Buffer>> {
+    match result.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(hex)) => Ok(Some(parse::<Bytes>(name, hex)?.to_vec().into())),
+        Some(_) => Err(error(format!("invalid {name}"))),
+    }
+}
+
+/// Check a CBOR withdrawal input locally, then prove it on the proving service at `url`.
+/// `mode` is `compressed`, `groth16` or `plonk`, and `token` the service's bearer token.
+#[napi]
+pub async fn request_proof(
+    url: String,
+    input: Buffer,
+    mode: Option<String>,
+    token: Option<String>,
+) -> Result<ProofResult> {
+    let input = decode_input(&input)?;
+    process_any_withdrawal(&input).map_err(error)?;
+    let mut params = json!({ "input": input });
+    if let Some(mode) = mode {
+        params["mode"] = mode.into();
+    }
+    let result = call(&url, token.as_deref(), "pool_prove", params).await?;
+    let public_values = result
+        .get("publicValues")
+        .ok_or_else(|| error("pool_prove returned no public values"))?;
+    Ok(ProofResult {
+        proof: hex_field(&result, "proof")?.ok_or_else(|| error("pool_prove returned no proof"))?,
+        proof_bytes: hex_field(&result, "proofBytes")?,
+        public_values: hex_field(public_values, "publicValues")?
+            .ok_or_else(|| error("pool_prove returned no public values"))?,
+    })
+}
+
+/// Execute a CBOR withdrawal input on the proving service at `url` without proving it,
+/// returning the cycles it takes.
+#[napi]
+pub async fn request_execution(url: String, input: Buffer, token: Option<String>) -> Result<i64> {
+    let input = decode_input(&input)?;
+    let result = call(
+        &url,
+        token.as_deref(),
+        "pool_execute",
+        json!({ "input": input }),
+    )
+    .await?;
+    result
+        .get("cycles")
+        .and_then(Value::as_i64)
+        .ok_or_else(|| error("pool_execute returned no cycles"))
+}
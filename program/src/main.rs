@@ -1,15 +1,19 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use alloy::sol_types::SolValue;
-use pool_lib::{process_withdrawal, WithdrawalInput};
+use pool_lib::{compact::CompactInput, withdrawal_public_values};
 
 pub fn main() {
     // let input = sp1_zkvm::io::read::<WithdrawalInput>();
+    println!("cycle-tracker-report-start: decode");
     let bytes = sp1_zkvm::io::read_vec();
-    let input = serde_cbor::from_slice::<WithdrawalInput>(&bytes).unwrap();
+    let input = serde_cbor::from_slice::<CompactInput>(&bytes)
+        .unwrap()
+        .expand()
+        .unwrap();
+    println!("cycle-tracker-report-end: decode");
 
-    let data = process_withdrawal(&input).unwrap();
+    let public_values = withdrawal_public_values(&input).unwrap();
 
-    sp1_zkvm::io::commit_slice(&data.abi_encode());
+    sp1_zkvm::io::commit_slice(&public_values);
 }
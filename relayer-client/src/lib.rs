@@ -0,0 +1,628 @@
+//! Client for the HTTP protocol of pool relayers: fetching and verifying signed fee quotes,
+//! submitting withdrawals and following their relay jobs.
+
+use alloy::{
+    primitives::{Address, Bytes, PrimitiveSignature, B256, U256},
+    signers::Signer,
+    sol_types::SolStruct,
+};
+use eyre::{ensure, Result};
+use pool_lib::{
+    bridge::BridgeMessage,
+    intent::{intent_domain, recover_typed_signer, FeeQuote},
+    token_fee::TokenFeeConsent,
+    WithdrawalData, WithdrawalInput,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, time::Duration};
+use tracing::{info, instrument};
+
+/// A relayer's fee quote, returned by `GET /quote` and handed back with the withdrawal. Signed
+/// by the relayer as a [`FeeQuote`] in the pool's EIP-712 domain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Quote {
+    pub chain_id: u64,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::address")
+    )]
+    pub pool: Address,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::address")
+    )]
+    pub relayer: Address,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::uint256")
+    )]
+    pub fee: U256,
+    /// Unix timestamp after which the quote is no longer honored.
+    pub expiry: u64,
+    /// ERC-20 token the fee is in, in its smallest unit, if not in wei.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::optional_address")
+    )]
+    pub fee_token: Option<Address>,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::signature")
+    )]
+    pub signature: PrimitiveSignature,
+}
+
+impl Quote {
+    /// Sign a quote with the relayer's key, which may be held remotely.
+    pub async fn sign<S: Signer + Send + Sync + ?Sized>(
+        signer: &S,
+        chain_id: u64,
+        pool: Address,
+        fee: U256,
+        expiry: u64,
+        fee_token: Option<Address>,
+    ) -> Result<Self> {
+        let quote = FeeQuote {
+            relayer: signer.address(),
+            fee,
+            expiry,
+            feeToken: fee_token.unwrap_or_default(),
+        };
+        let signature = signer
+            .sign_hash(&quote.eip712_signing_hash(&intent_domain(chain_id, pool)))
+            .await?;
+        Ok(Self {
+            chain_id,
+            pool,
+            relayer: signer.address(),
+            fee,
+            expiry,
+            fee_token,
+            signature,
+        })
+    }
+
+    pub fn typed(&self) -> FeeQuote {
+        FeeQuote {
+            relayer: self.relayer,
+            fee: self.fee,
+            expiry: self.expiry,
+            feeToken: self.fee_token.unwrap_or_default(),
+        }
+    }
+
+    /// Check the quote was signed by its relayer.
+    pub fn verify(&self) -> Result<()> {
+        let signer = recover_typed_signer(
+            &self.typed(),
+            &intent_domain(self.chain_id, self.pool),
+            &self.signature,
+        )?;
+        ensure!(signer == self.relayer, "quote not signed by its relayer");
+        Ok(())
+    }
+
+    /// Check the quote is authentic and that `data` takes it at time `now`.
+    pub fn check(&self, data: &WithdrawalData, now: u64) -> Result<()> {
+        self.verify()?;
+        ensure!(
+            data.contractAddress == self.pool,
+            "quote is for a different pool"
+        );
+        self.typed().check(data, now)
+    }
+}
+
+/// Body of `POST /withdraw`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WithdrawRequest {
+    pub chain_id: u64,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::address")
+    )]
+    pub pool: Address,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "pool_lib::schema::bytes"))]
+    pub public_values: Bytes,
+    #[cfg_attr(feature = "schema", schemars(schema_with = "pool_lib::schema::bytes"))]
+    pub proof: Bytes,
+    /// The relayer's quote the withdrawal was built for.
+    pub quote: Quote,
+    /// The bridge deposit the public values commit to, for withdrawals to an L2 address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge: Option<BridgeMessage>,
+    /// The recipient's consent to the fee, for withdrawals paying the relayer in a token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_fee: Option<TokenFeeConsent>,
+}
+
+/// Body of `POST /withdraw` asking the relayer to prove the withdrawal itself. The input holds
+/// the note secret, so only send it to a relayer trusted with the deposit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProveRequest {
+    pub chain_id: u64,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::address")
+    )]
+    pub pool: Address,
+    pub input: WithdrawalInput,
+    /// The relayer's quote the withdrawal was built for.
+    pub quote: Quote,
+    /// The recipient's consent to the fee, for withdrawals paying the relayer in a token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_fee: Option<TokenFeeConsent>,
+}
+
+/// Either kind of `POST /withdraw` body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum WithdrawBody {
+    Proof(WithdrawRequest),
+    Input(ProveRequest),
+}
+
+impl WithdrawBody {
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Self::Proof(request) => request.chain_id,
+            Self::Input(request) => request.chain_id,
+        }
+    }
+
+    pub fn pool(&self) -> Address {
+        match self {
+            Self::Proof(request) => request.pool,
+            Self::Input(request) => request.pool,
+        }
+    }
+
+    pub fn quote(&self) -> &Quote {
+        match self {
+            Self::Proof(request) => &request.quote,
+            Self::Input(request) => &request.quote,
+        }
+    }
+
+    pub fn token_fee(&self) -> Option<&TokenFeeConsent> {
+        match self {
+            Self::Proof(request) => request.token_fee.as_ref(),
+            Self::Input(request) => request.token_fee.as_ref(),
+        }
+    }
+}
+
+/// Error body returned by the relayer with a non-success status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ErrorResponse {
+    pub error: String,
+    /// Machine-readable reason, for rejections by the relayer's policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Response of `POST /withdraw`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WithdrawResponse {
+    pub job_id: String,
+}
+
+/// Progress of a relay job.
+///
+/// A job is `queued` until an attempt starts, `verifying` while it is proven and simulated,
+/// `broadcasting` until the transaction is mined, and then `mined`. A mined transaction that a
+/// reorg drops is `reorged` until it is mined again. Failed attempts go back to `queued`, and the
+/// job is `failed` once it runs out of attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Verifying,
+    Broadcasting,
+    Mined,
+    Reorged,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Verifying => "verifying",
+            Self::Broadcasting => "broadcasting",
+            Self::Mined => "mined",
+            Self::Reorged => "reorged",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "queued" => Ok(Self::Queued),
+            "verifying" => Ok(Self::Verifying),
+            "broadcasting" => Ok(Self::Broadcasting),
+            "mined" => Ok(Self::Mined),
+            "reorged" => Ok(Self::Reorged),
+            "failed" => Ok(Self::Failed),
+            _ => Err(eyre::eyre!("unknown job status {s}")),
+        }
+    }
+}
+
+/// A relay job, returned by `GET /jobs/:id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Job {
+    /// Derived from the chain, pool and nullifier, so submitting the same withdrawal again
+    /// returns the same job.
+    pub id: String,
+    pub status: JobStatus,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::address")
+    )]
+    pub pool: Address,
+    /// Submission attempts made so far.
+    pub attempts: u32,
+    /// Latest transaction sent for the job. Kept when the job fails after sending it.
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::optional_bytes32")
+    )]
+    pub tx_hash: Option<B256>,
+    /// Block the withdrawal was mined in.
+    pub block_number: Option<u64>,
+    /// Whether the withdrawal is mined deep enough that the relayer stopped watching it.
+    pub settled: bool,
+    /// Gas used by the mined withdrawal.
+    pub gas_used: Option<u64>,
+    /// What the mined withdrawal cost the relayer, in wei.
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "pool_lib::schema::optional_uint256")
+    )]
+    pub gas_cost: Option<U256>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl Job {
+    /// Whether the job will not change anymore.
+    pub fn is_final(&self) -> bool {
+        self.settled || self.status == JobStatus::Failed
+    }
+}
+
+/// A step a job went through, streamed by `GET /jobs/:id/events` as the data of a
+/// server-sent event named after its `stage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JobEvent {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub stage: JobStage,
+    /// Unix timestamp of the step.
+    pub at: u64,
+}
+
+/// Stages of a relay job, in the order they happen. Proofs from the prover are reported only as
+/// started and finished, since the prover does not say how far along it is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum JobStage {
+    Queued,
+    /// The relayer started checking the withdrawal, proving it first if sent as an input.
+    Verifying,
+    /// A raw input was run through the program, taking `cycles`.
+    Executed {
+        cycles: u64,
+    },
+    Proving,
+    Proved,
+    /// The proof passed the pool's verifier in a simulation against the chain.
+    Verified,
+    Broadcasting,
+    /// A transaction was sent, or replaced by one paying more gas.
+    Sent {
+        #[cfg_attr(
+            feature = "schema",
+            schemars(schema_with = "pool_lib::schema::bytes32")
+        )]
+        tx_hash: B256,
+    },
+    Mined {
+        #[cfg_attr(
+            feature = "schema",
+            schemars(schema_with = "pool_lib::schema::bytes32")
+        )]
+        tx_hash: B256,
+        block_number: u64,
+    },
+    Reorged,
+    /// The withdrawal is deep enough that the relayer stopped watching it. Last event of a
+    /// successful job.
+    Settled,
+    /// An attempt failed and the job will be retried.
+    Retrying {
+        attempts: u32,
+        error: String,
+    },
+    /// The job ran out of attempts. Last event of a failed job.
+    Failed {
+        error: String,
+    },
+}
+
+impl JobStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Verifying => "verifying",
+            Self::Executed { .. } => "executed",
+            Self::Proving => "proving",
+            Self::Proved => "proved",
+            Self::Verified => "verified",
+            Self::Broadcasting => "broadcasting",
+            Self::Sent { .. } => "sent",
+            Self::Mined { .. } => "mined",
+            Self::Reorged => "reorged",
+            Self::Settled => "settled",
+            Self::Retrying { .. } => "retrying",
+            Self::Failed { .. } => "failed",
+        }
+    }
+
+    /// Whether no events follow this one.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Settled | Self::Failed { .. })
+    }
+}
+
+/// Why a relayer request failed.
+#[derive(Debug)]
+pub enum Error {
+    /// The relayer could not be reached or sent an unreadable response.
+    Http(reqwest::Error),
+    /// The relayer URL cannot be joined with an endpoint path.
+    Url(String),
+    /// The relayer answered with an error status. `code` is set for rejections by its policy,
+    /// like `fee_too_low` or `rate_limited`.
+    Rejected {
+        status: u16,
+        code: Option<String>,
+        message: String,
+    },
+    /// The relayer's quote is not signed by it or is for a different pool.
+    InvalidQuote(eyre::Report),
+    /// The relay job ran out of attempts.
+    JobFailed {
+        job_id: String,
+        tx_hash: Option<B256>,
+        error: String,
+    },
+    /// The relay job was not settled within the time waited for it, though it may still be.
+    Timeout {
+        job_id: String,
+        status: JobStatus,
+        timeout: Duration,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(e) => write!(f, "relayer request failed: {e}"),
+            Self::Url(e) => write!(f, "invalid relayer URL: {e}"),
+            Self::Rejected {
+                code: Some(code),
+                message,
+                ..
+            } => write!(f, "relayer rejected the request ({code}): {message}"),
+            Self::Rejected {
+                status, message, ..
+            } => write!(f, "relayer error {status}: {message}"),
+            Self::InvalidQuote(e) => write!(f, "invalid quote: {e}"),
+            Self::JobFailed {
+                tx_hash: Some(tx_hash),
+                error,
+                ..
+            } => write!(f, "relay job failed after sending {tx_hash}: {error}"),
+            Self::JobFailed { error, .. } => write!(f, "relay job failed: {error}"),
+            Self::Timeout {
+                job_id,
+                status,
+                timeout,
+            } => write!(
+                f,
+                "relay job {job_id} not settled after {timeout:?}, last {status:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::InvalidQuote(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e)
+    }
+}
+
+/// Turn a relayer error response into a [`Error::Rejected`] carrying its message.
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let (code, message) = match response.json::<ErrorResponse>().await {
+        Ok(ErrorResponse { error, code }) => (code, error),
+        Err(_) => (None, status.to_string()),
+    };
+    Err(Error::Rejected {
+        status: status.as_u16(),
+        code,
+        message,
+    })
+}
+
+/// Client for a relayer's HTTP API.
+#[derive(Debug, Clone)]
+pub struct RelayerClient {
+    url: Url,
+    http: reqwest::Client,
+}
+
+impl RelayerClient {
+    pub fn new(url: Url) -> Self {
+        Self::with_client(url, reqwest::Client::new())
+    }
+
+    /// A client sending requests through `http`, for callers that set their own timeouts,
+    /// proxies or TLS roots.
+    pub fn with_client(url: Url, http: reqwest::Client) -> Self {
+        Self { url, http }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url, Error> {
+        self.url.join(path).map_err(|e| Error::Url(e.to_string()))
+    }
+
+    /// Fetch the relayer's quote for withdrawing from `pool` on `chain_id`, in wei or in
+    /// `fee_token`, checking it is signed by the relayer and for that pool and token.
+    pub async fn quote(
+        &self,
+        chain_id: u64,
+        pool: Address,
+        fee_token: Option<Address>,
+    ) -> Result<Quote, Error> {
+        let mut query = vec![
+            ("chain_id", chain_id.to_string()),
+            ("pool", pool.to_string()),
+        ];
+        if let Some(token) = fee_token {
+            query.push(("fee_token", token.to_string()));
+        }
+        let response = self
+            .http
+            .get(self.endpoint("quote")?)
+            .query(&query)
+            .send()
+            .await?;
+        let quote: Quote = check_response(response).await?.json().await?;
+        if quote.chain_id != chain_id || quote.pool != pool {
+            return Err(Error::InvalidQuote(eyre::eyre!(
+                "relayer quoted for a different pool"
+            )));
+        }
+        if quote.fee_token != fee_token {
+            return Err(Error::InvalidQuote(eyre::eyre!(
+                "relayer quoted in a different fee token"
+            )));
+        }
+        quote.verify().map_err(Error::InvalidQuote)?;
+        Ok(quote)
+    }
+
+    /// Whether the relayer can take withdrawals now, as its `GET /readyz` reports.
+    pub async fn ready(&self) -> Result<bool, Error> {
+        let response = self.http.get(self.endpoint("readyz")?).send().await?;
+        Ok(response.status().is_success())
+    }
+
+    /// Hand a proven withdrawal to the relayer, returning the job id.
+    pub async fn withdraw(&self, request: &WithdrawRequest) -> Result<String, Error> {
+        self.submit(&WithdrawBody::Proof(request.clone())).await
+    }
+
+    /// Hand either kind of withdrawal to the relayer, returning the job id. Submitting the same
+    /// withdrawal again returns the same job.
+    pub async fn submit(&self, body: &WithdrawBody) -> Result<String, Error> {
+        let response = self
+            .http
+            .post(self.endpoint("withdraw")?)
+            .json(body)
+            .send()
+            .await?;
+        let response: WithdrawResponse = check_response(response).await?.json().await?;
+        Ok(response.job_id)
+    }
+
+    pub async fn job(&self, id: &str) -> Result<Job, Error> {
+        let response = self
+            .http
+            .get(self.endpoint(&format!("jobs/{id}"))?)
+            .send()
+            .await?;
+        Ok(check_response(response).await?.json().await?)
+    }
+
+    /// Poll job `id` every `interval` until it is settled or failed, for at most `timeout`.
+    #[instrument(skip(self))]
+    pub async fn wait_with_interval(
+        &self,
+        id: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<Job, Error> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut last = None;
+        loop {
+            let job = self.job(id).await?;
+            if last != Some(job.status) {
+                info!(status = ?job.status, tx_hash = ?job.tx_hash, "relay job update");
+                last = Some(job.status);
+            }
+            if job.status == JobStatus::Failed {
+                return Err(Error::JobFailed {
+                    job_id: job.id,
+                    tx_hash: job.tx_hash,
+                    error: job.error.unwrap_or_else(|| "unknown error".to_string()),
+                });
+            }
+            if job.settled {
+                return Ok(job);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    job_id: job.id,
+                    status: job.status,
+                    timeout,
+                });
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Poll job `id` until it is settled or failed, for at most `timeout`.
+    pub async fn wait(&self, id: &str, timeout: Duration) -> Result<Job, Error> {
+        self.wait_with_interval(id, Duration::from_secs(3), timeout)
+            .await
+    }
+}
@@ -1,5 +1,26 @@
 use sp1_build::build_program_with_args;
 
+/// Variables pinning the vkey hash of each embedded program, read by `verify_build`.
+const PINNED_VKEYS: [&str; 3] = [
+    "POOL_PROGRAM_VKEY",
+    "POOL_AGGREGATION_VKEY",
+    "POOL_DISCLOSURE_VKEY",
+];
+
 fn main() {
-    build_program_with_args("../program", Default::default())
+    build_program_with_args("../program", Default::default());
+    build_program_with_args("../aggregation", Default::default());
+    build_program_with_args("../disclosure", Default::default());
+    tonic_build::compile_protos("proto/prover.proto").expect("failed to compile protos");
+
+    for var in PINNED_VKEYS {
+        println!("cargo:rerun-if-env-changed={var}");
+        if let Ok(vkey) = std::env::var(var) {
+            let hex = vkey.strip_prefix("0x").unwrap_or_default();
+            assert!(
+                hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+                "{var} must be a 0x-prefixed 32-byte vkey hash"
+            );
+        }
+    }
 }
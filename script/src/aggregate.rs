@@ -0,0 +1,61 @@
+use alloy::primitives::{keccak256, Bytes, B256};
+use eyre::{bail, Result};
+use pool_lib::{compute_merkle_branch, schema, InclusionBranches};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{
+    include_elf, HashableKey, SP1Proof, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey,
+};
+
+/// The ELF of the program aggregating many withdrawal proofs into one.
+pub const AGGREGATION_ELF: &[u8] = include_elf!("pool-aggregation");
+
+/// What the contract needs to process one child withdrawal of an aggregated proof.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Opening {
+    #[schemars(schema_with = "schema::bytes")]
+    pub public_values: Bytes,
+    /// keccak256 of the public values, the leaf under `publicValuesRoot`.
+    #[schemars(schema_with = "schema::bytes32")]
+    pub leaf: B256,
+    pub branch: InclusionBranches,
+}
+
+/// Encode compressed withdrawal proofs the way the aggregation program reads them.
+pub fn aggregation_stdin(
+    children: &[SP1ProofWithPublicValues],
+    child_vk: &SP1VerifyingKey,
+) -> Result<SP1Stdin> {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&child_vk.hash_u32());
+    stdin.write(
+        &children
+            .iter()
+            .map(|child| child.public_values.to_vec())
+            .collect::<Vec<_>>(),
+    );
+    for child in children {
+        let SP1Proof::Compressed(proof) = &child.proof else {
+            bail!("only compressed proofs can be aggregated");
+        };
+        stdin.write_proof(*proof.clone(), child_vk.vk.clone());
+    }
+    Ok(stdin)
+}
+
+/// Openings of every child against the aggregated public values root.
+pub fn openings(children: &[SP1ProofWithPublicValues]) -> Vec<Opening> {
+    let leaves: Vec<B256> = children
+        .iter()
+        .map(|child| keccak256(child.public_values.as_slice()))
+        .collect();
+    children
+        .iter()
+        .enumerate()
+        .map(|(i, child)| Opening {
+            public_values: child.public_values.to_vec().into(),
+            leaf: leaves[i],
+            branch: compute_merkle_branch(&leaves, i),
+        })
+        .collect()
+}
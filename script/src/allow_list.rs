@@ -0,0 +1,50 @@
+use alloy::primitives::Address;
+use eyre::{ensure, Result, WrapErr};
+use pool_lib::association::AllowListBundle;
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// Read an allow-list bundle and check it is authentic.
+pub fn load(path: &Path) -> Result<AllowListBundle> {
+    let bundle: AllowListBundle = serde_json::from_slice(&std::fs::read(path)?)
+        .wrap_err_with(|| format!("reading allow-list {}", path.display()))?;
+    bundle.verify_signature()?;
+    Ok(bundle)
+}
+
+/// Read the bundles at `paths`, checking each is authentic, issued by one of `trusted` and for
+/// `chain_id`. Bundles outside their validity window are kept, with a warning, so they can be
+/// matched once valid; callers check the window when they use one.
+pub fn load_trusted(
+    paths: &[impl AsRef<Path>],
+    trusted: &[Address],
+    chain_id: u64,
+) -> Result<Vec<AllowListBundle>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    paths
+        .iter()
+        .map(|path| {
+            let path = path.as_ref();
+            let bundle = load(path)?;
+            ensure!(
+                trusted.contains(&bundle.issuer),
+                "allow-list {} is issued by untrusted {}",
+                path.display(),
+                bundle.issuer
+            );
+            ensure!(
+                bundle.chain_id == chain_id,
+                "allow-list {} is for chain {}",
+                path.display(),
+                bundle.chain_id
+            );
+            if !bundle.is_valid_at(now) {
+                warn!(path = %path.display(), "allow-list is not currently valid");
+            }
+            Ok(bundle)
+        })
+        .collect()
+}
@@ -0,0 +1,580 @@
+use crate::{api::ApiError, db};
+use alloy::{
+    network::EthereumWallet,
+    primitives::{keccak256, Address, PrimitiveSignature, B256},
+    providers::ProviderBuilder,
+    signers::{local::PrivateKeySigner, SignerSync},
+    sol,
+    sol_types::SolStruct,
+    transports::http::reqwest::Url,
+};
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    routing::get,
+    Json, Router,
+};
+use eyre::{ensure, eyre, Result};
+use pool_lib::{
+    association::{
+        association_domain, AllowList, AllowListBundle, AssociationRoot, SignedRoot,
+        ALLOW_LIST_VERSION,
+    },
+    compute_merkle_branch, compute_merkle_root, InclusionBranches,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, instrument};
+
+sol! {
+    #[sol(rpc)]
+    contract AssociationRegistry {
+        event RootPublished(
+            address indexed provider,
+            bytes32 indexed setId,
+            bytes32 root,
+            uint64 leafCount
+        );
+
+        function roots(address provider, bytes32 setId) external view returns (bytes32);
+
+        function publishRoot(bytes32 setId, bytes32 root, uint64 leafCount) external;
+    }
+}
+
+/// Id of the association set named `name`, used in signed roots and the registry.
+pub fn set_id(name: &str) -> B256 {
+    keccak256(name.as_bytes())
+}
+
+/// An association set and its latest signed root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetInfo {
+    pub name: String,
+    pub id: B256,
+    pub chain_id: u64,
+    pub pool: Address,
+    pub members: u64,
+    pub root: Option<SignedRoot>,
+    /// Transaction that published the latest root to the registry.
+    pub published_tx: Option<B256>,
+}
+
+/// Response of `GET /sets/:name/branch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AspBranch {
+    pub root: SignedRoot,
+    pub branches: InclusionBranches,
+}
+
+/// An association set provider: keeps curated allow-lists of a pool's deposits in the shared
+/// sqlite database and signs the root of each as an [`AssociationRoot`], so a withdrawal's
+/// `exclusionSetRoot` can be traced back to the provider that vouched for it.
+///
+/// Members keep the order they were added in. Removing one shifts the ones after it, so every
+/// change is followed by a new signed root.
+pub struct Asp {
+    conn: Mutex<Connection>,
+    signer: PrivateKeySigner,
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+fn set(conn: &Connection, name: &str) -> Result<(u64, Address)> {
+    let (chain_id, pool) = conn
+        .query_row(
+            "SELECT chain_id, pool FROM association_sets WHERE name = ?1",
+            params![name],
+            |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| eyre!("no association set {name}"))?;
+    Ok((chain_id, pool.parse()?))
+}
+
+fn members(conn: &Connection, name: &str) -> Result<Vec<B256>> {
+    let mut statement = conn.prepare(
+        "SELECT commitment FROM association_members WHERE set_name = ?1 ORDER BY position",
+    )?;
+    let rows = statement
+        .query_map(params![name], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    rows.iter().map(|c| Ok(c.parse()?)).collect()
+}
+
+fn latest(conn: &Connection, name: &str) -> Result<Option<(SignedRoot, Option<B256>)>> {
+    let (chain_id, pool) = set(conn, name)?;
+    let row = conn
+        .query_row(
+            "SELECT root, leaf_count, issued_at, signature, published_tx FROM association_roots
+             WHERE set_name = ?1 ORDER BY issued_at DESC, rowid DESC LIMIT 1",
+            params![name],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u64>(1)?,
+                    row.get::<_, u64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((root, leaf_count, issued_at, signature, published_tx)) = row else {
+        return Ok(None);
+    };
+    let signed = SignedRoot {
+        root: AssociationRoot {
+            setId: set_id(name),
+            root: root.parse()?,
+            leafCount: leaf_count,
+            issuedAt: issued_at,
+        },
+        chain_id,
+        pool,
+        signature: PrimitiveSignature::try_from(hex::decode(signature)?.as_slice())?,
+    };
+    Ok(Some((
+        signed,
+        published_tx.map(|tx| tx.parse()).transpose()?,
+    )))
+}
+
+impl Asp {
+    pub fn open(path: &Path, signer: PrivateKeySigner) -> Result<Self> {
+        Ok(Self {
+            conn: Mutex::new(db::open(path)?),
+            signer,
+        })
+    }
+
+    /// Address roots are signed with.
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Create an empty set over the deposits of `pool` and sign its empty root.
+    pub fn create(&self, name: &str, chain_id: u64, pool: Address) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let created = tx.execute(
+            "INSERT OR IGNORE INTO association_sets (name, chain_id, pool, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![name, chain_id, pool.to_string(), now()?],
+        )?;
+        ensure!(created == 1, "association set {name} already exists");
+        Self::sign_in(&tx, &self.signer, name)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn sets(&self) -> Result<Vec<SetInfo>> {
+        let conn = self.conn.lock().unwrap();
+        let names = conn
+            .prepare("SELECT name FROM association_sets ORDER BY name")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        names.iter().map(|name| Self::info(&conn, name)).collect()
+    }
+
+    pub fn set(&self, name: &str) -> Result<SetInfo> {
+        Self::info(&self.conn.lock().unwrap(), name)
+    }
+
+    fn info(conn: &Connection, name: &str) -> Result<SetInfo> {
+        let (chain_id, pool) = set(conn, name)?;
+        let members: u64 = conn.query_row(
+            "SELECT COUNT(*) FROM association_members WHERE set_name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?;
+        let (root, published_tx) = latest(conn, name)?.unzip();
+        Ok(SetInfo {
+            name: name.to_string(),
+            id: set_id(name),
+            chain_id,
+            pool,
+            members,
+            root,
+            published_tx: published_tx.flatten(),
+        })
+    }
+
+    pub fn members(&self, name: &str) -> Result<Vec<B256>> {
+        let conn = self.conn.lock().unwrap();
+        set(&conn, name)?;
+        members(&conn, name)
+    }
+
+    /// Add `commitments` to the end of the set, skipping ones already in it, and sign the new
+    /// root. Unless `unchecked`, every commitment must be a deposit the indexer has seen.
+    #[instrument(skip_all, fields(set = name))]
+    pub fn add(&self, name: &str, commitments: &[B256], unchecked: bool) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let (chain_id, pool) = set(&conn, name)?;
+        let tx = conn.transaction()?;
+        let mut added = 0;
+        for commitment in commitments {
+            if !unchecked {
+                let deposited = tx
+                    .query_row(
+                        "SELECT 1 FROM deposits
+                         WHERE chain_id = ?1 AND pool = ?2 AND commitment = ?3",
+                        params![chain_id, pool.to_string(), commitment.to_string()],
+                        |_| Ok(()),
+                    )
+                    .optional()?
+                    .is_some();
+                ensure!(
+                    deposited,
+                    "{commitment} is not an indexed deposit of {pool}, run the indexer or pass \
+                     --unchecked"
+                );
+            }
+            added += tx.execute(
+                "INSERT OR IGNORE INTO association_members (set_name, commitment, position)
+                 SELECT ?1, ?2, COALESCE(MAX(position), -1) + 1
+                 FROM association_members WHERE set_name = ?1",
+                params![name, commitment.to_string()],
+            )?;
+        }
+        Self::sign_in(&tx, &self.signer, name)?;
+        tx.commit()?;
+        info!(added, "added members");
+        Ok(added)
+    }
+
+    /// Remove `commitments` from the set and sign the new root.
+    #[instrument(skip_all, fields(set = name))]
+    pub fn remove(&self, name: &str, commitments: &[B256]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        set(&conn, name)?;
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        for commitment in commitments {
+            removed += tx.execute(
+                "DELETE FROM association_members WHERE set_name = ?1 AND commitment = ?2",
+                params![name, commitment.to_string()],
+            )?;
+        }
+        Self::sign_in(&tx, &self.signer, name)?;
+        tx.commit()?;
+        info!(removed, "removed members");
+        Ok(removed)
+    }
+
+    /// The latest signed root of the set, without signing one. Every change to the set signs
+    /// its new root, so it is the root of the current members.
+    pub fn root(&self, name: &str) -> Result<Option<SignedRoot>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(latest(&conn, name)?.map(|(signed, _)| signed))
+    }
+
+    /// The signed root of the set's current members, signing it if it changed since the last.
+    pub fn sign(&self, name: &str) -> Result<SignedRoot> {
+        let conn = self.conn.lock().unwrap();
+        Self::sign_in(&conn, &self.signer, name)
+    }
+
+    fn sign_in(conn: &Connection, signer: &PrivateKeySigner, name: &str) -> Result<SignedRoot> {
+        let (chain_id, pool) = set(conn, name)?;
+        let leaves = members(conn, name)?;
+        let root = compute_merkle_root(&leaves);
+        if let Some((signed, _)) = latest(conn, name)? {
+            if signed.root.root == root && signed.root.leafCount == leaves.len() as u64 {
+                return Ok(signed);
+            }
+        }
+
+        let typed = AssociationRoot {
+            setId: set_id(name),
+            root,
+            leafCount: leaves.len() as u64,
+            issuedAt: now()?,
+        };
+        let signature = signer
+            .sign_hash_sync(&typed.eip712_signing_hash(&association_domain(chain_id, pool)))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO association_roots
+             (set_name, root, leaf_count, issued_at, signature)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                name,
+                root.to_string(),
+                typed.leafCount,
+                typed.issuedAt,
+                hex::encode(signature.as_bytes())
+            ],
+        )?;
+        info!(set = name, %root, leaf_count = typed.leafCount, "signed association root");
+        Ok(SignedRoot {
+            root: typed,
+            chain_id,
+            pool,
+            signature,
+        })
+    }
+
+    /// Branch proving `commitment` is in the set, against its latest signed root. Signs
+    /// nothing, so serving branches never writes to the database.
+    pub fn branch(&self, name: &str, commitment: B256) -> Result<Option<AspBranch>> {
+        let conn = self.conn.lock().unwrap();
+        set(&conn, name)?;
+        let leaves = members(&conn, name)?;
+        let Some(index) = leaves.iter().position(|leaf| *leaf == commitment) else {
+            return Ok(None);
+        };
+        let (root, _) = latest(&conn, name)?
+            .ok_or_else(|| eyre!("association set {name} has no signed root"))?;
+        ensure!(
+            root.root.root == compute_merkle_root(&leaves),
+            "association set {name} changed since its root was signed, run `asp sign {name}`"
+        );
+        Ok(Some(AspBranch {
+            root,
+            branches: compute_merkle_branch(&leaves, index),
+        }))
+    }
+
+    /// Sign the set's current members as an [`AllowListBundle`] valid from now for `valid_for`
+    /// seconds, with the leaves included.
+    pub fn allow_list(&self, name: &str, valid_for: u64) -> Result<AllowListBundle> {
+        let conn = self.conn.lock().unwrap();
+        let (chain_id, pool) = set(&conn, name)?;
+        let leaves = members(&conn, name)?;
+        let valid_from = now()?;
+        let allow_list = AllowList {
+            setId: set_id(name),
+            root: compute_merkle_root(&leaves),
+            leafCount: leaves.len() as u64,
+            validFrom: valid_from,
+            validUntil: valid_from + valid_for,
+        };
+        let signature = self
+            .signer
+            .sign_hash_sync(&allow_list.eip712_signing_hash(&association_domain(chain_id, pool)))?;
+        Ok(AllowListBundle {
+            version: ALLOW_LIST_VERSION,
+            chain_id,
+            pool,
+            issuer: self.address(),
+            allow_list,
+            signature,
+            leaves,
+        })
+    }
+
+    /// Publish the set's latest signed root to the registry at `registry` from the signer's
+    /// account, so contracts and light clients can read it without trusting this server.
+    #[instrument(skip_all, fields(set = name, %registry))]
+    pub async fn publish(&self, rpc_url: Url, registry: Address, name: &str) -> Result<B256> {
+        let signed = self.sign(name)?;
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(self.signer.clone()))
+            .on_http(rpc_url);
+        let receipt = AssociationRegistry::new(registry, &provider)
+            .publishRoot(signed.root.setId, signed.root.root, signed.root.leafCount)
+            .send()
+            .await?
+            .get_receipt()
+            .await?;
+        ensure!(
+            receipt.status(),
+            "publishing reverted in {}",
+            receipt.transaction_hash
+        );
+        self.conn.lock().unwrap().execute(
+            "UPDATE association_roots SET published_tx = ?1
+             WHERE set_name = ?2 AND root = ?3 AND issued_at = ?4",
+            params![
+                receipt.transaction_hash.to_string(),
+                name,
+                signed.root.root.to_string(),
+                signed.root.issuedAt
+            ],
+        )?;
+        Ok(receipt.transaction_hash)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AspBranchQuery {
+    pub commitment: B256,
+}
+
+/// HTTP API of `asp`. Only reads are served, and none of them signs or writes anything; sets
+/// are curated and signed through the CLI.
+pub fn router(asp: Arc<Asp>) -> Router {
+    Router::new()
+        .route("/sets", get(sets))
+        .route("/sets/:name", get(set_info))
+        .route("/sets/:name/root", get(root))
+        .route("/sets/:name/members", get(set_members))
+        .route("/sets/:name/branch", get(branch))
+        .with_state(asp)
+}
+
+/// Not-found for a missing set, internal errors otherwise.
+fn lookup_error(asp: &Asp, name: &str, e: eyre::Report) -> ApiError {
+    let conn = asp.conn.lock().unwrap();
+    match set(&conn, name) {
+        Err(missing) => ApiError::not_found(missing.to_string()),
+        Ok(_) => e.into(),
+    }
+}
+
+async fn sets(State(asp): State<Arc<Asp>>) -> Result<Json<Vec<SetInfo>>, ApiError> {
+    Ok(Json(asp.sets()?))
+}
+
+async fn set_info(
+    State(asp): State<Arc<Asp>>,
+    UrlPath(name): UrlPath<String>,
+) -> Result<Json<SetInfo>, ApiError> {
+    asp.set(&name)
+        .map(Json)
+        .map_err(|e| lookup_error(&asp, &name, e))
+}
+
+async fn root(
+    State(asp): State<Arc<Asp>>,
+    UrlPath(name): UrlPath<String>,
+) -> Result<Json<SignedRoot>, ApiError> {
+    asp.root(&name)
+        .map_err(|e| lookup_error(&asp, &name, e))?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("association set {name} has no signed root")))
+}
+
+async fn set_members(
+    State(asp): State<Arc<Asp>>,
+    UrlPath(name): UrlPath<String>,
+) -> Result<Json<Vec<B256>>, ApiError> {
+    asp.members(&name)
+        .map(Json)
+        .map_err(|e| lookup_error(&asp, &name, e))
+}
+
+async fn branch(
+    State(asp): State<Arc<Asp>>,
+    UrlPath(name): UrlPath<String>,
+    Query(query): Query<AspBranchQuery>,
+) -> Result<Json<AspBranch>, ApiError> {
+    asp.branch(&name, query.commitment)
+        .map_err(|e| lookup_error(&asp, &name, e))?
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "{} is not in association set {name}",
+                query.commitment
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pool_lib::compute_inclusion_root;
+
+    const POOL: Address = Address::repeat_byte(0x42);
+
+    /// A provider with a fresh database holding the empty set `name`.
+    fn asp(name: &str) -> Asp {
+        let path =
+            std::env::temp_dir().join(format!("pool-asp-{}-{name}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let asp = Asp::open(&path, PrivateKeySigner::random()).unwrap();
+        asp.create(name, 1, POOL).unwrap();
+        asp
+    }
+
+    fn commitments(n: u8) -> Vec<B256> {
+        (1..=n).map(B256::with_last_byte).collect()
+    }
+
+    #[test]
+    fn every_change_signs_the_root_of_the_members() {
+        let asp = asp("changes");
+        let empty = asp.root("changes").unwrap().unwrap();
+        assert_eq!(empty.root.leafCount, 0);
+
+        let members = commitments(3);
+        assert_eq!(asp.add("changes", &members, true).unwrap(), 3);
+        // Members already in the set are skipped.
+        assert_eq!(asp.add("changes", &members[..1], true).unwrap(), 0);
+        let signed = asp.root("changes").unwrap().unwrap();
+        assert_eq!(signed.root.root, compute_merkle_root(&members));
+        assert_eq!(signed.root.leafCount, 3);
+        assert_eq!(signed.signer().unwrap(), asp.address());
+
+        assert_eq!(asp.remove("changes", &members[1..2]).unwrap(), 1);
+        let rest = [members[0], members[2]];
+        assert_eq!(asp.members("changes").unwrap(), rest);
+        assert_eq!(
+            asp.root("changes").unwrap().unwrap().root.root,
+            compute_merkle_root(&rest)
+        );
+    }
+
+    #[test]
+    fn branches_prove_members_against_the_signed_root() {
+        let asp = asp("branches");
+        let members = commitments(5);
+        asp.add("branches", &members, true).unwrap();
+        let branch = asp.branch("branches", members[3]).unwrap().unwrap();
+        assert_eq!(
+            compute_inclusion_root(&members[3], &branch.branches),
+            branch.root.root.root
+        );
+        assert!(asp
+            .branch("branches", B256::repeat_byte(9))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn only_indexed_deposits_are_added() {
+        let asp = asp("indexed");
+        let members = commitments(1);
+        assert!(asp.add("indexed", &members, false).is_err());
+        assert!(asp.members("indexed").unwrap().is_empty());
+
+        asp.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO deposits
+                 (chain_id, pool, leaf_index, commitment, block_number, tx_hash)
+                 VALUES (1, ?1, 0, ?2, 1, ?3)",
+                params![
+                    POOL.to_string(),
+                    members[0].to_string(),
+                    B256::ZERO.to_string()
+                ],
+            )
+            .unwrap();
+        assert_eq!(asp.add("indexed", &members, false).unwrap(), 1);
+    }
+
+    #[test]
+    fn allow_lists_verify_for_their_issuer() {
+        let asp = asp("export");
+        let members = commitments(4);
+        asp.add("export", &members, true).unwrap();
+        let bundle = asp.allow_list("export", 60).unwrap();
+        let now = bundle.allow_list.validFrom;
+        bundle.verify(&[asp.address()], now).unwrap();
+        assert!(bundle.verify(&[Address::ZERO], now).is_err());
+        assert!(bundle.verify(&[asp.address()], now + 61).is_err());
+        assert_eq!(
+            compute_inclusion_root(&members[2], &bundle.branch(members[2]).unwrap()),
+            bundle.allow_list.root
+        );
+    }
+}
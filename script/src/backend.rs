@@ -0,0 +1,137 @@
+use crate::{
+    keys::KeyCache,
+    prove::{withdrawal_stdin, DynProver, ProofMode, ProverArgs, ELF},
+};
+use alloy::primitives::{Bytes, B256};
+use eyre::{eyre, Result};
+use pool_lib::WithdrawalInput;
+use sp1_sdk::{HashableKey, SP1ProofWithPublicValues, SP1ProvingKey, SP1VerifyingKey};
+use std::sync::{Arc, OnceLock};
+
+/// Result of running the withdrawal program without proving it.
+#[derive(Debug, Clone)]
+pub struct Execution {
+    /// ABI-encoded `WithdrawalData` the program committed.
+    pub public_values: Bytes,
+    pub cycles: u64,
+}
+
+/// A withdrawal proof from any backend.
+#[derive(Debug, Clone)]
+pub struct Proof {
+    /// ABI-encoded `WithdrawalData` the proof commits to.
+    pub public_values: Bytes,
+    /// Proof to pass to `Pool.withdraw`. Empty for proofs that cannot be verified onchain.
+    pub proof: Bytes,
+    /// The backend's own encoding of the proof, which [`ZkBackend::verify`] and the backend's
+    /// tooling read.
+    pub encoded: Vec<u8>,
+}
+
+/// A zkVM proving the withdrawal program, which checks inputs with pool-lib's
+/// `process_withdrawal` whatever it runs on. Methods block, so call them off the async runtime.
+pub trait ZkBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Derive the proving and verifying keys, if the backend has them, so later calls do not.
+    fn setup(&self) -> Result<()>;
+
+    /// Identifier of the program the pool's verifier accepts, as stored in `programVkey`.
+    fn vkey(&self) -> Result<B256>;
+
+    fn execute(&self, input: &WithdrawalInput) -> Result<Execution>;
+
+    fn prove(&self, input: &WithdrawalInput, mode: ProofMode) -> Result<Proof>;
+
+    /// Check a proof in the backend's own encoding, returning its public values.
+    fn verify(&self, encoded: &[u8]) -> Result<Bytes>;
+}
+
+/// The SP1 zkVM running the embedded withdrawal program.
+pub struct Sp1Backend {
+    prover: DynProver,
+    cache: Option<KeyCache>,
+    keys: OnceLock<(SP1ProvingKey, SP1VerifyingKey)>,
+}
+
+impl Sp1Backend {
+    pub fn new(prover: DynProver) -> Self {
+        Self {
+            prover,
+            cache: None,
+            keys: OnceLock::new(),
+        }
+    }
+
+    /// Load and save the keys in `cache` instead of deriving them every run.
+    pub fn with_key_cache(mut self, cache: KeyCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    fn keys(&self) -> &(SP1ProvingKey, SP1VerifyingKey) {
+        self.keys.get_or_init(|| match &self.cache {
+            Some(cache) => cache.setup(&*self.prover, ELF),
+            None => self.prover.setup(ELF),
+        })
+    }
+}
+
+impl ZkBackend for Sp1Backend {
+    fn name(&self) -> &'static str {
+        "sp1"
+    }
+
+    fn setup(&self) -> Result<()> {
+        self.keys();
+        Ok(())
+    }
+
+    fn vkey(&self) -> Result<B256> {
+        Ok(self.keys().1.bytes32().parse()?)
+    }
+
+    fn execute(&self, input: &WithdrawalInput) -> Result<Execution> {
+        let (output, report) = self
+            .prover
+            .execute(ELF, &withdrawal_stdin(input)?)
+            .map_err(|e| eyre!("execution failed: {e}"))?;
+        Ok(Execution {
+            public_values: output.to_vec().into(),
+            cycles: report.total_instruction_count(),
+        })
+    }
+
+    fn prove(&self, input: &WithdrawalInput, mode: ProofMode) -> Result<Proof> {
+        let proof = self
+            .prover
+            .prove(&self.keys().0, &withdrawal_stdin(input)?, mode.into())
+            .map_err(|e| eyre!("proving failed: {e}"))?;
+        let onchain = match mode {
+            ProofMode::Compressed => Bytes::new(),
+            ProofMode::Groth16 | ProofMode::Plonk => proof.bytes().into(),
+        };
+        Ok(Proof {
+            public_values: proof.public_values.to_vec().into(),
+            proof: onchain,
+            encoded: bincode::serialize(&proof)?,
+        })
+    }
+
+    fn verify(&self, encoded: &[u8]) -> Result<Bytes> {
+        let proof: SP1ProofWithPublicValues = bincode::deserialize(encoded)?;
+        self.prover
+            .verify(&proof, &self.keys().1)
+            .map_err(|e| eyre!("invalid proof: {e}"))?;
+        Ok(proof.public_values.to_vec().into())
+    }
+}
+
+impl ProverArgs {
+    /// The selected prover as a [`ZkBackend`].
+    pub fn backend(&self) -> Result<Arc<dyn ZkBackend>> {
+        Ok(Arc::new(
+            Sp1Backend::new(self.build()?).with_key_cache(self.keys.clone()),
+        ))
+    }
+}
@@ -0,0 +1,62 @@
+use alloy::primitives::B256;
+use eyre::Result;
+use reqwest::Url;
+use serde::Deserialize;
+use tracing::instrument;
+
+/// The execution block committed to by the latest finalized beacon block.
+#[derive(Debug, Clone)]
+pub struct FinalizedExecutionBlock {
+    pub slot: u64,
+    pub block_number: u64,
+    pub block_hash: B256,
+    /// Whether the beacon node has not fully verified the execution payload yet.
+    pub execution_optimistic: bool,
+}
+
+#[derive(Deserialize)]
+struct BlockResponse {
+    execution_optimistic: bool,
+    data: BlockData,
+}
+
+#[derive(Deserialize)]
+struct BlockData {
+    message: BeaconBlock,
+}
+
+/// Beacon API integers are decimal strings.
+#[derive(Deserialize)]
+struct BeaconBlock {
+    slot: String,
+    body: BeaconBlockBody,
+}
+
+#[derive(Deserialize)]
+struct BeaconBlockBody {
+    execution_payload: ExecutionPayload,
+}
+
+#[derive(Deserialize)]
+struct ExecutionPayload {
+    block_number: String,
+    block_hash: B256,
+}
+
+/// Fetch the finalized beacon block from a consensus-layer Beacon API and return the execution
+/// block it commits to.
+#[instrument]
+pub async fn finalized_execution_block(beacon_url: &Url) -> Result<FinalizedExecutionBlock> {
+    let response: BlockResponse = reqwest::get(beacon_url.join("eth/v2/beacon/blocks/finalized")?)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let block = response.data.message;
+    Ok(FinalizedExecutionBlock {
+        slot: block.slot.parse()?,
+        block_number: block.body.execution_payload.block_number.parse()?,
+        block_hash: block.body.execution_payload.block_hash,
+        execution_optimistic: response.execution_optimistic,
+    })
+}
@@ -0,0 +1,215 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, B256},
+    providers::{Provider, RootProvider},
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use clap::{Parser, Subcommand};
+use eyre::{bail, Result};
+use pool_script::{
+    asp::{router, Asp},
+    checkpoint::write_atomic,
+    logging::LogArgs,
+    secure::SecretParser,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tracing::info;
+
+/// Association set provider: curates allow-lists of deposits and serves their signed roots and
+/// branches.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    log: LogArgs,
+
+    /// Key roots are signed and published with.
+    #[clap(
+        long,
+        global = true,
+        env = "ASP_PRIVATE_KEY",
+        value_parser = SecretParser::<PrivateKeySigner>::new()
+    )]
+    private_key: PrivateKeySigner,
+
+    /// Database shared with the indexer, whose deposits members are checked against.
+    #[clap(
+        long,
+        global = true,
+        env = "POOL_DB",
+        default_value = ".pool/pool.sqlite"
+    )]
+    db: PathBuf,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Create an empty association set over a pool's deposits.
+    Create {
+        name: String,
+
+        /// Pool address.
+        #[clap(long)]
+        pool: Address,
+
+        /// Chain the pool is on. Read from `--rpc-url` if not set.
+        #[clap(long)]
+        chain_id: Option<u64>,
+
+        #[clap(long, env = "RPC_URL")]
+        rpc_url: Option<Url>,
+    },
+    /// Add deposits to a set and sign its new root.
+    Add {
+        name: String,
+
+        commitments: Vec<B256>,
+
+        /// Add commitments the indexer has not seen deposited.
+        #[clap(long)]
+        unchecked: bool,
+    },
+    /// Remove deposits from a set and sign its new root.
+    Remove {
+        name: String,
+        commitments: Vec<B256>,
+    },
+    /// Sign a set's root if its members changed since the last one, such as by editing the
+    /// database directly.
+    Sign { name: String },
+    /// List the sets and their latest roots.
+    List,
+    /// Publish a set's latest root to the association registry.
+    Publish {
+        name: String,
+
+        /// Address of the `AssociationRegistry` contract.
+        #[clap(long, env = "ASP_REGISTRY")]
+        registry: Address,
+
+        #[clap(long, env = "RPC_URL")]
+        rpc_url: Url,
+    },
+    /// Write a set as a signed allow-list bundle for distribution out of band.
+    ExportAllowList {
+        name: String,
+
+        /// Seconds from now the bundle is valid for.
+        #[clap(long, default_value_t = 7 * 24 * 60 * 60)]
+        valid_for: u64,
+
+        /// Where to write the bundle.
+        #[clap(long)]
+        out: PathBuf,
+    },
+    /// Serve the sets over HTTP.
+    Serve {
+        #[clap(long, env = "ASP_LISTEN", default_value = "0.0.0.0:3001")]
+        listen: SocketAddr,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    let args = Args::parse();
+    args.log.init();
+
+    let asp = Asp::open(&args.db, args.private_key)?;
+    match args.command {
+        Command::Create {
+            name,
+            pool,
+            chain_id,
+            rpc_url,
+        } => {
+            let chain_id = match (chain_id, rpc_url) {
+                (Some(chain_id), _) => chain_id,
+                (None, Some(rpc_url)) => {
+                    RootProvider::<Ethereum>::new_http(rpc_url)
+                        .get_chain_id()
+                        .await?
+                }
+                (None, None) => bail!("pass --chain-id or --rpc-url"),
+            };
+            asp.create(&name, chain_id, pool)?;
+            println!("Created association set {name} over pool {pool} on chain {chain_id}");
+        }
+        Command::Add {
+            name,
+            commitments,
+            unchecked,
+        } => {
+            let added = asp.add(&name, &commitments, unchecked)?;
+            let root = asp.sign(&name)?;
+            println!(
+                "Added {added} deposits, {} members, root {}",
+                root.root.leafCount, root.root.root
+            );
+        }
+        Command::Remove { name, commitments } => {
+            let removed = asp.remove(&name, &commitments)?;
+            let root = asp.sign(&name)?;
+            println!(
+                "Removed {removed} deposits, {} members, root {}",
+                root.root.leafCount, root.root.root
+            );
+        }
+        Command::Sign { name } => {
+            let root = asp.sign(&name)?;
+            println!(
+                "Set {name}: {} members, root {}",
+                root.root.leafCount, root.root.root
+            );
+        }
+        Command::List => {
+            for set in asp.sets()? {
+                let root = set
+                    .root
+                    .map(|signed| signed.root.root.to_string())
+                    .unwrap_or_else(|| "unsigned".to_string());
+                let published = set
+                    .published_tx
+                    .map(|tx| format!(", published in {tx}"))
+                    .unwrap_or_default();
+                println!(
+                    "{}: pool {} on chain {}, {} members, root {root}{published}",
+                    set.name, set.pool, set.chain_id, set.members
+                );
+            }
+        }
+        Command::Publish {
+            name,
+            registry,
+            rpc_url,
+        } => {
+            let tx_hash = asp.publish(rpc_url, registry, &name).await?;
+            println!("Published the root of {name} in {tx_hash}");
+        }
+        Command::ExportAllowList {
+            name,
+            valid_for,
+            out,
+        } => {
+            let bundle = asp.allow_list(&name, valid_for)?;
+            write_atomic(&out, &serde_json::to_vec_pretty(&bundle)?)?;
+            println!(
+                "Wrote {} members of {name} valid until {} to {}",
+                bundle.allow_list.leafCount,
+                bundle.allow_list.validUntil,
+                out.display()
+            );
+            println!("Root: {}", bundle.allow_list.root);
+        }
+        Command::Serve { listen } => {
+            info!(%listen, signer = %asp.address(), "serving association sets");
+            let listener = tokio::net::TcpListener::bind(listen).await?;
+            axum::serve(listener, router(Arc::new(asp))).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, B256, U256},
+    providers::{Provider, RootProvider},
+    transports::http::reqwest::Url,
+};
+use clap::{Parser, Subcommand};
+use eyre::{ensure, Result};
+use pool_lib::watch::WatchKey;
+use pool_script::{
+    checkpoint::write_atomic,
+    db,
+    indexer::{graphql, router, Indexer, IndexerConfig},
+    logging::LogArgs,
+    lookup::DepositLookup,
+    secure::SecretParser,
+    snapshot::DEFAULT_CHUNK_SIZE,
+    webhook::WebhookArgs,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tracing::{info, warn};
+
+/// Indexer daemon: keeps the deposits and withdrawals of pools in a sqlite database.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    log: LogArgs,
+
+    #[clap(long, global = true, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Database shared with the CLI and relayer.
+    #[clap(
+        long,
+        global = true,
+        env = "POOL_DB",
+        default_value = ".pool/pool.sqlite"
+    )]
+    db: PathBuf,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a snapshot of a pool's deposit tree at the last indexed block.
+    ExportSnapshot(ExportSnapshotArgs),
+    /// Write the commitment lookup of a pool at the last indexed block, which `pool withdraw`
+    /// reads from its data directory to find deposits without searching.
+    ExportLookup(ExportLookupArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    /// Pools to index.
+    #[clap(long = "pool", env = "INDEXER_POOLS", value_delimiter = ',')]
+    pools: Vec<Address>,
+
+    /// Block to start indexing new pools from, usually their deployment block.
+    #[clap(long, default_value_t = 0)]
+    from_block: u64,
+
+    /// Recent blocks kept to detect and roll back reorgs.
+    #[clap(long, default_value_t = 64)]
+    reorg_depth: u64,
+
+    /// Blocks requested per `eth_getLogs` call.
+    #[clap(long, default_value_t = 2000)]
+    batch_size: u64,
+
+    /// Seconds between polls for new blocks.
+    #[clap(long, default_value_t = 12)]
+    poll_interval: u64,
+
+    /// Commitments whose deposits are sent to the webhooks.
+    #[clap(long = "watch", env = "INDEXER_WATCH", value_delimiter = ',')]
+    watch: Vec<B256>,
+
+    /// Watch keys of notes whose deposits and withdrawals are sent to the webhooks.
+    #[clap(
+        long = "watch-key",
+        env = "INDEXER_WATCH_KEYS",
+        value_delimiter = ',',
+        value_parser = SecretParser::<WatchKey>::new()
+    )]
+    watch_keys: Vec<WatchKey>,
+
+    #[clap(flatten)]
+    webhooks: WebhookArgs,
+
+    /// Alert on withdrawals from pools whose denomination is at least this many wei.
+    #[clap(long)]
+    large_withdrawal: Option<U256>,
+
+    /// Serve `GET /roots`, `GET /branch` and `POST /graphql` on this address.
+    #[clap(long, env = "INDEXER_LISTEN")]
+    listen: Option<SocketAddr>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportSnapshotArgs {
+    /// Pool address.
+    address: Address,
+
+    /// Where to write the snapshot.
+    #[clap(long)]
+    out: PathBuf,
+
+    /// Leaves per checksummed chunk.
+    #[clap(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+    chunk_size: u32,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportLookupArgs {
+    /// Pool address.
+    address: Address,
+
+    /// `pool` data directory to write the lookup to.
+    #[clap(long, env = "POOL_DATA_DIR", default_value = ".pool")]
+    data_dir: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    let args = Args::parse();
+    args.log.init();
+
+    let provider = RootProvider::<Ethereum>::new_http(args.rpc_url);
+    let chain_id = provider.get_chain_id().await?;
+    let indexer = Arc::new(Indexer::open(
+        provider,
+        IndexerConfig {
+            chain_id,
+            pools: args.run.pools.clone(),
+            from_block: args.run.from_block,
+            reorg_depth: args.run.reorg_depth,
+            batch_size: args.run.batch_size,
+            watch: args
+                .run
+                .watch
+                .iter()
+                .copied()
+                .chain(args.run.watch_keys.iter().map(|key| key.commitment))
+                .collect(),
+            watch_nullifiers: args
+                .run
+                .watch_keys
+                .iter()
+                .map(|key| key.nullifier)
+                .collect(),
+            notifier: args.run.webhooks.notifier(),
+            large_withdrawal: args.run.large_withdrawal,
+        },
+        &args.db,
+    )?);
+
+    match args.command {
+        None => {
+            ensure!(!args.run.pools.is_empty(), "no pools to index, pass --pool");
+            info!(chain_id, pools = ?args.run.pools, db = %args.db.display(), "starting indexer");
+            if let Some(listen) = args.run.listen {
+                let listener = tokio::net::TcpListener::bind(listen).await?;
+                let schema = graphql::schema(db::open(&args.db)?, chain_id);
+                let app = router(indexer.clone()).merge(graphql::router(schema));
+                tokio::spawn(async move {
+                    if let Err(e) = axum::serve(listener, app).await {
+                        warn!("server failed: {e}");
+                    }
+                });
+                info!(%listen, "serving API");
+            }
+            indexer
+                .run(Duration::from_secs(args.run.poll_interval))
+                .await?;
+        }
+        Some(Command::ExportSnapshot(export)) => {
+            let snapshot = indexer.snapshot(export.address).await?;
+            let mut bytes = Vec::new();
+            snapshot.write(&mut bytes, export.chunk_size)?;
+            write_atomic(&export.out, &bytes)?;
+            println!(
+                "Wrote {} deposits at block {} to {}",
+                snapshot.leaves.len(),
+                snapshot.block_number,
+                export.out.display()
+            );
+            println!("Root: {:?}", snapshot.root());
+        }
+        Some(Command::ExportLookup(export)) => {
+            let snapshot = indexer.snapshot(export.address).await?;
+            let path = DepositLookup::path(&export.data_dir, chain_id, export.address);
+            DepositLookup::write(&path, &snapshot.leaves, snapshot.block_number)?;
+            println!(
+                "Wrote lookup of {} deposits at block {} to {}",
+                snapshot.leaves.len(),
+                snapshot.block_number,
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
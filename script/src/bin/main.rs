@@ -1,174 +1,2285 @@
 use alloy::{
-    consensus::BlockHeader,
     eips::BlockNumberOrTag,
     network::Ethereum,
-    primitives::{Address, B256, U256},
+    primitives::{Address, Bytes, B256, U256},
     providers::{Provider, RootProvider},
     rpc::types::BlockTransactionsKind,
-    sol,
+    signers::{local::PrivateKeySigner, SignerSync},
+    sol_types::{SolStruct, SolValue},
     transports::http::reqwest::Url,
 };
-use clap::Parser;
-use eyre::{ensure, Result};
-use pool_lib::{compute_commitment, compute_storage_keys, process_withdrawal, WithdrawalInput};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use eyre::{ensure, eyre, OptionExt, Result};
+use pool_lib::{
+    compute_commitment, compute_nft_commitment, decode_any_public_values,
+    disclosure::DisclosureData,
+    fixture,
+    intent::{intent_domain, SignedIntent, WithdrawalIntent},
+    note::Note,
+    process_any_withdrawal,
+    split::Split,
+    view::ViewKey,
+    watch::WatchKey,
+    withdrawal_public_values, AggregateData,
+};
+use pool_relayer_client::{RelayerClient, WithdrawRequest};
+use pool_script::{
+    aggregate::{aggregation_stdin, openings, AGGREGATION_ELF},
+    allow_list,
+    bundle::ProofBundle,
+    checkpoint::{write_atomic, Checkpoint},
+    db,
+    deposit::{send_commit, send_deposit, send_deposits, send_reveal},
+    disclosure::{disclosure_input, disclosure_stdin, DISCLOSURE_ELF},
+    ens::AddressOrName,
+    estimate::{
+        Estimate, HardwareProfile, GROTH16_VERIFY_GAS, PLONK_VERIFY_GAS, WITHDRAW_OVERHEAD_GAS,
+    },
+    gas_report::{measure, measure_aggregate},
+    index::{block_hash, deposits_length, root_status, DepositIndex},
+    jsonrpc::{router, JsonRpcConfig, JsonRpcServer},
+    linkage::check_linkage,
+    logging::LogArgs,
+    lookup::DepositLookup,
+    marketplace::{cheapest, RelayerListArgs},
+    notes::{NoteRecord, NoteStore, WatchedNote},
+    pool::Pool,
+    prove::{
+        prove_checkpointed, run_blocking, withdrawal_stdin, Cancelled, InputSize, ProofMode,
+        ProverArgs, ELF,
+    },
+    recover::{indexed_pools, recover_pool, Mnemonic, NoteSeed, DEFAULT_GAP_LIMIT},
+    rehearsal::{rehearse, RehearsalPool},
+    safe::{hosted_service, safe_tx_status, wait_for_execution, SafeProposer},
+    schema::schemas,
+    secure::{self, scrub_secret, Locked, SecretParser},
+    simulate::{check_public_values, simulate_withdrawal},
+    snapshot::Snapshot,
+    stats::{fetch_activity, PoolStats},
+    status::{deposit_origin, nullifier_status, NoteOrSecret},
+    submit::{submit_direct, submit_private, Withdrawal, FLASHBOTS_PROTECT_RPC},
+    token_fee::sign_token_fee,
+    userop::{Paymaster, UserOpSubmitter, ENTRY_POINT_V07},
+    verify_build::{
+        check_pool_vkey, rebuild, vkey, BuildId, AGGREGATION_PROGRAM, DISCLOSURE_PROGRAM,
+        SP1_DOCKER_TAG, WITHDRAWAL_PROGRAM,
+    },
+    watch::sync_watched,
+    witness::{build_witness, parse_split, BridgeArgs, WitnessArgs},
+};
 use rand::Rng;
-use sp1_sdk::{include_elf, setup_logger, ProverClient, SP1Stdin};
-use std::io::Write;
-
-/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
-pub const ELF: &[u8] = include_elf!("pool-program");
-
-sol! {
-    #[sol(rpc)]
-    contract Pool {
-        bytes32[] public deposits;
-    }
-}
+use sp1_sdk::{Prover, ProverClient, SP1ProofWithPublicValues};
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, info_span, warn, Instrument};
 
 // CLI with deposit and withdraw commands
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(flatten)]
+    log: LogArgs,
+
+    /// Directory for the local deposit index and other state.
+    #[clap(long, global = true, env = "POOL_DATA_DIR", default_value = ".pool")]
+    data_dir: PathBuf,
+
+    /// Lock all of the process's memory, so no copy of a note secret it makes is swapped out.
+    /// Needs a memory lock limit (`ulimit -l`) above what it uses, which proving locally exceeds.
+    #[clap(long, global = true, env = "POOL_LOCK_MEMORY")]
+    lock_memory: bool,
+
     #[clap(subcommand)]
     command: Command,
 }
 
 #[derive(Parser, Debug)]
 enum Command {
+    /// Generate a secret and commitment for a deposit.
     Deposit(DepositArgs),
+    /// Reveal a commit-reveal deposit whose reveal did not go through.
+    Reveal(RevealArgs),
+    /// Find the notes derived from a mnemonic among an indexer's deposits and restore them.
+    Recover(RecoverArgs),
+    /// Follow notes by their watch keys, without their secrets.
+    Watch(WatchArgs),
+    /// Build a withdrawal witness and optionally execute or prove it.
     Withdraw(WithdrawArgs),
+    /// Resume an interrupted proof from its checkpoint.
+    Resume(ResumeArgs),
+    /// Aggregate compressed withdrawal proofs into one onchain-verifiable proof.
+    Aggregate(AggregateArgs),
+    /// Prove to an auditor which deposit a note's withdrawal spent.
+    Disclose(DiscloseArgs),
+    /// Check a disclosure proof and print what it discloses.
+    VerifyDisclosure(VerifyDisclosureArgs),
+    /// Produce a signed intent a relayer can countersign.
+    RequestRelay(RequestRelayArgs),
+    /// Compare the fee quotes of relayers.
+    Relayers(RelayersArgs),
+    /// Submit a proven withdrawal onchain.
+    Submit(SubmitArgs),
+    /// Show the owners' confirmations of a withdrawal proposed to a Safe.
+    SafeStatus(SafeStatusArgs),
+    /// Convert a proof file into a JSON bundle that SP1 verifier tooling reads.
+    ExportProof(ExportProofArgs),
+    /// Check a proof file or bundle verifies against the program.
+    VerifyProof(VerifyProofArgs),
+    /// Check whether a note has been withdrawn.
+    NullifierStatus(NullifierStatusArgs),
+    /// Summarize a pool's activity from an indexer, and how exposed your notes in it are.
+    Stats(StatsArgs),
+    /// Print the view key of a note, which lets an auditor find its deposit but not withdraw it.
+    ExportViewkey(ExportViewkeyArgs),
+    /// Print the watch key of a note, which lets a wallet follow it but not withdraw it.
+    ExportWatchkey(ExportWatchkeyArgs),
+    /// Find the deposit of a view key and who made it.
+    Audit(AuditArgs),
+    /// Project cycles, proving time, cost and gas for a withdrawal.
+    Estimate(EstimateArgs),
+    /// Execute a fixed synthetic withdrawal and fail if it takes more cycles than a budget.
+    CyclesCheck(CyclesCheckArgs),
+    /// Check the contract interprets public values the same way as pool-lib.
+    DiffCheck(DiffCheckArgs),
+    /// Measure onchain verification gas for proofs on a local anvil fork.
+    GasReport(GasReportArgs),
+    /// Rehearse a deposit and withdrawal end to end on a local anvil fork.
+    Simulate(SimulateArgs),
+    /// Rebuild the guest reproducibly and check it matches the embedded and deployed circuit.
+    VerifyBuild(VerifyBuildArgs),
+    /// Sync the local deposit index.
+    Index(IndexArgs),
+    /// Compare the local deposit index against the chain.
+    RootStatus(IndexArgs),
+    /// Seed the local deposit index from a snapshot written by `indexer export-snapshot`.
+    ImportSnapshot(ImportSnapshotArgs),
+    /// Check a signed allow-list bundle is authentic, trusted and currently valid.
+    VerifyAllowList(VerifyAllowListArgs),
+    /// Serve input building, execution, proving and verification over JSON-RPC.
+    Serve(ServeArgs),
+    /// Print the JSON Schema of a wire type, or write them all to a directory.
+    Schema(SchemaArgs),
+    /// Print shell completions.
+    Completions(CompletionsArgs),
+    /// Write manpages for every command.
+    Man(ManArgs),
 }
 
 #[derive(Parser, Debug)]
-struct DepositArgs {}
+struct DepositArgs {
+    /// Send the deposit transaction and record the note.
+    #[clap(long, requires_all = ["rpc_url", "address", "private_key"])]
+    send: bool,
+
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Option<Url>,
+
+    /// Pool address.
+    #[clap(long)]
+    address: Option<Address>,
+
+    /// Key paying for the deposit.
+    #[clap(long, env = "PRIVATE_KEY", value_parser = SecretParser::<PrivateKeySigner>::new())]
+    private_key: Option<PrivateKeySigner>,
+
+    /// Deposit in two phases, for pools that take deposits by commit-reveal.
+    #[clap(long, requires = "send")]
+    commit_reveal: bool,
+
+    /// Notes to generate. With `--send`, more than one are deposited in a single `depositMany`
+    /// transaction, for pools that support it.
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    count: u64,
+
+    /// Collection of a token to deposit into an ERC-721 pool, which the commitment binds. The
+    /// token is deposited with the pool directly, along with the printed commitment.
+    #[clap(long, requires = "token_id", conflicts_with_all = ["send", "count"])]
+    collection: Option<Address>,
+
+    /// Id of the token to deposit.
+    #[clap(long, requires = "collection")]
+    token_id: Option<U256>,
+
+    /// Derive the notes from this mnemonic instead of generating them, so `pool recover` can
+    /// find them again from the mnemonic alone.
+    #[clap(
+        long,
+        env = "POOL_MNEMONIC",
+        requires = "send",
+        conflicts_with = "collection",
+        value_parser = SecretParser::<Mnemonic>::new()
+    )]
+    mnemonic: Option<Mnemonic>,
+
+    /// BIP-39 passphrase of the mnemonic.
+    #[clap(long, env = "POOL_MNEMONIC_PASSPHRASE", requires = "mnemonic")]
+    passphrase: Option<String>,
+}
 
 #[derive(Parser, Debug)]
-struct WithdrawArgs {
+struct RecoverArgs {
+    /// Mnemonic the notes were derived from.
+    #[clap(long, env = "POOL_MNEMONIC", value_parser = SecretParser::<Mnemonic>::new())]
+    mnemonic: Mnemonic,
+
+    /// BIP-39 passphrase of the mnemonic.
+    #[clap(long, env = "POOL_MNEMONIC_PASSPHRASE")]
+    passphrase: Option<String>,
+
+    /// Indexer database to match the derived notes against.
+    #[clap(long, env = "POOL_DB", default_value = ".pool/pool.sqlite")]
+    db: PathBuf,
+
+    /// Pools to search. Every pool the indexer follows if empty.
+    #[clap(long)]
+    pool: Vec<Address>,
+
+    /// Consecutive unused indices after which to stop deriving notes for a pool.
+    #[clap(long, default_value_t = DEFAULT_GAP_LIMIT)]
+    gap_limit: u64,
+}
+
+#[derive(Parser, Debug)]
+struct WatchArgs {
+    #[clap(subcommand)]
+    command: WatchCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum WatchCommand {
+    /// Watch notes by the keys `pool export-watchkey` prints.
+    Add(WatchAddArgs),
+    /// Stop watching a note.
+    Remove(WatchRemoveArgs),
+    /// Check the watched notes against an indexer's deposits and withdrawals.
+    Status(WatchStatusArgs),
+}
+
+#[derive(Parser, Debug)]
+struct WatchAddArgs {
+    #[clap(value_parser = SecretParser::<WatchKey>::new(), required = true)]
+    keys: Vec<WatchKey>,
+
+    /// Name to show the notes by.
     #[clap(long)]
+    label: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct WatchRemoveArgs {
+    /// Commitment of the watched note.
+    commitment: B256,
+}
+
+#[derive(Parser, Debug)]
+struct WatchStatusArgs {
+    /// Indexer database to check the notes against.
+    #[clap(long, env = "POOL_DB", default_value = ".pool/pool.sqlite")]
+    db: PathBuf,
+
+    /// Print JSON instead of a table.
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Parser, Debug)]
+struct RevealArgs {
+    /// Commitment of a recorded commit-reveal deposit.
+    commitment: B256,
+
+    #[clap(long, env = "RPC_URL")]
     rpc_url: Url,
 
-    address: Address,
+    /// Key that committed to the deposit.
+    #[clap(long, env = "PRIVATE_KEY", value_parser = SecretParser::<PrivateKeySigner>::new())]
+    private_key: PrivateKeySigner,
+}
 
-    secret: B256,
+#[derive(Parser, Debug)]
+struct WithdrawArgs {
+    #[clap(flatten)]
+    witness: WitnessArgs,
 
     #[clap(long)]
     execute: bool,
 
     #[clap(long)]
     prove: bool,
+
+    /// Proof type to generate. Only groth16 and plonk proofs can be verified onchain.
+    #[clap(long, value_enum, default_value_t = ProofMode::Compressed)]
+    proof_mode: ProofMode,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+
+    /// Seconds to allow for execution, proving, or waiting on the prover network.
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    /// Where to write the proof.
+    #[clap(long, default_value = "proof.bin")]
+    out: PathBuf,
+
+    /// Relay the withdrawal through this relayer: take its fee quote, prove, and hand the proof
+    /// to it for submission.
+    #[clap(long, conflicts_with_all = ["relayer", "relayer_fee", "token_fee", "header_file"])]
+    relayer_url: Option<reqwest::Url>,
+
+    /// Relay the withdrawal through the cheapest relayer of `--relayers` whose quote meets the
+    /// requirements.
+    #[clap(
+        long,
+        conflicts_with_all = ["relayer_url", "relayer", "relayer_fee", "token_fee", "header_file"]
+    )]
+    cheapest_relayer: bool,
+
+    #[clap(flatten)]
+    relayer_list: RelayerListArgs,
+
+    /// Seconds to wait for the relayer to settle the withdrawal before giving up on it.
+    #[clap(long, default_value_t = 1800)]
+    relay_timeout: u64,
+
+    /// Key of the recipient, which signs its consent to a relayer fee in a token.
+    #[clap(long, env = "RECIPIENT_KEY", value_parser = SecretParser::<PrivateKeySigner>::new())]
+    recipient_key: Option<PrivateKeySigner>,
+
+    /// Refuse to execute or prove a withdrawal input, as the program reads it, larger than this.
+    #[clap(long, env = "POOL_MAX_INPUT_BYTES")]
+    max_input_bytes: Option<usize>,
+}
+
+#[derive(Parser, Debug)]
+struct ResumeArgs {
+    /// Secret of the note being withdrawn, looked up in the note store if not given. Checkpoints
+    /// do not hold it.
+    #[clap(value_parser = SecretParser::<B256>::new())]
+    secret: Option<B256>,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+
+    /// Seconds to allow for proving or waiting on the prover network.
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    /// Give up on the checkpoint's network request and make a new one. The network has no way to
+    /// cancel a request, so the old one runs until its deadline.
+    #[clap(long)]
+    abandon: bool,
+}
+
+#[derive(Parser, Debug)]
+struct AggregateArgs {
+    /// Compressed proof files written by `withdraw --prove`.
+    #[clap(long, num_args = 1.., required = true)]
+    proofs: Vec<PathBuf>,
+
+    /// Proof type to wrap the aggregate in.
+    #[clap(long, value_enum, default_value_t = ProofMode::Groth16)]
+    proof_mode: ProofMode,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    #[clap(long, default_value = "aggregate.bin")]
+    out: PathBuf,
+
+    /// Where to write the per-withdrawal opening data.
+    #[clap(long, default_value = "openings.json")]
+    openings: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+struct DiscloseArgs {
+    #[clap(value_parser = SecretParser::<Note>::new())]
+    note: Note,
+
+    /// Transaction that deposited the note.
+    #[clap(long)]
+    tx: B256,
+
+    /// Address of the auditor the disclosure is for.
+    #[clap(long)]
+    auditor: Address,
+
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    #[clap(long, value_enum, default_value_t = ProofMode::Compressed)]
+    proof_mode: ProofMode,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    #[clap(long, default_value = "disclosure.bin")]
+    out: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+struct VerifyDisclosureArgs {
+    /// Proof file written by `pool disclose`.
+    proof: PathBuf,
+
+    /// Reject disclosures addressed to anyone else.
+    #[clap(long)]
+    auditor: Option<Address>,
+}
+
+#[derive(Parser, Debug)]
+struct RelayersArgs {
+    #[clap(subcommand)]
+    command: RelayersCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum RelayersCommand {
+    /// Fetch and check relayer quotes for withdrawing from a pool, cheapest compliant first.
+    Quote(RelayersQuoteArgs),
+}
+
+#[derive(Parser, Debug)]
+struct RelayersQuoteArgs {
+    /// Pool address, ENS name or `registry:<registry>/<denomination in wei>/<version>`.
+    address: AddressOrName,
+
+    /// Relayer to ask for a quote.
+    #[clap(required_unless_present = "all", conflicts_with = "all")]
+    relayer_url: Option<Url>,
+
+    /// Ask every relayer of `--relayers` in parallel.
+    #[clap(long)]
+    all: bool,
+
+    #[clap(flatten)]
+    list: RelayerListArgs,
+
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Print JSON instead of a table.
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Parser, Debug)]
+struct RequestRelayArgs {
+    #[clap(flatten)]
+    witness: WitnessArgs,
+
+    /// Maximum fee the relayer may take, in wei. Defaults to `--relayer-fee`. The intent binds
+    /// everything else the withdrawal commits, but leaves the relayer and its fee to whichever
+    /// `--allow-relayer` relays it.
+    #[clap(long)]
+    max_fee: Option<U256>,
+
+    /// Seconds until the intent expires.
+    #[clap(long, default_value_t = 3600)]
+    ttl: u64,
+
+    /// Relayers allowed to relay the withdrawal. Any relayer if empty.
+    #[clap(long = "allow-relayer")]
+    allowed_relayers: Vec<Address>,
+
+    /// Key to sign the intent with, which relayers hold the withdrawer to.
+    #[clap(
+        long,
+        env = "INTENT_PRIVATE_KEY",
+        value_parser = SecretParser::<PrivateKeySigner>::new()
+    )]
+    private_key: PrivateKeySigner,
+
+    #[clap(long, default_value = "intent.json")]
+    out: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+struct SubmitArgs {
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Pool address.
+    address: Address,
+
+    /// Proof file written by `withdraw --prove`, or a bundle written by `export-proof`.
+    #[clap(long, default_value = "proof.bin")]
+    proof: PathBuf,
+
+    /// Key that sends the transaction, or owns the smart account when using a bundler.
+    #[clap(long, env = "PRIVATE_KEY", value_parser = SecretParser::<PrivateKeySigner>::new())]
+    private_key: PrivateKeySigner,
+
+    /// Submit as an ERC-4337 user operation through this bundler instead of a transaction.
+    #[clap(long, env = "BUNDLER_URL")]
+    bundler_url: Option<Url>,
+
+    /// Smart account sending the user operation.
+    #[clap(long, requires = "bundler_url")]
+    account: Option<Address>,
+
+    #[clap(long, default_value_t = ENTRY_POINT_V07)]
+    entry_point: Address,
+
+    /// Paymaster sponsoring the user operation's gas.
+    #[clap(long, requires = "bundler_url")]
+    paymaster: Option<Address>,
+
+    #[clap(long, requires = "paymaster", default_value_t = Bytes::new())]
+    paymaster_data: Bytes,
+
+    /// Send the transaction through a private RPC instead of the public mempool.
+    #[clap(long, conflicts_with = "bundler_url")]
+    private_tx: bool,
+
+    /// Propose the withdrawal to this Safe instead of sending it, signed by `--private-key` as
+    /// one of its owners. The Safe executes it once enough owners confirm.
+    #[clap(long, conflicts_with_all = ["bundler_url", "private_tx"])]
+    safe: Option<Address>,
+
+    /// Safe transaction service to propose to. Defaults to the one Safe hosts for the chain.
+    #[clap(long, env = "SAFE_TX_SERVICE_URL")]
+    safe_tx_service_url: Option<Url>,
+
+    /// Nonce of the Safe transaction. Defaults to the next one not queued on the service.
+    #[clap(long, requires = "safe")]
+    safe_nonce: Option<u64>,
+
+    /// Return once the Safe transaction is proposed instead of waiting for it to be executed.
+    #[clap(long, requires = "safe")]
+    no_wait: bool,
+
+    /// Seconds to wait for a user operation to be included, or the Safe transaction to be
+    /// executed, before giving up.
+    #[clap(long, default_value_t = 3600)]
+    wait_timeout: u64,
+
+    #[clap(long, default_value = FLASHBOTS_PROTECT_RPC)]
+    private_rpc_url: Url,
+
+    /// Blocks to wait for private inclusion before broadcasting publicly.
+    #[clap(long, default_value_t = 25)]
+    fallback_blocks: u64,
+
+    /// Blocks to wait for the private transaction to be included, publicly or not, before giving
+    /// up.
+    #[clap(long, default_value_t = 100)]
+    max_blocks: u64,
+
+    /// Broadcast without first simulating the withdrawal in a local EVM.
+    #[clap(long)]
+    skip_simulation: bool,
+
+    /// The bridge deposit a withdrawal to an L2 address was proven for, through the bridge it
+    /// pays.
+    #[clap(flatten)]
+    bridge: BridgeArgs,
+
+    /// Recipient and amount of a split withdrawal, as `<address>=<wei>` once per recipient, in the
+    /// order the withdrawal was proven with.
+    #[clap(long = "split", value_name = "ADDRESS=WEI", value_parser = parse_split)]
+    splits: Vec<Split>,
+
+    /// Key of the recipient, which signs its consent to a relayer fee in a token.
+    #[clap(long, env = "RECIPIENT_KEY", value_parser = SecretParser::<PrivateKeySigner>::new())]
+    recipient_key: Option<PrivateKeySigner>,
+}
+
+#[derive(Parser, Debug)]
+struct SafeStatusArgs {
+    /// Safe transaction hash `pool submit --safe` printed.
+    safe_tx_hash: B256,
+
+    /// Safe transaction service it was proposed to. Defaults to the one Safe hosts for the chain.
+    #[clap(long, env = "SAFE_TX_SERVICE_URL")]
+    safe_tx_service_url: Option<Url>,
+
+    /// RPC endpoint of the chain, to find the hosted service.
+    #[clap(long, env = "RPC_URL", required_unless_present = "safe_tx_service_url")]
+    rpc_url: Option<Url>,
+
+    /// Keep following confirmations until the Safe transaction is executed.
+    #[clap(long)]
+    wait: bool,
+
+    /// Seconds to keep following confirmations before giving up.
+    #[clap(long, requires = "wait", default_value_t = 3600)]
+    wait_timeout: u64,
+}
+
+#[derive(Parser, Debug)]
+struct ExportProofArgs {
+    /// Groth16 or plonk proof file written by `withdraw --prove`.
+    #[clap(long, default_value = "proof.bin")]
+    proof: PathBuf,
+
+    #[clap(long, default_value = "proof.json")]
+    out: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+struct VerifyProofArgs {
+    /// Proof file written by `withdraw --prove`, or a bundle.
+    proof: PathBuf,
+
+    /// Program vkey to check against. Defaults to the embedded program's.
+    #[clap(long)]
+    vkey: Option<B256>,
+}
+
+#[derive(Parser, Debug)]
+struct NullifierStatusArgs {
+    /// Note or raw secret to check.
+    #[clap(value_parser = SecretParser::<NoteOrSecret>::new())]
+    note: NoteOrSecret,
+
+    /// RPC endpoints of the chains to check.
+    #[clap(
+        long = "rpc-url",
+        env = "RPC_URLS",
+        value_delimiter = ',',
+        required = true
+    )]
+    rpc_urls: Vec<Url>,
+
+    /// Pool address. Required when checking a raw secret.
+    #[clap(long)]
+    address: Option<Address>,
+
+    /// Blocks to search back for the withdrawal event.
+    #[clap(long, default_value_t = 100_000)]
+    lookback: u64,
+}
+
+#[derive(Parser, Debug)]
+struct StatsArgs {
+    /// Pool address.
+    #[clap(long)]
+    address: Address,
+
+    /// GraphQL endpoint of an indexer of the pool.
+    #[clap(long, env = "INDEXER_URL")]
+    indexer_url: Url,
+
+    /// Points of the anonymity set to show, from the first deposit to the last indexed block.
+    #[clap(long, default_value_t = 10)]
+    points: u64,
+
+    /// Blocks to measure deposit and withdrawal rates over, a week of 12-second blocks by
+    /// default.
+    #[clap(long, default_value_t = 50_400)]
+    window: u64,
+
+    /// Seconds per block, to turn blocks into days.
+    #[clap(long, default_value_t = 12)]
+    block_time: u64,
+
+    /// Print JSON instead of tables.
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Parser, Debug)]
+struct ExportViewkeyArgs {
+    #[clap(value_parser = SecretParser::<Note>::new())]
+    note: Note,
+}
+
+#[derive(Parser, Debug)]
+struct ExportWatchkeyArgs {
+    #[clap(value_parser = SecretParser::<Note>::new())]
+    note: Note,
+}
+
+#[derive(Parser, Debug)]
+struct AuditArgs {
+    /// View key from `pool export-viewkey`.
+    #[clap(value_parser = SecretParser::<ViewKey>::new())]
+    view_key: ViewKey,
+
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Blocks to search back for the deposit event.
+    #[clap(long, default_value_t = 1_000_000)]
+    lookback: u64,
+}
+
+#[derive(Parser, Debug)]
+struct EstimateArgs {
+    #[clap(flatten)]
+    witness: WitnessArgs,
+
+    /// Prover network price per million cycles, used to project network cost.
+    #[clap(long, default_value_t = 0.1)]
+    network_price: f64,
+}
+
+#[derive(Parser, Debug)]
+struct CyclesCheckArgs {
+    /// Most cycles the fixture withdrawal may take.
+    #[clap(long)]
+    budget: u64,
+}
+
+#[derive(Parser, Debug)]
+struct DiffCheckArgs {
+    #[clap(flatten)]
+    witness: WitnessArgs,
+}
+
+#[derive(Parser, Debug)]
+struct GasReportArgs {
+    /// RPC endpoint of the chain to fork. Must serve the blocks the proofs were made at.
+    #[clap(long, env = "RPC_URL")]
+    fork_url: Url,
+
+    /// Groth16 or plonk proof files written by `withdraw --prove`.
+    #[clap(long = "proof", required_unless_present = "aggregates")]
+    proofs: Vec<PathBuf>,
+
+    /// Groth16 or plonk aggregated proof files written by `pool aggregate`, one per batch size.
+    #[clap(long = "aggregate")]
+    aggregates: Vec<PathBuf>,
+
+    /// Forge artifact of the pool contract.
+    #[clap(long, default_value = "contracts/out/Pool.sol/Pool.json")]
+    artifact: PathBuf,
+
+    /// Pool denomination, in wei.
+    #[clap(long, default_value = "100000000000000000")]
+    amount: U256,
+}
+
+#[derive(Parser, Debug)]
+struct SimulateArgs {
+    /// RPC endpoint of the chain to fork.
+    #[clap(long, env = "RPC_URL")]
+    fork_url: Url,
+
+    /// Pool to rehearse against. One is deployed on the fork from `--artifact` if not given or
+    /// not on the chain.
+    #[clap(long)]
+    address: Option<Address>,
+
+    /// Forge artifact of the pool contract.
+    #[clap(long, default_value = "contracts/out/Pool.sol/Pool.json")]
+    artifact: PathBuf,
+
+    /// Denomination of a deployed pool, in wei.
+    #[clap(long, default_value = "100000000000000000")]
+    amount: U256,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+}
+
+#[derive(Parser, Debug)]
+struct VerifyBuildArgs {
+    #[clap(long, default_value = "program")]
+    program_dir: PathBuf,
+
+    /// SP1 docker image tag to build with.
+    #[clap(long, default_value = SP1_DOCKER_TAG)]
+    tag: String,
+
+    /// RPC endpoint to read the deployed pool's vkey from.
+    #[clap(long, env = "RPC_URL", requires = "address")]
+    rpc_url: Option<Url>,
+
+    /// Deployed pool to compare against.
+    #[clap(long)]
+    address: Option<Address>,
+}
+
+#[derive(Parser, Debug)]
+struct IndexArgs {
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Pool address.
+    address: Address,
+}
+
+#[derive(Parser, Debug)]
+struct ImportSnapshotArgs {
+    snapshot: PathBuf,
+
+    /// Check the snapshot block is canonical and its deposit count matches the chain. Without it
+    /// the snapshot's deposits are trusted as they are.
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Option<Url>,
+
+    /// Replace an index that is already further along.
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser, Debug)]
+struct VerifyAllowListArgs {
+    bundle: PathBuf,
+
+    /// Issuers the bundle is trusted from.
+    #[clap(
+        long = "issuer",
+        env = "POOL_ALLOW_LIST_ISSUERS",
+        value_delimiter = ',',
+        required = true
+    )]
+    issuers: Vec<Address>,
+}
+
+#[derive(Parser, Debug)]
+struct ServeArgs {
+    /// Serve JSON-RPC, the only mode so far.
+    #[clap(long)]
+    jsonrpc: bool,
+
+    #[clap(long, env = "POOL_RPC_LISTEN", default_value = "127.0.0.1:8547")]
+    listen: std::net::SocketAddr,
+
+    /// Execution RPC `pool_buildInput` reads the chain from.
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Bearer token required in the `Authorization` header of every request.
+    #[clap(long, env = "POOL_RPC_TOKEN")]
+    auth_token: Option<String>,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+
+    /// Seconds to allow each execution, proof or verification.
+    #[clap(long)]
+    timeout: Option<u64>,
+}
+
+#[derive(Parser, Debug)]
+struct SchemaArgs {
+    /// Type to print. Lists the types if not set.
+    name: Option<String>,
+
+    /// Write every schema to `<dir>/<name>.json` instead.
+    #[clap(long, conflicts_with = "name")]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct CompletionsArgs {
+    shell: Shell,
+}
+
+#[derive(Parser, Debug)]
+struct ManArgs {
+    /// Directory to write the manpages to.
+    #[clap(long, default_value = "man")]
+    out_dir: PathBuf,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    setup_logger();
+    match run().await {
+        // The runtime waits on blocking tasks when dropped, and an interrupted prover call keeps
+        // running on one.
+        Err(e) if e.is::<Cancelled>() => {
+            eprintln!("interrupted, resume with `pool resume`");
+            std::process::exit(130);
+        }
+        result => result,
+    }
+}
+
+async fn run() -> Result<()> {
+    dotenv::dotenv().ok();
 
     // Handle the command line arguments.
     let args = Args::parse();
+    args.log.init();
+    secure::harden();
+    if args.lock_memory {
+        secure::lock_all()?;
+    }
 
+    let data_dir = args.data_dir;
     match args.command {
-        Command::Deposit(_args) => {
-            println!("Depositing...");
-            // Generate random B256
+        Command::Completions(args) => {
+            let mut command = Args::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(args.shell, &mut command, name, &mut std::io::stdout());
+        }
+        Command::Man(args) => {
+            std::fs::create_dir_all(&args.out_dir)?;
+            clap_mangen::generate_to(Args::command(), &args.out_dir)?;
+            println!("Wrote manpages to {}", args.out_dir.display());
+        }
+        Command::Deposit(args) => {
+            ensure!(
+                args.count == 1 || !args.commit_reveal,
+                "commit-reveal deposits are made one at a time"
+            );
+            info!(count = args.count, "generating deposit secrets");
             let mut rng = rand::rng();
-            let secret = rng.random::<[u8; 32]>();
-            let (commitment, nullifier) = pool_lib::compute_commitment(&secret.into());
-            println!("Commitment: {:?}", commitment);
-            println!("Nullifier: {:?}", nullifier);
-            println!("Secret: {}", hex::encode(secret));
+            let (secrets, first_index): (Vec<Locked<B256>>, _) = match &args.mnemonic {
+                Some(mnemonic) => {
+                    let (Some(rpc_url), Some(pool)) = (&args.rpc_url, args.address) else {
+                        unreachable!("required by clap");
+                    };
+                    let chain_id = RootProvider::<Ethereum>::new_http(rpc_url.clone())
+                        .get_chain_id()
+                        .await?;
+                    let seed = NoteSeed::new(mnemonic, args.passphrase.as_deref())?;
+                    let first = NoteStore::load(&data_dir)?.next_derivation_index(chain_id, pool);
+                    let secrets = (first..first + args.count)
+                        .map(|index| Locked::new(seed.secret(chain_id, pool, index)))
+                        .collect();
+                    (secrets, Some(first))
+                }
+                None => {
+                    let secrets = (0..args.count)
+                        .map(|_| Locked::new(B256::from(rng.random::<[u8; 32]>())))
+                        .collect();
+                    (secrets, None)
+                }
+            };
+            let mut commitments = Vec::new();
+            for secret in &secrets {
+                let (commitment, nullifier) = match (args.collection, args.token_id) {
+                    (Some(collection), Some(token_id)) => {
+                        compute_nft_commitment(secret, &collection, &token_id)
+                    }
+                    _ => compute_commitment(secret),
+                };
+                println!("Commitment: {:?}", commitment);
+                println!("Nullifier: {:?}", nullifier);
+                println!("Secret: {}", hex::encode(**secret));
+                commitments.push(commitment);
+            }
+
+            if args.send {
+                let (Some(rpc_url), Some(pool), Some(signer)) =
+                    (args.rpc_url, args.address, args.private_key)
+                else {
+                    unreachable!("required by clap");
+                };
+                let chain_id = RootProvider::<Ethereum>::new_http(rpc_url.clone())
+                    .get_chain_id()
+                    .await?;
+                // Record the notes before sending so they are never lost.
+                let mut notes = NoteStore::load(&data_dir)?;
+                let mut records: Vec<NoteRecord> = secrets
+                    .iter()
+                    .zip(0..)
+                    .map(|(secret, i)| {
+                        let mut record = NoteRecord::new(Note {
+                            chain_id,
+                            pool,
+                            secret: **secret,
+                        });
+                        record.derivation_index = first_index.map(|first| first + i);
+                        record
+                    })
+                    .collect();
+                if args.commit_reveal {
+                    records[0].salt = Some(B256::from(rng.random::<[u8; 32]>()));
+                }
+                for record in &records {
+                    notes.insert(record.clone());
+                }
+                notes.save(&data_dir)?;
+
+                let receipts = match records[0].salt {
+                    Some(salt) => {
+                        let commitment = commitments[0];
+                        let commit =
+                            send_commit(rpc_url.clone(), signer.clone(), pool, commitment, salt)
+                                .await?;
+                        println!("Commit transaction: {:?}", commit);
+                        vec![send_reveal(rpc_url, signer, pool, commitment, salt).await?]
+                    }
+                    None if commitments.len() == 1 => {
+                        vec![send_deposit(rpc_url, signer, pool, commitments[0]).await?]
+                    }
+                    None => send_deposits(rpc_url, signer, pool, &commitments).await?,
+                };
+                for (record, receipt) in records.iter_mut().zip(&receipts) {
+                    record.leaf_index = Some(receipt.leaf_index);
+                    record.block_number = Some(receipt.block_number);
+                    record.tx_hash = Some(receipt.tx_hash);
+                    notes.insert(record.clone());
+                }
+                notes.save(&data_dir)?;
+
+                println!("Transaction: {:?}", receipts[0].tx_hash);
+                println!("Block: {}", receipts[0].block_number);
+                for (record, receipt) in records.iter().zip(&receipts) {
+                    println!("Note: {}", record.note);
+                    println!("Leaf index: {}", receipt.leaf_index);
+                }
+            }
+        }
+        Command::Recover(args) => {
+            let seed = NoteSeed::new(&args.mnemonic, args.passphrase.as_deref())?;
+            let conn = db::open(&args.db)?;
+            let mut pools = indexed_pools(&conn)?;
+            if !args.pool.is_empty() {
+                pools.retain(|(_, pool)| args.pool.contains(pool));
+            }
+            ensure!(!pools.is_empty(), "the indexer follows none of the pools");
+
+            let mut notes = NoteStore::load(&data_dir)?;
+            let mut restored = 0;
+            for (chain_id, pool) in pools {
+                for recovered in
+                    recover_pool(&conn, &args.db, &seed, chain_id, pool, args.gap_limit)?
+                {
+                    let record = recovered.record;
+                    println!(
+                        "Chain {} pool {} leaf {}: {}",
+                        chain_id,
+                        pool,
+                        record.leaf_index.unwrap_or_default(),
+                        if recovered.spent { "spent" } else { "unspent" }
+                    );
+                    if notes.find(&record.commitment).is_none() {
+                        restored += 1;
+                    }
+                    notes.insert(record);
+                }
+            }
+            notes.save(&data_dir)?;
+            println!("Restored {} notes", restored);
+        }
+        Command::Watch(WatchArgs {
+            command: WatchCommand::Add(args),
+        }) => {
+            let mut notes = NoteStore::load(&data_dir)?;
+            for key in args.keys {
+                if notes.find(&key.commitment).is_some() {
+                    warn!(commitment = %key.commitment, "note is already held with its secret");
+                    continue;
+                }
+                println!("Watching {:?} in {}", key.commitment, key.pool);
+                notes.watch(WatchedNote::new(key, args.label.clone()));
+            }
+            notes.save(&data_dir)?;
+        }
+        Command::Watch(WatchArgs {
+            command: WatchCommand::Remove(args),
+        }) => {
+            let mut notes = NoteStore::load(&data_dir)?;
+            ensure!(
+                notes.unwatch(&args.commitment),
+                "no watched note for the commitment"
+            );
+            notes.save(&data_dir)?;
+            println!("Stopped watching {:?}", args.commitment);
+        }
+        Command::Watch(WatchArgs {
+            command: WatchCommand::Status(args),
+        }) => {
+            let conn = db::open(&args.db)?;
+            let mut notes = NoteStore::load(&data_dir)?;
+            ensure!(
+                !notes.watched.is_empty(),
+                "no watched notes, add them with `pool watch add`"
+            );
+            let mut statuses = Vec::new();
+            for watched in &mut notes.watched {
+                let status = sync_watched(&conn, &args.db, watched)?;
+                if status.indexed_to.is_none() {
+                    warn!(
+                        chain_id = watched.key.chain_id,
+                        pool = %watched.key.pool,
+                        "the indexer does not follow the pool"
+                    );
+                }
+                statuses.push(status);
+            }
+            notes.save(&data_dir)?;
+            if args.json {
+                let json: Vec<_> = notes
+                    .watched
+                    .iter()
+                    .zip(&statuses)
+                    .map(|(watched, status)| {
+                        serde_json::json!({ "note": watched, "status": status })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
+
+            println!(
+                "{:>8} {:>12} {:>15} {:>10} {:>8}  note",
+                "leaf", "block", "deposits after", "unspent", "status"
+            );
+            for (watched, status) in notes.watched.iter().zip(&statuses) {
+                let state = match (watched.leaf_index, watched.spent_tx_hash) {
+                    (_, Some(_)) => "spent",
+                    (Some(_), None) => "unspent",
+                    (None, None) => "pending",
+                };
+                println!(
+                    "{:>8} {:>12} {:>15} {:>10} {:>8}  {}",
+                    watched
+                        .leaf_index
+                        .map_or_else(|| "-".to_string(), |leaf| leaf.to_string()),
+                    watched
+                        .block_number
+                        .map_or_else(|| "-".to_string(), |block| block.to_string()),
+                    status.deposits_after,
+                    status.unspent,
+                    state,
+                    watched
+                        .label
+                        .clone()
+                        .unwrap_or_else(|| format!("{:?}", watched.key.commitment))
+                );
+            }
+        }
+        Command::Reveal(args) => {
+            let mut notes = NoteStore::load(&data_dir)?;
+            let mut record = notes
+                .find(&args.commitment)
+                .cloned()
+                .ok_or_eyre("no recorded note for the commitment")?;
+            ensure!(record.leaf_index.is_none(), "deposit is already revealed");
+            let salt = record
+                .salt
+                .ok_or_eyre("note was not deposited by commit-reveal")?;
+            let receipt = send_reveal(
+                args.rpc_url,
+                args.private_key,
+                record.note.pool,
+                args.commitment,
+                salt,
+            )
+            .await?;
+            record.leaf_index = Some(receipt.leaf_index);
+            record.block_number = Some(receipt.block_number);
+            record.tx_hash = Some(receipt.tx_hash);
+            notes.insert(record);
+            notes.save(&data_dir)?;
+
+            println!("Transaction: {:?}", receipt.tx_hash);
+            println!("Block: {}", receipt.block_number);
+            println!("Leaf index: {}", receipt.leaf_index);
+        }
+        Command::Withdraw(mut args) => {
+            info!(pool = %args.witness.address, "building withdrawal");
+            if args.witness.leaf_index.is_none() {
+                let commitment = args.witness.commitment();
+                args.witness.leaf_index = NoteStore::load(&data_dir)?
+                    .find(&commitment)
+                    .and_then(|record| record.leaf_index);
+                // Lookups are keyed by chain, which is not known before connecting, so try those
+                // of every chain: the witness checks the leaf it is given and searches otherwise.
+                if let (None, AddressOrName::Address(pool)) =
+                    (args.witness.leaf_index, &args.witness.address)
+                {
+                    for path in DepositLookup::paths(&data_dir, *pool)? {
+                        let Some(lookup) = DepositLookup::open(&path)? else {
+                            continue;
+                        };
+                        if let Some(index) = lookup.find(&commitment) {
+                            debug!(index, lookup = %path.display(), "found deposit in lookup");
+                            args.witness.leaf_index = Some(index);
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let relay = if args.relayer_url.is_some() || args.cheapest_relayer {
+                let provider = args.witness.connect().await?;
+                let chain_id = provider.get_chain_id().await?;
+                let latest = provider.get_block_number().await?;
+                let pool = args.witness.address.resolve(&provider, latest).await?;
+
+                let (client, quote) = match args.relayer_url.clone() {
+                    Some(relayer_url) => {
+                        let client = RelayerClient::new(relayer_url);
+                        let quote = client
+                            .quote(chain_id, pool, args.relayer_list.fee_token_address())
+                            .await?;
+                        let value = args.relayer_list.check_quote(&provider, &quote).await?;
+                        debug!(%value, "valued relayer quote");
+                        (client, quote)
+                    }
+                    None => {
+                        let offers = args.relayer_list.offers(&provider, chain_id, pool).await?;
+                        let offer = cheapest(&offers).ok_or_else(|| {
+                            eyre!(
+                                "none of the {} relayers can relay the withdrawal",
+                                offers.len()
+                            )
+                        })?;
+                        info!(url = %offer.url, "picked the cheapest relayer");
+                        let client = RelayerClient::new(offer.url.parse()?);
+                        (
+                            client,
+                            offer.quote.clone().ok_or_eyre("offer has no quote")?,
+                        )
+                    }
+                };
+                info!(
+                    relayer = %quote.relayer,
+                    fee = %quote.fee,
+                    fee_token = ?quote.fee_token,
+                    "got relayer quote"
+                );
+                args.witness.relayer = AddressOrName::Address(quote.relayer);
+                match quote.fee_token {
+                    Some(token) => {
+                        args.witness.token_fee = Some(token);
+                        args.witness.token_fee_amount = Some(quote.fee);
+                    }
+                    None => args.witness.relayer_fee = quote.fee,
+                }
+
+                // Relayed proofs are verified onchain.
+                args.prove = true;
+                if args.proof_mode == ProofMode::Compressed {
+                    args.proof_mode = ProofMode::Groth16;
+                }
+                Some((client, chain_id, quote, provider))
+            } else {
+                None
+            };
+
+            let mut input = args.witness.build().await?;
+            scrub_secret(&mut args.witness.secret);
+            let pool = input.contract_address;
+            let bridge = input.bridge.clone();
+
+            let data = process_any_withdrawal(&input)?.withdrawal;
+            // The nullifier stays out of the log until the withdrawal is broadcast.
+            debug!(
+                recipient = %data.recipient,
+                relayer = %data.relayer,
+                relayer_fee = %data.relayerFee,
+                block_number = data.blockNumber,
+                "verified withdrawal"
+            );
+            // Warn before proving, while the withdrawal can still be changed. The relayer sends
+            // it, so the checks of the sender are against the relayer.
+            if let Some((_, _, quote, provider)) = &relay {
+                let notes = NoteStore::load(&data_dir)?;
+                let deposit = notes
+                    .notes
+                    .iter()
+                    .find(|record| compute_commitment(&record.note.secret).1 == data.nullifier);
+                for warning in check_linkage(provider, &data, quote.relayer, deposit).await? {
+                    warn!(
+                        mitigation = warning.mitigation,
+                        "privacy: {}", warning.issue
+                    );
+                }
+            }
+            let token_fee = match &relay {
+                Some((_, _, _, provider)) if !data.feeToken.is_zero() => {
+                    let key = args.recipient_key.as_ref().ok_or_eyre(
+                        "the relayer charges in a token, which needs --recipient-key to consent",
+                    )?;
+                    Some(sign_token_fee(provider, key, &data).await?)
+                }
+                _ => None,
+            };
+
+            let size = InputSize::of(&input)?;
+            info!(
+                bytes = size.total,
+                header = size.header,
+                account_proof = size.account_proof,
+                storage_proofs = size.storage_proofs,
+                branches = size.branches,
+                other = size.other,
+                "program input size"
+            );
+            if let Some(max) = args.max_input_bytes {
+                size.check(max)?;
+            }
+
+            let timeout = args.timeout.map(Duration::from_secs);
+            if args.execute {
+                let backend = args.prover.backend()?;
+                let mut execute_input = input.clone();
+                let execute = move || {
+                    let execution = backend.execute(&execute_input);
+                    scrub_secret(&mut execute_input.secret);
+                    execution
+                };
+                let execution = run_blocking(execute, timeout)
+                    .instrument(info_span!("execute"))
+                    .await?;
+                info!(cycles = execution.cycles, "executed");
+            }
+
+            if args.prove {
+                let vkey = WITHDRAWAL_PROGRAM.checked_vkey()?;
+                // A replayed cassette only holds the calls the witness made.
+                if args.witness.replay.is_none() {
+                    check_pool_vkey(&args.witness.connect().await?, pool, vkey).await?;
+                }
+                let start = std::time::Instant::now();
+                let mut secret = input.secret;
+                // The checkpoint zeroes its copy of the secret.
+                let checkpoint = Checkpoint::new(input, args.proof_mode, args.out);
+                let checkpoint_path = Checkpoint::default_path(&data_dir);
+                let proof = prove_checkpointed(
+                    &args.prover,
+                    checkpoint,
+                    &checkpoint_path,
+                    timeout,
+                    &secret,
+                )
+                .await;
+                scrub_secret(&mut secret);
+                let proof = proof?;
+                info!(elapsed = ?start.elapsed(), "generated proof");
+                debug!(proof = hex::encode(proof.bytes()), "proof bytes");
+
+                if let Some((client, chain_id, quote, _)) = relay {
+                    let withdrawal = Withdrawal::from_proof(&proof);
+                    let job_id = client
+                        .withdraw(&WithdrawRequest {
+                            chain_id,
+                            pool,
+                            public_values: withdrawal.public_values,
+                            proof: withdrawal.proof,
+                            quote,
+                            bridge,
+                            token_fee,
+                        })
+                        .await?;
+                    println!("Relay job: {}", job_id);
+                    let job = client
+                        .wait(&job_id, Duration::from_secs(args.relay_timeout))
+                        .await?;
+                    if let Some(tx_hash) = job.tx_hash {
+                        println!("Withdrawal transaction: {:?}", tx_hash);
+                    }
+                }
+            } else {
+                scrub_secret(&mut input.secret);
+            }
+        }
+        Command::Resume(args) => {
+            let checkpoint_path = Checkpoint::default_path(&data_dir);
+            let mut checkpoint = Checkpoint::load(&checkpoint_path)?;
+            WITHDRAWAL_PROGRAM.checked_vkey()?;
+            if args.abandon {
+                if let Some(request_id) = checkpoint.request_id.take() {
+                    checkpoint.save(&checkpoint_path)?;
+                    warn!(%request_id, "abandoned the network request");
+                }
+            }
+            let out = checkpoint.out.clone();
+            let timeout = args.timeout.map(Duration::from_secs);
+            let mut secret = match args.secret {
+                Some(secret) => secret,
+                None => {
+                    let notes = NoteStore::load(&data_dir)?;
+                    let record = notes.notes.iter().find(|record| {
+                        compute_commitment(&record.note.secret).1 == checkpoint.nullifier
+                    });
+                    record
+                        .ok_or_eyre("the note being withdrawn is not in the note store")?
+                        .note
+                        .secret
+                }
+            };
+            let proof =
+                prove_checkpointed(&args.prover, checkpoint, &checkpoint_path, timeout, &secret)
+                    .await;
+            scrub_secret(&mut secret);
+            proof?;
+            println!("Wrote proof to {}", out.display());
         }
-        Command::Withdraw(args) => {
-            println!("Withdrawing...");
-            println!("Address: {}", args.address);
-            println!("Secret: {}", args.secret);
+        Command::Aggregate(args) => {
+            let children = args
+                .proofs
+                .iter()
+                .map(|path| Ok(bincode::deserialize(&std::fs::read(path)?)?))
+                .collect::<Result<Vec<SP1ProofWithPublicValues>>>()?;
+            let openings = openings(&children);
+            WITHDRAWAL_PROGRAM.checked_vkey()?;
+            AGGREGATION_PROGRAM.checked_vkey()?;
 
+            let prover = args.prover.build()?;
+            let keys = args.prover.keys.clone();
+            let mode = args.proof_mode.into();
+            let timeout = args.timeout.map(Duration::from_secs);
+            let proof = run_blocking(
+                move || {
+                    let (_, child_vk) = keys.setup(&*prover, ELF);
+                    let (pk, _vk) = keys.setup(&*prover, AGGREGATION_ELF);
+                    let stdin = aggregation_stdin(&children, &child_vk)?;
+                    prover
+                        .prove(&pk, &stdin, mode)
+                        .map_err(|e| eyre!("proving failed: {e}"))
+                },
+                timeout,
+            )
+            .instrument(info_span!("aggregate"))
+            .await?;
+
+            let data = AggregateData::abi_decode(proof.public_values.as_slice(), true)?;
+            write_atomic(&args.out, &bincode::serialize(&proof)?)?;
+            std::fs::write(&args.openings, serde_json::to_vec_pretty(&openings)?)?;
+            println!("Aggregated {} withdrawals", data.count);
+            println!("Public values root: {:?}", data.publicValuesRoot);
+            println!("Wrote proof to {}", args.out.display());
+            println!("Wrote openings to {}", args.openings.display());
+        }
+        Command::Disclose(args) => {
             let provider = RootProvider::<Ethereum>::new_http(args.rpc_url);
-            let keys = compute_storage_keys(U256::from(0_u32), U256::from(1_u32));
-            println!("Keys: {:?}", keys);
-            let header = provider
-                .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
-                .await?
-                .unwrap();
-            let block_number = header.header.number();
-            println!("Block: {}", block_number);
-
-            let contract = Pool::new(args.address, &provider);
-            let length = provider
-                .get_storage_at(args.address, U256::from(0_u32))
-                .number(block_number)
+            let chain_id = provider.get_chain_id().await?;
+            ensure!(
+                chain_id == args.note.chain_id,
+                "note is for chain {}, RPC is on chain {chain_id}",
+                args.note.chain_id
+            );
+            let input = disclosure_input(&provider, &args.note, args.tx, args.auditor).await?;
+            DISCLOSURE_PROGRAM.checked_vkey()?;
+
+            let prover = args.prover.build()?;
+            let keys = args.prover.keys.clone();
+            let mode = args.proof_mode.into();
+            let timeout = args.timeout.map(Duration::from_secs);
+            let proof = run_blocking(
+                move || {
+                    let (pk, _vk) = keys.setup(&*prover, DISCLOSURE_ELF);
+                    prover
+                        .prove(&pk, &disclosure_stdin(&input)?, mode)
+                        .map_err(|e| eyre!("proving failed: {e}"))
+                },
+                timeout,
+            )
+            .instrument(info_span!("disclose"))
+            .await?;
+
+            write_atomic(&args.out, &bincode::serialize(&proof)?)?;
+            println!("Wrote disclosure to {}", args.out.display());
+        }
+        Command::VerifyDisclosure(args) => {
+            let proof: SP1ProofWithPublicValues =
+                bincode::deserialize(&std::fs::read(&args.proof)?)?;
+            let prover = ProverClient::builder().cpu().build();
+            let (_, vk) = prover.setup(DISCLOSURE_ELF);
+            prover
+                .verify(&proof, &vk)
+                .map_err(|e| eyre!("invalid disclosure: {e}"))?;
+            let data = DisclosureData::abi_decode(proof.public_values.as_slice(), true)?;
+            if let Some(auditor) = args.auditor {
+                ensure!(
+                    data.auditor == auditor,
+                    "disclosure is for {}, not {auditor}",
+                    data.auditor
+                );
+            }
+            println!("Disclosure verified");
+            println!("Auditor: {}", data.auditor);
+            println!("Pool: {}", data.pool);
+            println!("Nullifier: {:?}", data.nullifier);
+            println!("Commitment: {:?}", data.commitment);
+            println!("Deposit transaction: {:?}", data.txHash);
+            println!("Depositor: {}", data.depositor);
+            println!("Block: {}", data.blockNumber);
+        }
+        Command::Relayers(RelayersArgs {
+            command: RelayersCommand::Quote(args),
+        }) => {
+            let provider = RootProvider::<Ethereum>::new_http(args.rpc_url);
+            let chain_id = provider.get_chain_id().await?;
+            let latest = provider.get_block_number().await?;
+            let pool = args.address.resolve(&provider, latest).await?;
+            let mut list = args.list;
+            if let Some(relayer_url) = args.relayer_url {
+                list.relayers = vec![relayer_url];
+            }
+
+            let offers = list.offers(&provider, chain_id, pool).await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&offers)?);
+                return Ok(());
+            }
+            println!(
+                "{:<40} {:<42} {:>22} {:>22} {:>22}  status",
+                "relayer", "address", "fee", "value", "gas cost"
+            );
+            for offer in &offers {
+                let (address, fee) = match &offer.quote {
+                    Some(quote) => (quote.relayer.to_string(), quote.fee.to_string()),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                let value = offer
+                    .value
+                    .map_or_else(|| "-".to_string(), |value| value.to_string());
+                println!(
+                    "{:<40} {:<42} {:>22} {:>22} {:>22}  {}",
+                    offer.url,
+                    address,
+                    fee,
+                    value,
+                    offer.gas_cost,
+                    offer.problem.as_deref().unwrap_or("ok")
+                );
+            }
+            match cheapest(&offers) {
+                Some(offer) => println!("Cheapest: {}", offer.url),
+                None => println!("No relayer can relay the withdrawal"),
+            }
+        }
+        Command::RequestRelay(args) => {
+            let provider = args.witness.connect().await?;
+            let chain_id = provider.get_chain_id().await?;
+            let input = build_witness(&provider, &args.witness).await?;
+            let public_values = withdrawal_public_values(&input)?;
+
+            let max_fee = args.max_fee.unwrap_or(input.relayer_fee);
+            ensure!(max_fee >= input.relayer_fee, "max fee below relayer fee");
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let intent = WithdrawalIntent::new(
+                &public_values,
+                max_fee,
+                now + args.ttl,
+                args.allowed_relayers,
+            )?;
+
+            let signer = args.private_key;
+            let domain = intent_domain(chain_id, input.contract_address);
+            let signature = signer.sign_hash_sync(&intent.eip712_signing_hash(&domain))?;
+            let signed = SignedIntent {
+                intent,
+                chain_id,
+                pool: input.contract_address,
+                signature,
+                relayer_signature: None,
+            };
+            println!("Signer: {}", signer.address());
+            println!(
+                "Public values digest: {:?}",
+                signed.intent.publicValuesDigest
+            );
+
+            std::fs::write(&args.out, serde_json::to_vec_pretty(&signed)?)?;
+            println!("Wrote intent to {}", args.out.display());
+        }
+        Command::Submit(args) => {
+            let withdrawal = Withdrawal::load(&args.proof)?
+                .with_bridge(args.bridge.message())?
+                .with_splits(args.splits.clone())?;
+            let provider = RootProvider::<Ethereum>::new_http(args.rpc_url.clone());
+            let data = decode_any_public_values(&withdrawal.public_values)?.withdrawal;
+            let token_fee = match &args.recipient_key {
+                Some(key) if !data.feeToken.is_zero() => {
+                    Some(sign_token_fee(&provider, key, &data).await?)
+                }
+                _ => None,
+            };
+            let withdrawal = withdrawal.with_token_fee(token_fee)?;
+            let sender = args
+                .safe
+                .or(args.account)
+                .unwrap_or(args.private_key.address());
+            check_pool_vkey(&provider, args.address, WITHDRAWAL_PROGRAM.checked_vkey()?).await?;
+            if !args.skip_simulation {
+                simulate_withdrawal(&provider, args.address, sender, &withdrawal).await?;
+            }
+
+            let notes = NoteStore::load(&data_dir)?;
+            let deposit = notes
+                .notes
+                .iter()
+                .find(|record| compute_commitment(&record.note.secret).1 == data.nullifier);
+            for warning in check_linkage(&provider, &data, sender, deposit).await? {
+                warn!(
+                    mitigation = warning.mitigation,
+                    "privacy: {}", warning.issue
+                );
+            }
+            if let Some(safe) = args.safe {
+                let service = match args.safe_tx_service_url {
+                    Some(url) => url,
+                    None => hosted_service(provider.get_chain_id().await?)?,
+                };
+                let proposer = SafeProposer {
+                    provider,
+                    service,
+                    safe,
+                    owner: args.private_key,
+                    http: reqwest::Client::new(),
+                };
+                let safe_tx_hash = proposer
+                    .propose(args.address, &withdrawal, args.safe_nonce)
+                    .await?;
+                println!("Safe transaction: {:?}", safe_tx_hash);
+                if args.no_wait {
+                    return Ok(());
+                }
+                let tx_hash = wait_for_execution(
+                    &proposer.http,
+                    &proposer.service,
+                    safe_tx_hash,
+                    Duration::from_secs(args.wait_timeout),
+                )
+                .await?;
+                println!("Withdrawal transaction: {:?}", tx_hash);
+                return Ok(());
+            }
+            let tx_hash = match args.bundler_url {
+                Some(bundler_url) => {
+                    let account = args
+                        .account
+                        .ok_or_eyre("--account is required with a bundler")?;
+                    let submitter = UserOpSubmitter {
+                        provider: RootProvider::new_http(args.rpc_url),
+                        bundler: RootProvider::new_http(bundler_url),
+                        entry_point: args.entry_point,
+                        account,
+                        owner: args.private_key,
+                        paymaster: args.paymaster.map(|address| Paymaster {
+                            address,
+                            data: args.paymaster_data,
+                        }),
+                    };
+                    let op = submitter.build(args.address, &withdrawal).await?;
+                    submitter
+                        .send(&op, Duration::from_secs(args.wait_timeout))
+                        .await?
+                }
+                None if args.private_tx => {
+                    submit_private(
+                        args.rpc_url,
+                        args.private_rpc_url,
+                        args.private_key,
+                        args.address,
+                        &withdrawal,
+                        args.fallback_blocks,
+                        args.max_blocks,
+                    )
+                    .await?
+                }
+                None => {
+                    submit_direct(args.rpc_url, args.private_key, args.address, &withdrawal).await?
+                }
+            };
+            println!("Withdrawal transaction: {:?}", tx_hash);
+        }
+        Command::SafeStatus(args) => {
+            let service = match (args.safe_tx_service_url, args.rpc_url) {
+                (Some(url), _) => url,
+                (None, Some(rpc_url)) => {
+                    let chain_id = RootProvider::<Ethereum>::new_http(rpc_url)
+                        .get_chain_id()
+                        .await?;
+                    hosted_service(chain_id)?
+                }
+                (None, None) => unreachable!("required by clap"),
+            };
+            let http = reqwest::Client::new();
+            if args.wait {
+                let tx_hash = wait_for_execution(
+                    &http,
+                    &service,
+                    args.safe_tx_hash,
+                    Duration::from_secs(args.wait_timeout),
+                )
                 .await?;
-            println!("Length: {}", length);
+                println!("Withdrawal transaction: {:?}", tx_hash);
+                return Ok(());
+            }
+            let status = safe_tx_status(&http, &service, args.safe_tx_hash).await?;
+            println!("Safe: {}", status.safe);
+            println!("Nonce: {}", status.nonce);
+            println!(
+                "Confirmations: {} of {}",
+                status.confirmations.len(),
+                status.confirmations_required
+            );
+            for confirmation in &status.confirmations {
+                println!("  {}", confirmation.owner);
+            }
+            match status.transaction_hash {
+                Some(tx_hash) if status.is_executed => {
+                    println!("Executed in {:?}", tx_hash);
+                    if status.is_successful == Some(false) {
+                        println!("The withdrawal reverted");
+                    }
+                }
+                _ => println!("Not executed"),
+            }
+        }
+        Command::ExportProof(args) => {
+            let proof: SP1ProofWithPublicValues =
+                bincode::deserialize(&std::fs::read(&args.proof)?)?;
+            let bundle = ProofBundle::new(&proof, vkey(ELF)?)?;
+            bundle.verify()?;
+            std::fs::write(&args.out, serde_json::to_vec_pretty(&bundle)?)?;
+            println!("Wrote bundle to {}", args.out.display());
+        }
+        Command::VerifyProof(args) => {
+            let embedded = vkey(ELF)?;
+            let bundle = ProofBundle::load(&args.proof, embedded)?;
+            bundle.verify_for(args.vkey.unwrap_or(embedded))?;
+            let values = decode_any_public_values(&bundle.public_values)?;
+            let data = values.withdrawal;
+            println!("Proof verified");
+            println!("Mode: {:?}", bundle.mode);
+            println!("Vkey: {:?}", bundle.vkey);
+            println!("Pool: {}", data.contractAddress);
+            println!("Nullifier: {:?}", data.nullifier);
+            println!("Recipient: {}", data.recipient);
+            println!("Relayer fee: {}", data.relayerFee);
+            if !data.feeToken.is_zero() {
+                println!("Fee token: {}", data.feeToken);
+                println!("Fee token amount: {}", data.feeTokenAmount);
+            }
+            println!("Block: {}", data.blockNumber);
+            if let Some((collection, token_id)) = values.nft {
+                println!("Collection: {}", collection);
+                println!("Token id: {}", token_id);
+            }
+            if let Some(protocol_fee) = values.protocol_fee {
+                println!("Protocol fee: {}", protocol_fee);
+            }
+            if let Some((l2_chain_id, l2_recipient, message_hash)) = values.bridge {
+                println!("L2 chain: {}", l2_chain_id);
+                println!("L2 recipient: {}", l2_recipient);
+                println!("Bridge message hash: {:?}", message_hash);
+            }
+            if let Some((registry, denomination, version)) = values.registry {
+                println!("Registry: {}", registry);
+                println!("Denomination: {}", denomination);
+                println!("Version: {}", version);
+            }
+            if let Some((oracle, code_hash, proven_number, proven_hash)) = values.oracle {
+                println!("Block hash oracle: {}", oracle);
+                println!("Oracle code hash: {:?}", code_hash);
+                println!("Proven block: {}", proven_number);
+                println!("Proven block hash: {:?}", proven_hash);
+            }
+            if let Some((distribution_hash, note_value)) = values.split {
+                println!("Distribution hash: {:?}", distribution_hash);
+                println!("Note value: {}", note_value);
+            }
+        }
+        Command::Stats(args) => {
+            let client = reqwest::Client::new();
+            let activity = fetch_activity(&client, &args.indexer_url, args.address).await?;
+            let notes = NoteStore::load(&data_dir)?;
+            let stats = PoolStats::compute(
+                args.address,
+                &activity,
+                &notes.notes,
+                &notes.watched,
+                args.points,
+                args.window,
+                args.block_time,
+            );
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(());
+            }
+
+            let days = |blocks: u64| blocks as f64 * args.block_time as f64 / 86_400.0;
+            println!("Pool: {}", stats.pool);
+            println!("Indexed to block: {}", stats.block_number);
+            println!(
+                "Deposits: {}, withdrawals: {}, unspent: {}",
+                stats.deposits, stats.withdrawals, stats.unspent
+            );
+            println!(
+                "Over the last {} blocks: {:.2} deposits and {:.2} withdrawals per day",
+                stats.window_blocks, stats.deposits_per_day, stats.withdrawals_per_day
+            );
+            if let Some(blocks) = stats.median_blocks_in_pool {
+                println!(
+                    "Median time in pool (estimated): {} blocks, {:.1} days",
+                    blocks,
+                    days(blocks)
+                );
+            }
 
-            let (target_commitment, nullifier) = compute_commitment(&args.secret);
-            println!("Commitment: {:?}", target_commitment);
+            println!();
+            println!(
+                "{:>12} {:>10} {:>12} {:>10}",
+                "block", "deposits", "withdrawals", "unspent"
+            );
+            for point in &stats.anonymity_set {
+                println!(
+                    "{:>12} {:>10} {:>12} {:>10}",
+                    point.block_number, point.deposits, point.withdrawals, point.unspent
+                );
+            }
+
+            if !stats.notes.is_empty() {
+                println!();
+                println!(
+                    "{:>8} {:>12} {:>15} {:>6} {:>10}  commitment",
+                    "leaf", "block", "deposits after", "spent", "watch-only"
+                );
+                for note in &stats.notes {
+                    println!(
+                        "{:>8} {:>12} {:>15} {:>6} {:>10}  {:?}",
+                        note.leaf_index,
+                        note.block_number,
+                        note.deposits_after,
+                        note.spent,
+                        note.watch_only,
+                        note.commitment
+                    );
+                }
+            }
+        }
+        Command::NullifierStatus(args) => {
+            let (_, nullifier) = compute_commitment(&args.note.secret());
             println!("Nullifier: {:?}", nullifier);
-            let mut found_index = None;
-            for i in 0..length.to::<u64>() {
-                let commitment = contract
-                    .deposits(U256::from(i))
-                    .block(block_number.into())
-                    .call()
+
+            for rpc_url in args.rpc_urls {
+                let provider = RootProvider::<Ethereum>::new_http(rpc_url);
+                let chain_id = provider.get_chain_id().await?;
+                let pool = match &args.note {
+                    NoteOrSecret::Note(note) if note.chain_id != chain_id => continue,
+                    NoteOrSecret::Note(note) => note.pool,
+                    NoteOrSecret::Secret(_) => args
+                        .address
+                        .ok_or_eyre("--address is required when checking a raw secret")?,
+                };
+
+                let status = nullifier_status(&provider, pool, nullifier, args.lookback).await?;
+                match (status.spent, status.spend) {
+                    (false, _) => println!("Chain {}: pool {} unspent", chain_id, pool),
+                    (true, None) => println!(
+                        "Chain {}: pool {} spent, withdrawal not in the last {} blocks",
+                        chain_id, pool, args.lookback
+                    ),
+                    (true, Some(spend)) => {
+                        println!("Chain {}: pool {} spent", chain_id, pool);
+                        println!("  Block: {}", spend.block_number);
+                        if let Some(timestamp) = spend.block_timestamp {
+                            println!("  Timestamp: {}", timestamp);
+                        }
+                        println!("  Transaction: {:?}", spend.tx_hash);
+                        println!("  Recipient: {}", spend.recipient);
+                        println!("  Relayer: {} (fee {})", spend.relayer, spend.relayer_fee);
+                    }
+                }
+            }
+        }
+        Command::ExportViewkey(args) => {
+            println!("{}", args.note.view_key());
+        }
+        Command::ExportWatchkey(args) => {
+            println!("{}", args.note.watch_key());
+        }
+        Command::Audit(args) => {
+            let view_key = args.view_key;
+            let provider = RootProvider::<Ethereum>::new_http(args.rpc_url);
+            let chain_id = provider.get_chain_id().await?;
+            ensure!(
+                chain_id == view_key.chain_id,
+                "view key is for chain {}, RPC is on chain {chain_id}",
+                view_key.chain_id
+            );
+            println!("Pool: {}", view_key.pool);
+            println!("Commitment: {:?}", view_key.commitment);
+            let origin =
+                deposit_origin(&provider, view_key.pool, view_key.commitment, args.lookback)
                     .await?
-                    ._0;
-                if commitment == target_commitment {
-                    found_index = Some(i);
-                    break;
-                }
-            }
-            ensure!(found_index.is_some(), "commitment not found");
-            let found_index = found_index.unwrap();
-            println!("Found index: {}", found_index);
-
-            let proof = provider
-                .get_proof(args.address, vec![keys.0, keys.1])
-                .number(block_number)
-                .await
-                .unwrap();
-
-            let input = WithdrawalInput {
-                secret: args.secret,
-                account_proof: proof,
-                array_index: U256::from(found_index),
-                block_header: header.header.inner,
-                inclusion_set_branches: None,
-                contract_address: args.address,
-                array_slot: U256::from(0_u32),
-                relayer_fee: U256::from(0_u32),
-                recipient: Address::with_last_byte(0),
-                relayer: Address::with_last_byte(0),
+                    .ok_or_else(|| {
+                        eyre!(
+                            "no deposit of the commitment in the last {} blocks",
+                            args.lookback
+                        )
+                    })?;
+            println!("Leaf index: {}", origin.leaf_index);
+            println!("Block: {}", origin.block_number);
+            if let Some(timestamp) = origin.block_timestamp {
+                println!("Timestamp: {}", timestamp);
+            }
+            println!("Transaction: {:?}", origin.tx_hash);
+            println!("Depositor: {}", origin.depositor);
+        }
+        Command::CyclesCheck(args) => {
+            // Proofs as deep as a busy mainnet pool's.
+            let input = fixture::withdrawal(100_000, 1_000_000);
+            let prover = ProverClient::builder().cpu().build();
+            let (_, report) = prover
+                .execute(ELF, &withdrawal_stdin(&input)?)
+                .run()
+                .map_err(|e| eyre!("execution failed: {e}"))?;
+            let cycles = report.total_instruction_count();
+
+            println!("Cycles: {cycles}");
+            let mut regions: Vec<_> = report.cycle_tracker.iter().collect();
+            regions.sort();
+            for (region, region_cycles) in regions {
+                println!("  {region}: {region_cycles}");
+            }
+            ensure!(
+                cycles <= args.budget,
+                "{cycles} cycles is over the budget of {}",
+                args.budget
+            );
+            println!("Within the budget of {}", args.budget);
+        }
+        Command::Estimate(args) => {
+            let input = args.witness.build().await?;
+            process_any_withdrawal(&input)?;
+
+            let prover = ProverClient::builder().cpu().build();
+            let (_, report) = prover
+                .execute(ELF, &withdrawal_stdin(&input)?)
+                .run()
+                .map_err(|e| eyre!("execution failed: {e}"))?;
+            let estimate = Estimate {
+                cycles: report.total_instruction_count(),
+                syscalls: report.total_syscall_count(),
             };
 
-            let data = process_withdrawal(&input).unwrap();
-            println!("Data: {:?}", data);
+            println!("Cycles: {}", estimate.cycles);
+            println!("Syscalls: {}", estimate.syscalls);
+            println!("Projected local proving time (groth16):");
+            for profile in HardwareProfile::ALL {
+                let profile_name = profile.to_possible_value().unwrap();
+                println!(
+                    "  {:<12} {:>6}s",
+                    profile_name.get_name(),
+                    estimate.proving_seconds(profile)
+                );
+            }
+            println!(
+                "Projected network cost: {:.4}",
+                estimate.network_cost(args.network_price)
+            );
+            println!(
+                "Expected withdraw gas: {} (groth16), {} (plonk)",
+                GROTH16_VERIFY_GAS + WITHDRAW_OVERHEAD_GAS,
+                PLONK_VERIFY_GAS + WITHDRAW_OVERHEAD_GAS
+            );
+        }
+        Command::DiffCheck(args) => {
+            let provider = args.witness.connect().await?;
+            let input = build_witness(&provider, &args.witness).await?;
+            let data = process_any_withdrawal(&input)?.withdrawal;
 
-            if !args.execute && !args.prove {
-                return Ok(());
+            let mismatches = check_public_values(&provider, input.contract_address, &data).await?;
+            for mismatch in &mismatches {
+                println!(
+                    "{}: circuit {} != contract {}",
+                    mismatch.field, mismatch.circuit, mismatch.contract
+                );
             }
+            ensure!(
+                mismatches.is_empty(),
+                "{} fields differ between pool-lib and the contract",
+                mismatches.len()
+            );
+            println!("Contract agrees with pool-lib on all public values");
+        }
+        Command::Simulate(args) => {
+            let pool = RehearsalPool {
+                address: args.address,
+                artifact: &args.artifact,
+                vkey: vkey(ELF)?,
+                amount: args.amount,
+            };
+            let rehearsal = rehearse(&args.fork_url, pool, args.prover.backend()?).await?;
+            println!(
+                "Pool: {}{}",
+                rehearsal.pool,
+                if rehearsal.deployed {
+                    " (deployed on the fork)"
+                } else {
+                    ""
+                }
+            );
+            println!("Deposit transaction: {:?}", rehearsal.deposit.tx_hash);
+            println!("Leaf index: {}", rehearsal.deposit.leaf_index);
+            println!("Proof block: {}", rehearsal.block_number);
+            println!("Cycles: {}", rehearsal.cycles);
+            for mismatch in &rehearsal.mismatches {
+                println!(
+                    "{}: circuit {} != contract {}",
+                    mismatch.field, mismatch.circuit, mismatch.contract
+                );
+            }
+            ensure!(
+                rehearsal.mismatches.is_empty(),
+                "{} fields differ between pool-lib and the contract",
+                rehearsal.mismatches.len()
+            );
+            println!("Simulated submission succeeded");
+        }
+        Command::GasReport(args) => {
+            let vkey = vkey(ELF)?;
 
-            let prover = ProverClient::from_env();
-            if args.execute {
-                let mut stdin = SP1Stdin::new();
-                let serialized = serde_cbor::to_vec(&input).unwrap();
-                stdin.write_slice(&serialized);
-                let (_output, report) = prover.execute(ELF, &stdin).run().unwrap();
-                println!("Cycles: {}", report.total_instruction_count());
-                println!("Report: {}", report);
+            let mut measurements = Vec::new();
+            for path in &args.proofs {
+                let measurement =
+                    measure(&args.fork_url, &args.artifact, vkey, args.amount, path).await?;
+                measurements.push((path, measurement));
+            }
+            let aggregation_vkey = vkey(AGGREGATION_ELF)?;
+            let mut aggregates = Vec::new();
+            for path in &args.aggregates {
+                let measurement = measure_aggregate(&args.fork_url, aggregation_vkey, path).await?;
+                aggregates.push((path, measurement));
             }
+            aggregates.sort_by_key(|(_, m)| m.count);
 
-            if args.prove {
-                let mut stdin = SP1Stdin::new();
-                let serialized = serde_cbor::to_vec(&input).unwrap();
-                stdin.write_slice(&serialized);
-                let (pk, _vk) = prover.setup(ELF);
-                let start = std::time::Instant::now();
-                let proof = prover.prove(&pk, &stdin).compressed().run().unwrap();
-                println!("Successfully generated proof after {:?}", start.elapsed());
-                println!("Proof bytes: {}", hex::encode(proof.bytes()));
+            println!(
+                "{:<10} {:>6} {:>12} {:>12} {:>14} {:>16}  proof",
+                "mode", "batch", "proof bytes", "verify gas", "withdraw gas", "verify gas each"
+            );
+            for (path, m) in &measurements {
+                let mode = m.mode.to_possible_value().unwrap();
+                println!(
+                    "{:<10} {:>6} {:>12} {:>12} {:>14} {:>16}  {}",
+                    mode.get_name(),
+                    1,
+                    m.proof_bytes,
+                    m.verify_gas,
+                    m.withdraw_gas,
+                    m.verify_gas,
+                    path.display()
+                );
+            }
+            // No pool takes aggregated proofs yet, so they have no withdraw gas to report.
+            for (path, m) in &aggregates {
+                let mode = m.mode.to_possible_value().unwrap();
+                println!(
+                    "{:<10} {:>6} {:>12} {:>12} {:>14} {:>16}  {}",
+                    mode.get_name(),
+                    m.count,
+                    m.proof_bytes,
+                    m.verify_gas,
+                    "-",
+                    m.verify_gas_per_withdrawal(),
+                    path.display()
+                );
+            }
+            for mode in [ProofMode::Groth16, ProofMode::Plonk] {
+                let gas: Vec<u64> = measurements
+                    .iter()
+                    .filter(|(_, m)| m.mode == mode)
+                    .map(|(_, m)| m.withdraw_gas)
+                    .collect();
+                if gas.is_empty() {
+                    continue;
+                }
+                let total: u64 = gas.iter().sum();
+                println!(
+                    "{}: {} withdrawals, {} gas total, {} gas average",
+                    mode.to_possible_value().unwrap().get_name(),
+                    gas.len(),
+                    total,
+                    total / gas.len() as u64
+                );
+            }
+        }
+        Command::VerifyBuild(args) => {
+            let rebuilt = BuildId::of(&rebuild(&args.program_dir, &args.tag)?)?;
+            let embedded = BuildId::of(ELF)?;
+            println!("Rebuilt ELF hash:  {:?}", rebuilt.elf_hash);
+            println!("Embedded ELF hash: {:?}", embedded.elf_hash);
+            println!("Rebuilt vkey:      {:?}", rebuilt.vkey);
+            println!("Embedded vkey:     {:?}", embedded.vkey);
 
-                // Write proof to file
-                let mut file = std::fs::File::create("proof.bin").unwrap();
-                let serialized = bincode::serialize(&proof).unwrap();
-                file.write_all(&serialized).unwrap();
+            let mut mismatches = Vec::new();
+            if rebuilt.elf_hash != embedded.elf_hash {
+                mismatches.push("embedded ELF was not built from this source");
+            }
+            if rebuilt.vkey != embedded.vkey {
+                mismatches.push("embedded vkey differs from the rebuilt vkey");
+            }
+            if let (Some(rpc_url), Some(address)) = (args.rpc_url, args.address) {
+                let provider = RootProvider::<Ethereum>::new_http(rpc_url);
+                let deployed = Pool::new(address, &provider).programVkey().call().await?._0;
+                println!("Deployed vkey:     {:?}", deployed);
+                if deployed != rebuilt.vkey {
+                    mismatches.push("deployed pool verifies a different program");
+                }
+            }
+
+            for mismatch in &mismatches {
+                println!("Mismatch: {}", mismatch);
+            }
+            ensure!(mismatches.is_empty(), "build is not reproducible");
+            println!("Build verified");
+        }
+        Command::Index(args) => {
+            let provider = RootProvider::<Ethereum>::new_http(args.rpc_url);
+            let chain_id = provider.get_chain_id().await?;
+            let finalized = provider
+                .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+                .await?
+                .ok_or_eyre("finalized block not found")?;
+
+            let mut index = DepositIndex::load(&data_dir, chain_id, args.address)?;
+            index.sync(&provider, finalized.header.number).await?;
+            index.save(&data_dir)?;
+            println!(
+                "Indexed {} deposits at block {}",
+                index.deposits.len(),
+                index.block_number
+            );
+        }
+        Command::ImportSnapshot(args) => {
+            let snapshot = Snapshot::read(&mut std::io::BufReader::new(std::fs::File::open(
+                &args.snapshot,
+            )?))?;
+            if let Some(rpc_url) = args.rpc_url {
+                let provider = RootProvider::<Ethereum>::new_http(rpc_url);
+                ensure!(
+                    provider.get_chain_id().await? == snapshot.chain_id,
+                    "snapshot is for chain {}",
+                    snapshot.chain_id
+                );
+                ensure!(
+                    block_hash(&provider, snapshot.block_number).await? == snapshot.block_hash,
+                    "snapshot block {} is not canonical",
+                    snapshot.block_number
+                );
+                let length =
+                    deposits_length(&provider, snapshot.pool, snapshot.block_number).await?;
+                ensure!(
+                    length == snapshot.leaves.len() as u64,
+                    "pool had {length} deposits at block {}, snapshot has {}",
+                    snapshot.block_number,
+                    snapshot.leaves.len()
+                );
+            } else {
+                warn!(
+                    block_number = snapshot.block_number,
+                    "importing a snapshot without --rpc-url, its deposits are not checked against \
+                     the chain"
+                );
+            }
+
+            let existing = DepositIndex::load(&data_dir, snapshot.chain_id, snapshot.pool)?;
+            ensure!(
+                args.force || existing.block_number < snapshot.block_number,
+                "local index is already at block {}, pass --force to replace it",
+                existing.block_number
+            );
+            let index = DepositIndex {
+                chain_id: snapshot.chain_id,
+                pool: snapshot.pool,
+                block_number: snapshot.block_number,
+                block_hash: snapshot.block_hash,
+                deposits: snapshot.leaves,
+            };
+            index.save(&data_dir)?;
+            println!(
+                "Imported {} deposits at block {}",
+                index.deposits.len(),
+                index.block_number
+            );
+        }
+        Command::VerifyAllowList(args) => {
+            let bundle = allow_list::load(&args.bundle)?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            bundle.verify(&args.issuers, now)?;
+            println!(
+                "Allow-list {} for pool {} on chain {}, issued by {}",
+                bundle.allow_list.setId, bundle.pool, bundle.chain_id, bundle.issuer
+            );
+            println!(
+                "Root: {} ({} members{})",
+                bundle.allow_list.root,
+                bundle.allow_list.leafCount,
+                if bundle.leaves.is_empty() {
+                    ", leaves not included"
+                } else {
+                    ""
+                }
+            );
+            println!(
+                "Valid from {} until {}",
+                bundle.allow_list.validFrom, bundle.allow_list.validUntil
+            );
+        }
+        Command::Serve(args) => {
+            ensure!(args.jsonrpc, "pass --jsonrpc, the only mode so far");
+            let server = JsonRpcServer::new(JsonRpcConfig {
+                rpc_url: args.rpc_url,
+                prover: args.prover,
+                timeout: args.timeout.map(Duration::from_secs),
+                auth_token: args.auth_token,
+            })?;
+            info!(listen = %args.listen, "serving JSON-RPC");
+            let listener = tokio::net::TcpListener::bind(args.listen).await?;
+            axum::serve(listener, router(Arc::new(server))).await?;
+        }
+        Command::Schema(args) => {
+            let schemas = schemas();
+            match (args.name, args.out_dir) {
+                (Some(name), _) => {
+                    let (_, schema) = schemas
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .ok_or_else(|| eyre!("no schema {name}, see `pool schema`"))?;
+                    println!("{}", serde_json::to_string_pretty(schema)?);
+                }
+                (None, Some(out_dir)) => {
+                    std::fs::create_dir_all(&out_dir)?;
+                    for (name, schema) in &schemas {
+                        let path = out_dir.join(format!("{name}.json"));
+                        std::fs::write(&path, serde_json::to_vec_pretty(schema)?)?;
+                    }
+                    println!("Wrote {} schemas to {}", schemas.len(), out_dir.display());
+                }
+                (None, None) => {
+                    for (name, _) in &schemas {
+                        println!("{name}");
+                    }
+                }
+            }
+        }
+        Command::RootStatus(args) => {
+            let provider = RootProvider::<Ethereum>::new_http(args.rpc_url);
+            let chain_id = provider.get_chain_id().await?;
+            let index = DepositIndex::load(&data_dir, chain_id, args.address)?;
+            ensure!(
+                index.block_number != 0,
+                "pool not indexed, run `pool index` first"
+            );
+
+            let status = root_status(&provider, &index).await?;
+            println!("Block: {}", index.block_number);
+            println!(
+                "Local root: {:?} ({} deposits)",
+                status.local_root,
+                index.deposits.len()
+            );
+            println!("Chain root: {:?}", status.chain_root);
+            if status.reorged {
+                println!(
+                    "Indexed block {:?} is no longer canonical",
+                    index.block_hash
+                );
+            }
+            if let Some(i) = status.first_mismatch {
+                println!("First divergent deposit: {}", i);
+            }
+            if status.pending > 0 {
+                println!("{} deposits since the indexed block", status.pending);
             }
+            ensure!(!status.diverged(), "local index diverges from chain");
+            println!("Local index matches chain");
         }
     }
 
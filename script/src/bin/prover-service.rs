@@ -0,0 +1,86 @@
+use clap::Parser;
+use eyre::{ensure, Result};
+use pool_script::{
+    keys::KeyCache,
+    logging::LogArgs,
+    prove::{ProverBackend, ProverTuning},
+    prover_service::{backends, ProverService},
+    relayer::shutdown_signal,
+    secure,
+    verify_build::WITHDRAWAL_PROGRAM,
+};
+use std::{net::SocketAddr, sync::Arc};
+use tracing::info;
+
+/// Proving service: proves withdrawals over gRPC for any number of frontends.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    log: LogArgs,
+
+    #[clap(long, env = "PROVER_LISTEN", default_value = "0.0.0.0:50051")]
+    listen: SocketAddr,
+
+    /// Backends to prove on, in order of preference. Jobs go to the first one with a free slot.
+    #[clap(
+        long = "backend",
+        env = "PROVER_BACKENDS",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "cpu"
+    )]
+    backends: Vec<ProverBackend>,
+
+    /// Jobs each local backend proves at once.
+    #[clap(long, default_value_t = 1)]
+    local_concurrency: u32,
+
+    /// Jobs the prover network proves at once.
+    #[clap(long, default_value_t = 16)]
+    network_concurrency: u32,
+
+    /// Whitelisted prover network key. Required with `--backend network`.
+    #[clap(long, env = "NETWORK_PRIVATE_KEY")]
+    network_private_key: Option<String>,
+
+    /// Prover network RPC endpoint.
+    #[clap(long, env = "NETWORK_RPC_URL")]
+    network_rpc_url: Option<String>,
+
+    #[clap(flatten)]
+    tuning: ProverTuning,
+
+    #[clap(flatten)]
+    keys: KeyCache,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    let args = Args::parse();
+    args.log.init();
+    secure::harden();
+
+    ensure!(
+        !args.backends.contains(&ProverBackend::Network) || args.network_private_key.is_some(),
+        "--network-private-key is required with --backend network"
+    );
+    WITHDRAWAL_PROGRAM.checked_vkey()?;
+    let backends = backends(
+        &args.backends,
+        args.network_private_key,
+        args.network_rpc_url,
+        &args.tuning,
+        &args.keys,
+        args.local_concurrency,
+        args.network_concurrency,
+    )?;
+    let service = Arc::new(ProverService::new(backends)?);
+    info!(listen = %args.listen, backends = ?args.backends, "serving prover");
+    tonic::transport::Server::builder()
+        .add_service(service.into_server())
+        .serve_with_shutdown(args.listen, shutdown_signal())
+        .await?;
+    Ok(())
+}
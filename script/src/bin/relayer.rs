@@ -0,0 +1,464 @@
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::BlockTransactionsKind,
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use clap::Parser;
+use eyre::{ensure, OptionExt, Result};
+use pool_script::{
+    allow_list,
+    ens::AddressOrName,
+    logging::LogArgs,
+    price::{PricedToken, DEFAULT_MAX_PRICE_AGE},
+    prove::ProverArgs,
+    relayer::{
+        gas::{GasStrategy, GasStrategyKind},
+        jobs::JobStore,
+        keys::{KeyManager, KeySource},
+        policy::{Policy, RateLimits},
+        router,
+        screening::Screener,
+        shutdown_signal,
+        spending::{FundingConfig, SpendingLimits},
+        txmanager::TxConfig,
+        Relayer, RelayerConfig, Schedule,
+    },
+    secure::{self, SecretParser},
+    verify_build::{check_pool_vkey, WITHDRAWAL_PROGRAM},
+    webhook::WebhookArgs,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tracing::info;
+
+/// Relayer server: quotes fees and submits withdrawals from its own wallet.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    log: LogArgs,
+
+    #[clap(long, env = "RPC_URL")]
+    rpc_url: Url,
+
+    /// Key of the relayer wallet, which pays gas and receives fees: `keystore:<path>`,
+    /// `aws-kms:<key id or ARN>`, `gcp-kms:<key version name>`, or a private key.
+    #[clap(
+        long,
+        visible_alias = "private-key",
+        env = "RELAYER_KEY",
+        value_parser = SecretParser::<KeySource>::new()
+    )]
+    key: KeySource,
+
+    /// File holding the password of keystore keys.
+    #[clap(long, env = "RELAYER_KEYSTORE_PASSWORD_FILE")]
+    keystore_password_file: Option<PathBuf>,
+
+    /// Key to rotate to at `--rotate-at`, in the same forms as `--key`.
+    #[clap(
+        long,
+        env = "RELAYER_NEXT_KEY",
+        requires = "rotate_at",
+        value_parser = SecretParser::<KeySource>::new()
+    )]
+    next_key: Option<KeySource>,
+
+    /// Unix time at which quotes start being signed by `--next-key`.
+    #[clap(long, env = "RELAYER_ROTATE_AT", requires = "next_key")]
+    rotate_at: Option<u64>,
+
+    /// Seconds after the rotation that quotes of the previous key are still taken. Defaults to
+    /// `--quote-ttl`, so every quote handed out before the rotation can be used.
+    #[clap(long, env = "RELAYER_ROTATION_OVERLAP")]
+    rotation_overlap: Option<u64>,
+
+    /// Lock all of the relayer's memory so its keys, and the note secrets of inputs it proves,
+    /// are never swapped out. Needs a memory lock limit (`ulimit -l`) above what it uses.
+    #[clap(long, env = "RELAYER_LOCK_MEMORY")]
+    lock_memory: bool,
+
+    #[clap(long, env = "RELAYER_LISTEN", default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Fee charged per withdrawal, in wei.
+    #[clap(long, env = "RELAYER_FEE")]
+    fee: U256,
+
+    /// Seconds a quote stays valid. Clients prove after taking a quote, so this must cover
+    /// proving time.
+    #[clap(long, default_value_t = 3600)]
+    quote_ttl: u64,
+
+    /// Pools to serve, as addresses, ENS names or
+    /// `registry:<registry>/<denomination in wei>/<version>`, resolved at the finalized block on
+    /// startup. Any pool if not set.
+    #[clap(long = "pool", env = "RELAYER_POOLS", value_delimiter = ',')]
+    pools: Vec<AddressOrName>,
+
+    /// ERC-20 tokens fees may be paid in, as `<token>=<price feed>` with a Chainlink-style
+    /// `<token>/ETH` feed. Token quotes are worth `--fee` at the feed's price.
+    #[clap(long = "fee-token", env = "RELAYER_FEE_TOKENS", value_delimiter = ',')]
+    fee_tokens: Vec<PricedToken>,
+
+    /// Seconds after which a price feed is too stale to quote or value fees with.
+    #[clap(long, default_value_t = DEFAULT_MAX_PRICE_AGE)]
+    max_price_age: u64,
+
+    /// Minimum fee as basis points of the pool denomination, on top of `--fee`.
+    #[clap(long, env = "RELAYER_MIN_FEE_BPS")]
+    min_fee_bps: Option<u64>,
+
+    /// Highest gas price, in wei, withdrawals are submitted at, and stuck ones replaced at.
+    #[clap(long, env = "RELAYER_MAX_GAS_PRICE")]
+    max_gas_price: Option<u128>,
+
+    /// Most blocks between a proof's block and the current block.
+    #[clap(long)]
+    max_proof_age: Option<u64>,
+
+    /// Signed allow-list bundles whose roots are the only `exclusionSetRoot`s accepted. Any root
+    /// if none are given.
+    #[clap(
+        long = "allow-list",
+        env = "RELAYER_ALLOW_LISTS",
+        value_delimiter = ',',
+        requires = "allow_list_issuers"
+    )]
+    allow_lists: Vec<PathBuf>,
+
+    /// Issuers allow-list bundles are trusted from.
+    #[clap(
+        long = "allow-list-issuer",
+        env = "RELAYER_ALLOW_LIST_ISSUERS",
+        value_delimiter = ','
+    )]
+    allow_list_issuers: Vec<Address>,
+
+    /// Screening service asked about each recipient, answering in the format of the
+    /// Chainalysis sanctions API.
+    #[clap(long, env = "RELAYER_SCREENING_URL")]
+    screening_url: Option<Url>,
+
+    /// API key sent to the screening service as `X-API-Key`.
+    #[clap(long, env = "RELAYER_SCREENING_API_KEY", requires = "screening_url")]
+    screening_api_key: Option<String>,
+
+    /// Seconds to wait for the screening service.
+    #[clap(long, default_value_t = 5)]
+    screening_timeout: u64,
+
+    /// Accept withdrawals when the screening service fails, instead of rejecting them.
+    #[clap(long, requires = "screening_url")]
+    screening_fail_open: bool,
+
+    /// Withdrawals accepted per client IP every `--rate-limit-window`.
+    #[clap(long, env = "RELAYER_IP_RATE_LIMIT")]
+    ip_rate_limit: Option<u32>,
+
+    /// Withdrawals accepted per nullifier prefix every `--rate-limit-window`.
+    #[clap(long)]
+    nullifier_rate_limit: Option<u32>,
+
+    /// Bytes of the nullifier grouped by `--nullifier-rate-limit`.
+    #[clap(long, default_value_t = 4)]
+    nullifier_prefix_bytes: usize,
+
+    /// Seconds of the rate limit window.
+    #[clap(long, default_value_t = 60)]
+    rate_limit_window: u64,
+
+    /// Wallet balance, in wei, below which `GET /readyz` reports the relayer not ready.
+    #[clap(long, env = "RELAYER_MIN_BALANCE", default_value_t = U256::ZERO)]
+    min_balance: U256,
+
+    /// Pending jobs above which `GET /readyz` reports the relayer not ready.
+    #[clap(long, env = "RELAYER_MAX_QUEUE_DEPTH")]
+    max_queue_depth: Option<u64>,
+
+    /// Most wei of gas the wallet spends in any rolling hour.
+    #[clap(long, env = "RELAYER_MAX_SPEND_PER_HOUR")]
+    max_spend_per_hour: Option<U256>,
+
+    /// Most wei of gas the wallet spends in any rolling day.
+    #[clap(long, env = "RELAYER_MAX_SPEND_PER_DAY")]
+    max_spend_per_day: Option<U256>,
+
+    /// Key of a funding wallet that keeps the relayer wallet topped up, so the relayer wallet
+    /// only holds a small balance.
+    #[clap(
+        long,
+        env = "RELAYER_FUNDING_PRIVATE_KEY",
+        requires_all = ["top_up_below", "top_up_to", "max_top_up_per_day"],
+        value_parser = SecretParser::<PrivateKeySigner>::new()
+    )]
+    funding_private_key: Option<PrivateKeySigner>,
+
+    /// Relayer wallet balance, in wei, below which it is topped up.
+    #[clap(long)]
+    top_up_below: Option<U256>,
+
+    /// Balance, in wei, the relayer wallet is topped up to.
+    #[clap(long)]
+    top_up_to: Option<U256>,
+
+    /// Most wei the funding wallet sends in any rolling day.
+    #[clap(long)]
+    max_top_up_per_day: Option<U256>,
+
+    /// Seconds between checks of the relayer wallet balance.
+    #[clap(long, default_value_t = 60)]
+    top_up_interval: u64,
+
+    /// Seconds to wait on SIGTERM for transactions being sent to be mined before exiting.
+    #[clap(long, default_value_t = 120)]
+    shutdown_timeout: u64,
+
+    /// Job database.
+    #[clap(long, env = "RELAYER_DB", default_value = ".pool/relayer.sqlite")]
+    db: PathBuf,
+
+    /// Attempts made at a withdrawal before giving up on it.
+    #[clap(long, default_value_t = 5)]
+    max_attempts: u32,
+
+    /// Blocks on top of a mined withdrawal before it is no longer watched for reorgs.
+    #[clap(long, default_value_t = 3)]
+    confirmations: u64,
+
+    /// Most seconds a withdrawal is randomly delayed by before it is sent. Proofs must still
+    /// be recent when they land, so keep this well under the proof age limit.
+    #[clap(long, env = "RELAYER_JITTER", default_value_t = 0)]
+    jitter: u64,
+
+    /// Hold verified withdrawals and send them together every this many seconds.
+    #[clap(long, env = "RELAYER_BATCH_WINDOW")]
+    batch_window: Option<u64>,
+
+    /// Seconds without a receipt after which a withdrawal is resent with higher fees.
+    #[clap(long, default_value_t = 60)]
+    stuck_after: u64,
+
+    /// Percentage fees are raised by when replacing a stuck withdrawal.
+    #[clap(long, default_value_t = 15)]
+    fee_bump_percent: u64,
+
+    /// Replacements made of a stuck withdrawal transaction, which is then rebroadcast as it is.
+    #[clap(long, default_value_t = 5)]
+    max_fee_bumps: u32,
+
+    /// Seconds after which a withdrawal attempt fails if none of its transactions is mined.
+    #[clap(long, default_value_t = 30 * 60)]
+    tx_deadline: u64,
+
+    #[clap(flatten)]
+    webhooks: WebhookArgs,
+
+    /// How transactions are priced.
+    #[clap(long, value_enum, default_value_t = GasStrategyKind::Estimate)]
+    gas_strategy: GasStrategyKind,
+
+    /// Priority fee in wei for `--gas-strategy fixed-tip`.
+    #[clap(long, required_if_eq("gas_strategy", "fixed-tip"))]
+    gas_tip: Option<u128>,
+
+    /// Priority fee percentile of recent blocks for `--gas-strategy percentile`.
+    #[clap(long, default_value_t = 50.0)]
+    gas_percentile: f64,
+
+    /// Blocks sampled by `--gas-strategy percentile`.
+    #[clap(long, default_value_t = 20)]
+    gas_percentile_blocks: u64,
+
+    /// Fee oracle for `--gas-strategy oracle`.
+    #[clap(long, required_if_eq("gas_strategy", "oracle"))]
+    gas_oracle_url: Option<Url>,
+
+    /// Most a withdrawal may cost in gas, as a percentage of its relayer fee. 100 means the
+    /// relayer never spends more than it collects.
+    #[clap(long, env = "RELAYER_MAX_GAS_COST_PERCENT")]
+    max_gas_cost_percent: Option<u64>,
+
+    /// Accept raw withdrawal inputs and prove them.
+    #[clap(long)]
+    prove: bool,
+
+    #[clap(flatten)]
+    prover: ProverArgs,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    let args = Args::parse();
+    args.log.init();
+    secure::harden();
+    if args.lock_memory {
+        secure::lock_all()?;
+    }
+
+    let chain_id = RootProvider::<Ethereum>::new_http(args.rpc_url.clone())
+        .get_chain_id()
+        .await?;
+    let gas = match args.gas_strategy {
+        GasStrategyKind::Estimate => GasStrategy::Estimate,
+        GasStrategyKind::FixedTip => GasStrategy::FixedTip {
+            tip: args.gas_tip.ok_or_eyre("--gas-tip is required")?,
+        },
+        GasStrategyKind::Percentile => GasStrategy::Percentile {
+            percentile: args.gas_percentile,
+            blocks: args.gas_percentile_blocks,
+        },
+        GasStrategyKind::Oracle => GasStrategy::Oracle {
+            url: args
+                .gas_oracle_url
+                .ok_or_eyre("--gas-oracle-url is required")?,
+        },
+    };
+    let allow_lists =
+        allow_list::load_trusted(&args.allow_lists, &args.allow_list_issuers, chain_id)?;
+    let screener = args
+        .screening_url
+        .map(|url| {
+            Screener::new(
+                url,
+                args.screening_api_key,
+                Duration::from_secs(args.screening_timeout),
+                args.screening_fail_open,
+            )
+        })
+        .transpose()?;
+    let password_file = args
+        .keystore_password_file
+        .as_ref()
+        .map(std::fs::read_to_string)
+        .transpose()?;
+    let password = password_file
+        .as_deref()
+        .map(|password| password.trim_end_matches(['\r', '\n']));
+    let mut keys = KeyManager::load(&args.key, password, chain_id).await?;
+    if let (Some(next_key), Some(rotate_at)) = (&args.next_key, args.rotate_at) {
+        let overlap = Duration::from_secs(args.rotation_overlap.unwrap_or(args.quote_ttl));
+        keys.rotate(next_key, password, chain_id, rotate_at, overlap)
+            .await?;
+    }
+    if let Some(password_file) = password_file {
+        secure::scrub(&mut password_file.into_bytes());
+    }
+    let jobs = JobStore::open(&args.db)?;
+    // Proofs are only accepted for the program this binary embeds, and no configured pool may
+    // verify another one.
+    let vkey = WITHDRAWAL_PROGRAM.checked_vkey()?;
+    let provider = RootProvider::<Ethereum>::new_http(args.rpc_url.clone());
+    let finalized = provider
+        .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+        .await?
+        .ok_or_eyre("finalized block not found")?
+        .header
+        .number;
+    let mut pools = Vec::with_capacity(args.pools.len());
+    for pool in &args.pools {
+        let address = pool.resolve(&provider, finalized).await?;
+        check_pool_vkey(&provider, address, vkey).await?;
+        pools.push(address);
+    }
+    let relayer = Relayer::new(
+        RelayerConfig {
+            rpc_url: args.rpc_url,
+            keys: Arc::new(keys),
+            chain_id,
+            fee: args.fee,
+            quote_ttl: args.quote_ttl,
+            policy: Policy {
+                pools,
+                min_fee: args.fee,
+                min_fee_bps: args.min_fee_bps,
+                max_gas_price: args.max_gas_price,
+                max_proof_age: args.max_proof_age,
+                allow_lists,
+                screener,
+                fee_tokens: args.fee_tokens,
+                max_price_age: args.max_price_age,
+            },
+            limits: RateLimits {
+                per_ip: args.ip_rate_limit,
+                per_nullifier: args.nullifier_rate_limit,
+                nullifier_prefix: args.nullifier_prefix_bytes,
+                window: Duration::from_secs(args.rate_limit_window),
+            },
+            vkey,
+            prover: args.prove.then_some(args.prover),
+            max_attempts: args.max_attempts,
+            confirmations: args.confirmations,
+            schedule: Schedule {
+                jitter: Duration::from_secs(args.jitter),
+                batch_window: args.batch_window.map(Duration::from_secs),
+            },
+            notifier: args.webhooks.notifier(),
+            tx: TxConfig {
+                stuck_after: Duration::from_secs(args.stuck_after),
+                bump_percent: args.fee_bump_percent,
+                max_bumps: args.max_fee_bumps,
+                deadline: Duration::from_secs(args.tx_deadline),
+                gas,
+                max_cost_percent: args.max_gas_cost_percent,
+                max_fee_per_gas: args.max_gas_price,
+            },
+            min_balance: args.min_balance,
+            max_queue_depth: args.max_queue_depth,
+            spending: SpendingLimits {
+                per_hour: args.max_spend_per_hour,
+                per_day: args.max_spend_per_day,
+            },
+        },
+        jobs,
+    )?;
+    info!(
+        relayer = %relayer.address(),
+        chain_id,
+        %vkey,
+        listen = %args.listen,
+        "starting relayer"
+    );
+
+    let relayer = Arc::new(relayer);
+    relayer.resume()?;
+    if let Some(signer) = args.funding_private_key {
+        let threshold = args.top_up_below.ok_or_eyre("--top-up-below is required")?;
+        let target = args.top_up_to.ok_or_eyre("--top-up-to is required")?;
+        ensure!(
+            target > threshold,
+            "--top-up-to must be above --top-up-below"
+        );
+        relayer.spawn_funder(FundingConfig {
+            signer,
+            threshold,
+            target,
+            max_per_day: args
+                .max_top_up_per_day
+                .ok_or_eyre("--max-top-up-per-day is required")?,
+            interval: Duration::from_secs(args.top_up_interval),
+        });
+    }
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    let shutdown = {
+        let relayer = relayer.clone();
+        async move {
+            shutdown_signal().await;
+            relayer.begin_shutdown();
+        }
+    };
+    axum::serve(
+        listener,
+        router(relayer.clone()).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await?;
+    relayer
+        .drain(Duration::from_secs(args.shutdown_timeout))
+        .await?;
+    Ok(())
+}
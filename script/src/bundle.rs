@@ -0,0 +1,102 @@
+use crate::{
+    prove::{ProofMode, ELF},
+    relayer::verify::verify_proof,
+    submit::Withdrawal,
+};
+use alloy::primitives::{keccak256, Bytes, B256};
+use eyre::{bail, ensure, Result};
+use pool_lib::schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{SP1Proof, SP1ProofWithPublicValues};
+use std::path::Path;
+
+/// A groth16 or plonk proof in the JSON layout of SP1 contract fixtures, with `vkey`,
+/// `publicValues` and `proof` as `ISP1Verifier.verifyProof` takes them, so the proof can be
+/// checked by tooling that knows nothing about pool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofBundle {
+    /// Verifying key hash of the program, as the pool stores it in `programVkey`.
+    #[schemars(schema_with = "schema::bytes32")]
+    pub vkey: B256,
+    pub mode: ProofMode,
+    /// Proof with the 4-byte selector of the SP1 verifier it is for.
+    #[schemars(schema_with = "schema::bytes")]
+    pub proof: Bytes,
+    /// ABI-encoded `WithdrawalData`.
+    #[schemars(schema_with = "schema::bytes")]
+    pub public_values: Bytes,
+    /// Version of this crate, which embeds the program.
+    pub program_version: String,
+    /// keccak256 of the program ELF, as `pool verify-build` reports it.
+    #[schemars(schema_with = "schema::bytes32")]
+    pub elf_hash: B256,
+    pub sp1_version: String,
+}
+
+impl ProofBundle {
+    /// Bundle a proof of the embedded program, whose verifying key hash is `vkey`.
+    pub fn new(proof: &SP1ProofWithPublicValues, vkey: B256) -> Result<Self> {
+        let mode = match &proof.proof {
+            SP1Proof::Groth16(_) => ProofMode::Groth16,
+            SP1Proof::Plonk(_) => ProofMode::Plonk,
+            _ => bail!("only groth16 and plonk proofs can be verified outside SP1"),
+        };
+        Ok(Self {
+            vkey,
+            mode,
+            proof: proof.bytes().into(),
+            public_values: proof.public_values.to_vec().into(),
+            program_version: env!("CARGO_PKG_VERSION").to_string(),
+            elf_hash: keccak256(ELF),
+            sp1_version: proof.sp1_version.clone(),
+        })
+    }
+
+    /// Read a bundle, or a bincode proof file written by `withdraw --prove` which is bundled
+    /// with `vkey`.
+    pub fn load(path: &Path, vkey: B256) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if is_bundle(&bytes) {
+            return Ok(serde_json::from_slice(&bytes)?);
+        }
+        Self::new(&bincode::deserialize(&bytes)?, vkey)
+    }
+
+    /// Check the proof against its `vkey` the way the SP1 verifier contracts do.
+    pub fn verify(&self) -> Result<()> {
+        verify_proof(self.vkey, &self.public_values, &self.proof)?;
+        Ok(())
+    }
+
+    /// Check the bundle is for the program `vkey` and that its proof verifies.
+    pub fn verify_for(&self, vkey: B256) -> Result<()> {
+        ensure!(
+            self.vkey == vkey,
+            "proof is for program {}, not {vkey}",
+            self.vkey
+        );
+        self.verify()
+    }
+}
+
+impl From<ProofBundle> for Withdrawal {
+    fn from(bundle: ProofBundle) -> Self {
+        Self {
+            public_values: bundle.public_values,
+            proof: bundle.proof,
+            bridge: None,
+            splits: Vec::new(),
+            token_fee: None,
+        }
+    }
+}
+
+/// Whether `bytes` hold a [`ProofBundle`] rather than a bincode proof file.
+pub fn is_bundle(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'{')
+}
@@ -0,0 +1,55 @@
+use alloy::{
+    primitives::{keccak256, Address, B256},
+    providers::Provider,
+};
+use eyre::{bail, ensure, Result};
+use tracing::warn;
+
+/// Runtime bytecode hashes of known pool deployments.
+///
+/// The pool stores its verifier, vkey and amount as immutables, so every deployment has a distinct
+/// runtime bytecode hash, which can only be taken from the deployment itself. Entries are added
+/// here once a deployment has been checked; there is none yet, so every pool needs
+/// `--expected-code-hash` or `--allow-unknown-pool`.
+pub const KNOWN_POOL_CODE_HASHES: &[(B256, &str)] = &[];
+
+/// Fetch the runtime bytecode at `address` at `block_number` and check its hash against `expected`,
+/// or against [`KNOWN_POOL_CODE_HASHES`] if no hash is given. A pool matching neither is an error
+/// unless `allow_unknown`, when it is only warned about. Returns the bytecode hash.
+pub async fn verify_pool_code<P: Provider>(
+    provider: &P,
+    address: Address,
+    block_number: u64,
+    expected: Option<B256>,
+    allow_unknown: bool,
+) -> Result<B256> {
+    let code = provider.get_code_at(address).number(block_number).await?;
+    ensure!(!code.is_empty(), "no contract deployed at {address}");
+
+    let code_hash = keccak256(&code);
+    match expected {
+        Some(expected) => ensure!(
+            code_hash == expected,
+            "pool bytecode hash mismatch: expected {expected}, got {code_hash}"
+        ),
+        None if is_known(code_hash) => {}
+        None if allow_unknown => warn!(
+            %address,
+            %code_hash,
+            "unknown pool deployment, not checking its bytecode"
+        ),
+        None => bail!(
+            "unknown pool deployment at {address} (bytecode hash {code_hash}), pass \
+             --expected-code-hash {code_hash} if you trust it, or --allow-unknown-pool"
+        ),
+    }
+
+    Ok(code_hash)
+}
+
+/// Whether `code_hash` is that of a deployment in [`KNOWN_POOL_CODE_HASHES`].
+fn is_known(code_hash: B256) -> bool {
+    KNOWN_POOL_CODE_HASHES
+        .iter()
+        .any(|(hash, _)| *hash == code_hash)
+}
@@ -0,0 +1,254 @@
+use alloy::{
+    rpc::json_rpc::{RequestPacket, Response, ResponsePacket, SerializedRequest},
+    transports::{TransportError, TransportErrorKind, TransportFut},
+};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// One recorded JSON-RPC request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub params: Option<Box<RawValue>>,
+    pub response: Response,
+}
+
+impl Interaction {
+    fn key(&self) -> String {
+        key(&self.method, self.params.as_deref())
+    }
+}
+
+fn key(method: &str, params: Option<&RawValue>) -> String {
+    format!("{method}:{}", params.map(RawValue::get).unwrap_or_default())
+}
+
+/// A recording of all JSON-RPC traffic of one run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Pair each request in a packet with its response by id.
+fn pairs<'a>(
+    request: &'a RequestPacket,
+    response: &'a ResponsePacket,
+) -> impl Iterator<Item = (&'a SerializedRequest, &'a Response)> {
+    let requests = match request {
+        RequestPacket::Single(request) => std::slice::from_ref(request),
+        RequestPacket::Batch(requests) => requests.as_slice(),
+    };
+    let responses = match response {
+        ResponsePacket::Single(response) => std::slice::from_ref(response),
+        ResponsePacket::Batch(responses) => responses.as_slice(),
+    };
+    let responses: HashMap<_, _> = responses
+        .iter()
+        .map(|response| (response.id.clone(), response))
+        .collect();
+    requests.iter().filter_map(move |request| {
+        responses
+            .get(request.id())
+            .map(|response| (request, *response))
+    })
+}
+
+/// Layer that records every request and response passing through into a cassette file. The file
+/// is rewritten after each response, so a crashed run still leaves a usable cassette.
+#[derive(Debug, Clone)]
+pub struct RecordLayer {
+    path: PathBuf,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl RecordLayer {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cassette: Default::default(),
+        }
+    }
+}
+
+impl<S> Layer<S> for RecordLayer {
+    type Service = RecordService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecordService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordService<S> {
+    inner: S,
+    layer: RecordLayer,
+}
+
+impl<S> Service<RequestPacket> for RecordService<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Send
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let layer = self.layer.clone();
+        let future = self.inner.call(request.clone());
+        Box::pin(async move {
+            let response = future.await?;
+            let mut cassette = layer.cassette.lock().unwrap();
+            for (request, response) in pairs(&request, &response) {
+                cassette.interactions.push(Interaction {
+                    method: request.method().to_string(),
+                    params: request.params().map(ToOwned::to_owned),
+                    response: response.clone(),
+                });
+            }
+            cassette
+                .save(&layer.path)
+                .map_err(|e| TransportErrorKind::custom_str(&e.to_string()))?;
+            Ok(response)
+        })
+    }
+}
+
+/// Transport that answers requests from a cassette instead of the network. Repeated identical
+/// requests are answered in recorded order, with the last response reused once exhausted.
+#[derive(Debug, Clone)]
+pub struct ReplayTransport {
+    responses: Arc<Mutex<HashMap<String, VecDeque<Response>>>>,
+}
+
+impl ReplayTransport {
+    pub fn new(cassette: Cassette) -> Self {
+        let mut responses: HashMap<String, VecDeque<Response>> = HashMap::new();
+        for interaction in cassette.interactions {
+            responses
+                .entry(interaction.key())
+                .or_default()
+                .push_back(interaction.response);
+        }
+        Self {
+            responses: Arc::new(Mutex::new(responses)),
+        }
+    }
+
+    fn respond(&self, request: &SerializedRequest) -> Result<Response, TransportError> {
+        let key = key(request.method(), request.params());
+        let mut responses = self.responses.lock().unwrap();
+        let queue = responses.get_mut(&key).ok_or_else(|| {
+            TransportErrorKind::custom_str(&format!("no recorded response for {key}"))
+        })?;
+        let mut response = if queue.len() > 1 {
+            queue.pop_front().unwrap()
+        } else {
+            queue.front().unwrap().clone()
+        };
+        response.id = request.id().clone();
+        Ok(response)
+    }
+}
+
+impl Service<RequestPacket> for ReplayTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let response = match &request {
+            RequestPacket::Single(request) => self.respond(request).map(ResponsePacket::Single),
+            RequestPacket::Batch(requests) => requests
+                .iter()
+                .map(|request| self.respond(request))
+                .collect::<Result<_, _>>()
+                .map(ResponsePacket::Batch),
+        };
+        Box::pin(async move { response })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        eips::BlockNumberOrTag,
+        network::Ethereum,
+        primitives::{b256, Address},
+        providers::{Provider, RootProvider},
+        rpc::{client::ClientBuilder, types::BlockTransactionsKind},
+    };
+
+    /// A provider answering from the checked-in cassette of mainnet responses.
+    fn replay() -> RootProvider<Ethereum> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/cassette.json");
+        let transport = ReplayTransport::new(Cassette::load(&path).unwrap());
+        RootProvider::new(ClientBuilder::default().transport(transport, true))
+    }
+
+    #[tokio::test]
+    async fn replays_repeated_requests_in_order() {
+        let provider = replay();
+        assert_eq!(provider.get_chain_id().await.unwrap(), 1);
+        assert_eq!(provider.get_block_number().await.unwrap(), 0x10);
+        assert_eq!(provider.get_block_number().await.unwrap(), 0x11);
+        // The last response is reused once the recorded ones run out.
+        assert_eq!(provider.get_block_number().await.unwrap(), 0x11);
+    }
+
+    #[tokio::test]
+    async fn replays_blocks() {
+        let block = replay()
+            .get_block_by_number(BlockNumberOrTag::Number(0), BlockTransactionsKind::Hashes)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            block.header.hash,
+            b256!("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3")
+        );
+        assert_eq!(block.header.inner.hash_slow(), block.header.hash);
+    }
+
+    #[tokio::test]
+    async fn rejects_unrecorded_requests() {
+        let error = replay().get_balance(Address::ZERO).await.unwrap_err();
+        assert!(error.to_string().contains("no recorded response"));
+    }
+}
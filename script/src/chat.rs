@@ -0,0 +1,119 @@
+//! Alerts posted to chat services, for operators who want to be paged without running their own
+//! webhook receiver. Only events that are alerts are sent, as one line of text each.
+
+use crate::webhook::{Event, Sink};
+use eyre::Result;
+use futures::future::BoxFuture;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// POST `body` to `url`, keeping the URL, which holds a token, out of errors.
+async fn post(http: &reqwest::Client, url: &Url, body: &Value) -> Result<()> {
+    http.post(url.clone())
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.without_url())?;
+    Ok(())
+}
+
+/// Sends alerts from a Telegram bot to a chat.
+pub struct Telegram {
+    /// `sendMessage` endpoint of the bot, which holds its token.
+    url: Url,
+    chat_id: String,
+    http: reqwest::Client,
+}
+
+impl Telegram {
+    pub fn new(token: &str, chat_id: &str, http: reqwest::Client) -> Self {
+        Self {
+            url: format!("https://api.telegram.org/bot{token}/sendMessage")
+                .parse()
+                .expect("Telegram URL is valid"),
+            chat_id: chat_id.to_string(),
+            http,
+        }
+    }
+}
+
+impl fmt::Debug for Telegram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Telegram")
+            .field("chat_id", &self.chat_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Sink for Telegram {
+    fn target(&self) -> String {
+        format!("telegram:{}", self.chat_id)
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        event.is_alert()
+    }
+
+    fn deliver<'a>(
+        &'a self,
+        _delivery: &'a str,
+        event: &'a Event,
+        _body: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            post(
+                &self.http,
+                &self.url,
+                &json!({ "chat_id": self.chat_id, "text": event.to_string() }),
+            )
+            .await
+        })
+    }
+}
+
+/// Posts alerts to a Discord channel webhook.
+pub struct Discord {
+    url: Url,
+    http: reqwest::Client,
+}
+
+impl Discord {
+    pub fn new(url: Url, http: reqwest::Client) -> Self {
+        Self { url, http }
+    }
+}
+
+impl fmt::Debug for Discord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Discord").finish_non_exhaustive()
+    }
+}
+
+impl Sink for Discord {
+    fn target(&self) -> String {
+        // The path holds the webhook's token.
+        format!("discord:{}", self.url.host_str().unwrap_or_default())
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        event.is_alert()
+    }
+
+    fn deliver<'a>(
+        &'a self,
+        _delivery: &'a str,
+        event: &'a Event,
+        _body: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            post(
+                &self.http,
+                &self.url,
+                &json!({ "content": event.to_string() }),
+            )
+            .await
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use crate::prove::ProofMode;
+use alloy::primitives::B256;
+use eyre::{ensure, Result};
+use pool_lib::{compute_commitment, WithdrawalInput};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::SP1ProofWithPublicValues;
+use std::path::{Path, PathBuf};
+
+/// Progress of an in-flight proof, persisted so an interrupted run can be resumed without
+/// refetching the witness or re-requesting a network proof.
+///
+/// The input is kept without its secret, and only the nullifier tells which note it is for, so
+/// resuming takes the secret again. The checkpoint is removed once the proof is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The withdrawal input, with a zero secret.
+    input: WithdrawalInput,
+    /// Nullifier of the note being withdrawn.
+    pub nullifier: B256,
+    pub mode: ProofMode,
+    /// Where the finished proof is written.
+    pub out: PathBuf,
+    /// Prover network request, once one has been made.
+    pub request_id: Option<B256>,
+}
+
+impl Checkpoint {
+    pub fn new(mut input: WithdrawalInput, mode: ProofMode, out: PathBuf) -> Self {
+        let nullifier = compute_commitment(&input.secret).1;
+        input.secret = B256::ZERO;
+        Self {
+            input,
+            nullifier,
+            mode,
+            out,
+            request_id: None,
+        }
+    }
+
+    /// The withdrawal input with `secret`, which must be the note's the checkpoint is for.
+    pub fn input(&self, secret: &B256) -> Result<WithdrawalInput> {
+        ensure!(
+            compute_commitment(secret).1 == self.nullifier,
+            "secret is not for the note being withdrawn"
+        );
+        Ok(WithdrawalInput {
+            secret: *secret,
+            ..self.input.clone()
+        })
+    }
+
+    /// Default checkpoint location inside `data_dir`.
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("checkpoint.json")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    pub fn remove(path: &Path) -> Result<()> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Write the finished proof to `self.out`.
+    pub fn write_proof(&self, proof: &SP1ProofWithPublicValues) -> Result<()> {
+        write_atomic(&self.out, &bincode::serialize(proof)?)
+    }
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into place, so readers never
+/// see a partially written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
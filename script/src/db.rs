@@ -0,0 +1,147 @@
+use eyre::Result;
+use rusqlite::Connection;
+use std::path::Path;
+
+/// Schema shared by the relayer, indexer and CLI, as migrations applied in order. The number of
+/// migrations applied is kept in `user_version`. Never edit a released migration, add a new one.
+const MIGRATIONS: &[&str] = &[
+    "
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    status TEXT NOT NULL,
+    pool TEXT NOT NULL,
+    body TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    tx_hash TEXT,
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS jobs_status ON jobs (status);
+",
+    "
+ALTER TABLE jobs ADD COLUMN gas_used INTEGER;
+ALTER TABLE jobs ADD COLUMN gas_cost TEXT;
+",
+    "
+ALTER TABLE jobs ADD COLUMN block_number INTEGER;
+ALTER TABLE jobs ADD COLUMN settled INTEGER NOT NULL DEFAULT 0;
+UPDATE jobs SET status = 'queued' WHERE status = 'received';
+UPDATE jobs SET status = 'verifying' WHERE status = 'validated';
+UPDATE jobs SET status = 'broadcasting' WHERE status = 'submitted';
+UPDATE jobs SET status = 'mined', settled = 1 WHERE status = 'confirmed';
+",
+    "
+CREATE TABLE blocks (
+    chain_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (chain_id, number)
+);
+CREATE TABLE indexed_pools (
+    chain_id INTEGER NOT NULL,
+    pool TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    PRIMARY KEY (chain_id, pool)
+);
+CREATE TABLE deposits (
+    chain_id INTEGER NOT NULL,
+    pool TEXT NOT NULL,
+    leaf_index INTEGER NOT NULL,
+    commitment TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (chain_id, pool, leaf_index)
+);
+CREATE INDEX deposits_commitment ON deposits (commitment);
+CREATE TABLE withdrawals (
+    chain_id INTEGER NOT NULL,
+    pool TEXT NOT NULL,
+    nullifier TEXT NOT NULL,
+    exclusion_set_root TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    relayer TEXT NOT NULL,
+    relayer_fee TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (chain_id, pool, nullifier)
+);
+",
+    "
+CREATE TABLE roots (
+    chain_id INTEGER NOT NULL,
+    pool TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    leaf_count INTEGER NOT NULL,
+    root TEXT NOT NULL,
+    PRIMARY KEY (chain_id, pool, block_number)
+);
+",
+    "
+CREATE TABLE association_sets (
+    name TEXT PRIMARY KEY,
+    chain_id INTEGER NOT NULL,
+    pool TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE association_members (
+    set_name TEXT NOT NULL REFERENCES association_sets (name),
+    commitment TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (set_name, commitment)
+);
+CREATE TABLE association_roots (
+    set_name TEXT NOT NULL REFERENCES association_sets (name),
+    root TEXT NOT NULL,
+    leaf_count INTEGER NOT NULL,
+    issued_at INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    published_tx TEXT,
+    PRIMARY KEY (set_name, issued_at, root)
+);
+",
+    "
+CREATE TABLE spends (
+    job_id TEXT PRIMARY KEY,
+    amount TEXT NOT NULL,
+    settled INTEGER NOT NULL DEFAULT 0,
+    at INTEGER NOT NULL
+);
+CREATE INDEX spends_at ON spends (at);
+CREATE TABLE top_ups (
+    tx_hash TEXT PRIMARY KEY,
+    amount TEXT NOT NULL,
+    at INTEGER NOT NULL
+);
+",
+    "
+CREATE TABLE job_txs (
+    job_id TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (job_id, tx_hash)
+);
+INSERT INTO job_txs (job_id, tx_hash) SELECT id, tx_hash FROM jobs WHERE tx_hash IS NOT NULL;
+",
+];
+
+/// Open the sqlite database at `path`, creating it and its tables if needed.
+pub fn open(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut conn = Connection::open(path)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
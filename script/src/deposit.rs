@@ -0,0 +1,229 @@
+use crate::pool::Pool;
+use alloy::{
+    network::EthereumWallet,
+    primitives::{Address, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::{BlockTransactionsKind, TransactionReceipt},
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use eyre::{ensure, OptionExt, Result};
+use pool_lib::commit_reveal::{
+    deposit_hash, mapping_key, verify_pending_deposit, PENDING_DEPOSITS_SLOT,
+};
+use tracing::{info, instrument};
+
+/// Where a deposit landed.
+#[derive(Debug, Clone)]
+pub struct DepositReceipt {
+    pub tx_hash: B256,
+    pub block_number: u64,
+    pub leaf_index: u64,
+}
+
+/// Deposit `commitment` into `pool`, paying the pool's amount, and wait for the `Deposit` event.
+#[instrument(skip_all, fields(%pool))]
+pub async fn send_deposit(
+    rpc_url: Url,
+    signer: PrivateKeySigner,
+    pool: Address,
+    commitment: B256,
+) -> Result<DepositReceipt> {
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(rpc_url);
+    let contract = Pool::new(pool, &provider);
+    let amount = contract.amount().call().await?._0;
+
+    let receipt = contract
+        .deposit(commitment)
+        .value(amount)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    ensure!(
+        receipt.status(),
+        "deposit reverted in {}",
+        receipt.transaction_hash
+    );
+
+    confirmed_deposit(&receipt, pool, commitment)
+}
+
+/// Deposit all of `commitments` into a pool supporting `depositMany` in one transaction, paying
+/// the pool's amount for each, and wait for the `DepositMany` event.
+#[instrument(skip_all, fields(%pool, count = commitments.len()))]
+pub async fn send_deposits(
+    rpc_url: Url,
+    signer: PrivateKeySigner,
+    pool: Address,
+    commitments: &[B256],
+) -> Result<Vec<DepositReceipt>> {
+    ensure!(!commitments.is_empty(), "no commitments to deposit");
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(rpc_url);
+    let contract = Pool::new(pool, &provider);
+    let amount = contract.amount().call().await?._0;
+
+    let receipt = contract
+        .depositMany(commitments.to_vec())
+        .value(amount * U256::from(commitments.len()))
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    ensure!(
+        receipt.status(),
+        "deposits reverted in {}",
+        receipt.transaction_hash
+    );
+
+    let event = receipt
+        .inner
+        .logs()
+        .iter()
+        .filter(|log| log.address() == pool)
+        .find_map(|log| log.log_decode::<Pool::DepositMany>().ok())
+        .ok_or_eyre("no DepositMany event in receipt")?;
+    ensure!(
+        event.inner.data.commitments == commitments,
+        "DepositMany event is for different commitments"
+    );
+    let block_number = receipt
+        .block_number
+        .ok_or_eyre("receipt missing block number")?;
+    let first: u64 = event.inner.data.firstLeafIndex.to();
+    info!(first_leaf_index = first, block_number, "deposits confirmed");
+    Ok((first..first + commitments.len() as u64)
+        .map(|leaf_index| DepositReceipt {
+            tx_hash: receipt.transaction_hash,
+            block_number,
+            leaf_index,
+        })
+        .collect())
+}
+
+/// Where the `Deposit` event of `commitment` in `receipt` put it.
+fn confirmed_deposit(
+    receipt: &TransactionReceipt,
+    pool: Address,
+    commitment: B256,
+) -> Result<DepositReceipt> {
+    let event = receipt
+        .inner
+        .logs()
+        .iter()
+        .filter(|log| log.address() == pool)
+        .find_map(|log| log.log_decode::<Pool::Deposit>().ok())
+        .ok_or_eyre("no Deposit event in receipt")?;
+    ensure!(
+        event.inner.data.commitment == commitment,
+        "Deposit event is for a different commitment"
+    );
+
+    let deposit = DepositReceipt {
+        tx_hash: receipt.transaction_hash,
+        block_number: receipt
+            .block_number
+            .ok_or_eyre("receipt missing block number")?,
+        leaf_index: event.inner.data.leafIndex.to(),
+    };
+    info!(
+        leaf_index = deposit.leaf_index,
+        block_number = deposit.block_number,
+        "deposit confirmed"
+    );
+    Ok(deposit)
+}
+
+/// Commit to depositing `commitment` into a commit-reveal `pool`, paying the pool's amount, and
+/// check from a storage proof at the commit's block that the pool recorded it for the signer.
+/// Returns the commit's transaction hash.
+#[instrument(skip_all, fields(%pool))]
+pub async fn send_commit(
+    rpc_url: Url,
+    signer: PrivateKeySigner,
+    pool: Address,
+    commitment: B256,
+    salt: B256,
+) -> Result<B256> {
+    let depositor = signer.address();
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(rpc_url);
+    let contract = Pool::new(pool, &provider);
+    let amount = contract.amount().call().await?._0;
+    let hash = deposit_hash(&commitment, &depositor, &salt);
+
+    let receipt = contract
+        .commitDeposit(hash)
+        .value(amount)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    ensure!(
+        receipt.status(),
+        "deposit commit reverted in {}",
+        receipt.transaction_hash
+    );
+    let block_hash = receipt
+        .block_hash
+        .ok_or_eyre("receipt missing block hash")?;
+
+    let slot = U256::from(PENDING_DEPOSITS_SLOT);
+    let block = provider
+        .get_block_by_hash(block_hash, BlockTransactionsKind::Hashes)
+        .await?
+        .ok_or_eyre("commit block not found")?;
+    // Hash the header ourselves so the proof is checked against the block the commit is in.
+    ensure!(
+        block.header.inner.hash_slow() == block_hash,
+        "RPC returned the wrong header for block {block_hash}"
+    );
+    let proof = provider
+        .get_proof(pool, vec![mapping_key(slot, &hash)])
+        .block_id(block_hash.into())
+        .await?;
+    verify_pending_deposit(
+        &pool,
+        &slot,
+        &hash,
+        &depositor,
+        &block.header.inner.state_root,
+        &proof,
+    )?;
+    info!(
+        block_number = block.header.inner.number,
+        "deposit committed"
+    );
+    Ok(receipt.transaction_hash)
+}
+
+/// Reveal a deposit committed with [`send_commit`] and wait for its `Deposit` event.
+#[instrument(skip_all, fields(%pool))]
+pub async fn send_reveal(
+    rpc_url: Url,
+    signer: PrivateKeySigner,
+    pool: Address,
+    commitment: B256,
+    salt: B256,
+) -> Result<DepositReceipt> {
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(rpc_url);
+    let receipt = Pool::new(pool, &provider)
+        .revealDeposit(commitment, salt)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    ensure!(
+        receipt.status(),
+        "deposit reveal reverted in {}",
+        receipt.transaction_hash
+    );
+    confirmed_deposit(&receipt, pool, commitment)
+}
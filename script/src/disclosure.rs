@@ -0,0 +1,111 @@
+use alloy::{
+    eips::eip2718::Encodable2718,
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    rlp,
+    rpc::types::BlockTransactionsKind,
+};
+use alloy_trie::{proof::ProofRetainer, root::adjust_index_for_rlp, HashBuilder, Nibbles};
+use eyre::{ensure, OptionExt, Result};
+use pool_lib::{
+    disclosure::{process_disclosure, DisclosureInput},
+    note::Note,
+};
+use sp1_sdk::{include_elf, SP1Stdin};
+use tracing::info;
+
+/// The ELF of the program proving which deposit a nullifier belongs to.
+pub const DISCLOSURE_ELF: &[u8] = include_elf!("pool-disclosure");
+
+pub fn disclosure_stdin(input: &DisclosureInput) -> Result<SP1Stdin> {
+    let mut stdin = SP1Stdin::new();
+    stdin.write_vec(serde_cbor::to_vec(input)?);
+    Ok(stdin)
+}
+
+/// Root of the trie of `items` keyed by their RLP-encoded index, as transaction and receipt
+/// tries are built, and the proof of the item at `index`.
+fn ordered_proof(items: &[Vec<u8>], index: usize) -> (B256, Vec<Bytes>) {
+    let target = Nibbles::unpack(rlp::encode(index));
+    let mut builder =
+        HashBuilder::default().with_proof_retainer(ProofRetainer::new(vec![target.clone()]));
+    for i in 0..items.len() {
+        let i = adjust_index_for_rlp(i, items.len());
+        builder.add_leaf(Nibbles::unpack(rlp::encode(i)), &items[i]);
+    }
+    let root = builder.root();
+    let proof = builder
+        .take_proof_nodes()
+        .into_nodes_sorted()
+        .into_iter()
+        .filter(|(path, _)| target.starts_with(path))
+        .map(|(_, node)| node)
+        .collect();
+    (root, proof)
+}
+
+/// Assemble and validate the input disclosing that `note` was deposited by `tx_hash`, addressed
+/// to `auditor`.
+pub async fn disclosure_input<P: Provider>(
+    provider: &P,
+    note: &Note,
+    tx_hash: B256,
+    auditor: Address,
+) -> Result<DisclosureInput> {
+    let receipt = provider
+        .get_transaction_receipt(tx_hash)
+        .await?
+        .ok_or_eyre("deposit transaction not found")?;
+    let block_hash = receipt
+        .block_hash
+        .ok_or_eyre("deposit transaction is pending")?;
+    let tx_index = receipt
+        .transaction_index
+        .ok_or_eyre("receipt missing transaction index")?;
+    let (block, receipts) = futures::try_join!(
+        provider.get_block_by_hash(block_hash, BlockTransactionsKind::Full),
+        provider.get_block_receipts(block_hash.into()),
+    )?;
+    let block = block.ok_or_eyre("deposit block not found")?;
+    let receipts = receipts.ok_or_eyre("deposit block receipts not found")?;
+    let header = block.header.inner;
+
+    let transactions: Vec<Vec<u8>> = block
+        .transactions
+        .txns()
+        .map(|tx| tx.inner.encoded_2718())
+        .collect();
+    let receipts: Vec<Vec<u8>> = receipts
+        .into_iter()
+        .map(|receipt| receipt.inner.map_logs(|log| log.inner).encoded_2718())
+        .collect();
+    let index = tx_index as usize;
+    ensure!(
+        index < transactions.len() && transactions.len() == receipts.len(),
+        "block {} returned incomplete transactions or receipts",
+        header.number
+    );
+
+    let (transactions_root, transaction_proof) = ordered_proof(&transactions, index);
+    let (receipts_root, receipt_proof) = ordered_proof(&receipts, index);
+    ensure!(
+        transactions_root == header.transactions_root && receipts_root == header.receipts_root,
+        "RPC transactions or receipts do not match block {}",
+        header.number
+    );
+
+    let input = DisclosureInput {
+        secret: note.secret,
+        pool: note.pool,
+        auditor,
+        block_header: header,
+        tx_index,
+        transaction: transactions[index].clone().into(),
+        transaction_proof,
+        receipt: receipts[index].clone().into(),
+        receipt_proof,
+    };
+    let data = process_disclosure(&input)?;
+    info!(block_number = data.blockNumber, depositor = %data.depositor, "disclosure input valid");
+    Ok(input)
+}
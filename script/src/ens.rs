@@ -0,0 +1,139 @@
+use crate::registry::RegistryRef;
+use alloy::{
+    primitives::{address, keccak256, Address, B256},
+    providers::Provider,
+    sol,
+};
+use eyre::{ensure, Result};
+use std::{fmt, str::FromStr};
+
+/// The ENS registry, deployed at the same address on mainnet and testnets.
+pub const ENS_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+sol! {
+    #[sol(rpc)]
+    contract EnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    #[sol(rpc)]
+    contract EnsResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string);
+    }
+}
+
+/// A CLI argument that is either a raw address, an ENS name or, for pools,
+/// `registry:<registry>/<denomination in wei>/<version>` to look the pool up in a registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressOrName {
+    Address(Address),
+    Name(String),
+    Registry(RegistryRef),
+}
+
+impl FromStr for AddressOrName {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(address) = Address::from_str(s) {
+            return Ok(Self::Address(address));
+        }
+        if let Some(entry) = s.strip_prefix("registry:") {
+            return Ok(Self::Registry(entry.parse()?));
+        }
+        ensure!(
+            s.contains('.') && !s.starts_with('.') && !s.ends_with('.'),
+            "invalid address or ENS name: {s}"
+        );
+        Ok(Self::Name(s.to_lowercase()))
+    }
+}
+
+impl fmt::Display for AddressOrName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{address}"),
+            Self::Name(name) => write!(f, "{name}"),
+            Self::Registry(entry) => write!(f, "registry:{entry}"),
+        }
+    }
+}
+
+impl AddressOrName {
+    /// Resolve to an address at `block_number`. Names must resolve to a nonzero address and
+    /// that address's reverse record must point back to the same name, and registry entries are
+    /// read with a proof against the block's state root.
+    pub async fn resolve<P: Provider>(&self, provider: &P, block_number: u64) -> Result<Address> {
+        match self {
+            Self::Address(address) => Ok(*address),
+            Self::Name(name) => resolve_name(provider, name, block_number).await,
+            Self::Registry(entry) => Ok(entry.entry(provider, block_number).await?.0),
+        }
+    }
+}
+
+/// Compute the ENS namehash of `name`.
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let mut input = [0u8; 64];
+        input[..32].copy_from_slice(&node.0);
+        input[32..].copy_from_slice(&keccak256(label.as_bytes()).0);
+        node = keccak256(input);
+    }
+    node
+}
+
+/// Resolve `name` to an address at `block_number`, confirming the reverse record matches.
+pub async fn resolve_name<P: Provider>(
+    provider: &P,
+    name: &str,
+    block_number: u64,
+) -> Result<Address> {
+    let registry = EnsRegistry::new(ENS_REGISTRY, provider);
+
+    let node = namehash(name);
+    let resolver = registry
+        .resolver(node)
+        .block(block_number.into())
+        .call()
+        .await?
+        ._0;
+    ensure!(!resolver.is_zero(), "no ENS resolver for {name}");
+    let address = EnsResolver::new(resolver, provider)
+        .addr(node)
+        .block(block_number.into())
+        .call()
+        .await?
+        ._0;
+    ensure!(!address.is_zero(), "{name} does not resolve to an address");
+
+    // Reverse check: the resolved address must claim the same name.
+    let reverse_node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+    let reverse_resolver = registry
+        .resolver(reverse_node)
+        .block(block_number.into())
+        .call()
+        .await?
+        ._0;
+    ensure!(
+        !reverse_resolver.is_zero(),
+        "{name} resolves to {address}, which has no reverse record"
+    );
+    let reverse_name = EnsResolver::new(reverse_resolver, provider)
+        .name(reverse_node)
+        .block(block_number.into())
+        .call()
+        .await?
+        ._0;
+    ensure!(
+        reverse_name.to_lowercase() == name,
+        "{name} resolves to {address}, but its reverse record is {reverse_name:?}"
+    );
+
+    Ok(address)
+}
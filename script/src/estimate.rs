@@ -0,0 +1,67 @@
+use clap::ValueEnum;
+
+/// Hardware classes used to project local proving time.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareProfile {
+    /// 8-16 core laptop with 16-32 GB of memory.
+    Laptop,
+    /// 32-64 core workstation with 128 GB of memory.
+    Workstation,
+    /// 128+ core server with 512 GB of memory.
+    Server,
+    /// Single consumer GPU with the CUDA prover.
+    Gpu,
+}
+
+impl HardwareProfile {
+    pub const ALL: [Self; 4] = [Self::Laptop, Self::Workstation, Self::Server, Self::Gpu];
+
+    /// Rough compressed-proof throughput in cycles per second, measured on SP1 4.0.
+    pub fn cycles_per_second(self) -> u64 {
+        match self {
+            Self::Laptop => 150_000,
+            Self::Workstation => 600_000,
+            Self::Server => 1_500_000,
+            Self::Gpu => 3_000_000,
+        }
+    }
+
+    /// Fixed overhead for wrapping a compressed proof into groth16, in seconds.
+    pub fn wrap_seconds(self) -> u64 {
+        match self {
+            Self::Laptop => 180,
+            Self::Workstation => 90,
+            Self::Server => 60,
+            Self::Gpu => 30,
+        }
+    }
+}
+
+/// Gas used by the SP1 groth16 verifier.
+pub const GROTH16_VERIFY_GAS: u64 = 275_000;
+
+/// Gas used by the SP1 plonk verifier.
+pub const PLONK_VERIFY_GAS: u64 = 300_000;
+
+/// Gas used by `Pool.withdraw` outside of proof verification: decoding, the nullifier write, the
+/// event and the transfers.
+pub const WITHDRAW_OVERHEAD_GAS: u64 = 65_000;
+
+/// Projected cost of proving and verifying one withdrawal.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub cycles: u64,
+    pub syscalls: u64,
+}
+
+impl Estimate {
+    /// Projected local proving time in seconds, including the groth16 wrap.
+    pub fn proving_seconds(&self, profile: HardwareProfile) -> u64 {
+        self.cycles / profile.cycles_per_second() + profile.wrap_seconds()
+    }
+
+    /// Projected network cost, given a price per million cycles.
+    pub fn network_cost(&self, price_per_million_cycles: f64) -> f64 {
+        (self.cycles as f64 / 1_000_000.0) * price_per_million_cycles
+    }
+}
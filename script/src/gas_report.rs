@@ -0,0 +1,227 @@
+use crate::{pool::Pool, prove::ProofMode, submit::Withdrawal};
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    node_bindings::Anvil,
+    primitives::{address, Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::{SolCall, SolValue},
+    transports::http::reqwest::Url,
+};
+use eyre::{ensure, eyre, OptionExt, Result};
+use pool_lib::{AggregateData, WithdrawalData};
+use serde::Deserialize;
+use sp1_sdk::{SP1Proof, SP1ProofWithPublicValues};
+use std::path::Path;
+use tracing::{info, instrument};
+
+/// SP1 groth16 verifier gateway, deployed at the same address on every supported chain.
+pub const SP1_GROTH16_GATEWAY: Address = address!("397A5f7f3dBd538f23DE225B51f532c34448dA9B");
+
+/// SP1 plonk verifier gateway, deployed at the same address on every supported chain.
+pub const SP1_PLONK_GATEWAY: Address = address!("3B6041173B80E77f038f3F2C0f9744f04837185e");
+
+sol! {
+    interface ISP1Verifier {
+        function verifyProof(
+            bytes32 programVKey,
+            bytes calldata publicValues,
+            bytes calldata proofBytes
+        ) external view;
+    }
+}
+
+/// The parts of a forge build artifact needed to deploy a contract.
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    bytecode: ArtifactBytecode,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtifactBytecode {
+    object: Bytes,
+}
+
+/// Measured cost of verifying and withdrawing with one proof.
+#[derive(Debug, Clone)]
+pub struct GasMeasurement {
+    pub mode: ProofMode,
+    pub proof_bytes: usize,
+    /// Gas used by the verifier gateway alone.
+    pub verify_gas: u64,
+    /// Gas used by the whole `Pool.withdraw` transaction.
+    pub withdraw_gas: u64,
+}
+
+/// Measured cost of verifying one aggregated proof over a batch of withdrawals.
+#[derive(Debug, Clone)]
+pub struct AggregateMeasurement {
+    pub mode: ProofMode,
+    pub proof_bytes: usize,
+    /// Withdrawals the proof aggregates.
+    pub count: u64,
+    /// Gas used by the verifier gateway to verify the aggregated proof.
+    pub verify_gas: u64,
+}
+
+impl AggregateMeasurement {
+    /// Verification gas each withdrawal of the batch pays for its share of the proof.
+    pub fn verify_gas_per_withdrawal(&self) -> u64 {
+        self.verify_gas / self.count.max(1)
+    }
+}
+
+/// Proof mode of an onchain-verifiable proof.
+pub fn proof_mode(proof: &SP1ProofWithPublicValues) -> Result<ProofMode> {
+    match proof.proof {
+        SP1Proof::Groth16(_) => Ok(ProofMode::Groth16),
+        SP1Proof::Plonk(_) => Ok(ProofMode::Plonk),
+        _ => Err(eyre!(
+            "only groth16 and plonk proofs can be verified onchain"
+        )),
+    }
+}
+
+/// Deploy a pool from the forge `artifact`, verifying proofs of `vkey` through `gateway` and
+/// taking deposits of `amount` wei.
+pub async fn deploy_pool<P: Provider>(
+    provider: &P,
+    artifact: &Path,
+    gateway: Address,
+    vkey: B256,
+    amount: U256,
+) -> Result<Address> {
+    let artifact: Artifact = serde_json::from_slice(&std::fs::read(artifact)?)?;
+    let mut code = artifact.bytecode.object.to_vec();
+    code.extend((gateway, vkey, amount).abi_encode_params());
+    let deployed = provider
+        .send_transaction(TransactionRequest::default().with_deploy_code(code))
+        .await?
+        .get_receipt()
+        .await?
+        .contract_address
+        .ok_or_eyre("pool deployment failed")?;
+    info!(%deployed, "deployed pool");
+    Ok(deployed)
+}
+
+/// Fork `fork_url` with anvil just after the proof's block, deploy a pool from `artifact` backed
+/// by the SP1 gateway for the proof's mode at the address the proof was made for, and withdraw
+/// with the proof.
+#[instrument(skip_all, fields(path = %path.display()))]
+pub async fn measure(
+    fork_url: &Url,
+    artifact: &Path,
+    vkey: B256,
+    amount: U256,
+    path: &Path,
+) -> Result<GasMeasurement> {
+    let proof = SP1ProofWithPublicValues::load(path).map_err(|e| eyre!("{e}"))?;
+    let mode = proof_mode(&proof)?;
+    let withdrawal = Withdrawal::from_proof(&proof);
+    let data = WithdrawalData::abi_decode(&withdrawal.public_values, true)?;
+    let gateway = match mode {
+        ProofMode::Plonk => SP1_PLONK_GATEWAY,
+        _ => SP1_GROTH16_GATEWAY,
+    };
+
+    // The pool checks `blockhash(blockNumber)`, so the fork must be within 256 blocks of it.
+    let anvil = Anvil::new()
+        .fork(fork_url.as_str())
+        .fork_block_number(data.blockNumber + 1)
+        .try_spawn()?;
+    let signer = PrivateKeySigner::from(anvil.keys()[0].clone());
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(anvil.endpoint_url());
+    ensure!(
+        !provider.get_code_at(gateway).await?.is_empty(),
+        "no SP1 verifier gateway at {gateway} on the forked chain"
+    );
+
+    let deployed = deploy_pool(&provider, artifact, gateway, vkey, amount).await?;
+
+    // Move the pool to the address committed in the public values.
+    let runtime = provider.get_code_at(deployed).await?;
+    provider
+        .raw_request::<_, ()>("anvil_setCode".into(), (data.contractAddress, runtime))
+        .await?;
+    provider
+        .raw_request::<_, ()>("anvil_setBalance".into(), (data.contractAddress, amount))
+        .await?;
+
+    let verify_gas = provider
+        .estimate_gas(
+            &TransactionRequest::default().with_to(gateway).with_input(
+                ISP1Verifier::verifyProofCall {
+                    programVKey: vkey,
+                    publicValues: withdrawal.public_values.clone(),
+                    proofBytes: withdrawal.proof.clone(),
+                }
+                .abi_encode(),
+            ),
+        )
+        .await?;
+    let receipt = Pool::new(data.contractAddress, &provider)
+        .withdraw(withdrawal.public_values.clone(), withdrawal.proof.clone())
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    ensure!(receipt.status(), "withdrawal reverted on the fork");
+
+    Ok(GasMeasurement {
+        mode,
+        proof_bytes: withdrawal.proof.len(),
+        verify_gas,
+        withdraw_gas: receipt.gas_used,
+    })
+}
+
+/// Fork `fork_url` with anvil at its latest block and verify the aggregated proof at `path`,
+/// written by `pool aggregate`, through the SP1 gateway for its mode. No pool takes aggregated
+/// proofs, so only the verification is measured.
+#[instrument(skip_all, fields(path = %path.display()))]
+pub async fn measure_aggregate(
+    fork_url: &Url,
+    vkey: B256,
+    path: &Path,
+) -> Result<AggregateMeasurement> {
+    let proof = SP1ProofWithPublicValues::load(path).map_err(|e| eyre!("{e}"))?;
+    let mode = proof_mode(&proof)?;
+    let data = AggregateData::abi_decode(proof.public_values.as_slice(), true)?;
+    let gateway = match mode {
+        ProofMode::Plonk => SP1_PLONK_GATEWAY,
+        _ => SP1_GROTH16_GATEWAY,
+    };
+
+    let anvil = Anvil::new().fork(fork_url.as_str()).try_spawn()?;
+    let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+    ensure!(
+        !provider.get_code_at(gateway).await?.is_empty(),
+        "no SP1 verifier gateway at {gateway} on the forked chain"
+    );
+
+    let proof_bytes = Bytes::from(proof.bytes());
+    let verify_gas = provider
+        .estimate_gas(
+            &TransactionRequest::default().with_to(gateway).with_input(
+                ISP1Verifier::verifyProofCall {
+                    programVKey: vkey,
+                    publicValues: proof.public_values.to_vec().into(),
+                    proofBytes: proof_bytes.clone(),
+                }
+                .abi_encode(),
+            ),
+        )
+        .await?;
+
+    Ok(AggregateMeasurement {
+        mode,
+        proof_bytes: proof_bytes.len(),
+        count: data.count.try_into()?,
+        verify_gas,
+    })
+}
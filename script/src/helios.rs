@@ -0,0 +1,165 @@
+use alloy::{
+    network::Ethereum,
+    providers::{Provider, RootProvider},
+    rpc::json_rpc::RequestPacket,
+};
+use eyre::{bail, Result};
+use reqwest::Url;
+use std::{
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::{Layer, Service};
+use tracing::{info, instrument};
+
+/// How long to wait for Helios to sync before giving up.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Options for running a Helios light client as the witness RPC.
+#[derive(clap::Args, Debug, Clone)]
+pub struct HeliosArgs {
+    /// Fetch the witness through a local Helios light client, which verifies headers against
+    /// sync-committee signatures and proofs against the verified state root.
+    #[clap(long, requires = "consensus_rpc")]
+    pub helios: bool,
+
+    /// Consensus-layer RPC Helios syncs from.
+    #[clap(long, env = "HELIOS_CONSENSUS_RPC")]
+    pub consensus_rpc: Option<String>,
+
+    /// Trusted weak-subjectivity checkpoint (beacon block root) to start syncing from.
+    #[clap(long, env = "HELIOS_CHECKPOINT")]
+    pub checkpoint: Option<String>,
+
+    #[clap(long, default_value = "mainnet")]
+    pub helios_network: String,
+
+    /// Helios binary.
+    #[clap(long, default_value = "helios")]
+    pub helios_bin: PathBuf,
+
+    #[clap(long, default_value_t = 8546)]
+    pub helios_port: u16,
+}
+
+/// A running Helios process, killed on drop.
+#[derive(Debug)]
+pub struct HeliosNode {
+    child: Child,
+    url: Url,
+}
+
+impl HeliosNode {
+    /// Start Helios proxying `execution_rpc` and wait until it serves verified data.
+    #[instrument(skip_all, fields(network = %args.helios_network))]
+    pub async fn spawn(args: &HeliosArgs, execution_rpc: &Url) -> Result<Self> {
+        let mut command = Command::new(&args.helios_bin);
+        command
+            .arg("ethereum")
+            .args(["--network", &args.helios_network])
+            .args(["--execution-rpc", execution_rpc.as_str()])
+            .args(["--rpc-port", &args.helios_port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(consensus_rpc) = &args.consensus_rpc {
+            command.args(["--consensus-rpc", consensus_rpc]);
+        }
+        if let Some(checkpoint) = &args.checkpoint {
+            command.args(["--checkpoint", checkpoint]);
+        }
+        let child = command.spawn().map_err(|e| {
+            eyre::eyre!(
+                "failed to start {}: {e}, is helios installed?",
+                args.helios_bin.display()
+            )
+        })?;
+        let node = Self {
+            child,
+            url: format!("http://127.0.0.1:{}", args.helios_port).parse()?,
+        };
+        node.wait_synced().await?;
+        Ok(node)
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    async fn wait_synced(&self) -> Result<()> {
+        let provider = RootProvider::<Ethereum>::new_http(self.url.clone());
+        let start = Instant::now();
+        loop {
+            if let Ok(number) = provider.get_block_number().await {
+                if number > 0 {
+                    info!(block_number = number, "helios synced");
+                    return Ok(());
+                }
+            }
+            if start.elapsed() > SYNC_TIMEOUT {
+                bail!("helios did not sync within {:?}", SYNC_TIMEOUT);
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+}
+
+impl Drop for HeliosNode {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Layer that keeps a value alive for as long as the transport it wraps.
+#[derive(Debug)]
+pub struct KeepAliveLayer<T>(pub Arc<T>);
+
+// Not derived, which would require `T: Clone`.
+impl<T> Clone for KeepAliveLayer<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S, T> Layer<S> for KeepAliveLayer<T> {
+    type Service = KeepAlive<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        KeepAlive {
+            inner,
+            _guard: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct KeepAlive<S, T> {
+    inner: S,
+    _guard: Arc<T>,
+}
+
+impl<S: Clone, T> Clone for KeepAlive<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _guard: self._guard.clone(),
+        }
+    }
+}
+
+impl<S: Service<RequestPacket>, T> Service<RequestPacket> for KeepAlive<S, T> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        self.inner.call(request)
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{lookup::DepositLookup, pool::DEPOSITS_SLOT};
+use alloy::{
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
+};
+use eyre::{ensure, OptionExt, Result};
+use pool_lib::{compute_merkle_root, compute_storage_keys};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use tracing::{debug, info, instrument, warn};
+
+/// Number of storage keys requested per `eth_getProof` call.
+const PROOF_CHUNK_SIZE: usize = 256;
+
+/// Number of storage slots requested per `debug_storageRangeAt` page.
+const STORAGE_RANGE_PAGE_SIZE: u64 = 4096;
+
+/// A local copy of a pool's `deposits` array as of a block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositIndex {
+    pub chain_id: u64,
+    pub pool: Address,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub deposits: Vec<B256>,
+}
+
+impl DepositIndex {
+    pub fn new(chain_id: u64, pool: Address) -> Self {
+        Self {
+            chain_id,
+            pool,
+            block_number: 0,
+            block_hash: B256::ZERO,
+            deposits: Vec::new(),
+        }
+    }
+
+    /// Path of the index file for `pool` on `chain_id` inside `data_dir`.
+    pub fn path(data_dir: &Path, chain_id: u64, pool: Address) -> PathBuf {
+        data_dir
+            .join("index")
+            .join(format!("{chain_id}-{pool:#x}.json"))
+    }
+
+    /// Load the index for `pool`, or an empty one if none has been written yet.
+    pub fn load(data_dir: &Path, chain_id: u64, pool: Address) -> Result<Self> {
+        let path = Self::path(data_dir, chain_id, pool);
+        if !path.exists() {
+            return Ok(Self::new(chain_id, pool));
+        }
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Write the index and its [`DepositLookup`].
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = Self::path(data_dir, self.chain_id, self.pool);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_vec(self)?)?;
+        DepositLookup::write(
+            &DepositLookup::path(data_dir, self.chain_id, self.pool),
+            &self.deposits,
+            self.block_number,
+        )
+    }
+
+    /// Look up the index of `commitment` in the deposits array.
+    pub fn position(&self, commitment: &B256) -> Option<u64> {
+        self.deposits
+            .iter()
+            .position(|deposit| deposit == commitment)
+            .map(|i| i as u64)
+    }
+
+    /// Bring the index up to `block_number`, refetching everything if the indexed block was
+    /// reorged out.
+    #[instrument(skip_all, fields(pool = %self.pool, block_number = block_number))]
+    pub async fn sync<P: Provider>(&mut self, provider: &P, block_number: u64) -> Result<()> {
+        if self.block_number != 0
+            && block_hash(provider, self.block_number).await? != self.block_hash
+        {
+            warn!(
+                block_number = self.block_number,
+                "indexed block was reorged, reindexing"
+            );
+            self.deposits.clear();
+        }
+
+        let length = deposits_length(provider, self.pool, block_number).await?;
+        ensure!(
+            length >= self.deposits.len() as u64,
+            "deposits array shrank from {} to {}",
+            self.deposits.len(),
+            length
+        );
+        let new = fetch_deposits(
+            provider,
+            self.pool,
+            self.deposits.len() as u64..length,
+            block_number,
+        )
+        .await?;
+        info!(new = new.len(), "fetched deposits");
+        self.deposits.extend(new);
+        self.block_number = block_number;
+        self.block_hash = block_hash(provider, block_number).await?;
+        Ok(())
+    }
+}
+
+/// Fetch the hash of block `number`.
+pub async fn block_hash<P: Provider>(provider: &P, number: u64) -> Result<B256> {
+    Ok(provider
+        .get_block_by_number(number.into(), BlockTransactionsKind::Hashes)
+        .await?
+        .ok_or_eyre("block not found")?
+        .header
+        .hash)
+}
+
+/// Read the length of the pool's `deposits` array at `block_number`.
+pub async fn deposits_length<P: Provider>(
+    provider: &P,
+    pool: Address,
+    block_number: u64,
+) -> Result<u64> {
+    let length = provider
+        .get_storage_at(pool, U256::from(DEPOSITS_SLOT))
+        .number(block_number)
+        .await?;
+    Ok(length.to::<u64>())
+}
+
+/// Fetch `deposits[range]` at `block_number`. Large ranges are read by paging through the pool's
+/// storage when the endpoint supports the Erigon/Otterscan extensions, falling back to batched
+/// `eth_getProof` calls otherwise.
+pub async fn fetch_deposits<P: Provider>(
+    provider: &P,
+    pool: Address,
+    range: std::ops::Range<u64>,
+    block_number: u64,
+) -> Result<Vec<B256>> {
+    if range.end - range.start > PROOF_CHUNK_SIZE as u64 && supports_ots(provider).await {
+        match fetch_deposits_storage_range(provider, pool, range.clone(), block_number).await {
+            Ok(deposits) => return Ok(deposits),
+            Err(e) => warn!("storage range scan failed, falling back to eth_getProof: {e}"),
+        }
+    }
+    fetch_deposits_proofs(provider, pool, range, block_number).await
+}
+
+/// Whether the endpoint serves the Otterscan `ots_` namespace, which implies an Erigon-style
+/// archive with `debug_storageRangeAt` and key preimages.
+pub async fn supports_ots<P: Provider>(provider: &P) -> bool {
+    match provider
+        .raw_request::<_, u64>("ots_getApiLevel".into(), ())
+        .await
+    {
+        Ok(level) => {
+            debug!(level, "endpoint supports ots_ extensions");
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageRange {
+    storage: HashMap<B256, StorageRangeEntry>,
+    next_key: Option<B256>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StorageRangeEntry {
+    /// Preimage of the hashed trie key, i.e. the storage slot.
+    key: Option<U256>,
+    value: B256,
+}
+
+/// Fetch `deposits[range]` at `block_number` by paging through all of the pool's storage with
+/// `debug_storageRangeAt` and keeping the slots that fall inside the array.
+async fn fetch_deposits_storage_range<P: Provider>(
+    provider: &P,
+    pool: Address,
+    range: std::ops::Range<u64>,
+    block_number: u64,
+) -> Result<Vec<B256>> {
+    let block = provider
+        .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+        .await?
+        .ok_or_eyre("block not found")?;
+    // State after the last transaction of the block.
+    let tx_index = block.transactions.len() as u64;
+    let base = compute_storage_keys(U256::from(DEPOSITS_SLOT), U256::ZERO).1;
+    let base = U256::from_be_bytes(base.0);
+
+    let mut deposits = vec![B256::ZERO; (range.end - range.start) as usize];
+    let mut start_key = B256::ZERO;
+    loop {
+        let page: StorageRange = provider
+            .raw_request(
+                "debug_storageRangeAt".into(),
+                (
+                    block.header.hash,
+                    tx_index,
+                    pool,
+                    start_key,
+                    STORAGE_RANGE_PAGE_SIZE,
+                ),
+            )
+            .await?;
+        for entry in page.storage.into_values() {
+            let slot = entry
+                .key
+                .ok_or_eyre("endpoint did not return key preimages")?;
+            let index = slot.wrapping_sub(base);
+            if index >= U256::from(range.start) && index < U256::from(range.end) {
+                deposits[(index.to::<u64>() - range.start) as usize] = entry.value;
+            }
+        }
+        match page.next_key {
+            Some(next_key) => start_key = next_key,
+            None => break,
+        }
+    }
+    Ok(deposits)
+}
+
+/// Fetch `deposits[range]` at `block_number` using batched `eth_getProof` calls.
+async fn fetch_deposits_proofs<P: Provider>(
+    provider: &P,
+    pool: Address,
+    range: std::ops::Range<u64>,
+    block_number: u64,
+) -> Result<Vec<B256>> {
+    let keys: Vec<B256> = range
+        .map(|i| compute_storage_keys(U256::from(DEPOSITS_SLOT), U256::from(i)).1)
+        .collect();
+
+    let mut deposits = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(PROOF_CHUNK_SIZE) {
+        let proof = provider
+            .get_proof(pool, chunk.to_vec())
+            .number(block_number)
+            .await?;
+        deposits.extend(
+            proof
+                .storage_proof
+                .iter()
+                .map(|slot| B256::from(slot.value)),
+        );
+    }
+    Ok(deposits)
+}
+
+/// Comparison of a local index against the chain.
+#[derive(Debug, Clone)]
+pub struct RootStatus {
+    pub local_root: B256,
+    pub chain_root: B256,
+    /// Whether the indexed block is no longer canonical.
+    pub reorged: bool,
+    /// First array index where the local copy differs from the chain at the indexed block.
+    pub first_mismatch: Option<u64>,
+    /// Deposits made on chain after the indexed block.
+    pub pending: u64,
+}
+
+impl RootStatus {
+    pub fn diverged(&self) -> bool {
+        self.reorged || self.local_root != self.chain_root
+    }
+}
+
+/// Recompute the deposit root from `index` and from the chain at the indexed block.
+pub async fn root_status<P: Provider>(provider: &P, index: &DepositIndex) -> Result<RootStatus> {
+    let reorged = block_hash(provider, index.block_number).await? != index.block_hash;
+    let length = deposits_length(provider, index.pool, index.block_number).await?;
+    let chain = fetch_deposits(provider, index.pool, 0..length, index.block_number).await?;
+
+    let first_mismatch = chain
+        .iter()
+        .zip(&index.deposits)
+        .position(|(a, b)| a != b)
+        .or((chain.len() != index.deposits.len()).then_some(chain.len().min(index.deposits.len())))
+        .map(|i| i as u64);
+
+    let tip = provider.get_block_number().await?;
+    let tip_length = deposits_length(provider, index.pool, tip).await?;
+
+    Ok(RootStatus {
+        local_root: compute_merkle_root(&index.deposits),
+        chain_root: compute_merkle_root(&chain),
+        reorged,
+        first_mismatch,
+        pending: tip_length.saturating_sub(length),
+    })
+}
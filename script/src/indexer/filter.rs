@@ -0,0 +1,166 @@
+use crate::checkpoint::write_atomic;
+use alloy::primitives::{Address, B256};
+use eyre::{ensure, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const MAGIC: &[u8; 8] = b"poolblm1";
+/// Bits per commitment, which with [`HASHES`] gives a false positive rate under 1%.
+const BITS_PER_ITEM: usize = 10;
+const HASHES: u64 = 7;
+/// Commitments a new filter has room for before it is rebuilt larger.
+const MIN_CAPACITY: usize = 1 << 16;
+
+/// A bloom filter over the commitments indexed for a pool, kept in a file next to the database so
+/// checking many commitments only queries the database for the few the filter may contain.
+///
+/// The filter covers the deposits with leaf indices below `leaves`. Reorged deposits are never
+/// removed, which only adds false positives; the indexer lowers `leaves` before rolling back so
+/// the deposits that replace them are added again.
+#[derive(Debug, Clone)]
+pub struct CommitmentFilter {
+    bits: Vec<u64>,
+    leaves: u64,
+}
+
+impl CommitmentFilter {
+    fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY);
+        Self {
+            bits: vec![0; (capacity * BITS_PER_ITEM).div_ceil(64)],
+            leaves: 0,
+        }
+    }
+
+    /// Path of the filter for `pool` on `chain_id` of the database at `db`.
+    pub fn path(db: &Path, chain_id: u64, pool: Address) -> PathBuf {
+        let mut dir = db.as_os_str().to_owned();
+        dir.push(".bloom");
+        PathBuf::from(dir).join(format!("{chain_id}-{pool:#x}"))
+    }
+
+    /// Load the filter at `path`, or an empty one if none has been written yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::with_capacity(0));
+        }
+        let bytes = std::fs::read(path)?;
+        ensure!(
+            bytes.len() > 16 && (bytes.len() - 16) % 8 == 0 && &bytes[..8] == MAGIC,
+            "{} is not a commitment filter",
+            path.display()
+        );
+        Ok(Self {
+            leaves: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            bits: bytes[16..]
+                .chunks_exact(8)
+                .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+                .collect(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let mut bytes = Vec::with_capacity(16 + self.bits.len() * 8);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.leaves.to_le_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        write_atomic(path, &bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.bits.len() * 64 / BITS_PER_ITEM
+    }
+
+    /// Bit positions of `commitment`. Commitments are keccak256 hashes, so their own bytes serve
+    /// as the two hashes of the double hashing scheme.
+    fn positions(&self, commitment: &B256) -> impl Iterator<Item = usize> {
+        let len = self.bits.len() as u64 * 64;
+        let h1 = u64::from_le_bytes(commitment[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(commitment[8..16].try_into().unwrap()) | 1;
+        (0..HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    fn insert(&mut self, commitment: &B256) {
+        for bit in self.positions(commitment) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Whether `commitment` may have been deposited. `false` is certain.
+    pub fn may_contain(&self, commitment: &B256) -> bool {
+        self.positions(commitment)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Forget that deposits from leaf `leaves` on are covered, so they are added again by the
+    /// next [`sync`](Self::sync).
+    pub fn truncate(&mut self, leaves: u64) {
+        self.leaves = self.leaves.min(leaves);
+    }
+
+    /// Add the deposits of `pool` indexed since the filter was last synced, rebuilding it larger
+    /// if it is full. Returns whether anything was added.
+    pub fn sync(&mut self, conn: &Connection, chain_id: u64, pool: Address) -> Result<bool> {
+        let count: u64 = conn.query_row(
+            "SELECT COUNT(*) FROM deposits WHERE chain_id = ?1 AND pool = ?2",
+            params![chain_id, pool.to_string()],
+            |row| row.get(0),
+        )?;
+        if count as usize > self.capacity() {
+            *self = Self::with_capacity(count as usize * 2);
+        }
+        let mut statement = conn.prepare(
+            "SELECT leaf_index, commitment FROM deposits
+             WHERE chain_id = ?1 AND pool = ?2 AND leaf_index >= ?3",
+        )?;
+        let rows = statement
+            .query_map(params![chain_id, pool.to_string(), self.leaves], |row| {
+                Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (leaf_index, commitment) in &rows {
+            self.insert(&commitment.parse()?);
+            self.leaves = self.leaves.max(leaf_index + 1);
+        }
+        Ok(!rows.is_empty())
+    }
+}
+
+/// Leaf indices of those of `commitments` deposited into `pool`, checking each against the
+/// filter of the database at `db` first and querying the database only for possible members.
+pub fn find_deposits(
+    conn: &Connection,
+    db: &Path,
+    chain_id: u64,
+    pool: Address,
+    commitments: &[B256],
+) -> Result<HashMap<B256, u64>> {
+    let mut filter = CommitmentFilter::load(&CommitmentFilter::path(db, chain_id, pool))?;
+    filter.sync(conn, chain_id, pool)?;
+    let mut statement = conn.prepare(
+        "SELECT leaf_index FROM deposits WHERE chain_id = ?1 AND pool = ?2 AND commitment = ?3
+         ORDER BY leaf_index LIMIT 1",
+    )?;
+    let mut found = HashMap::new();
+    for commitment in commitments {
+        if !filter.may_contain(commitment) {
+            continue;
+        }
+        let leaf_index = statement
+            .query_row(
+                params![chain_id, pool.to_string(), commitment.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(leaf_index) = leaf_index {
+            found.insert(*commitment, leaf_index);
+        }
+    }
+    Ok(found)
+}
@@ -0,0 +1,293 @@
+use alloy::primitives::{Address, B256, U256};
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use async_graphql_axum::GraphQL;
+use axum::Router;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::{str::FromStr, sync::Mutex};
+
+/// Most rows returned by a list query.
+const MAX_LIMIT: u32 = 1000;
+
+pub type IndexerSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Read-only GraphQL queries over the indexer database.
+pub struct QueryRoot {
+    conn: Mutex<Connection>,
+    chain_id: u64,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Deposit {
+    pub leaf_index: u64,
+    pub commitment: String,
+    pub block_number: u64,
+    pub tx_hash: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Withdrawal {
+    pub nullifier: String,
+    pub exclusion_set_root: String,
+    pub recipient: String,
+    pub relayer: String,
+    /// In wei.
+    pub relayer_fee: String,
+    pub block_number: u64,
+    pub tx_hash: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct NullifierStatus {
+    pub spent: bool,
+    pub withdrawal: Option<Withdrawal>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct PoolStats {
+    pub address: String,
+    /// Last indexed block.
+    pub block_number: Option<u64>,
+    pub deposits: u64,
+    pub withdrawals: u64,
+    /// Deposits not yet withdrawn, the largest anonymity set a withdrawal can hide in.
+    pub unspent: u64,
+    /// Sum of relayer fees paid, in wei.
+    pub relayer_fees: String,
+    /// Root of the deposits array at the last block with deposits.
+    pub root: Option<String>,
+}
+
+/// Cumulative counts up to the end of a range of blocks.
+#[derive(Debug, Clone, SimpleObject)]
+pub struct AnonymitySetPoint {
+    pub block_number: u64,
+    pub deposits: u64,
+    pub withdrawals: u64,
+}
+
+type GqlResult<T> = async_graphql::Result<T>;
+
+fn parse<T: FromStr>(name: &str, value: &str) -> GqlResult<T> {
+    value
+        .parse()
+        .map_err(|_| async_graphql::Error::new(format!("invalid {name} {value}")))
+}
+
+fn deposit_from_row(row: &Row) -> rusqlite::Result<Deposit> {
+    Ok(Deposit {
+        leaf_index: row.get("leaf_index")?,
+        commitment: row.get("commitment")?,
+        block_number: row.get("block_number")?,
+        tx_hash: row.get("tx_hash")?,
+    })
+}
+
+fn withdrawal_from_row(row: &Row) -> rusqlite::Result<Withdrawal> {
+    Ok(Withdrawal {
+        nullifier: row.get("nullifier")?,
+        exclusion_set_root: row.get("exclusion_set_root")?,
+        recipient: row.get("recipient")?,
+        relayer: row.get("relayer")?,
+        relayer_fee: row.get("relayer_fee")?,
+        block_number: row.get("block_number")?,
+        tx_hash: row.get("tx_hash")?,
+    })
+}
+
+impl QueryRoot {
+    /// Count the rows of `table` for `pool` up to `block_number`.
+    fn count(
+        conn: &Connection,
+        table: &str,
+        chain_id: u64,
+        pool: &str,
+        block_number: u64,
+    ) -> rusqlite::Result<u64> {
+        conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {table}
+                 WHERE chain_id = ?1 AND pool = ?2 AND block_number <= ?3"
+            ),
+            params![chain_id, pool, block_number],
+            |row| row.get(0),
+        )
+    }
+}
+
+#[Object]
+impl QueryRoot {
+    /// The deposit of `commitment` into `pool`.
+    async fn deposit(&self, pool: String, commitment: String) -> GqlResult<Option<Deposit>> {
+        let pool: Address = parse("pool", &pool)?;
+        let commitment: B256 = parse("commitment", &commitment)?;
+        Ok(self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT * FROM deposits WHERE chain_id = ?1 AND pool = ?2 AND commitment = ?3",
+                params![self.chain_id, pool.to_string(), commitment.to_string()],
+                deposit_from_row,
+            )
+            .optional()?)
+    }
+
+    /// Deposits into `pool` from leaf index `from`, in leaf order.
+    async fn deposits(
+        &self,
+        pool: String,
+        #[graphql(default = 0)] from: u64,
+        #[graphql(default = 100)] limit: u32,
+    ) -> GqlResult<Vec<Deposit>> {
+        let pool: Address = parse("pool", &pool)?;
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT * FROM deposits WHERE chain_id = ?1 AND pool = ?2 AND leaf_index >= ?3
+             ORDER BY leaf_index LIMIT ?4",
+        )?;
+        let deposits = statement
+            .query_map(
+                params![self.chain_id, pool.to_string(), from, limit.min(MAX_LIMIT)],
+                deposit_from_row,
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(deposits)
+    }
+
+    /// Withdrawals from `pool` in the order they were made, skipping the first `from`.
+    async fn withdrawals(
+        &self,
+        pool: String,
+        #[graphql(default = 0)] from: u64,
+        #[graphql(default = 100)] limit: u32,
+    ) -> GqlResult<Vec<Withdrawal>> {
+        let pool: Address = parse("pool", &pool)?;
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT * FROM withdrawals WHERE chain_id = ?1 AND pool = ?2
+             ORDER BY block_number, rowid LIMIT ?4 OFFSET ?3",
+        )?;
+        let withdrawals = statement
+            .query_map(
+                params![self.chain_id, pool.to_string(), from, limit.min(MAX_LIMIT)],
+                withdrawal_from_row,
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(withdrawals)
+    }
+
+    /// Whether `nullifier` was spent in `pool`, and the withdrawal that spent it.
+    async fn nullifier(&self, pool: String, nullifier: String) -> GqlResult<NullifierStatus> {
+        let pool: Address = parse("pool", &pool)?;
+        let nullifier: B256 = parse("nullifier", &nullifier)?;
+        let withdrawal = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT * FROM withdrawals WHERE chain_id = ?1 AND pool = ?2 AND nullifier = ?3",
+                params![self.chain_id, pool.to_string(), nullifier.to_string()],
+                withdrawal_from_row,
+            )
+            .optional()?;
+        Ok(NullifierStatus {
+            spent: withdrawal.is_some(),
+            withdrawal,
+        })
+    }
+
+    /// Totals of `pool` as of the last indexed block.
+    async fn pool(&self, address: String) -> GqlResult<PoolStats> {
+        let pool = parse::<Address>("pool", &address)?.to_string();
+        let conn = self.conn.lock().unwrap();
+        let block_number: Option<u64> = conn
+            .query_row(
+                "SELECT block_number FROM indexed_pools WHERE chain_id = ?1 AND pool = ?2",
+                params![self.chain_id, pool],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let latest = i64::MAX as u64;
+        let deposits = Self::count(&conn, "deposits", self.chain_id, &pool, latest)?;
+        let withdrawals = Self::count(&conn, "withdrawals", self.chain_id, &pool, latest)?;
+
+        let mut statement =
+            conn.prepare("SELECT relayer_fee FROM withdrawals WHERE chain_id = ?1 AND pool = ?2")?;
+        let mut relayer_fees = U256::ZERO;
+        for fee in
+            statement.query_map(params![self.chain_id, pool], |row| row.get::<_, String>(0))?
+        {
+            relayer_fees += parse::<U256>("fee", &fee?)?;
+        }
+
+        let root = conn
+            .query_row(
+                "SELECT root FROM roots WHERE chain_id = ?1 AND pool = ?2
+                 ORDER BY block_number DESC LIMIT 1",
+                params![self.chain_id, pool],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(PoolStats {
+            address: pool,
+            block_number,
+            deposits,
+            withdrawals,
+            unspent: deposits.saturating_sub(withdrawals),
+            relayer_fees: relayer_fees.to_string(),
+            root,
+        })
+    }
+
+    /// Deposits and withdrawals of `pool` made by the end of every `step` blocks from
+    /// `from_block` to `to_block`.
+    async fn anonymity_set(
+        &self,
+        pool: String,
+        from_block: u64,
+        to_block: u64,
+        step: u64,
+    ) -> GqlResult<Vec<AnonymitySetPoint>> {
+        let pool = parse::<Address>("pool", &pool)?.to_string();
+        if step == 0 || to_block < from_block || (to_block - from_block) / step >= MAX_LIMIT as u64
+        {
+            return Err(async_graphql::Error::new(format!(
+                "step must be positive and cover the range in at most {MAX_LIMIT} points"
+            )));
+        }
+        let conn = self.conn.lock().unwrap();
+        let mut points = Vec::new();
+        let mut block_number = from_block;
+        loop {
+            points.push(AnonymitySetPoint {
+                block_number,
+                deposits: Self::count(&conn, "deposits", self.chain_id, &pool, block_number)?,
+                withdrawals: Self::count(&conn, "withdrawals", self.chain_id, &pool, block_number)?,
+            });
+            if block_number == to_block {
+                break;
+            }
+            block_number = (block_number + step).min(to_block);
+        }
+        Ok(points)
+    }
+}
+
+/// Build the schema over `conn`, a connection to the indexer database.
+pub fn schema(conn: Connection, chain_id: u64) -> IndexerSchema {
+    Schema::build(
+        QueryRoot {
+            conn: Mutex::new(conn),
+            chain_id,
+        },
+        EmptyMutation,
+        EmptySubscription,
+    )
+    .limit_depth(8)
+    .finish()
+}
+
+/// `POST /graphql`.
+pub fn router(schema: IndexerSchema) -> Router {
+    Router::new().route_service("/graphql", GraphQL::new(schema))
+}
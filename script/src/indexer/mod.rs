@@ -0,0 +1,812 @@
+pub mod filter;
+pub mod graphql;
+
+use crate::{
+    api::ApiError,
+    db,
+    index::block_hash,
+    indexer::filter::CommitmentFilter,
+    pool::Pool,
+    snapshot::Snapshot,
+    webhook::{Event, Notifier},
+};
+use alloy::{
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::{Filter, Log},
+    sol_types::SolEvent,
+};
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use eyre::{bail, ensure, OptionExt, Result};
+use pool_lib::{compute_merkle_branch, compute_merkle_root, InclusionBranches, MerkleFrontier};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::{info, instrument, warn};
+
+/// Blocks back the pool accepts proofs against, the reach of the `BLOCKHASH` opcode.
+pub const BLOCKHASH_WINDOW: u64 = 256;
+
+/// A root of a pool's deposits array and the block it took effect at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootEntry {
+    pub block_number: u64,
+    pub leaf_count: u64,
+    pub root: B256,
+}
+
+/// Settings of an [`Indexer`].
+#[derive(Debug, Clone)]
+pub struct IndexerConfig {
+    pub chain_id: u64,
+    pub pools: Vec<Address>,
+    /// Block pools without any progress are indexed from, usually their deployment block.
+    pub from_block: u64,
+    /// Recent blocks whose hashes are kept to detect reorgs. A deeper reorg stops the indexer.
+    pub reorg_depth: u64,
+    /// Blocks requested per `eth_getLogs` call.
+    pub batch_size: u64,
+    /// Commitments whose deposits are sent to the webhooks.
+    pub watch: HashSet<B256>,
+    /// Nullifiers whose withdrawals are sent to the webhooks.
+    pub watch_nullifiers: HashSet<B256>,
+    pub notifier: Option<Notifier>,
+    /// Denomination, in wei, from which withdrawals are sent to the notifier as
+    /// `large_withdrawal` alerts.
+    pub large_withdrawal: Option<U256>,
+}
+
+/// Ingests the `Deposit`, `DepositMany` and `Withdrawal` events of pools into the shared sqlite
+/// database, rolling back and re-applying entries of blocks that were reorged out.
+pub struct Indexer<P> {
+    provider: P,
+    config: IndexerConfig,
+    conn: Mutex<Connection>,
+    /// Denominations of the pools, read once when first needed.
+    amounts: Mutex<HashMap<Address, U256>>,
+    /// Database path, next to which the [`CommitmentFilter`]s are kept.
+    path: PathBuf,
+    filters: Mutex<HashMap<Address, CommitmentFilter>>,
+}
+
+impl<P: Provider> Indexer<P> {
+    pub fn open(provider: P, config: IndexerConfig, path: &Path) -> Result<Self> {
+        Ok(Self {
+            provider,
+            config,
+            conn: Mutex::new(db::open(path)?),
+            amounts: Mutex::new(HashMap::new()),
+            path: path.to_path_buf(),
+            filters: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Index new blocks every `interval`, forever.
+    pub async fn run(&self, interval: Duration) -> Result<()> {
+        loop {
+            if let Err(e) = self.step().await {
+                warn!("indexing failed: {e}");
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Undo any reorged blocks, then bring every pool up to the current head.
+    #[instrument(skip_all, fields(chain_id = self.config.chain_id))]
+    pub async fn step(&self) -> Result<()> {
+        let head = self.provider.get_block_number().await?;
+        self.handle_reorg().await?;
+        self.record_blocks(head).await?;
+        for pool in &self.config.pools {
+            self.index_pool(*pool, head).await?;
+        }
+        self.conn.lock().unwrap().execute(
+            "DELETE FROM blocks WHERE chain_id = ?1 AND number < ?2",
+            params![
+                self.config.chain_id,
+                head.saturating_sub(self.config.reorg_depth)
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Compare the recorded block hashes against the chain, newest first, and roll back to the
+    /// newest block that is still canonical.
+    async fn handle_reorg(&self) -> Result<()> {
+        let recorded = self.recorded_blocks()?;
+        let Some((newest, _)) = recorded.first() else {
+            return Ok(());
+        };
+        for (number, hash) in &recorded {
+            if block_hash(&self.provider, *number).await? == *hash {
+                if number != newest {
+                    warn!(
+                        from = number + 1,
+                        to = newest,
+                        "blocks were reorged, rolling back"
+                    );
+                    self.rollback(*number)?;
+                    if let Some(notifier) = &self.config.notifier {
+                        notifier.notify(Event::Reorg {
+                            chain_id: self.config.chain_id,
+                            from_block: number + 1,
+                            to_block: *newest,
+                        });
+                    }
+                }
+                return Ok(());
+            }
+        }
+        bail!(
+            "reorg deeper than {} blocks, reindex from scratch",
+            self.config.reorg_depth
+        )
+    }
+
+    /// Recorded block hashes, newest first.
+    fn recorded_blocks(&self) -> Result<Vec<(u64, B256)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn
+            .prepare("SELECT number, hash FROM blocks WHERE chain_id = ?1 ORDER BY number DESC")?;
+        let rows = statement
+            .query_map([self.config.chain_id], |row| {
+                Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(number, hash)| Ok((number, hash.parse()?)))
+            .collect()
+    }
+
+    /// Delete everything indexed after block `ancestor`.
+    fn rollback(&self, ancestor: u64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let chain_id = self.config.chain_id;
+        // Filters forget the deposits being deleted first, so a failed rollback still leaves
+        // them covering no more than the database.
+        for pool in &self.config.pools {
+            let kept: u64 = conn.query_row(
+                "SELECT COUNT(*) FROM deposits
+                 WHERE chain_id = ?1 AND pool = ?2 AND block_number <= ?3",
+                params![chain_id, pool.to_string(), ancestor],
+                |row| row.get(0),
+            )?;
+            let path = CommitmentFilter::path(&self.path, chain_id, *pool);
+            let mut filters = self.filters.lock().unwrap();
+            let filter = match filters.entry(*pool) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(CommitmentFilter::load(&path)?),
+            };
+            filter.truncate(kept);
+            filter.save(&path)?;
+        }
+        let tx = conn.transaction()?;
+        for table in ["blocks", "deposits", "withdrawals", "roots"] {
+            let column = if table == "blocks" {
+                "number"
+            } else {
+                "block_number"
+            };
+            tx.execute(
+                &format!("DELETE FROM {table} WHERE chain_id = ?1 AND {column} > ?2"),
+                params![chain_id, ancestor],
+            )?;
+        }
+        tx.execute(
+            "UPDATE indexed_pools SET block_number = ?2 WHERE chain_id = ?1 AND block_number > ?2",
+            params![chain_id, ancestor],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record the hashes of the last `reorg_depth` blocks up to `head` that are not yet known.
+    async fn record_blocks(&self, head: u64) -> Result<()> {
+        let newest = self.recorded_blocks()?.first().map(|(number, _)| *number);
+        let from = head
+            .saturating_sub(self.config.reorg_depth)
+            .max(newest.map_or(0, |newest| newest + 1));
+        for number in from..=head {
+            let hash = block_hash(&self.provider, number).await?;
+            self.conn.lock().unwrap().execute(
+                "INSERT OR REPLACE INTO blocks (chain_id, number, hash) VALUES (?1, ?2, ?3)",
+                params![self.config.chain_id, number, hash.to_string()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Last block indexed for `pool`.
+    fn cursor(&self, pool: Address) -> Result<Option<u64>> {
+        Ok(self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT block_number FROM indexed_pools WHERE chain_id = ?1 AND pool = ?2",
+                params![self.config.chain_id, pool.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn index_pool(&self, pool: Address, head: u64) -> Result<()> {
+        let mut from = self
+            .cursor(pool)?
+            .map_or(self.config.from_block, |cursor| cursor + 1);
+        while from <= head {
+            let to = (from + self.config.batch_size - 1).min(head);
+            let filter = Filter::new()
+                .address(pool)
+                .event_signature(vec![
+                    Pool::Deposit::SIGNATURE_HASH,
+                    Pool::DepositMany::SIGNATURE_HASH,
+                    Pool::Withdrawal::SIGNATURE_HASH,
+                ])
+                .from_block(from)
+                .to_block(to);
+            let logs = self.provider.get_logs(&filter).await?;
+            if self.apply(pool, &logs, to)? {
+                self.update_filter(pool)?;
+            }
+            self.alert_large_withdrawals(pool, &logs).await?;
+            if !logs.is_empty() {
+                info!(from, to, logs = logs.len(), "indexed events");
+            }
+            from = to + 1;
+        }
+        Ok(())
+    }
+
+    /// Tell the notifier about withdrawals in `logs` if `pool`'s denomination is at least
+    /// `large_withdrawal`.
+    async fn alert_large_withdrawals(&self, pool: Address, logs: &[Log]) -> Result<()> {
+        let (Some(notifier), Some(threshold)) =
+            (&self.config.notifier, self.config.large_withdrawal)
+        else {
+            return Ok(());
+        };
+        let mut withdrawals = logs
+            .iter()
+            .filter_map(|log| Some((log, log.log_decode::<Pool::Withdrawal>().ok()?)))
+            .peekable();
+        if withdrawals.peek().is_none() {
+            return Ok(());
+        }
+        let cached = self.amounts.lock().unwrap().get(&pool).copied();
+        let amount = match cached {
+            Some(amount) => amount,
+            None => {
+                let amount = Pool::new(pool, &self.provider).amount().call().await?._0;
+                self.amounts.lock().unwrap().insert(pool, amount);
+                amount
+            }
+        };
+        if amount < threshold {
+            return Ok(());
+        }
+        for (log, event) in withdrawals {
+            let event = event.inner.data;
+            notifier.notify(Event::LargeWithdrawal {
+                chain_id: self.config.chain_id,
+                pool,
+                nullifier: event.nullifier,
+                amount,
+                recipient: event.recipient,
+                tx_hash: log.transaction_hash.unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Add the deposits of `pool` indexed since its filter was last saved to it.
+    fn update_filter(&self, pool: Address) -> Result<()> {
+        let path = CommitmentFilter::path(&self.path, self.config.chain_id, pool);
+        let conn = self.conn.lock().unwrap();
+        let mut filters = self.filters.lock().unwrap();
+        let filter = match filters.entry(pool) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(CommitmentFilter::load(&path)?),
+        };
+        if filter.sync(&conn, self.config.chain_id, pool)? {
+            filter.save(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Store the events in `logs` and move the cursor of `pool` to `to`, all or nothing. Returns
+    /// whether any were deposits.
+    fn apply(&self, pool: Address, logs: &[Log], to: u64) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut hashes = HashMap::new();
+        // Leaf count after each block with deposits.
+        let mut leaf_counts = BTreeMap::new();
+        let mut watched = Vec::new();
+        for log in logs {
+            let block_number = log.block_number.ok_or_eyre("log missing block number")?;
+            let block_hash = log.block_hash.ok_or_eyre("log missing block hash")?;
+            // Logs from a fork other than the recorded blocks are retried on the next step,
+            // after the reorg is handled.
+            let recorded = match hashes.get(&block_number) {
+                Some(hash) => Some(*hash),
+                None => recorded_hash(&tx, self.config.chain_id, block_number)?,
+            };
+            if let Some(recorded) = recorded {
+                ensure!(
+                    recorded == block_hash,
+                    "log of block {block_number} is from another fork"
+                );
+            }
+            hashes.insert(block_number, block_hash);
+            for (leaf_index, commitment) in self.apply_log(&tx, pool, log, block_number)? {
+                leaf_counts.insert(block_number, leaf_index + 1);
+                if self.config.watch.contains(&commitment) {
+                    watched.push(Event::DepositConfirmed {
+                        chain_id: self.config.chain_id,
+                        pool,
+                        commitment,
+                        leaf_index,
+                        block_number,
+                        tx_hash: log.transaction_hash.unwrap_or_default(),
+                    });
+                }
+            }
+            if let Ok(event) = log.log_decode::<Pool::Withdrawal>() {
+                let event = event.inner.data;
+                if self.config.watch_nullifiers.contains(&event.nullifier) {
+                    watched.push(Event::NoteSpent {
+                        chain_id: self.config.chain_id,
+                        pool,
+                        nullifier: event.nullifier,
+                        recipient: event.recipient,
+                        block_number,
+                        tx_hash: log.transaction_hash.unwrap_or_default(),
+                    });
+                }
+            }
+        }
+        if !leaf_counts.is_empty() {
+            self.record_roots(&tx, pool, &leaf_counts)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO indexed_pools (chain_id, pool, block_number)
+             VALUES (?1, ?2, ?3)",
+            params![self.config.chain_id, pool.to_string(), to],
+        )?;
+        tx.commit()?;
+        if let Some(notifier) = &self.config.notifier {
+            for event in watched {
+                notifier.notify(event);
+            }
+        }
+        Ok(!leaf_counts.is_empty())
+    }
+
+    /// Store the event in `log`, returning the leaf index and commitment of every deposit in it,
+    /// in leaf order.
+    fn apply_log(
+        &self,
+        tx: &Transaction,
+        pool: Address,
+        log: &Log,
+        block_number: u64,
+    ) -> Result<Vec<(u64, B256)>> {
+        let tx_hash = log
+            .transaction_hash
+            .ok_or_eyre("log missing transaction hash")?
+            .to_string();
+        let deposits: Vec<(u64, B256)> = if let Ok(event) = log.log_decode::<Pool::Deposit>() {
+            let event = event.inner.data;
+            vec![(event.leafIndex.to(), event.commitment)]
+        } else if let Ok(event) = log.log_decode::<Pool::DepositMany>() {
+            // A batch appends its commitments as contiguous leaves.
+            let event = event.inner.data;
+            let first = event.firstLeafIndex.to::<u64>();
+            (first..).zip(event.commitments).collect()
+        } else {
+            Vec::new()
+        };
+        for (leaf_index, commitment) in &deposits {
+            tx.execute(
+                "INSERT OR REPLACE INTO deposits
+                 (chain_id, pool, leaf_index, commitment, block_number, tx_hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    self.config.chain_id,
+                    pool.to_string(),
+                    leaf_index,
+                    commitment.to_string(),
+                    block_number,
+                    tx_hash
+                ],
+            )?;
+        }
+        if let Ok(event) = log.log_decode::<Pool::Withdrawal>() {
+            let event = event.inner.data;
+            tx.execute(
+                "INSERT OR REPLACE INTO withdrawals
+                 (chain_id, pool, nullifier, exclusion_set_root, recipient, relayer, relayer_fee,
+                  block_number, tx_hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    self.config.chain_id,
+                    pool.to_string(),
+                    event.nullifier.to_string(),
+                    event.exclusionSetRoot.to_string(),
+                    event.recipient.to_string(),
+                    event.relayer.to_string(),
+                    event.relayerFee.to_string(),
+                    block_number,
+                    tx_hash
+                ],
+            )?;
+        }
+        Ok(deposits)
+    }
+
+    /// Record the root of the deposits array after each block in `leaf_counts`. Roots need the
+    /// full array, so none are recorded while the index is missing earlier deposits.
+    fn record_roots(
+        &self,
+        tx: &Transaction,
+        pool: Address,
+        leaf_counts: &BTreeMap<u64, u64>,
+    ) -> Result<()> {
+        let leaves = match deposits(tx, self.config.chain_id, pool) {
+            Ok(leaves) => leaves,
+            Err(e) => {
+                warn!(%pool, "not recording roots: {e}");
+                return Ok(());
+            }
+        };
+        // Leaf counts only grow with the block number, so one pass over the leaves gives every
+        // root.
+        let mut frontier = MerkleFrontier::default();
+        for (block_number, leaf_count) in leaf_counts {
+            for leaf in &leaves[frontier.len() as usize..*leaf_count as usize] {
+                frontier.push(*leaf);
+            }
+            let root = frontier.root();
+            tx.execute(
+                "INSERT OR REPLACE INTO roots (chain_id, pool, block_number, leaf_count, root)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    self.config.chain_id,
+                    pool.to_string(),
+                    block_number,
+                    leaf_count,
+                    root.to_string()
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Root in effect at `block_number`: the one recorded at the last block with deposits up to
+    /// it.
+    pub fn root_at(&self, pool: Address, block_number: u64) -> Result<Option<RootEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT block_number, leaf_count, root FROM roots
+                 WHERE chain_id = ?1 AND pool = ?2 AND block_number <= ?3
+                 ORDER BY block_number DESC LIMIT 1",
+                params![self.config.chain_id, pool.to_string(), block_number],
+                root_from_row,
+            )
+            .optional()?;
+        row.map(|(block_number, leaf_count, root)| {
+            Ok(RootEntry {
+                block_number,
+                leaf_count,
+                root: root.parse()?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Roots a withdrawal proven now could use: the one in effect `BLOCKHASH_WINDOW` blocks
+    /// before the last indexed block, and every one recorded since, oldest first.
+    pub fn recent_roots(&self, pool: Address) -> Result<Vec<RootEntry>> {
+        let Some(cursor) = self.cursor(pool)? else {
+            return Ok(Vec::new());
+        };
+        let oldest = cursor.saturating_sub(BLOCKHASH_WINDOW);
+        let mut roots: Vec<RootEntry> = self.root_at(pool, oldest)?.into_iter().collect();
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT block_number, leaf_count, root FROM roots
+             WHERE chain_id = ?1 AND pool = ?2 AND block_number > ?3 ORDER BY block_number",
+        )?;
+        let rows = statement
+            .query_map(
+                params![self.config.chain_id, pool.to_string(), oldest],
+                root_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (block_number, leaf_count, root) in rows {
+            roots.push(RootEntry {
+                block_number,
+                leaf_count,
+                root: root.parse()?,
+            });
+        }
+        Ok(roots)
+    }
+
+    /// Branch of `deposits[index]` against the root in effect at `block_number`.
+    pub fn branch(
+        &self,
+        pool: Address,
+        index: u64,
+        block_number: u64,
+    ) -> Result<Option<(RootEntry, InclusionBranches)>> {
+        let Some(root) = self.root_at(pool, block_number)? else {
+            return Ok(None);
+        };
+        ensure!(
+            index < root.leaf_count,
+            "deposit {index} was not made by block {block_number}"
+        );
+        let leaves = self.deposits(pool)?;
+        let leaves = &leaves[..root.leaf_count as usize];
+        ensure!(
+            compute_merkle_root(leaves) == root.root,
+            "indexed deposits do not match the recorded root"
+        );
+        let branches = compute_merkle_branch(leaves, index as usize);
+        Ok(Some((root, branches)))
+    }
+
+    /// Indexed deposit commitments of `pool`, in leaf order.
+    pub fn deposits(&self, pool: Address) -> Result<Vec<B256>> {
+        deposits(&self.conn.lock().unwrap(), self.config.chain_id, pool)
+    }
+
+    /// Snapshot of the deposit tree of `pool` at the last indexed block.
+    pub async fn snapshot(&self, pool: Address) -> Result<Snapshot> {
+        let (block_number, leaves) = {
+            let mut conn = self.conn.lock().unwrap();
+            // One read transaction, so a running daemon cannot move the cursor in between.
+            let tx = conn.transaction()?;
+            let block_number: u64 = tx
+                .query_row(
+                    "SELECT block_number FROM indexed_pools WHERE chain_id = ?1 AND pool = ?2",
+                    params![self.config.chain_id, pool.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_eyre("pool is not indexed")?;
+            (block_number, deposits(&tx, self.config.chain_id, pool)?)
+        };
+        Ok(Snapshot {
+            chain_id: self.config.chain_id,
+            pool,
+            block_number,
+            block_hash: block_hash(&self.provider, block_number).await?,
+            leaves,
+        })
+    }
+}
+
+fn root_from_row(row: &rusqlite::Row) -> rusqlite::Result<(u64, u64, String)> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+}
+
+fn recorded_hash(conn: &Connection, chain_id: u64, number: u64) -> Result<Option<B256>> {
+    let hash: Option<String> = conn
+        .query_row(
+            "SELECT hash FROM blocks WHERE chain_id = ?1 AND number = ?2",
+            params![chain_id, number],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(hash.map(|hash| hash.parse()).transpose()?)
+}
+
+/// Indexed deposit commitments of `pool` on `chain_id`, in leaf order. Fails if the index has a
+/// gap, which means it is still catching up or was started after the pool's first deposit.
+pub fn deposits(conn: &Connection, chain_id: u64, pool: Address) -> Result<Vec<B256>> {
+    let mut statement = conn.prepare(
+        "SELECT leaf_index, commitment FROM deposits WHERE chain_id = ?1 AND pool = ?2
+         ORDER BY leaf_index",
+    )?;
+    let rows = statement
+        .query_map(params![chain_id, pool.to_string()], |row| {
+            Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, (leaf_index, commitment))| {
+            ensure!(
+                leaf_index == i as u64,
+                "deposit {i} is missing from the index"
+            );
+            Ok(commitment.parse()?)
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RootsQuery {
+    pub pool: Address,
+    /// Return the root in effect at this block instead of the recent ones.
+    pub block: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BranchQuery {
+    pub pool: Address,
+    pub index: u64,
+    /// Block whose root the branch is for.
+    pub block: u64,
+}
+
+/// Response of `GET /branch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchResponse {
+    pub root: RootEntry,
+    pub branches: InclusionBranches,
+}
+
+/// HTTP API of `indexer`.
+pub fn router<P: Provider + 'static>(indexer: Arc<Indexer<P>>) -> Router {
+    Router::new()
+        .route("/roots", get(roots::<P>))
+        .route("/branch", get(branch::<P>))
+        .with_state(indexer)
+}
+
+async fn roots<P: Provider>(
+    State(indexer): State<Arc<Indexer<P>>>,
+    Query(query): Query<RootsQuery>,
+) -> Result<Json<Vec<RootEntry>>, ApiError> {
+    Ok(Json(match query.block {
+        Some(block) => indexer.root_at(query.pool, block)?.into_iter().collect(),
+        None => indexer.recent_roots(query.pool)?,
+    }))
+}
+
+async fn branch<P: Provider>(
+    State(indexer): State<Arc<Indexer<P>>>,
+    Query(query): Query<BranchQuery>,
+) -> Result<Json<BranchResponse>, ApiError> {
+    let (root, branches) = indexer
+        .branch(query.pool, query.index, query.block)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?
+        .ok_or_else(|| ApiError::not_found(format!("no deposits by block {}", query.block)))?;
+    Ok(Json(BranchResponse { root, branches }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{network::Ethereum, providers::RootProvider};
+
+    const POOL: Address = Address::repeat_byte(0x42);
+
+    /// An indexer of [`POOL`] over a fresh database, whose provider is never called.
+    fn indexer(name: &str) -> Indexer<RootProvider<Ethereum>> {
+        let dir = std::env::temp_dir().join(format!("pool-indexer-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = IndexerConfig {
+            chain_id: 1,
+            pools: vec![POOL],
+            from_block: 0,
+            reorg_depth: 64,
+            batch_size: 1000,
+            watch: HashSet::new(),
+            watch_nullifiers: HashSet::new(),
+            notifier: None,
+            large_withdrawal: None,
+        };
+        let provider = RootProvider::new_http("http://127.0.0.1:1".parse().unwrap());
+        Indexer::open(provider, config, &dir.join("pool.sqlite")).unwrap()
+    }
+
+    fn commitment(i: u64) -> B256 {
+        B256::from(U256::from(i + 1))
+    }
+
+    fn deposit(leaf_index: u64, block_number: u64, block_hash: B256) -> Log {
+        let event = Pool::Deposit {
+            commitment: commitment(leaf_index),
+            leafIndex: U256::from(leaf_index),
+        };
+        Log {
+            inner: alloy::primitives::Log {
+                address: POOL,
+                data: event.encode_log_data(),
+            },
+            block_number: Some(block_number),
+            block_hash: Some(block_hash),
+            transaction_hash: Some(B256::with_last_byte(leaf_index as u8)),
+            ..Default::default()
+        }
+    }
+
+    fn record_block(indexer: &Indexer<RootProvider<Ethereum>>, number: u64, hash: B256) {
+        indexer
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO blocks (chain_id, number, hash) VALUES (1, ?1, ?2)",
+                params![number, hash.to_string()],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn records_the_root_after_each_block_with_deposits() {
+        let indexer = indexer("roots");
+        let logs = [
+            deposit(0, 10, B256::with_last_byte(10)),
+            deposit(1, 10, B256::with_last_byte(10)),
+            deposit(2, 12, B256::with_last_byte(12)),
+        ];
+        assert!(indexer.apply(POOL, &logs, 12).unwrap());
+
+        let leaves: Vec<B256> = (0..3).map(commitment).collect();
+        assert_eq!(indexer.deposits(POOL).unwrap(), leaves);
+        assert!(indexer.root_at(POOL, 9).unwrap().is_none());
+        let root = indexer.root_at(POOL, 11).unwrap().unwrap();
+        assert_eq!((root.block_number, root.leaf_count), (10, 2));
+        assert_eq!(root.root, compute_merkle_root(&leaves[..2]));
+        assert_eq!(
+            indexer.root_at(POOL, 12).unwrap().unwrap().root,
+            compute_merkle_root(&leaves)
+        );
+
+        let (root, branches) = indexer.branch(POOL, 1, 11).unwrap().unwrap();
+        assert_eq!(
+            pool_lib::compute_inclusion_root(&leaves[1], &branches),
+            root.root
+        );
+        // Deposit 2 was not made by block 11.
+        assert!(indexer.branch(POOL, 2, 11).is_err());
+    }
+
+    #[test]
+    fn rollback_undoes_reorged_blocks() {
+        let indexer = indexer("rollback");
+        let logs = [
+            deposit(0, 10, B256::with_last_byte(10)),
+            deposit(1, 12, B256::with_last_byte(12)),
+        ];
+        indexer.apply(POOL, &logs, 12).unwrap();
+
+        indexer.rollback(11).unwrap();
+        assert_eq!(indexer.deposits(POOL).unwrap(), vec![commitment(0)]);
+        assert_eq!(indexer.cursor(POOL).unwrap(), Some(11));
+        assert_eq!(indexer.root_at(POOL, 12).unwrap().unwrap().block_number, 10);
+
+        // The new fork's block 12 is applied as if the old one never was.
+        let reapplied = [deposit(1, 12, B256::with_last_byte(0xf))];
+        indexer.apply(POOL, &reapplied, 12).unwrap();
+        assert_eq!(indexer.root_at(POOL, 12).unwrap().unwrap().leaf_count, 2);
+    }
+
+    #[test]
+    fn rejects_logs_from_another_fork() {
+        let indexer = indexer("fork");
+        record_block(&indexer, 10, B256::with_last_byte(10));
+        let logs = [deposit(0, 10, B256::with_last_byte(0xf))];
+        assert!(indexer.apply(POOL, &logs, 10).is_err());
+        // Nothing of the batch is kept, and the cursor stays put for the retry.
+        assert!(indexer.deposits(POOL).unwrap().is_empty());
+        assert_eq!(indexer.cursor(POOL).unwrap(), None);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::{
+    backend::ZkBackend,
+    prove::{run_blocking, ProofMode, ProverArgs},
+    witness::WitnessArgs,
+};
+use alloy::{primitives::Bytes, transports::http::reqwest::Url};
+use axum::{
+    body::Bytes as Body,
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use clap::Parser;
+use eyre::Result;
+use pool_lib::{decode_any_public_values, withdrawal_public_values, WithdrawalInput};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::Duration};
+use tracing::{info, instrument, warn};
+
+/// `pool withdraw` flags `pool_buildInput` accepts, by their camelCase names. Flags that read or
+/// write files on the server, or have it contact other hosts, are left out.
+const BUILD_INPUT_FLAGS: &[&str] = &[
+    "recipient",
+    "relayer",
+    "leafIndex",
+    "relayerFee",
+    "tokenFee",
+    "tokenFeeAmount",
+    "expectedCodeHash",
+    "minAnonymitySet",
+    "force",
+];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// Settings calls run with, as given to `pool serve`.
+#[derive(Debug, Clone)]
+pub struct JsonRpcConfig {
+    pub rpc_url: Url,
+    pub prover: ProverArgs,
+    pub timeout: Option<Duration>,
+    /// Bearer token every request must carry, if set.
+    pub auth_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug)]
+struct Error {
+    code: i64,
+    message: String,
+}
+
+impl Error {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<eyre::Report> for Error {
+    fn from(e: eyre::Report) -> Self {
+        Self::new(SERVER_ERROR, format!("{e:#}"))
+    }
+}
+
+fn response(id: Value, result: Result<Value, Error>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+/// Check `input` the way the program does, returning the public values it commits to.
+fn check(input: &WithdrawalInput) -> Result<Vec<u8>, Error> {
+    withdrawal_public_values(input)
+        .map_err(|e| Error::new(INVALID_PARAMS, format!("invalid withdrawal: {e}")))
+}
+
+/// Params of a method taking one object, passed by name or as the only positional param.
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, Error> {
+    let params = match params {
+        Value::Array(mut params) if params.len() == 1 => params.remove(0),
+        params => params,
+    };
+    serde_json::from_value(params).map_err(|e| Error::new(INVALID_PARAMS, e.to_string()))
+}
+
+#[derive(Parser)]
+struct BuildInputArgs {
+    #[clap(flatten)]
+    witness: WitnessArgs,
+}
+
+#[derive(Deserialize)]
+struct InputParams {
+    input: WithdrawalInput,
+}
+
+#[derive(Deserialize)]
+struct ProveParams {
+    input: WithdrawalInput,
+    #[serde(default = "default_mode")]
+    mode: ProofMode,
+}
+
+fn default_mode() -> ProofMode {
+    ProofMode::Compressed
+}
+
+#[derive(Deserialize)]
+struct VerifyParams {
+    /// Proof in the backend's encoding, as `pool withdraw --prove` writes it.
+    proof: Bytes,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublicValues {
+    public_values: Bytes,
+    nullifier: String,
+    block_number: u64,
+}
+
+fn public_values(encoded: &[u8]) -> Result<PublicValues, Error> {
+    let data = decode_any_public_values(encoded)
+        .map_err(eyre::Report::from)?
+        .withdrawal;
+    Ok(PublicValues {
+        public_values: encoded.to_vec().into(),
+        nullifier: data.nullifier.to_string(),
+        block_number: data.blockNumber,
+    })
+}
+
+/// Serves `pool_buildInput`, `pool_execute`, `pool_prove` and `pool_verify`, which behave like
+/// `pool withdraw` and its `--execute` and `--prove` flags.
+pub struct JsonRpcServer {
+    config: JsonRpcConfig,
+    backend: Arc<dyn ZkBackend>,
+}
+
+impl JsonRpcServer {
+    pub fn new(config: JsonRpcConfig) -> Result<Self> {
+        let backend = config.prover.backend()?;
+        Ok(Self { config, backend })
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = &self.config.auth_token else {
+            return true;
+        };
+        let given = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        // Compare digests so the comparison takes the same time however much of the token matches.
+        Sha256::digest(given) == Sha256::digest(token)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        match method {
+            "pool_buildInput" => self.build_input(params).await,
+            "pool_execute" => self.execute(self::params(params)?).await,
+            "pool_prove" => self.prove(self::params(params)?).await,
+            "pool_verify" => self.verify(self::params(params)?).await,
+            _ => Err(Error::new(METHOD_NOT_FOUND, format!("no method {method}"))),
+        }
+    }
+
+    /// Build and check a withdrawal input from `{ address, secret, ... }`, taking the other
+    /// `pool withdraw` flags in [`BUILD_INPUT_FLAGS`].
+    #[instrument(skip_all)]
+    async fn build_input(&self, params: Value) -> Result<Value, Error> {
+        let Value::Object(mut params) = self::params::<Value>(params)? else {
+            return Err(Error::new(INVALID_PARAMS, "expected an object"));
+        };
+        // Values are attached to their flags and positionals follow `--`, so no value can be
+        // taken for another flag.
+        let mut positionals = vec!["--".to_string()];
+        for name in ["address", "secret"] {
+            match params.remove(name) {
+                Some(Value::String(value)) => positionals.push(value),
+                _ => return Err(Error::new(INVALID_PARAMS, format!("missing {name}"))),
+            }
+        }
+        let mut argv = vec![
+            "pool_buildInput".to_string(),
+            format!("--rpc-url={}", self.config.rpc_url),
+        ];
+        for (name, value) in params {
+            if !BUILD_INPUT_FLAGS.contains(&name.as_str()) {
+                return Err(Error::new(INVALID_PARAMS, format!("unknown param {name}")));
+            }
+            let flag = format!(
+                "--{}",
+                name.chars().fold(String::new(), |mut flag, c| {
+                    if c.is_ascii_uppercase() {
+                        flag.push('-');
+                    }
+                    flag.push(c.to_ascii_lowercase());
+                    flag
+                })
+            );
+            match value {
+                Value::Bool(true) => argv.push(flag),
+                Value::Bool(false) | Value::Null => {}
+                Value::String(value) => argv.push(format!("{flag}={value}")),
+                Value::Number(value) => argv.push(format!("{flag}={value}")),
+                _ => return Err(Error::new(INVALID_PARAMS, format!("invalid {name}"))),
+            }
+        }
+        argv.extend(positionals);
+        let args = BuildInputArgs::try_parse_from(argv)
+            .map_err(|e| Error::new(INVALID_PARAMS, e.render().to_string()))?;
+
+        let input = args.witness.build().await?;
+        let public_values = public_values(&check(&input)?)?;
+        Ok(json!({ "input": input, "publicValues": public_values }))
+    }
+
+    #[instrument(skip_all)]
+    async fn execute(&self, params: InputParams) -> Result<Value, Error> {
+        check(&params.input)?;
+        let backend = self.backend.clone();
+        let execution =
+            run_blocking(move || backend.execute(&params.input), self.config.timeout).await?;
+        Ok(json!({
+            "cycles": execution.cycles,
+            "publicValues": public_values(&execution.public_values)?,
+        }))
+    }
+
+    #[instrument(skip_all, fields(mode = ?params.mode))]
+    async fn prove(&self, params: ProveParams) -> Result<Value, Error> {
+        check(&params.input)?;
+        let backend = self.backend.clone();
+        let proof = run_blocking(
+            move || backend.prove(&params.input, params.mode),
+            self.config.timeout,
+        )
+        .await?;
+        Ok(json!({
+            "proof": Bytes::from(proof.encoded),
+            "proofBytes": (!proof.proof.is_empty()).then_some(proof.proof),
+            "publicValues": public_values(&proof.public_values)?,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn verify(&self, params: VerifyParams) -> Result<Value, Error> {
+        let backend = self.backend.clone();
+        let verified = run_blocking(move || backend.verify(&params.proof), self.config.timeout)
+            .await
+            .map_err(|e| Error::new(INVALID_PARAMS, format!("{e:#}")))?;
+        let data = decode_any_public_values(&verified)
+            .map_err(|e| Error::new(INVALID_PARAMS, format!("invalid public values: {e}")))?
+            .withdrawal;
+        Ok(json!({
+            "relayerFee": data.relayerFee,
+            "feeToken": data.feeToken,
+            "feeTokenAmount": data.feeTokenAmount,
+            "recipient": data.recipient,
+            "relayer": data.relayer,
+            "pool": data.contractAddress,
+            "publicValues": public_values(&verified)?,
+        }))
+    }
+}
+
+async fn handle(
+    State(server): State<Arc<JsonRpcServer>>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    if !server.authorized(&headers) {
+        return (StatusCode::UNAUTHORIZED, "missing or invalid bearer token").into_response();
+    }
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            return Json(response(
+                Value::Null,
+                Err(Error::new(PARSE_ERROR, e.to_string())),
+            ))
+            .into_response()
+        }
+    };
+    let request: Request = match serde_json::from_value(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(request) => {
+            let error = Error::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+            return Json(response(request.id, Err(error))).into_response();
+        }
+        Err(e) => {
+            let error = Error::new(INVALID_REQUEST, e.to_string());
+            return Json(response(Value::Null, Err(error))).into_response();
+        }
+    };
+    info!(method = %request.method, "call");
+    let result = server.call(&request.method, request.params).await;
+    if let Err(error) = &result {
+        warn!(method = %request.method, code = error.code, "call failed: {}", error.message);
+    }
+    Json(response(request.id, result)).into_response()
+}
+
+/// JSON-RPC over HTTP POST at `/`.
+pub fn router(server: Arc<JsonRpcServer>) -> Router {
+    Router::new().route("/", post(handle)).with_state(server)
+}
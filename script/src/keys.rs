@@ -0,0 +1,56 @@
+use crate::checkpoint::write_atomic;
+use alloy::primitives::keccak256;
+use clap::Args;
+use eyre::Result;
+use sp1_sdk::{CpuProverComponents, Prover, SP1ProvingKey, SP1VerifyingKey, SP1_CIRCUIT_VERSION};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// Where proving and verifying keys are cached between runs.
+#[derive(Args, Debug, Clone)]
+pub struct KeyCache {
+    /// Directory proving and verifying keys are cached in, by the program's ELF hash and SP1
+    /// version.
+    #[clap(long, env = "POOL_KEY_CACHE", default_value = ".pool/keys")]
+    pub key_cache: PathBuf,
+
+    /// Derive the keys again instead of loading cached ones, replacing the cache.
+    #[clap(long)]
+    pub refresh_keys: bool,
+}
+
+impl KeyCache {
+    /// The proving and verifying keys of `elf`, loaded from the cache or derived with `prover` and
+    /// cached. A cache that cannot be read or written only costs the time of deriving the keys.
+    pub fn setup<P: Prover<CpuProverComponents> + ?Sized>(
+        &self,
+        prover: &P,
+        elf: &[u8],
+    ) -> (SP1ProvingKey, SP1VerifyingKey) {
+        let path = self
+            .key_cache
+            .join(format!("{:x}-{SP1_CIRCUIT_VERSION}.bin", keccak256(elf)));
+        if !self.refresh_keys && path.exists() {
+            match std::fs::read(&path)
+                .map_err(eyre::Report::from)
+                .and_then(|bytes| Ok(bincode::deserialize(&bytes)?))
+            {
+                Ok(keys) => {
+                    info!(path = %path.display(), "loaded cached keys");
+                    return keys;
+                }
+                Err(e) => warn!(path = %path.display(), "ignoring unreadable cached keys: {e}"),
+            }
+        }
+        let keys = prover.setup(elf);
+        let saved: Result<()> = (|| {
+            std::fs::create_dir_all(&self.key_cache)?;
+            write_atomic(&path, &bincode::serialize(&keys)?)
+        })();
+        match saved {
+            Ok(()) => info!(path = %path.display(), "cached keys"),
+            Err(e) => warn!(path = %path.display(), "failed to cache keys: {e}"),
+        }
+        keys
+    }
+}
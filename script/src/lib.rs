@@ -0,0 +1,49 @@
+pub mod aggregate;
+pub mod allow_list;
+pub mod api;
+pub mod asp;
+pub mod backend;
+pub mod beacon;
+pub mod bundle;
+pub mod bytecode;
+pub mod cassette;
+pub mod chat;
+pub mod checkpoint;
+pub mod db;
+pub mod deposit;
+pub mod disclosure;
+pub mod ens;
+pub mod estimate;
+pub mod gas_report;
+pub mod helios;
+pub mod index;
+pub mod indexer;
+pub mod jsonrpc;
+pub mod keys;
+pub mod linkage;
+pub mod logging;
+pub mod lookup;
+pub mod marketplace;
+pub mod notes;
+pub mod pool;
+pub mod price;
+pub mod prove;
+pub mod prover_service;
+pub mod recover;
+pub mod registry;
+pub mod rehearsal;
+pub mod relayer;
+pub mod safe;
+pub mod schema;
+pub mod secure;
+pub mod simulate;
+pub mod snapshot;
+pub mod stats;
+pub mod status;
+pub mod submit;
+pub mod token_fee;
+pub mod userop;
+pub mod verify_build;
+pub mod watch;
+pub mod webhook;
+pub mod witness;
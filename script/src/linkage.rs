@@ -0,0 +1,139 @@
+use crate::{index::supports_ots, notes::NoteRecord};
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
+};
+use eyre::{OptionExt, Result};
+use pool_lib::WithdrawalData;
+use serde::Deserialize;
+use std::fmt;
+use tracing::{debug, instrument};
+
+/// Withdrawals sooner than this after the deposit are easy to pair up by timing.
+const MIN_DEPOSIT_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Number of the depositor's recent transactions searched for funding from the recipient.
+const FUNDING_SEARCH_PAGE_SIZE: u64 = 100;
+
+/// A way the withdrawal can be tied back to the deposit, and what to do about it.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub issue: String,
+    pub mitigation: &'static str,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.issue, self.mitigation)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OtsTransactions {
+    txs: Vec<OtsTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OtsTransaction {
+    from: Address,
+    to: Option<Address>,
+}
+
+/// Look for obvious links between the deposit in `deposit` and a withdrawal of `data` sent by
+/// `sender`. Checks that need the deposit transaction are skipped when it is not known.
+#[instrument(skip_all, fields(%sender))]
+pub async fn check_linkage<P: Provider>(
+    provider: &P,
+    data: &WithdrawalData,
+    sender: Address,
+    deposit: Option<&NoteRecord>,
+) -> Result<Vec<Warning>> {
+    let mut warnings = Vec::new();
+    let self_submitted = data.relayer == Address::ZERO || data.relayerFee == U256::ZERO;
+
+    if self_submitted && sender == data.recipient {
+        warnings.push(Warning {
+            issue: "the recipient pays the gas for its own withdrawal, so it must already have \
+                    been funded"
+                .to_string(),
+            mitigation: "withdraw through a relayer to a fresh address",
+        });
+    }
+
+    let depositor = match deposit.and_then(|record| record.tx_hash) {
+        Some(tx_hash) => provider
+            .get_transaction_by_hash(tx_hash)
+            .await?
+            .map(|tx| tx.from),
+        None => None,
+    };
+    let Some(depositor) = depositor else {
+        debug!("deposit transaction unknown, skipping depositor checks");
+        return Ok(warnings);
+    };
+
+    if data.recipient == depositor {
+        warnings.push(Warning {
+            issue: format!("the recipient {} made the deposit", data.recipient),
+            mitigation: "withdraw to an address with no history linked to the depositor",
+        });
+    }
+    if self_submitted && sender == depositor {
+        warnings.push(Warning {
+            issue: format!(
+                "the withdrawal is sent without a relayer from the deposit wallet {depositor}"
+            ),
+            mitigation: "submit from an unrelated wallet or through a relayer",
+        });
+    }
+
+    if supports_ots(provider).await {
+        let history: OtsTransactions = provider
+            .raw_request(
+                "ots_searchTransactionsBefore".into(),
+                (depositor, 0u64, FUNDING_SEARCH_PAGE_SIZE),
+            )
+            .await?;
+        if history
+            .txs
+            .iter()
+            .any(|tx| tx.from == data.recipient && tx.to == Some(depositor))
+        {
+            warnings.push(Warning {
+                issue: format!(
+                    "the recipient {} previously sent funds to the depositor {depositor}",
+                    data.recipient
+                ),
+                mitigation: "withdraw to an address that never interacted with the depositor",
+            });
+        }
+    } else {
+        debug!("endpoint has no ots_ extensions, skipping funding check");
+    }
+
+    if let Some(block_number) = deposit.and_then(|record| record.block_number) {
+        let deposited = provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_eyre("deposit block not found")?
+            .header
+            .timestamp;
+        let latest = provider
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_eyre("latest block not found")?
+            .header
+            .timestamp;
+        let age = latest.saturating_sub(deposited);
+        if age < MIN_DEPOSIT_AGE_SECS {
+            warnings.push(Warning {
+                issue: format!("the deposit was made only {} minutes ago", age / 60),
+                mitigation: "wait at least a day, ideally a random interval, before withdrawing",
+            });
+        }
+    }
+
+    Ok(warnings)
+}
@@ -0,0 +1,88 @@
+use clap::{ArgAction, Args, ValueEnum};
+use pool_lib::redact;
+use tracing::{warn, Metadata};
+use tracing_subscriber::{
+    filter::{filter_fn, EnvFilter},
+    fmt,
+    prelude::*,
+};
+
+/// Field names whose values are never logged, unless `--unsafe-log-secrets` is set.
+pub const SENSITIVE_FIELDS: &[&str] = &["secret", "nullifier", "note", "private_key", "view_key"];
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+/// Logging options shared by all commands.
+#[derive(Args, Debug, Clone)]
+pub struct LogArgs {
+    #[clap(long, global = true, value_enum, default_value_t = LogFormat::Pretty)]
+    pub log_format: LogFormat,
+
+    /// Increase log verbosity. Repeat for more.
+    #[clap(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Log secrets and nullifiers, and show them in debug output, instead of redacting them.
+    /// Only in debug builds, for local development.
+    #[cfg(debug_assertions)]
+    #[clap(long, global = true)]
+    pub unsafe_log_secrets: bool,
+}
+
+/// Whether a span or event may be emitted. Those that carry a sensitive field are dropped, so
+/// secrets only ever reach the log when `--unsafe-log-secrets` was explicitly requested.
+fn allow(metadata: &Metadata<'_>) -> bool {
+    if redact::secrets_revealed() {
+        return true;
+    }
+    !metadata
+        .fields()
+        .iter()
+        .any(|field| SENSITIVE_FIELDS.contains(&field.name()))
+}
+
+impl LogArgs {
+    /// Directives used when `RUST_LOG` is not set.
+    fn default_directives(&self) -> &'static str {
+        match self.verbose {
+            0 => "warn,pool=info,pool_script=info",
+            1 => "info,pool=debug,pool_script=debug",
+            _ => "debug,pool=trace,pool_script=trace",
+        }
+    }
+
+    /// Install the global subscriber. Logs go to stderr, leaving stdout for command output.
+    pub fn init(&self) {
+        #[cfg(debug_assertions)]
+        if self.unsafe_log_secrets {
+            redact::reveal_secrets();
+        }
+        let filter = EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(self.default_directives()));
+        let registry = tracing_subscriber::registry().with(filter);
+        match self.log_format {
+            LogFormat::Pretty => registry
+                .with(
+                    fmt::layer()
+                        .with_writer(std::io::stderr)
+                        .with_filter(filter_fn(allow)),
+                )
+                .init(),
+            LogFormat::Json => registry
+                .with(
+                    fmt::layer()
+                        .json()
+                        .with_writer(std::io::stderr)
+                        .with_filter(filter_fn(allow)),
+                )
+                .init(),
+        }
+        if redact::secrets_revealed() {
+            warn!("--unsafe-log-secrets is set, secrets will be logged");
+        }
+    }
+}
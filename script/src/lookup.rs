@@ -0,0 +1,134 @@
+use alloy::primitives::{Address, B256};
+use eyre::{ensure, Result};
+use memmap2::Mmap;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+const MAGIC: &[u8; 8] = b"poolidx1";
+/// Magic and the block number the lookup was built at.
+const HEADER_LEN: usize = 16;
+/// A commitment and its big-endian leaf index.
+const RECORD_LEN: usize = 40;
+
+/// A memory-mapped commitment → leaf index map, written next to the deposit index so `withdraw`
+/// can skip searching the pool's deposits. The file is the header followed by the records sorted
+/// by commitment, so a lookup is a binary search touching a few pages however large the pool.
+pub struct DepositLookup {
+    map: Mmap,
+}
+
+impl DepositLookup {
+    /// Path of the lookup for `pool` on `chain_id` inside `data_dir`.
+    pub fn path(data_dir: &Path, chain_id: u64, pool: Address) -> PathBuf {
+        data_dir
+            .join("index")
+            .join(format!("{chain_id}-{pool:#x}.lookup"))
+    }
+
+    /// Lookups for `pool` on any chain inside `data_dir`.
+    pub fn paths(data_dir: &Path, pool: Address) -> Result<Vec<PathBuf>> {
+        let dir = data_dir.join("index");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let suffix = format!("-{pool:#x}.lookup");
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(&suffix))
+            {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Write the lookup of `deposits`, in leaf order as of `block_number`, to `path`. Commitments
+    /// deposited more than once map to their first leaf.
+    pub fn write(path: &Path, deposits: &[B256], block_number: u64) -> Result<()> {
+        let mut records: Vec<(B256, u64)> = deposits
+            .iter()
+            .enumerate()
+            .map(|(i, commitment)| (*commitment, i as u64))
+            .collect();
+        records.sort_unstable();
+        records.dedup_by_key(|(commitment, _)| *commitment);
+
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&block_number.to_be_bytes())?;
+        for (commitment, index) in records {
+            out.write_all(commitment.as_slice())?;
+            out.write_all(&index.to_be_bytes())?;
+        }
+        out.into_inner()?.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Map the lookup at `path`, if one has been written.
+    pub fn open(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = File::open(path)?;
+        // SAFETY: lookups are only ever replaced by renaming a new file over them, never written
+        // in place, so the mapped file does not change under us.
+        let map = unsafe { Mmap::map(&file)? };
+        ensure!(
+            map.len() >= HEADER_LEN && &map[..8] == MAGIC,
+            "{} is not a deposit lookup",
+            path.display()
+        );
+        ensure!(
+            (map.len() - HEADER_LEN) % RECORD_LEN == 0,
+            "{} is truncated",
+            path.display()
+        );
+        Ok(Some(Self { map }))
+    }
+
+    /// Block the lookup was built at. Deposits made after it are not in the lookup.
+    pub fn block_number(&self) -> u64 {
+        u64::from_be_bytes(self.map[8..HEADER_LEN].try_into().unwrap())
+    }
+
+    pub fn len(&self) -> usize {
+        (self.map.len() - HEADER_LEN) / RECORD_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn record(&self, i: usize) -> &[u8] {
+        let start = HEADER_LEN + i * RECORD_LEN;
+        &self.map[start..start + RECORD_LEN]
+    }
+
+    /// Leaf index of `commitment`.
+    pub fn find(&self, commitment: &B256) -> Option<u64> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let record = self.record(mid);
+            match record[..32].cmp(commitment.as_slice()) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    return Some(u64::from_be_bytes(record[32..].try_into().unwrap()))
+                }
+            }
+        }
+        None
+    }
+}
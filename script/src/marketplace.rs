@@ -0,0 +1,198 @@
+//! Quotes from a list of relayers, compared so a withdrawal goes through the cheapest one that
+//! will take it. Each quote is checked to be signed by the relayer it names and for the pool
+//! asked about, so a listed endpoint cannot quote on behalf of another relayer. Quotes in an
+//! ERC-20 token are valued at an on-chain price feed, not at the relayer's word.
+
+use crate::{
+    estimate::{GROTH16_VERIFY_GAS, WITHDRAW_OVERHEAD_GAS},
+    price::{PricedToken, TokenPrice, DEFAULT_MAX_PRICE_AGE},
+};
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+};
+use clap::Args;
+use eyre::{ensure, Result};
+use futures::future::join_all;
+use pool_relayer_client::{Quote, RelayerClient};
+use reqwest::Url;
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, instrument};
+
+/// Gas a relayed withdrawal is expected to use, verifying a groth16 proof.
+pub const RELAYED_WITHDRAW_GAS: u64 = GROTH16_VERIFY_GAS + WITHDRAW_OVERHEAD_GAS;
+
+/// The relayers to compare and what a quote must offer to be taken.
+#[derive(Args, Debug, Clone)]
+pub struct RelayerListArgs {
+    /// Relayer endpoints to ask for quotes.
+    #[clap(long, env = "POOL_RELAYERS", value_delimiter = ',')]
+    pub relayers: Vec<Url>,
+
+    /// Highest fee to pay a relayer, in wei, or worth in wei for a fee in `--fee-token`.
+    #[clap(long)]
+    pub max_relayer_fee: Option<U256>,
+
+    /// Pay the relayer in this ERC-20 token instead of ETH, as `<token>=<price feed>` with a
+    /// Chainlink-style `<token>/ETH` feed quotes are valued at.
+    #[clap(long, env = "POOL_FEE_TOKEN")]
+    pub fee_token: Option<PricedToken>,
+
+    /// Seconds after which the price feed is too stale to value quotes with.
+    #[clap(long, default_value_t = DEFAULT_MAX_PRICE_AGE)]
+    pub max_price_age: u64,
+
+    /// Seconds a quote must stay valid for, to prove the withdrawal before it expires.
+    #[clap(long, default_value_t = 900)]
+    pub min_quote_ttl: u64,
+
+    /// Seconds to wait for each relayer.
+    #[clap(long, default_value_t = 10)]
+    pub quote_timeout: u64,
+}
+
+/// What one relayer offered.
+#[derive(Debug, Clone, Serialize)]
+pub struct Offer {
+    pub url: String,
+    pub quote: Option<Quote>,
+    /// What the quoted fee is worth in wei, at the price feed's rate for a fee in a token.
+    pub value: Option<U256>,
+    /// Whether the relayer's `GET /readyz` says it can take withdrawals.
+    pub ready: bool,
+    /// What the relayer is expected to spend on gas relaying the withdrawal, in wei.
+    pub gas_cost: U256,
+    /// Why the relayer should not relay the withdrawal, if it should not.
+    pub problem: Option<String>,
+}
+
+impl Offer {
+    /// Whether the quote is usable and meets the requirements.
+    pub fn is_compliant(&self) -> bool {
+        self.quote.is_some() && self.problem.is_none()
+    }
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+impl RelayerListArgs {
+    /// The token to quote fees in, if not in ETH.
+    pub fn fee_token_address(&self) -> Option<Address> {
+        self.fee_token.map(|priced| priced.token)
+    }
+
+    /// The current price of `--fee-token`, if there is one.
+    pub async fn fee_token_price<P: Provider>(&self, provider: &P) -> Result<Option<TokenPrice>> {
+        match &self.fee_token {
+            Some(priced) => Ok(Some(priced.price(provider, self.max_price_age).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// What `quote` is worth in wei, failing if that is above `--max-relayer-fee`.
+    pub async fn check_quote<P: Provider>(&self, provider: &P, quote: &Quote) -> Result<U256> {
+        let value = quote_value(quote, self.fee_token_price(provider).await?.as_ref());
+        if let Some(max) = self.max_relayer_fee {
+            ensure!(
+                value <= max,
+                "relayer fee worth {value} wei is above the maximum {max}"
+            );
+        }
+        Ok(value)
+    }
+
+    /// Ask every listed relayer in parallel for a quote for withdrawing from `pool`, and check
+    /// each against the requirements. Offers are sorted compliant first, then by fee.
+    #[instrument(skip(self, provider))]
+    pub async fn offers<P: Provider>(
+        &self,
+        provider: &P,
+        chain_id: u64,
+        pool: Address,
+    ) -> Result<Vec<Offer>> {
+        ensure!(
+            !self.relayers.is_empty(),
+            "no relayers given with --relayers"
+        );
+        let fees = provider.estimate_eip1559_fees(None).await?;
+        let gas_cost = U256::from(RELAYED_WITHDRAW_GAS) * U256::from(fees.max_fee_per_gas);
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.quote_timeout))
+            .build()?;
+        let now = now()?;
+        let price = self.fee_token_price(provider).await?;
+        let fee_token = self.fee_token_address();
+
+        let mut offers = join_all(self.relayers.iter().map(|url| {
+            let client = RelayerClient::with_client(url.clone(), http.clone());
+            async move {
+                let (quote, ready) =
+                    tokio::join!(client.quote(chain_id, pool, fee_token), client.ready());
+                let ready = ready.unwrap_or(false);
+                let (quote, value, problem) = match quote {
+                    Ok(quote) => {
+                        let value = quote_value(&quote, price.as_ref());
+                        let problem = self.problem(&quote, value, ready, gas_cost, now);
+                        (Some(quote), Some(value), problem)
+                    }
+                    Err(e) => (None, None, Some(e.to_string())),
+                };
+                debug!(%url, ?problem, "got relayer offer");
+                Offer {
+                    url: url.to_string(),
+                    quote,
+                    value,
+                    ready,
+                    gas_cost,
+                    problem,
+                }
+            }
+        }))
+        .await;
+        offers.sort_by_key(|offer| (!offer.is_compliant(), offer.value));
+        Ok(offers)
+    }
+
+    fn problem(
+        &self,
+        quote: &Quote,
+        value: U256,
+        ready: bool,
+        gas_cost: U256,
+        now: u64,
+    ) -> Option<String> {
+        if !ready {
+            return Some("relayer is not ready".to_string());
+        }
+        if quote.expiry < now + self.min_quote_ttl {
+            return Some(format!(
+                "quote expires in {}s",
+                quote.expiry.saturating_sub(now)
+            ));
+        }
+        if let Some(max) = self.max_relayer_fee.filter(|max| value > *max) {
+            return Some(format!("fee worth {value} wei is above the maximum {max}"));
+        }
+        // A relayer quoting below its gas cost is likely to reject the withdrawal or leave it
+        // underpriced in the mempool.
+        if value < gas_cost {
+            return Some(format!(
+                "fee worth {value} wei does not cover the expected gas cost {gas_cost}"
+            ));
+        }
+        None
+    }
+}
+
+/// What the fee `quote` offers is worth in wei, at `price` for a quote in the fee token.
+fn quote_value(quote: &Quote, price: Option<&TokenPrice>) -> U256 {
+    price.map_or(quote.fee, |price| price.to_wei(quote.fee))
+}
+
+/// The cheapest compliant offer of those [`RelayerListArgs::offers`] returns.
+pub fn cheapest(offers: &[Offer]) -> Option<&Offer> {
+    offers.first().filter(|offer| offer.is_compliant())
+}
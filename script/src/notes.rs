@@ -0,0 +1,172 @@
+use crate::secure::scrub;
+use alloy::primitives::{Address, B256};
+use eyre::Result;
+use pool_lib::{compute_commitment, note::Note, watch::WatchKey};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+#[cfg(unix)]
+use std::{
+    fs::Permissions,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+};
+
+/// A note together with what is known about its deposit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteRecord {
+    pub note: Note,
+    pub commitment: B256,
+    pub leaf_index: Option<u64>,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<B256>,
+    /// Salt of a commit-reveal deposit that may not have been revealed yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<B256>,
+    /// Index of the note among those derived from a mnemonic for its pool, see
+    /// [`crate::recover`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_index: Option<u64>,
+}
+
+impl NoteRecord {
+    pub fn new(note: Note) -> Self {
+        let (commitment, _) = compute_commitment(&note.secret);
+        Self {
+            note,
+            commitment,
+            leaf_index: None,
+            block_number: None,
+            tx_hash: None,
+            salt: None,
+            derivation_index: None,
+        }
+    }
+}
+
+/// A note followed by its watch key alone, such as one held on a hardware wallet or by a
+/// counterparty. Its deposit and withdrawal can be seen but, without the secret, never proven.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedNote {
+    pub key: WatchKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub leaf_index: Option<u64>,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<B256>,
+    /// Transaction that withdrew the note, once its nullifier has been seen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spent_tx_hash: Option<B256>,
+}
+
+impl WatchedNote {
+    pub fn new(key: WatchKey, label: Option<String>) -> Self {
+        Self {
+            key,
+            label,
+            leaf_index: None,
+            block_number: None,
+            tx_hash: None,
+            spent_tx_hash: None,
+        }
+    }
+}
+
+/// Notes kept in the data directory as plain JSON. The file holds secrets and is not encrypted, so
+/// it is only readable by its owner and should be protected like a key file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoteStore {
+    pub notes: Vec<NoteRecord>,
+    /// Watch-only notes, kept apart from `notes` so nothing that spends a note can pick one up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watched: Vec<WatchedNote>,
+}
+
+impl NoteStore {
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("notes.json")
+    }
+
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = Self::path(data_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let mut bytes = std::fs::read(path)?;
+        let store = serde_json::from_slice(&bytes);
+        scrub(&mut bytes);
+        Ok(store?)
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(data_dir)?;
+        let mut bytes = serde_json::to_vec_pretty(self)?;
+        let written = write_private(&Self::path(data_dir), &bytes);
+        scrub(&mut bytes);
+        Ok(written?)
+    }
+
+    /// Add `record`, replacing any existing record for the same commitment.
+    pub fn insert(&mut self, record: NoteRecord) {
+        self.notes
+            .retain(|existing| existing.commitment != record.commitment);
+        self.notes.push(record);
+    }
+
+    /// Index of the next note to derive from a mnemonic for `pool` on `chain_id`.
+    pub fn next_derivation_index(&self, chain_id: u64, pool: Address) -> u64 {
+        self.notes
+            .iter()
+            .filter(|record| record.note.chain_id == chain_id && record.note.pool == pool)
+            .filter_map(|record| record.derivation_index)
+            .max()
+            .map_or(0, |index| index + 1)
+    }
+
+    pub fn find(&self, commitment: &B256) -> Option<&NoteRecord> {
+        self.notes
+            .iter()
+            .find(|record| record.commitment == *commitment)
+    }
+
+    /// Add `watched`, keeping what is already known about a note watched under the same
+    /// commitment and only replacing its label if `watched` has one.
+    pub fn watch(&mut self, watched: WatchedNote) {
+        match self
+            .watched
+            .iter_mut()
+            .find(|existing| existing.key.commitment == watched.key.commitment)
+        {
+            Some(existing) => {
+                existing.key = watched.key;
+                if watched.label.is_some() {
+                    existing.label = watched.label;
+                }
+            }
+            None => self.watched.push(watched),
+        }
+    }
+
+    /// Stop watching the note with `commitment`, returning whether it was watched.
+    pub fn unwatch(&mut self, commitment: &B256) -> bool {
+        let count = self.watched.len();
+        self.watched
+            .retain(|watched| watched.key.commitment != *commitment);
+        self.watched.len() != count
+    }
+}
+
+/// Write `contents` to `path`, creating it readable and writable by its owner alone.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, so tighten one written before.
+    #[cfg(unix)]
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
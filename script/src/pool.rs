@@ -0,0 +1,116 @@
+//! Bindings of every pool contract in `contracts/src`, written out by hand rather than generated
+//! from an ABI: the commands call these functions by name, so a deployment with other methods
+//! needs code using them, not only bindings, and the comments here say which contract has each.
+
+use alloy::sol;
+
+sol! {
+    #[sol(rpc)]
+    contract Pool {
+        function verifier() external view returns (address);
+
+        function programVkey() external view returns (bytes32);
+
+        function amount() external view returns (uint256);
+
+        bytes32[] public deposits;
+
+        mapping(bytes32 => bool) public nullifiers;
+
+        event Deposit(bytes32 indexed commitment, uint256 leafIndex);
+
+        event Withdrawal(
+            bytes32 indexed nullifier,
+            bytes32 exclusionSetRoot,
+            address recipient,
+            address relayer,
+            uint256 relayerFee
+        );
+
+        function deposit(bytes32 _commitment) public payable;
+
+        // Only in pools taking batched deposits, which append the commitments as contiguous
+        // leaves from `firstLeafIndex`.
+        event DepositMany(bytes32[] commitments, uint256 firstLeafIndex);
+
+        function depositMany(bytes32[] calldata _commitments) public payable;
+
+        // Only in pools taking deposits by commit-reveal, see `pool_lib::commit_reveal`.
+        mapping(bytes32 => address) public pendingDeposits;
+
+        event DepositCommitted(bytes32 indexed depositHash, address depositor);
+
+        function commitDeposit(bytes32 _depositHash) public payable;
+
+        function revealDeposit(bytes32 _commitment, bytes32 _salt) public;
+
+        function withdraw(bytes calldata _publicValues, bytes calldata _proofBytes) public;
+
+        // The recipient's consent to a token fee, see `pool_lib::token_fee`. The permit is
+        // skipped if its deadline is zero.
+        struct TokenFeePermit {
+            bytes authorization;
+            uint256 deadline;
+            uint8 v;
+            bytes32 r;
+            bytes32 s;
+        }
+
+        // Withdraw a `WithdrawalData` paying its relayer in a token, taking the fee from the
+        // recipient with their consent.
+        function withdrawWithTokenFee(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes,
+            TokenFeePermit calldata _fee
+        ) public;
+
+        // Only in `FeePool`: withdraw a `FeeWithdrawalData`, paying the protocol fee it commits
+        // to.
+        function withdrawWithProtocolFee(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes
+        ) public;
+
+        // Only in `RegistryPool`: withdraw a `RegistryWithdrawalData` proving an entry of the
+        // pool's registry.
+        function withdrawRegistered(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes
+        ) public;
+
+        // Only in `BridgePool`: withdraw a `BridgeWithdrawalData` to its L2 recipient. The pool
+        // checks the message hash and deposits through the bridge the withdrawal pays, one it
+        // trusts for the L2 chain, in the same call.
+        function withdrawToL2(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes,
+            uint32 _minGasLimit,
+            bytes calldata _extraData
+        ) public;
+
+        // Withdraw a `SplitWithdrawalData` to its recipients, whose hash the pool checks.
+        function withdrawSplit(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes,
+            address[] calldata _recipients,
+            uint256[] calldata _amounts
+        ) public;
+
+        // Only in `OraclePool`: withdraw an `OracleWithdrawalData` anchored through one of the
+        // pool's trusted block hash oracles.
+        function withdrawWithOracle(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes
+        ) public;
+
+        // Only in `AssociationPool`: withdraw an `AssociationWithdrawalData` whose association
+        // root one of the pool's trusted providers signed.
+        function withdrawAssociated(
+            bytes calldata _publicValues,
+            bytes calldata _proofBytes
+        ) public;
+    }
+}
+
+/// Storage slot of the pool's `deposits` array.
+pub const DEPOSITS_SLOT: u64 = 0;
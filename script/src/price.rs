@@ -0,0 +1,203 @@
+//! Prices of ERC-20 fee tokens in ETH, read from Chainlink-style `<token>/ETH` price feeds on
+//! chain. Relayers price their quotes with them and clients check quotes against them, so
+//! neither side takes the other's word for what a token is worth.
+
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    sol,
+};
+use eyre::{ensure, eyre, Result};
+use futures::try_join;
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::debug;
+
+sol! {
+    #[sol(rpc)]
+    interface AggregatorV3 {
+        function decimals() external view returns (uint8);
+        function latestRoundData()
+            external
+            view
+            returns (
+                uint80 roundId,
+                int256 answer,
+                uint256 startedAt,
+                uint256 updatedAt,
+                uint80 answeredInRound
+            );
+    }
+
+    #[sol(rpc)]
+    interface Erc20 {
+        function decimals() external view returns (uint8);
+    }
+}
+
+const WEI_PER_ETH: u64 = 1_000_000_000_000_000_000;
+
+/// Price feeds older than this are not trusted, an hour past the daily heartbeat of most
+/// `<token>/ETH` feeds.
+pub const DEFAULT_MAX_PRICE_AGE: u64 = 25 * 60 * 60;
+
+/// A fee token and the feed pricing it in ETH, parsed from `<token>=<feed>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PricedToken {
+    pub token: Address,
+    pub feed: Address,
+}
+
+impl FromStr for PricedToken {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (token, feed) = s
+            .split_once('=')
+            .ok_or_else(|| eyre!("expected <token>=<price feed>"))?;
+        Ok(Self {
+            token: token.parse()?,
+            feed: feed.parse()?,
+        })
+    }
+}
+
+/// What a token is worth in ETH, as of a feed's latest round.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenPrice {
+    pub token: Address,
+    /// ETH per whole token, scaled by `10^feed_decimals`.
+    pub answer: U256,
+    pub feed_decimals: u8,
+    pub token_decimals: u8,
+    /// Unix timestamp the feed was last updated at.
+    pub updated_at: u64,
+}
+
+impl PricedToken {
+    /// Read the latest price, failing if the feed has no positive answer or is older than
+    /// `max_age` seconds.
+    pub async fn price<P: Provider>(&self, provider: &P, max_age: u64) -> Result<TokenPrice> {
+        let feed = AggregatorV3::new(self.feed, provider);
+        let token = Erc20::new(self.token, provider);
+        let (decimals, round, token_decimals) = try_join!(
+            async { Ok::<_, eyre::Report>(feed.decimals().call().await?._0) },
+            async { Ok::<_, eyre::Report>(feed.latestRoundData().call().await?) },
+            async { Ok::<_, eyre::Report>(token.decimals().call().await?._0) },
+        )?;
+        ensure!(
+            round.answer.is_positive(),
+            "price feed {} has no price for {}",
+            self.feed,
+            self.token
+        );
+        let updated_at = round.updatedAt.saturating_to::<u64>();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        ensure!(
+            now.saturating_sub(updated_at) <= max_age,
+            "price feed {} was last updated {}s ago",
+            self.feed,
+            now.saturating_sub(updated_at)
+        );
+        let price = TokenPrice {
+            token: self.token,
+            answer: round.answer.into_raw(),
+            feed_decimals: decimals,
+            token_decimals,
+            updated_at,
+        };
+        debug!(token = %self.token, answer = %price.answer, "read token price");
+        Ok(price)
+    }
+}
+
+impl TokenPrice {
+    /// `10^feed_decimals * 10^token_decimals`, what `answer` is divided by to give the wei per
+    /// smallest token unit once scaled by `10^18`.
+    fn scale(&self) -> U256 {
+        U256::from(10).pow(U256::from(
+            self.feed_decimals as u64 + self.token_decimals as u64,
+        ))
+    }
+
+    /// Value of `amount` of the token in wei, rounding down.
+    pub fn to_wei(&self, amount: U256) -> U256 {
+        amount
+            .saturating_mul(self.answer)
+            .saturating_mul(U256::from(WEI_PER_ETH))
+            / self.scale()
+    }
+
+    /// Amount of the token worth at least `wei`.
+    pub fn from_wei(&self, wei: U256) -> U256 {
+        let denominator = self.answer.saturating_mul(U256::from(WEI_PER_ETH));
+        wei.saturating_mul(self.scale()).div_ceil(denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// USDC at 0.0004 ETH, through an 18-decimal feed.
+    fn usdc() -> TokenPrice {
+        TokenPrice {
+            token: Address::ZERO,
+            answer: U256::from(400_000_000_000_000u64),
+            feed_decimals: 18,
+            token_decimals: 6,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn to_wei_scales_by_both_decimals() {
+        let price = usdc();
+        assert_eq!(
+            price.to_wei(U256::from(1_000_000)),
+            U256::from(400_000_000_000_000u64)
+        );
+        assert_eq!(price.to_wei(U256::ZERO), U256::ZERO);
+    }
+
+    #[test]
+    fn from_wei_inverts_to_wei() {
+        let price = usdc();
+        let wei = U256::from(400_000_000_000_000u64);
+        assert_eq!(price.from_wei(wei), U256::from(1_000_000));
+        assert_eq!(price.to_wei(price.from_wei(wei)), wei);
+    }
+
+    #[test]
+    fn from_wei_rounds_up_and_to_wei_rounds_down() {
+        let price = usdc();
+        let wei = U256::from(400_000_000_000_001u64);
+        assert_eq!(price.from_wei(wei), U256::from(1_000_001));
+        assert!(price.to_wei(price.from_wei(wei)) >= wei);
+
+        // A token worth 10^-8 ETH: one of its 10^-18 units is worth less than a wei.
+        let cheap = TokenPrice {
+            answer: U256::from(1),
+            feed_decimals: 8,
+            token_decimals: 18,
+            ..usdc()
+        };
+        assert_eq!(cheap.to_wei(U256::from(1)), U256::ZERO);
+        assert_eq!(cheap.from_wei(U256::from(1)), U256::from(100_000_000));
+    }
+
+    #[test]
+    fn parses_priced_tokens() {
+        let priced: PricedToken = "0x0000000000000000000000000000000000000001=\
+                                   0x0000000000000000000000000000000000000002"
+            .parse()
+            .unwrap();
+        assert_eq!(priced.token, Address::with_last_byte(1));
+        assert_eq!(priced.feed, Address::with_last_byte(2));
+        assert!("0x0000000000000000000000000000000000000001"
+            .parse::<PricedToken>()
+            .is_err());
+    }
+}
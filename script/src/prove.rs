@@ -0,0 +1,485 @@
+use crate::{checkpoint::Checkpoint, keys::KeyCache, secure::scrub_secret};
+use alloy::primitives::B256;
+use clap::{Args, ValueEnum};
+use eyre::{bail, eyre, Result};
+use pool_lib::{
+    compact::{prune_proof, CompactInput},
+    WithdrawalInput,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{
+    include_elf, network::prover::NetworkProver, CpuProverComponents, Prover, ProverClient,
+    SP1ProofMode, SP1ProofWithPublicValues, SP1Stdin,
+};
+use std::{future::Future, path::Path, process::Command, time::Duration};
+use tracing::{debug, info, instrument, warn};
+
+/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
+pub const ELF: &[u8] = include_elf!("pool-program");
+
+/// Proof type to generate. Only groth16 and plonk proofs can be verified onchain.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofMode {
+    Compressed,
+    Groth16,
+    Plonk,
+}
+
+impl From<ProofMode> for SP1ProofMode {
+    fn from(mode: ProofMode) -> Self {
+        match mode {
+            ProofMode::Compressed => SP1ProofMode::Compressed,
+            ProofMode::Groth16 => SP1ProofMode::Groth16,
+            ProofMode::Plonk => SP1ProofMode::Plonk,
+        }
+    }
+}
+
+/// Proving backends selectable with `--prover`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverBackend {
+    /// Local proving on the CPU.
+    Cpu,
+    /// Local proving on an NVIDIA GPU. Requires building with the `cuda` feature.
+    Cuda,
+    /// Remote proving on the Succinct prover network.
+    Network,
+    /// Mock proofs that are not verifiable. For testing only.
+    Mock,
+}
+
+/// Prover selection and backend options.
+#[derive(Args, Debug, Clone)]
+pub struct ProverArgs {
+    #[clap(long, value_enum, default_value_t = ProverBackend::Cpu)]
+    pub prover: ProverBackend,
+
+    /// Whitelisted prover network key. Required with `--prover network`.
+    #[clap(long, env = "NETWORK_PRIVATE_KEY", required_if_eq("prover", "network"))]
+    pub network_private_key: Option<String>,
+
+    /// Prover network RPC endpoint.
+    #[clap(long, env = "NETWORK_RPC_URL")]
+    pub network_rpc_url: Option<String>,
+
+    #[clap(flatten)]
+    pub tuning: ProverTuning,
+
+    #[clap(flatten)]
+    pub keys: KeyCache,
+}
+
+/// Settings of local CPU proving. Unset ones are picked from the machine's memory, so small
+/// machines prove in smaller pieces instead of running out of memory.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ProverTuning {
+    /// Rows per shard, a power of two. Smaller shards use less memory and prove slower.
+    #[clap(long, env = "SHARD_SIZE")]
+    pub shard_size: Option<u32>,
+
+    /// Shards proved at once. Memory use grows with it.
+    #[clap(long, env = "SHARD_BATCH_SIZE")]
+    pub shard_batch_size: Option<usize>,
+
+    /// Threads proving uses. Defaults to one per core.
+    #[clap(long, env = "RAYON_NUM_THREADS")]
+    pub prover_threads: Option<usize>,
+
+    /// Memory, in GB, to size the defaults of the other settings for instead of the machine's.
+    #[clap(long, env = "POOL_PROVER_MEMORY")]
+    pub prover_memory: Option<u64>,
+}
+
+impl ProverTuning {
+    /// Shard size and batch size suited to `memory_gb` of memory.
+    fn defaults(memory_gb: u64) -> (u32, usize) {
+        match memory_gb {
+            0..=16 => (1 << 19, 1),
+            17..=32 => (1 << 20, 2),
+            33..=64 => (1 << 21, 4),
+            65..=128 => (1 << 22, 8),
+            _ => (1 << 22, 16),
+        }
+    }
+
+    /// Pass the settings to SP1, which reads them from the environment when it starts proving.
+    pub fn apply(&self) -> Result<()> {
+        let memory = self.prover_memory.or_else(total_memory_gb);
+        let (shard_size, shard_batch_size) = match memory {
+            Some(memory) => {
+                let (shard_size, shard_batch_size) = Self::defaults(memory);
+                (
+                    Some(self.shard_size.unwrap_or(shard_size)),
+                    Some(self.shard_batch_size.unwrap_or(shard_batch_size)),
+                )
+            }
+            None => (self.shard_size, self.shard_batch_size),
+        };
+        if let Some(shard_size) = shard_size {
+            if !shard_size.is_power_of_two() {
+                bail!("--shard-size must be a power of two, got {shard_size}");
+            }
+            std::env::set_var("SHARD_SIZE", shard_size.to_string());
+        }
+        if let Some(shard_batch_size) = shard_batch_size {
+            std::env::set_var("SHARD_BATCH_SIZE", shard_batch_size.to_string());
+        }
+        if let Some(threads) = self.prover_threads {
+            std::env::set_var("RAYON_NUM_THREADS", threads.to_string());
+        }
+        info!(
+            memory_gb = ?memory,
+            ?shard_size,
+            ?shard_batch_size,
+            threads = ?self.prover_threads,
+            "tuned local prover"
+        );
+        Ok(())
+    }
+}
+
+/// Total memory of the machine in GB, from `/proc/meminfo` on Linux and `sysctl` on macOS.
+fn total_memory_gb() -> Option<u64> {
+    if let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") {
+        let total = meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix("MemTotal:"))?;
+        let kb: u64 = total.trim().trim_end_matches("kB").trim().parse().ok()?;
+        return Some(kb >> 20);
+    }
+    let output = Command::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .output()
+        .ok()?;
+    let bytes: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(bytes >> 30)
+}
+
+/// A prover of any backend.
+pub type DynProver = Box<dyn Prover<CpuProverComponents>>;
+
+impl ProverArgs {
+    /// Build the selected prover, checking that its requirements are met.
+    pub fn build(&self) -> Result<DynProver> {
+        match self.prover {
+            ProverBackend::Cpu => {
+                self.tuning.apply()?;
+                Ok(Box::new(ProverClient::builder().cpu().build()))
+            }
+            ProverBackend::Cuda => cuda_prover(),
+            ProverBackend::Network => {
+                let key = self.network_private_key.as_deref().ok_or_else(|| {
+                    eyre!("--network-private-key is required for network proving")
+                })?;
+                let mut builder = ProverClient::builder().network().private_key(key);
+                if let Some(rpc_url) = &self.network_rpc_url {
+                    builder = builder.rpc_url(rpc_url);
+                }
+                Ok(Box::new(builder.build()))
+            }
+            ProverBackend::Mock => Ok(Box::new(ProverClient::builder().mock().build())),
+        }
+    }
+
+    /// Build a prover network client.
+    pub fn network_prover(&self) -> Result<NetworkProver> {
+        let key = self
+            .network_private_key
+            .as_deref()
+            .ok_or_else(|| eyre!("--network-private-key is required for network proving"))?;
+        let mut builder = ProverClient::builder().network().private_key(key);
+        if let Some(rpc_url) = &self.network_rpc_url {
+            builder = builder.rpc_url(rpc_url);
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(feature = "cuda")]
+fn cuda_prover() -> Result<DynProver> {
+    check_cuda()?;
+    Ok(Box::new(ProverClient::builder().cuda().build()))
+}
+
+#[cfg(not(feature = "cuda"))]
+fn cuda_prover() -> Result<DynProver> {
+    Err(eyre!(
+        "this binary was built without CUDA support, rebuild with `--features cuda`"
+    ))
+}
+
+/// Check that an NVIDIA GPU and driver are usable, and that docker is available to run the SP1
+/// GPU prover container.
+pub fn check_cuda() -> Result<()> {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=name,driver_version", "--format=csv,noheader"])
+        .output()
+        .map_err(|_| eyre!("nvidia-smi not found, is the NVIDIA driver installed?"))?;
+    let gpus = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() || gpus.trim().is_empty() {
+        return Err(eyre!("no CUDA-capable GPU found by nvidia-smi"));
+    }
+    for gpu in gpus.lines() {
+        info!(gpu = gpu.trim(), "found GPU");
+    }
+
+    let docker = Command::new("docker").arg("info").output();
+    if !docker.is_ok_and(|output| output.status.success()) {
+        return Err(eyre!(
+            "docker is required to run the SP1 GPU prover but is not available"
+        ));
+    }
+    Ok(())
+}
+
+/// `input` as the program reads it, with its proofs pruned and shared nodes stored once.
+fn program_input(input: &WithdrawalInput) -> CompactInput {
+    let mut input = input.clone();
+    prune_proof(&mut input.account_proof, input.block_header.state_root);
+    CompactInput::new(&input)
+}
+
+/// Encoded size of a withdrawal input as the program reads it, by part. Proof nodes shared by
+/// the account and storage proofs are counted with the account proof.
+#[derive(Debug, Clone, Copy)]
+pub struct InputSize {
+    pub header: usize,
+    pub account_proof: usize,
+    pub storage_proofs: usize,
+    pub branches: usize,
+    /// Everything else: the secret, addresses, fee and encoding overhead.
+    pub other: usize,
+    pub total: usize,
+}
+
+impl InputSize {
+    pub fn of(input: &WithdrawalInput) -> Result<Self> {
+        let compact = program_input(input);
+        let size = |node: &u32| -> Result<usize> {
+            Ok(serde_cbor::to_vec(&compact.nodes[*node as usize])?.len())
+        };
+        let account_proof = compact
+            .account_proof
+            .iter()
+            .map(size)
+            .sum::<Result<usize>>()?;
+        let storage_proofs = compact
+            .storage_proofs
+            .iter()
+            .flatten()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .filter(|node| !compact.account_proof.contains(*node))
+            .map(size)
+            .sum::<Result<usize>>()?;
+        let header = serde_cbor::to_vec(&compact.input.block_header)?.len();
+        let branches = serde_cbor::to_vec(&compact.input.inclusion_set_branches)?.len();
+        let total = serde_cbor::to_vec(&compact)?.len();
+        Ok(Self {
+            header,
+            account_proof,
+            storage_proofs,
+            branches,
+            other: total.saturating_sub(header + account_proof + storage_proofs + branches),
+            total,
+        })
+    }
+
+    /// Fail if the input is over `max` bytes, saying which part to shrink.
+    pub fn check(&self, max: usize) -> Result<()> {
+        if self.total <= max {
+            return Ok(());
+        }
+        let advice = if self.branches >= self.account_proof + self.storage_proofs {
+            "the inclusion branches are the largest part, withdraw without `--allow-list` or use a \
+             smaller set"
+        } else if self.header > self.account_proof + self.storage_proofs {
+            "the block header is the largest part, which only happens on chains with oversized \
+             header fields"
+        } else {
+            "the proofs are already pruned to the proven paths, so an RPC returning this much is \
+             likely misbehaving; try another endpoint or a `--proof-file` from a trusted node"
+        };
+        bail!(
+            "withdrawal input is {} bytes, over the {max} byte budget ({self}): {advice}",
+            self.total
+        )
+    }
+}
+
+impl std::fmt::Display for InputSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "header {}, account proof {}, storage proofs {}, branches {}, other {}",
+            self.header, self.account_proof, self.storage_proofs, self.branches, self.other
+        )
+    }
+}
+
+/// Encode `input` the way the withdrawal program reads it, as a [`CompactInput`] without the
+/// proof nodes it does not need.
+pub fn withdrawal_stdin(input: &WithdrawalInput) -> Result<SP1Stdin> {
+    let compact = program_input(input);
+    let encoded = serde_cbor::to_vec(&compact)?;
+    debug!(
+        bytes = encoded.len(),
+        nodes = compact.nodes.len(),
+        "encoded program input"
+    );
+    let mut stdin = SP1Stdin::new();
+    stdin.write_slice(&encoded);
+    Ok(stdin)
+}
+
+/// A prover call stopped by Ctrl-C. The call's worker thread cannot be stopped from the outside
+/// and is still running, so the process should exit once it has cleaned up.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("interrupted")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Deadline of prover network requests when no timeout is given, after which the network drops
+/// a request nobody is waiting on.
+pub const DEFAULT_NETWORK_DEADLINE: Duration = Duration::from_secs(60 * 60);
+
+/// Run a blocking prover call on a worker thread, giving up after `timeout`. Ctrl-C fails with
+/// [`Cancelled`].
+pub async fn run_blocking<T, F>(f: F, timeout: Option<Duration>) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let cancelled = async {
+        let _ = tokio::signal::ctrl_c().await;
+        Cancelled.into()
+    };
+    run_blocking_until(f, timeout, cancelled).await
+}
+
+/// Run a blocking prover call on a worker thread, giving up after `timeout` or with the error
+/// `stop` resolves to, for callers with their own way of being stopped.
+pub async fn run_blocking_until<T, F>(
+    f: F,
+    timeout: Option<Duration>,
+    stop: impl Future<Output = eyre::Report>,
+) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let task = tokio::task::spawn_blocking(f);
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        result = task => result?,
+        _ = deadline => bail!("timed out after {:?}", timeout.unwrap()),
+        error = stop => Err(error),
+    }
+}
+
+/// Generate a proof for the input in `checkpoint` with the note's `secret`, saving progress to
+/// `checkpoint_path` so an interrupted run can be resumed.
+///
+/// Network requests are given `timeout`, or [`DEFAULT_NETWORK_DEADLINE`], as their deadline. On
+/// Ctrl-C the request stays in the checkpoint and keeps running, so resuming waits on it again
+/// rather than paying for a new one.
+#[instrument(skip_all, fields(prover = ?args.prover, mode = ?checkpoint.mode))]
+pub async fn prove_checkpointed(
+    args: &ProverArgs,
+    mut checkpoint: Checkpoint,
+    checkpoint_path: &Path,
+    timeout: Option<Duration>,
+    secret: &B256,
+) -> Result<SP1ProofWithPublicValues> {
+    let mut input = checkpoint.input(secret)?;
+    let stdin = withdrawal_stdin(&input);
+    scrub_secret(&mut input.secret);
+    let stdin = stdin?;
+    checkpoint.save(checkpoint_path)?;
+
+    let proof = if args.prover == ProverBackend::Network {
+        let prover = args.network_prover()?;
+        let (pk, _vk) = args.keys.setup(&prover, ELF);
+        let request_id = match checkpoint.request_id {
+            Some(request_id) => request_id,
+            None => {
+                let request = prover
+                    .prove(&pk, &stdin)
+                    .mode(checkpoint.mode.into())
+                    .timeout(timeout.unwrap_or(DEFAULT_NETWORK_DEADLINE));
+                let request_id = request
+                    .request_async()
+                    .await
+                    .map_err(|e| eyre!("failed to request proof: {e}"))?;
+                checkpoint.request_id = Some(request_id);
+                checkpoint.save(checkpoint_path)?;
+                request_id
+            }
+        };
+        info!(%request_id, "waiting for network proof");
+
+        tokio::select! {
+            proof = prover.wait_proof(request_id, timeout) => {
+                proof.map_err(|e| eyre!("network proof failed: {e}"))?
+            }
+            _ = tokio::signal::ctrl_c() => {
+                warn!(
+                    %request_id,
+                    "left the network request running, `pool resume` waits on it and \
+                     `pool resume --abandon` makes a new one"
+                );
+                return Err(Cancelled.into());
+            }
+        }
+    } else {
+        let prover = args.build()?;
+        let keys = args.keys.clone();
+        let mode = checkpoint.mode.into();
+        run_blocking(
+            move || {
+                let (pk, _vk) = keys.setup(&*prover, ELF);
+                prover
+                    .prove(&pk, &stdin, mode)
+                    .map_err(|e| eyre!("proving failed: {e}"))
+            },
+            timeout,
+        )
+        .await?
+    };
+
+    info!(out = %checkpoint.out.display(), "writing proof");
+    checkpoint.write_proof(&proof)?;
+    Checkpoint::remove(checkpoint_path)?;
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stops_waiting_on_a_blocked_call() {
+        let (_release, blocked) = std::sync::mpsc::channel::<()>();
+        let call = move || Ok(blocked.recv().is_ok());
+        let error = run_blocking_until(call, None, async { eyre!("shutting down") })
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "shutting down");
+    }
+}
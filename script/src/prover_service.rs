@@ -0,0 +1,353 @@
+use crate::{
+    backend::{Proof, Sp1Backend, ZkBackend},
+    keys::KeyCache,
+    prove::{run_blocking, ProofMode, ProverArgs, ProverBackend, ProverTuning},
+};
+use clap::ValueEnum;
+use eyre::{eyre, Result};
+use futures::future::select_all;
+use pool_lib::{process_any_withdrawal, WithdrawalInput};
+use proto::{
+    prover_server::{Prover, ProverServer},
+    ExecuteRequest, ExecuteResponse, ProveBatchRequest, ProveProgress, ProveRequest, Stage,
+    StatusRequest, StatusResponse,
+};
+use rand::Rng;
+use sp1_sdk::ProverClient;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{info, instrument, warn};
+
+pub mod proto {
+    tonic::include_proto!("pool.prover.v1");
+}
+
+/// How long finished jobs stay queryable with `Status`.
+const JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// A prover backend and how many jobs it runs at once.
+pub struct Backend {
+    name: String,
+    zkvm: Arc<dyn ZkBackend>,
+    capacity: u32,
+    permits: Arc<Semaphore>,
+}
+
+impl Backend {
+    pub fn new(args: &ProverArgs, capacity: u32) -> Result<Self> {
+        let name = args
+            .prover
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        Ok(Self {
+            name,
+            zkvm: args.backend()?,
+            capacity,
+            permits: Arc::new(Semaphore::new(capacity as usize)),
+        })
+    }
+
+    fn busy(&self) -> u32 {
+        self.capacity - self.permits.available_permits() as u32
+    }
+}
+
+struct Job {
+    progress: ProveProgress,
+    finished: Option<Instant>,
+}
+
+/// Proves withdrawals on a pool of backends, handing each job to the first backend with a free
+/// slot.
+pub struct ProverService {
+    backends: Vec<Backend>,
+    /// Runs `Execute` calls, on the CPU since they are cheap.
+    executor: Arc<dyn ZkBackend>,
+    jobs: Mutex<HashMap<String, Job>>,
+    queued: AtomicU32,
+}
+
+fn proof_mode(mode: i32) -> Result<ProofMode, Status> {
+    match proto::ProofMode::try_from(mode) {
+        Ok(proto::ProofMode::Unspecified | proto::ProofMode::Groth16) => Ok(ProofMode::Groth16),
+        Ok(proto::ProofMode::Plonk) => Ok(ProofMode::Plonk),
+        Ok(proto::ProofMode::Compressed) => Ok(ProofMode::Compressed),
+        Err(_) => Err(Status::invalid_argument(format!(
+            "unknown proof mode {mode}"
+        ))),
+    }
+}
+
+/// Decode and check an input, so bad ones are rejected before they take up a backend.
+fn decode_input(input: &[u8]) -> Result<WithdrawalInput, Status> {
+    let input: WithdrawalInput = serde_cbor::from_slice(input)
+        .map_err(|e| Status::invalid_argument(format!("invalid input: {e}")))?;
+    process_any_withdrawal(&input)
+        .map_err(|e| Status::invalid_argument(format!("invalid withdrawal: {e}")))?;
+    Ok(input)
+}
+
+type ProgressSender = mpsc::Sender<Result<ProveProgress, Status>>;
+
+impl ProverService {
+    pub fn new(backends: Vec<Backend>) -> Result<Self> {
+        if backends.is_empty() {
+            return Err(eyre!("at least one backend is required"));
+        }
+        Ok(Self {
+            backends,
+            executor: Arc::new(Sp1Backend::new(Box::new(
+                ProverClient::builder().cpu().build(),
+            ))),
+            jobs: Mutex::new(HashMap::new()),
+            queued: AtomicU32::new(0),
+        })
+    }
+
+    /// Wait for a slot on any backend, preferring them in the order they were given.
+    async fn acquire(&self) -> (&Backend, OwnedSemaphorePermit) {
+        for backend in &self.backends {
+            if let Ok(permit) = backend.permits.clone().try_acquire_owned() {
+                return (backend, permit);
+            }
+        }
+        let waits = self
+            .backends
+            .iter()
+            .map(|backend| Box::pin(backend.permits.clone().acquire_owned()));
+        let (permit, index, _) = select_all(waits).await;
+        (
+            &self.backends[index],
+            permit.expect("semaphore is never closed"),
+        )
+    }
+
+    fn new_job(&self, index: u32) -> ProveProgress {
+        let job_id = hex::encode(rand::rng().random::<[u8; 16]>());
+        let progress = ProveProgress {
+            job_id: job_id.clone(),
+            index,
+            stage: Stage::Queued.into(),
+            ..Default::default()
+        };
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, job| job.finished.map_or(true, |at| at.elapsed() < JOB_RETENTION));
+        jobs.insert(
+            job_id,
+            Job {
+                progress: progress.clone(),
+                finished: None,
+            },
+        );
+        progress
+    }
+
+    /// Record `progress` for `Status` and send it to the client, who may have gone away.
+    async fn update(&self, progress: ProveProgress, tx: &ProgressSender) {
+        let finished = matches!(progress.stage(), Stage::Done | Stage::Failed);
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&progress.job_id) {
+            job.progress = progress.clone();
+            job.finished = finished.then(Instant::now);
+        }
+        let _ = tx.send(Ok(progress)).await;
+    }
+
+    /// Queue `input` for proving, streaming its progress to `tx`.
+    fn spawn(
+        self: &Arc<Self>,
+        index: u32,
+        input: WithdrawalInput,
+        mode: ProofMode,
+        tx: ProgressSender,
+    ) {
+        let service = self.clone();
+        let mut progress = self.new_job(index);
+        tokio::spawn(async move {
+            service.update(progress.clone(), &tx).await;
+            service.queued.fetch_add(1, Ordering::Relaxed);
+            // Execute on the CPU while queued, so clients see the cycle count before proving
+            // starts.
+            let (executor, executed) = (service.executor.clone(), input.clone());
+            match run_blocking(move || executor.execute(&executed), None).await {
+                Ok(execution) => {
+                    progress.stage = Stage::Executed.into();
+                    progress.cycles = execution.cycles;
+                    service.update(progress.clone(), &tx).await;
+                }
+                Err(e) => {
+                    service.queued.fetch_sub(1, Ordering::Relaxed);
+                    progress.stage = Stage::Failed.into();
+                    progress.error = e.to_string();
+                    warn!(job_id = %progress.job_id, "execution failed: {e}");
+                    service.update(progress, &tx).await;
+                    return;
+                }
+            }
+            let (backend, _permit) = service.acquire().await;
+            service.queued.fetch_sub(1, Ordering::Relaxed);
+
+            progress.stage = Stage::Proving.into();
+            progress.backend = backend.name.clone();
+            service.update(progress.clone(), &tx).await;
+            match service.prove(backend, input, mode).await {
+                Ok(proof) => {
+                    progress.stage = Stage::Done.into();
+                    progress.public_values = proof.public_values.to_vec();
+                    progress.proof = proof.proof.to_vec();
+                    progress.sp1_proof = proof.encoded;
+                    info!(job_id = %progress.job_id, backend = %backend.name, "proved withdrawal");
+                }
+                Err(e) => {
+                    progress.stage = Stage::Failed.into();
+                    progress.error = e.to_string();
+                    warn!(job_id = %progress.job_id, backend = %backend.name, "proving failed: {e}");
+                }
+            }
+            service.update(progress, &tx).await;
+        });
+    }
+
+    #[instrument(skip_all, fields(backend = %backend.name, mode = ?mode))]
+    async fn prove(
+        &self,
+        backend: &Backend,
+        input: WithdrawalInput,
+        mode: ProofMode,
+    ) -> Result<Proof> {
+        let zkvm = backend.zkvm.clone();
+        run_blocking(move || zkvm.prove(&input, mode), None).await
+    }
+
+    /// Serve the gRPC API.
+    pub fn into_server(self: Arc<Self>) -> ProverServer<Arc<Self>> {
+        ProverServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl Prover for Arc<ProverService> {
+    async fn execute(
+        &self,
+        request: Request<ExecuteRequest>,
+    ) -> Result<Response<ExecuteResponse>, Status> {
+        let input = decode_input(&request.into_inner().input)?;
+        let executor = self.executor.clone();
+        let execution = run_blocking(move || executor.execute(&input), None)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(ExecuteResponse {
+            public_values: execution.public_values.to_vec(),
+            cycles: execution.cycles,
+        }))
+    }
+
+    type ProveStream = ReceiverStream<Result<ProveProgress, Status>>;
+
+    async fn prove(
+        &self,
+        request: Request<ProveRequest>,
+    ) -> Result<Response<Self::ProveStream>, Status> {
+        let request = request.into_inner();
+        let mode = proof_mode(request.mode)?;
+        let input = decode_input(&request.input)?;
+        let (tx, rx) = mpsc::channel(4);
+        self.spawn(0, input, mode, tx);
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type ProveBatchStream = ReceiverStream<Result<ProveProgress, Status>>;
+
+    async fn prove_batch(
+        &self,
+        request: Request<ProveBatchRequest>,
+    ) -> Result<Response<Self::ProveBatchStream>, Status> {
+        let request = request.into_inner();
+        let mode = proof_mode(request.mode)?;
+        let inputs = request
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                decode_input(input).map_err(|e| {
+                    Status::invalid_argument(format!("input {index}: {}", e.message()))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (tx, rx) = mpsc::channel(4 * inputs.len().max(1));
+        for (index, input) in inputs.into_iter().enumerate() {
+            self.spawn(index as u32, input, mode, tx.clone());
+        }
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn status(
+        &self,
+        request: Request<StatusRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let job_id = request.into_inner().job_id;
+        let job = if job_id.is_empty() {
+            None
+        } else {
+            let jobs = self.jobs.lock().unwrap();
+            let job = jobs
+                .get(&job_id)
+                .ok_or_else(|| Status::not_found(format!("no job {job_id}")))?;
+            Some(job.progress.clone())
+        };
+        let backends = self
+            .backends
+            .iter()
+            .map(|backend| proto::Backend {
+                name: backend.name.clone(),
+                capacity: backend.capacity,
+                busy: backend.busy(),
+            })
+            .collect();
+        Ok(Response::new(StatusResponse {
+            backends,
+            queued: self.queued.load(Ordering::Relaxed),
+            job,
+        }))
+    }
+}
+
+/// Backends for `backends`, each running `capacity` jobs at once except the network, which runs
+/// `network_capacity`.
+pub fn backends(
+    backends: &[ProverBackend],
+    network_private_key: Option<String>,
+    network_rpc_url: Option<String>,
+    tuning: &ProverTuning,
+    keys: &KeyCache,
+    capacity: u32,
+    network_capacity: u32,
+) -> Result<Vec<Backend>> {
+    backends
+        .iter()
+        .map(|&prover| {
+            let args = ProverArgs {
+                prover,
+                network_private_key: network_private_key.clone(),
+                network_rpc_url: network_rpc_url.clone(),
+                tuning: tuning.clone(),
+                keys: keys.clone(),
+            };
+            let capacity = if prover == ProverBackend::Network {
+                network_capacity
+            } else {
+                capacity
+            };
+            Backend::new(&args, capacity)
+        })
+        .collect()
+}
@@ -0,0 +1,177 @@
+//! Notes derived from a BIP-39 mnemonic, so a wallet's notes can be found again from its seed
+//! alone. The mnemonic derives one note key, and each note secret is a hash of that key with the
+//! chain, pool and the note's index among those deposited into the pool.
+//!
+//! Recovery derives the notes of each pool an indexer follows in order, matches their commitments
+//! against its deposits, and stops a pool after a gap of unused indices, as HD wallets do with
+//! addresses.
+
+use crate::{
+    indexer::filter::find_deposits,
+    notes::NoteRecord,
+    secure::{scrub, Locked},
+};
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    signers::local::{
+        coins_bip39::{English, Mnemonic as Bip39Mnemonic},
+        MnemonicBuilder,
+    },
+    sol_types::SolValue,
+};
+use eyre::{eyre, Result};
+use pool_lib::{compute_commitment, note::Note};
+use rusqlite::{params, Connection};
+use std::{fmt, path::Path, str::FromStr};
+use tracing::{debug, info, instrument};
+
+/// Derivation path of the note key. A hardened account of its own, so the key is unrelated to the
+/// account keys wallets derive from the same mnemonic.
+pub const NOTE_DERIVATION_PATH: &str = "m/44'/60'/1000'/0/0";
+
+/// Consecutive unused indices after which recovery stops deriving notes for a pool.
+pub const DEFAULT_GAP_LIMIT: u64 = 20;
+
+/// A BIP-39 mnemonic phrase checked against the English wordlist.
+#[derive(Clone)]
+pub struct Mnemonic(String);
+
+impl FromStr for Mnemonic {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let phrase = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        Bip39Mnemonic::<English>::new_from_phrase(&phrase)
+            .map_err(|_| eyre!("invalid mnemonic"))?;
+        Ok(Self(phrase))
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        // SAFETY: zeros are valid UTF-8.
+        scrub(unsafe { self.0.as_bytes_mut() });
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Mnemonic(..)")
+    }
+}
+
+/// The note key of a mnemonic.
+#[derive(Debug)]
+pub struct NoteSeed {
+    key: Locked<B256>,
+}
+
+impl NoteSeed {
+    pub fn new(mnemonic: &Mnemonic, passphrase: Option<&str>) -> Result<Self> {
+        let mut builder = MnemonicBuilder::<English>::default()
+            .phrase(mnemonic.0.as_str())
+            .derivation_path(NOTE_DERIVATION_PATH)?;
+        if let Some(passphrase) = passphrase {
+            builder = builder.password(passphrase);
+        }
+        let signer = builder.build()?;
+        Ok(Self {
+            key: Locked::new(signer.to_bytes()),
+        })
+    }
+
+    /// Secret of note `index` of `pool` on `chain_id`.
+    pub fn secret(&self, chain_id: u64, pool: Address, index: u64) -> B256 {
+        keccak256((*self.key, U256::from(chain_id), pool, U256::from(index)).abi_encode())
+    }
+
+    pub fn note(&self, chain_id: u64, pool: Address, index: u64) -> NoteRecord {
+        let mut record = NoteRecord::new(Note {
+            chain_id,
+            pool,
+            secret: self.secret(chain_id, pool, index),
+        });
+        record.derivation_index = Some(index);
+        record
+    }
+}
+
+/// A derived note found among the indexed deposits.
+#[derive(Debug, Clone)]
+pub struct RecoveredNote {
+    pub record: NoteRecord,
+    /// Whether the indexer has seen the note withdrawn.
+    pub spent: bool,
+}
+
+/// Pools the indexer database follows, as `(chain_id, pool)`.
+pub fn indexed_pools(conn: &Connection) -> Result<Vec<(u64, Address)>> {
+    let mut statement =
+        conn.prepare("SELECT chain_id, pool FROM indexed_pools ORDER BY chain_id, pool")?;
+    let rows = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    rows.into_iter()
+        .map(|(chain_id, pool)| Ok((chain_id, pool.parse()?)))
+        .collect()
+}
+
+/// Derive the notes of `pool` on `chain_id` in order and return those deposited according to
+/// the indexer database at `db`, stopping once `gap_limit` indices in a row are unused.
+#[instrument(skip(conn, seed))]
+pub fn recover_pool(
+    conn: &Connection,
+    db: &Path,
+    seed: &NoteSeed,
+    chain_id: u64,
+    pool: Address,
+    gap_limit: u64,
+) -> Result<Vec<RecoveredNote>> {
+    let mut deposit = conn.prepare(
+        "SELECT block_number, tx_hash FROM deposits
+         WHERE chain_id = ?1 AND pool = ?2 AND leaf_index = ?3",
+    )?;
+    let mut withdrawn = conn.prepare(
+        "SELECT EXISTS (SELECT 1 FROM withdrawals
+         WHERE chain_id = ?1 AND pool = ?2 AND nullifier = ?3)",
+    )?;
+
+    let mut recovered = Vec::new();
+    let mut next = 0;
+    let mut end = gap_limit;
+    while next < end {
+        let records: Vec<NoteRecord> = (next..end)
+            .map(|index| seed.note(chain_id, pool, index))
+            .collect();
+        let commitments: Vec<B256> = records.iter().map(|record| record.commitment).collect();
+        let found = find_deposits(conn, db, chain_id, pool, &commitments)?;
+        debug!(
+            from = next,
+            to = end,
+            found = found.len(),
+            "checked derived notes"
+        );
+        next = end;
+        for mut record in records {
+            let Some(&leaf_index) = found.get(&record.commitment) else {
+                continue;
+            };
+            let (block_number, tx_hash): (u64, String) = deposit
+                .query_row(params![chain_id, pool.to_string(), leaf_index], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?;
+            let (_, nullifier) = compute_commitment(&record.note.secret);
+            let spent = withdrawn.query_row(
+                params![chain_id, pool.to_string(), nullifier.to_string()],
+                |row| row.get(0),
+            )?;
+            record.leaf_index = Some(leaf_index);
+            record.block_number = Some(block_number);
+            record.tx_hash = Some(tx_hash.parse()?);
+            end = end.max(record.derivation_index.unwrap_or_default() + 1 + gap_limit);
+            recovered.push(RecoveredNote { record, spent });
+        }
+    }
+    info!(notes = recovered.len(), "recovered notes");
+    Ok(recovered)
+}
@@ -0,0 +1,102 @@
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
+};
+use eyre::{ensure, eyre, Result};
+use pool_lib::{
+    compact::prune_proof,
+    registry::{pools_entry_key, registry_key, RegistryEntry},
+};
+use std::{fmt, str::FromStr};
+use tracing::info;
+
+/// A pool looked up in a registry by denomination and version, on whatever chain it is read
+/// from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryRef {
+    pub registry: Address,
+    /// Amount each deposit of the pool is, in wei.
+    pub denomination: U256,
+    pub version: u64,
+}
+
+impl FromStr for RegistryRef {
+    type Err = eyre::Report;
+
+    /// Parse `<registry>/<denomination in wei>/<version>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || eyre!("invalid registry entry {s}, expected <registry>/<wei>/<version>");
+        let mut parts = s.split('/');
+        let (Some(registry), Some(denomination), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            registry: registry.parse().map_err(|_| invalid())?,
+            denomination: denomination.parse().map_err(|_| invalid())?,
+            version: version.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for RegistryRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}",
+            self.registry, self.denomination, self.version
+        )
+    }
+}
+
+impl RegistryRef {
+    /// Fetch the entry at `block_number` with its proof, and verify the proof against the
+    /// block's state root rather than trusting the RPC's answer.
+    pub async fn entry<P: Provider>(
+        &self,
+        provider: &P,
+        block_number: u64,
+    ) -> Result<(Address, RegistryEntry)> {
+        let chain_id = provider.get_chain_id().await?;
+        let key = registry_key(chain_id, self.denomination, self.version);
+        let (block, mut proof) = futures::try_join!(
+            async {
+                Ok::<_, eyre::Report>(
+                    provider
+                        .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+                        .await?,
+                )
+            },
+            async {
+                Ok::<_, eyre::Report>(
+                    provider
+                        .get_proof(self.registry, vec![pools_entry_key(key)])
+                        .number(block_number)
+                        .await?,
+                )
+            },
+        )?;
+        let state_root = block
+            .ok_or_else(|| eyre!("block {block_number} not found"))?
+            .header
+            .state_root;
+        prune_proof(&mut proof, state_root);
+        let entry = RegistryEntry {
+            registry: self.registry,
+            chain_id,
+            denomination: self.denomination,
+            version: self.version,
+            proof,
+        };
+        let pool = entry.pool()?;
+        ensure!(
+            !pool.is_zero(),
+            "no pool registered for {self} on chain {chain_id}"
+        );
+        entry.verify(&state_root, &pool)?;
+        info!(registry = %self.registry, %pool, block_number, "resolved pool from registry");
+        Ok((pool, entry))
+    }
+}
@@ -0,0 +1,157 @@
+//! End-to-end rehearsals of a withdrawal on an anvil fork of a live chain. The pool is deployed on
+//! the fork if it is not there, a fresh note is deposited into it, and the withdrawal is built
+//! against the fork with the real witness code, executed in the guest and submitted in a
+//! simulation. Everything but proving runs against the chain's real state, without real funds.
+
+use crate::{
+    backend::ZkBackend,
+    deposit::{send_deposit, DepositReceipt},
+    ens::AddressOrName,
+    gas_report::{deploy_pool, SP1_GROTH16_GATEWAY},
+    prove::run_blocking,
+    simulate::{check_public_values, Mismatch},
+    witness::{build_witness, WitnessArgs},
+};
+use alloy::{
+    network::{Ethereum, EthereumWallet},
+    node_bindings::Anvil,
+    primitives::{keccak256, Address, B256, U256},
+    providers::{Provider, ProviderBuilder, RootProvider},
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use clap::{Args, Command, FromArgMatches};
+use eyre::{ensure, Result};
+use pool_lib::{compute_commitment, decode_any_public_values, withdrawal_public_values};
+use rand::Rng;
+use std::{path::Path, sync::Arc};
+use tracing::{info, instrument};
+
+/// Blocks to mine after the deposit so the fork's finalized block includes it. Anvil reports the
+/// block two epochs of 32 slots behind its head as finalized.
+const FINALITY_DEPTH: u64 = 2 * 32 + 1;
+
+/// Where the pool to rehearse against comes from.
+#[derive(Debug, Clone)]
+pub struct RehearsalPool<'a> {
+    /// Pool on the forked chain, or the address to deploy one at.
+    pub address: Option<Address>,
+    /// Forge artifact of the pool contract, for pools not on the chain.
+    pub artifact: &'a Path,
+    /// Program vkey of a deployed pool.
+    pub vkey: B256,
+    /// Deposit amount of a deployed pool, in wei.
+    pub amount: U256,
+}
+
+/// What a rehearsal did.
+#[derive(Debug, Clone)]
+pub struct Rehearsal {
+    pub pool: Address,
+    /// Whether the pool was deployed on the fork.
+    pub deployed: bool,
+    pub deposit: DepositReceipt,
+    /// Block the withdrawal was proven against.
+    pub block_number: u64,
+    pub cycles: u64,
+    /// Where the contract read the public values differently from the program, empty if the
+    /// simulated submission went through as the program committed it.
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// Fork `fork_url` with anvil and rehearse a deposit and withdrawal from `pool` there, executing
+/// the guest with `backend`.
+#[instrument(skip_all, fields(%fork_url))]
+pub async fn rehearse(
+    fork_url: &Url,
+    pool: RehearsalPool<'_>,
+    backend: Arc<dyn ZkBackend>,
+) -> Result<Rehearsal> {
+    let anvil = Anvil::new().fork(fork_url.as_str()).try_spawn()?;
+    let signer = PrivateKeySigner::from(anvil.keys()[0].clone());
+    let recipient = anvil.addresses()[1];
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer.clone()))
+        .on_http(anvil.endpoint_url());
+    info!(
+        block_number = provider.get_block_number().await?,
+        "forked chain"
+    );
+
+    let existing = match pool.address {
+        Some(address) => !provider.get_code_at(address).await?.is_empty(),
+        None => false,
+    };
+    let address = match pool.address {
+        Some(address) if existing => address,
+        _ => {
+            ensure!(
+                !provider.get_code_at(SP1_GROTH16_GATEWAY).await?.is_empty(),
+                "no SP1 verifier gateway at {SP1_GROTH16_GATEWAY} on the forked chain"
+            );
+            let deployed = deploy_pool(
+                &provider,
+                pool.artifact,
+                SP1_GROTH16_GATEWAY,
+                pool.vkey,
+                pool.amount,
+            )
+            .await?;
+            match pool.address {
+                Some(address) => {
+                    let runtime = provider.get_code_at(deployed).await?;
+                    provider
+                        .raw_request::<_, ()>("anvil_setCode".into(), (address, runtime))
+                        .await?;
+                    address
+                }
+                None => deployed,
+            }
+        }
+    };
+
+    let secret = B256::from(rand::rng().random::<[u8; 32]>());
+    let (commitment, _) = compute_commitment(&secret);
+    let deposit = send_deposit(anvil.endpoint_url(), signer, address, commitment).await?;
+    info!(leaf_index = deposit.leaf_index, "deposited");
+    provider
+        .raw_request::<_, ()>("anvil_mine".into(), (U256::from(FINALITY_DEPTH),))
+        .await?;
+
+    let code_hash = keccak256(provider.get_code_at(address).await?);
+    let matches = WitnessArgs::augment_args(Command::new("witness")).try_get_matches_from([
+        "witness".to_string(),
+        "--rpc-url".to_string(),
+        anvil.endpoint(),
+        address.to_string(),
+        secret.to_string(),
+    ])?;
+    let mut witness = WitnessArgs::from_arg_matches(&matches)?;
+    witness.recipient = AddressOrName::Address(recipient);
+    witness.leaf_index = Some(deposit.leaf_index);
+    witness.expected_code_hash = Some(code_hash);
+    // The rehearsal deposit is the fork's only new one.
+    witness.min_anonymity_set = 0;
+    witness.beacon_url = None;
+    let fork = RootProvider::<Ethereum>::new_http(anvil.endpoint_url());
+    let input = build_witness(&fork, &witness).await?;
+
+    let expected = withdrawal_public_values(&input)?;
+    let execution = run_blocking(move || backend.execute(&input), None).await?;
+    ensure!(
+        execution.public_values[..] == expected[..],
+        "the guest committed different public values than pool-lib"
+    );
+    info!(cycles = execution.cycles, "executed");
+
+    let data = decode_any_public_values(&execution.public_values)?.withdrawal;
+    let mismatches = check_public_values(&fork, address, &data).await?;
+    Ok(Rehearsal {
+        pool: address,
+        deployed: !existing,
+        deposit,
+        block_number: data.blockNumber,
+        cycles: execution.cycles,
+        mismatches,
+    })
+}
@@ -0,0 +1,88 @@
+use alloy::{eips::eip1559::Eip1559Estimation, eips::BlockNumberOrTag, providers::Provider};
+use clap::ValueEnum;
+use eyre::{ensure, OptionExt, Result};
+use reqwest::Url;
+use serde::Deserialize;
+
+/// How the relayer prices its transactions.
+#[derive(Debug, Clone)]
+pub enum GasStrategy {
+    /// The node's fee history estimate.
+    Estimate,
+    /// A fixed priority fee, in wei, on top of twice the next base fee.
+    FixedTip { tip: u128 },
+    /// The median of the `percentile`th priority fee of each of the last `blocks` blocks.
+    Percentile { percentile: f64, blocks: u64 },
+    /// Fees from an HTTP oracle answering `{"maxFeePerGas": .., "maxPriorityFeePerGas": ..}`
+    /// in wei.
+    Oracle { url: Url },
+}
+
+/// Names of the strategies on the command line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasStrategyKind {
+    Estimate,
+    FixedTip,
+    Percentile,
+    Oracle,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OracleFees {
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+}
+
+/// Base fee of the next block.
+pub async fn next_base_fee<P: Provider>(provider: &P) -> Result<u128> {
+    provider
+        .get_fee_history(1, BlockNumberOrTag::Latest, &[])
+        .await?
+        .next_block_base_fee()
+        .ok_or_eyre("node returned no base fee")
+}
+
+impl GasStrategy {
+    /// Fees for a transaction sent now.
+    pub async fn fees<P: Provider>(&self, provider: &P) -> Result<Eip1559Estimation> {
+        let tip = match self {
+            Self::Estimate => return Ok(provider.estimate_eip1559_fees(None).await?),
+            Self::Oracle { url } => {
+                let fees: OracleFees = reqwest::get(url.clone())
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                ensure!(
+                    fees.max_priority_fee_per_gas <= fees.max_fee_per_gas,
+                    "gas oracle returned a priority fee above the max fee"
+                );
+                return Ok(Eip1559Estimation {
+                    max_fee_per_gas: fees.max_fee_per_gas,
+                    max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+                });
+            }
+            Self::FixedTip { tip } => *tip,
+            Self::Percentile { percentile, blocks } => {
+                let history = provider
+                    .get_fee_history(*blocks, BlockNumberOrTag::Latest, &[*percentile])
+                    .await?;
+                let mut rewards: Vec<u128> = history
+                    .reward
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|reward| reward.first().copied())
+                    .collect();
+                ensure!(!rewards.is_empty(), "node returned no fee rewards");
+                rewards.sort_unstable();
+                rewards[rewards.len() / 2]
+            }
+        };
+        // Twice the base fee stays valid through six full blocks of base fee increases.
+        Ok(Eip1559Estimation {
+            max_fee_per_gas: 2 * next_base_fee(provider).await? + tip,
+            max_priority_fee_per_gas: tip,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+use super::Relayer;
+use alloy::{primitives::B256, providers::Provider};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Longest an RPC call made by a readiness check may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of one readiness check.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(ok: bool, detail: impl Into<String>) -> Self {
+        Self {
+            ok,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Response of `GET /readyz`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub rpc: Check,
+    pub balance: Check,
+    pub program: Check,
+    pub queue: Check,
+    pub shutting_down: bool,
+}
+
+impl Relayer {
+    /// Whether the relayer can take withdrawals: the RPC answers, the wallet holds at least
+    /// `min_balance`, the program is pinned, the queue is under `max_queue_depth` and it is not
+    /// shutting down.
+    pub async fn readiness(&self) -> Readiness {
+        let head = tokio::time::timeout(CHECK_TIMEOUT, self.provider.get_block_number());
+        let rpc = match head.await {
+            Ok(Ok(head)) => Check::new(true, format!("head {head}")),
+            Ok(Err(e)) => Check::new(false, e.to_string()),
+            Err(_) => Check::new(false, "timed out"),
+        };
+        let balance =
+            tokio::time::timeout(CHECK_TIMEOUT, self.provider.get_balance(self.address()));
+        let balance = match balance.await {
+            Ok(Ok(balance)) => Check::new(
+                balance >= self.config.min_balance,
+                format!("{balance} wei, minimum {}", self.config.min_balance),
+            ),
+            Ok(Err(e)) => Check::new(false, e.to_string()),
+            Err(_) => Check::new(false, "timed out"),
+        };
+        let program = if self.config.vkey == B256::ZERO {
+            Check::new(false, "no program pinned")
+        } else {
+            let prover = if self.config.prover.is_some() {
+                "proving enabled"
+            } else {
+                "proofs only"
+            };
+            Check::new(true, format!("{}, {prover}", self.config.vkey))
+        };
+        let queue = match self.jobs.pending_count() {
+            Ok(depth) => match self.config.max_queue_depth {
+                Some(max) => Check::new(depth <= max, format!("{depth} pending, maximum {max}")),
+                None => Check::new(true, format!("{depth} pending")),
+            },
+            Err(e) => Check::new(false, e.to_string()),
+        };
+        let shutting_down = self.is_shutting_down();
+        Readiness {
+            ready: rpc.ok && balance.ok && program.ok && queue.ok && !shutting_down,
+            rpc,
+            balance,
+            program,
+            queue,
+            shutting_down,
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{db, relayer::txmanager::Mined};
+use alloy::primitives::{Address, B256, U256};
+use eyre::Result;
+use pool_relayer_client::{Job, JobStatus, WithdrawBody};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Relay jobs persisted in sqlite, so in-flight withdrawals survive a restart.
+pub struct JobStore {
+    conn: Mutex<Connection>,
+}
+
+/// A job that still has work to do, with the request it was created from.
+#[derive(Debug, Clone)]
+pub struct PendingJob {
+    pub job: Job,
+    pub body: WithdrawBody,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn job_from_row(row: &Row) -> rusqlite::Result<(Job, String)> {
+    let status: String = row.get("status")?;
+    let pool: String = row.get("pool")?;
+    let tx_hash: Option<String> = row.get("tx_hash")?;
+    let gas_cost: Option<String> = row.get("gas_cost")?;
+    let invalid = |e: eyre::Report| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+    };
+    Ok((
+        Job {
+            id: row.get("id")?,
+            status: status.parse().map_err(invalid)?,
+            pool: pool.parse::<Address>().map_err(|e| invalid(e.into()))?,
+            attempts: row.get("attempts")?,
+            tx_hash: tx_hash
+                .map(|hash| hash.parse::<B256>())
+                .transpose()
+                .map_err(|e| invalid(e.into()))?,
+            block_number: row.get("block_number")?,
+            settled: row.get("settled")?,
+            gas_used: row.get("gas_used")?,
+            gas_cost: gas_cost
+                .map(|cost| cost.parse::<U256>())
+                .transpose()
+                .map_err(|e| invalid(e.into()))?,
+            error: row.get("error")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        },
+        row.get("body")?,
+    ))
+}
+
+impl JobStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            conn: Mutex::new(db::open(path)?),
+        })
+    }
+
+    /// Record a newly received job.
+    pub fn insert(&self, id: &str, pool: Address, body: &WithdrawBody) -> Result<Job> {
+        let created_at = now();
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO jobs (id, status, pool, body, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![
+                id,
+                JobStatus::Queued.as_str(),
+                pool.to_string(),
+                serde_json::to_string(body)?,
+                created_at
+            ],
+        )?;
+        Ok(Job {
+            id: id.to_string(),
+            status: JobStatus::Queued,
+            pool,
+            attempts: 0,
+            tx_hash: None,
+            block_number: None,
+            settled: false,
+            gas_used: None,
+            gas_cost: None,
+            error: None,
+            created_at,
+            updated_at: created_at,
+        })
+    }
+
+    /// Start a failed job over with a new request.
+    pub fn requeue(&self, id: &str, body: &WithdrawBody) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET status = ?2, body = ?3, attempts = 0, tx_hash = NULL,
+             block_number = NULL, error = NULL, updated_at = ?4 WHERE id = ?1",
+            params![
+                id,
+                JobStatus::Queued.as_str(),
+                serde_json::to_string(body)?,
+                now()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Job>> {
+        Ok(self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT * FROM jobs WHERE id = ?1", [id], job_from_row)
+            .optional()?
+            .map(|(job, _)| job))
+    }
+
+    /// Most recent jobs first.
+    pub fn list(&self, limit: u32, offset: u32) -> Result<Vec<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement =
+            conn.prepare("SELECT * FROM jobs ORDER BY created_at DESC, id LIMIT ?1 OFFSET ?2")?;
+        let jobs = statement
+            .query_map([limit, offset], job_from_row)?
+            .map(|row| row.map(|(job, _)| job))
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(jobs)
+    }
+
+    /// Jobs that are neither settled nor failed, oldest first.
+    pub fn pending(&self) -> Result<Vec<PendingJob>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT * FROM jobs WHERE status != 'failed' AND settled = 0 ORDER BY created_at",
+        )?;
+        let rows = statement
+            .query_map([], job_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(job, body)| {
+                Ok(PendingJob {
+                    job,
+                    body: serde_json::from_str(&body)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Number of jobs not yet settled or failed.
+    pub fn pending_count(&self) -> Result<u64> {
+        Ok(self.conn.lock().unwrap().query_row(
+            "SELECT COUNT(*) FROM jobs WHERE status != 'failed' AND settled = 0",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Write the write-ahead log back into the database file, so it is complete on its own
+    /// once the relayer exits.
+    pub fn checkpoint(&self) -> Result<()> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    pub fn set_status(&self, id: &str, status: JobStatus) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET status = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, status.as_str(), now()],
+        )?;
+        Ok(())
+    }
+
+    /// Count a submission attempt, returning the new total.
+    pub fn add_attempt(&self, id: &str) -> Result<u32> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET attempts = attempts + 1, updated_at = ?2 WHERE id = ?1",
+            params![id, now()],
+        )?;
+        Ok(
+            conn.query_row("SELECT attempts FROM jobs WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })?,
+        )
+    }
+
+    /// Record a transaction sent for the job. The job shows the latest, and every one is kept
+    /// since any of the replacements at a nonce may be the one mined.
+    pub fn add_tx_hash(&self, id: &str, tx_hash: B256) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO job_txs (job_id, tx_hash) VALUES (?1, ?2)",
+            params![id, tx_hash.to_string()],
+        )?;
+        conn.execute(
+            "UPDATE jobs SET tx_hash = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, tx_hash.to_string(), now()],
+        )?;
+        Ok(())
+    }
+
+    /// Every transaction sent for the job, including those a requeue cleared from it.
+    pub fn tx_hashes(&self, id: &str) -> Result<Vec<B256>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare("SELECT tx_hash FROM job_txs WHERE job_id = ?1")?;
+        let hashes = statement
+            .query_map([id], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hashes
+            .iter()
+            .map(|hash| hash.parse())
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Mark the job mined by `mined`.
+    pub fn mined(&self, id: &str, mined: &Mined) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET status = ?2, tx_hash = ?3, block_number = ?4, gas_used = ?5,
+             gas_cost = ?6, error = NULL, updated_at = ?7 WHERE id = ?1",
+            params![
+                id,
+                JobStatus::Mined.as_str(),
+                mined.tx_hash.to_string(),
+                mined.block_number,
+                mined.gas_used,
+                mined.cost.to_string(),
+                now()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stop watching a mined job.
+    pub fn settle(&self, id: &str) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET settled = 1, updated_at = ?2 WHERE id = ?1",
+            params![id, now()],
+        )?;
+        Ok(())
+    }
+
+    /// Gas spent, or reserved by transactions not mined yet, since unix time `since`, leaving
+    /// out `except`'s own reservation.
+    pub fn spent_since(&self, since: u64, except: &str) -> Result<U256> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement =
+            conn.prepare("SELECT amount FROM spends WHERE at >= ?1 AND job_id != ?2")?;
+        let amounts = statement
+            .query_map(params![since, except], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        amounts
+            .iter()
+            .try_fold(U256::ZERO, |sum, amount| Ok(sum + amount.parse::<U256>()?))
+    }
+
+    /// Reserve `amount`, the most job `id`'s transaction can cost, against the spending caps.
+    /// Sending a replacement raises the reservation.
+    pub fn reserve_spend(&self, id: &str, amount: U256) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO spends (job_id, amount, at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (job_id) DO UPDATE SET amount = excluded.amount, settled = 0",
+            params![id, amount.to_string(), now()],
+        )?;
+        Ok(())
+    }
+
+    /// Replace job `id`'s reservation with what its mined transaction cost.
+    pub fn settle_spend(&self, id: &str, cost: U256) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE spends SET amount = ?2, settled = 1 WHERE job_id = ?1",
+            params![id, cost.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_top_up(&self, tx_hash: B256, amount: U256) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO top_ups (tx_hash, amount, at) VALUES (?1, ?2, ?3)",
+            params![tx_hash.to_string(), amount.to_string(), now()],
+        )?;
+        Ok(())
+    }
+
+    /// Sent to the hot wallet by the funding wallet since unix time `since`.
+    pub fn topped_up_since(&self, since: u64) -> Result<U256> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare("SELECT amount FROM top_ups WHERE at >= ?1")?;
+        let amounts = statement
+            .query_map(params![since], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        amounts
+            .iter()
+            .try_fold(U256::ZERO, |sum, amount| Ok(sum + amount.parse::<U256>()?))
+    }
+
+    /// Record the error of the last attempt, marking the job failed if `terminal`.
+    pub fn record_error(&self, id: &str, error: &str, terminal: bool) -> Result<()> {
+        let status = if terminal {
+            JobStatus::Failed
+        } else {
+            JobStatus::Queued
+        };
+        self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET status = ?2, error = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, status.as_str(), error, now()],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use alloy::{
+    network::{EthereumWallet, TxSigner},
+    primitives::{Address, PrimitiveSignature},
+    signers::{
+        aws::{aws_sdk_kms, AwsSigner},
+        gcp::{
+            gcloud_sdk::{
+                google::cloud::kms::v1::key_management_service_client::KeyManagementServiceClient,
+                GoogleApi,
+            },
+            GcpKeyRingRef, GcpSigner, KeySpecifier,
+        },
+        local::PrivateKeySigner,
+        Signer,
+    },
+};
+use eyre::{bail, ensure, eyre, OptionExt, Result};
+use std::{fmt, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use tracing::info;
+
+/// Where a relayer key is kept.
+#[derive(Clone)]
+pub enum KeySource {
+    /// A plaintext private key. The key is on the host, so prefer the other sources.
+    Local(PrivateKeySigner),
+    /// An encrypted JSON keystore, decrypted at startup with a password.
+    Keystore(PathBuf),
+    /// An `ECC_SECG_P256K1` key in AWS KMS, by key id or ARN, which signs remotely.
+    AwsKms(String),
+    /// An `EC_SIGN_SECP256K1_SHA256` key version in GCP KMS, which signs remotely.
+    GcpKms {
+        project: String,
+        location: String,
+        key_ring: String,
+        key: String,
+        version: u64,
+    },
+}
+
+impl fmt::Debug for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(signer) => write!(f, "Local({})", signer.address()),
+            Self::Keystore(path) => write!(f, "Keystore({})", path.display()),
+            Self::AwsKms(key_id) => write!(f, "AwsKms({key_id})"),
+            Self::GcpKms {
+                project,
+                location,
+                key_ring,
+                key,
+                version,
+            } => write!(f, "GcpKms({project}/{location}/{key_ring}/{key}/{version})"),
+        }
+    }
+}
+
+impl FromStr for KeySource {
+    type Err = eyre::Report;
+
+    /// Parse `keystore:<path>`, `aws-kms:<key id or ARN>`,
+    /// `gcp-kms:projects/<p>/locations/<l>/keyRings/<r>/cryptoKeys/<k>/cryptoKeyVersions/<v>`
+    /// or a private key.
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(path) = s.strip_prefix("keystore:") {
+            return Ok(Self::Keystore(path.into()));
+        }
+        if let Some(key_id) = s.strip_prefix("aws-kms:") {
+            ensure!(!key_id.is_empty(), "missing AWS KMS key id");
+            return Ok(Self::AwsKms(key_id.to_string()));
+        }
+        if let Some(name) = s.strip_prefix("gcp-kms:") {
+            let parts: Vec<&str> = name.split('/').collect();
+            let labels = [
+                "projects",
+                "locations",
+                "keyRings",
+                "cryptoKeys",
+                "cryptoKeyVersions",
+            ];
+            let [_, project, _, location, _, key_ring, _, key, _, version] = parts[..] else {
+                bail!("invalid GCP KMS key version name");
+            };
+            ensure!(
+                parts.iter().step_by(2).eq(labels.iter()),
+                "invalid GCP KMS key version name"
+            );
+            return Ok(Self::GcpKms {
+                project: project.to_string(),
+                location: location.to_string(),
+                key_ring: key_ring.to_string(),
+                key: key.to_string(),
+                version: version.parse()?,
+            });
+        }
+        Ok(Self::Local(
+            s.parse().map_err(|_| eyre!("invalid relayer key"))?,
+        ))
+    }
+}
+
+/// A key the relayer signs with, from `active_from` (unix seconds) on.
+struct ManagedKey {
+    signer: Arc<dyn Signer<PrimitiveSignature> + Send + Sync>,
+    active_from: u64,
+}
+
+/// The relayer's keys: the one quotes are signed with now, and those it rotated away from or
+/// will rotate to.
+///
+/// Rotating to a new key at `active_from` switches new quotes to it. Withdrawals commit to the
+/// relayer address of their quote, so they are sent from that key however long after; quotes
+/// signed by the previous key are still taken for `overlap` after the rotation.
+pub struct KeyManager {
+    keys: Vec<ManagedKey>,
+    overlap: Duration,
+    wallet: EthereumWallet,
+}
+
+impl fmt::Debug for KeyManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyManager")
+            .field("addresses", &self.addresses())
+            .field("overlap", &self.overlap)
+            .finish()
+    }
+}
+
+impl KeyManager {
+    /// Load the key at `source`, active from the start. `password` decrypts keystores.
+    pub async fn load(source: &KeySource, password: Option<&str>, chain_id: u64) -> Result<Self> {
+        let mut keys = Self {
+            keys: Vec::new(),
+            overlap: Duration::ZERO,
+            wallet: EthereumWallet::default(),
+        };
+        keys.load_key(source, password, chain_id, 0).await?;
+        Ok(keys)
+    }
+
+    /// Rotate to the key at `source` at `active_from`, taking quotes of the key before it for
+    /// `overlap` after.
+    pub async fn rotate(
+        &mut self,
+        source: &KeySource,
+        password: Option<&str>,
+        chain_id: u64,
+        active_from: u64,
+        overlap: Duration,
+    ) -> Result<()> {
+        let last = self.keys.last().map_or(0, |key| key.active_from);
+        ensure!(
+            active_from > last,
+            "keys must be rotated to in order of activation"
+        );
+        self.overlap = overlap;
+        self.load_key(source, password, chain_id, active_from).await
+    }
+
+    async fn load_key(
+        &mut self,
+        source: &KeySource,
+        password: Option<&str>,
+        chain_id: u64,
+        active_from: u64,
+    ) -> Result<()> {
+        match source {
+            KeySource::Local(signer) => self.add(signer.clone(), active_from),
+            KeySource::Keystore(path) => {
+                let password = password.ok_or_eyre("a keystore password is required")?;
+                let signer = PrivateKeySigner::decrypt_keystore(path, password)
+                    .map_err(|e| eyre!("failed to decrypt {}: {e}", path.display()))?;
+                self.add(signer, active_from);
+            }
+            KeySource::AwsKms(key_id) => {
+                let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                let client = aws_sdk_kms::Client::new(&config);
+                let signer = AwsSigner::new(client, key_id.clone(), Some(chain_id))
+                    .await
+                    .map_err(|e| eyre!("failed to load AWS KMS key {key_id}: {e}"))?;
+                self.add(signer, active_from);
+            }
+            KeySource::GcpKms {
+                project,
+                location,
+                key_ring,
+                key,
+                version,
+            } => {
+                let client = GoogleApi::from_function(
+                    KeyManagementServiceClient::new,
+                    "https://cloudkms.googleapis.com",
+                    None,
+                )
+                .await?;
+                let specifier = KeySpecifier::new(
+                    GcpKeyRingRef::new(project, location, key_ring),
+                    key,
+                    *version,
+                );
+                let signer = GcpSigner::new(client, specifier, Some(chain_id))
+                    .await
+                    .map_err(|e| eyre!("failed to load GCP KMS key {key}: {e}"))?;
+                self.add(signer, active_from);
+            }
+        }
+        let key = self.keys.last().unwrap();
+        info!(address = %key.signer.address(), active_from, "loaded relayer key");
+        Ok(())
+    }
+
+    fn add<S>(&mut self, signer: S, active_from: u64)
+    where
+        S: Signer<PrimitiveSignature>
+            + TxSigner<PrimitiveSignature>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.wallet.register_signer(signer.clone());
+        self.keys.push(ManagedKey {
+            signer: Arc::new(signer),
+            active_from,
+        });
+    }
+
+    fn current_index(&self, now: u64) -> usize {
+        self.keys
+            .iter()
+            .rposition(|key| key.active_from <= now)
+            .unwrap_or(0)
+    }
+
+    /// The key quotes are signed with at `now`.
+    pub fn current(&self, now: u64) -> &(dyn Signer<PrimitiveSignature> + Send + Sync) {
+        &*self.keys[self.current_index(now)].signer
+    }
+
+    /// Whether quotes signed by `address` are taken at `now`: it is the current key, or the one
+    /// before it and the rotation was less than `overlap` ago.
+    pub fn accepts(&self, address: Address, now: u64) -> bool {
+        let index = self.current_index(now);
+        if self.keys[index].signer.address() == address {
+            return true;
+        }
+        index > 0
+            && self.keys[index - 1].signer.address() == address
+            && now < self.keys[index].active_from + self.overlap.as_secs()
+    }
+
+    /// Whether `address` is one of the relayer's keys, which it can send transactions from.
+    pub fn holds(&self, address: Address) -> bool {
+        self.keys.iter().any(|key| key.signer.address() == address)
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.keys.iter().map(|key| key.signer.address()).collect()
+    }
+
+    /// Wallet signing transactions from any of the keys.
+    pub fn wallet(&self) -> EthereumWallet {
+        self.wallet.clone()
+    }
+}
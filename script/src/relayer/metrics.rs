@@ -0,0 +1,178 @@
+use alloy::{
+    primitives::{utils::format_units, U256},
+    rpc::json_rpc::{RequestPacket, ResponsePacket},
+    transports::{TransportError, TransportFut},
+};
+use pool_relayer_client::JobStatus;
+use prometheus::{
+    Encoder, Gauge, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Prometheus metrics of a relayer.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    /// Jobs that reached each status.
+    pub jobs: IntCounterVec,
+    /// Time to check a proof against the chain before submitting it.
+    pub verification_seconds: Histogram,
+    pub gas_used: IntCounter,
+    /// Gwei spent on gas.
+    pub gas_spent: IntCounter,
+    /// Gwei earned in relayer fees, valued in ETH.
+    pub fee_revenue: IntCounter,
+    /// Balance of the relayer wallet, updated on each scrape.
+    pub wallet_balance: Gauge,
+    pub rpc_requests: IntCounterVec,
+    pub rpc_errors: IntCounterVec,
+}
+
+/// Convert a wei amount to ETH for a gauge.
+pub fn eth(wei: U256) -> f64 {
+    format_units(wei, "ether")
+        .ok()
+        .and_then(|eth| eth.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Convert a wei amount to whole gwei for a counter, which only counts integers and would
+/// overflow in wei.
+pub fn gwei(wei: U256) -> u64 {
+    (wei / U256::from(1_000_000_000u64)).saturating_to()
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("relayer".to_string()), None)?;
+        let metrics = Self {
+            jobs: IntCounterVec::new(
+                Opts::new("jobs_total", "Jobs that reached each status"),
+                &["status"],
+            )?,
+            verification_seconds: Histogram::with_opts(
+                HistogramOpts::new(
+                    "proof_verification_seconds",
+                    "Time to verify a withdrawal against the chain",
+                )
+                .buckets(vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            )?,
+            gas_used: IntCounter::new("gas_used_total", "Gas used by withdrawal transactions")?,
+            gas_spent: IntCounter::new("gas_spent_gwei_total", "Gwei spent on withdrawal gas")?,
+            fee_revenue: IntCounter::new("fee_revenue_gwei_total", "Gwei earned in relayer fees")?,
+            wallet_balance: Gauge::new("wallet_balance_eth", "Balance of the relayer wallet")?,
+            rpc_requests: IntCounterVec::new(
+                Opts::new("rpc_requests_total", "JSON-RPC requests by method"),
+                &["method"],
+            )?,
+            rpc_errors: IntCounterVec::new(
+                Opts::new("rpc_errors_total", "Failed JSON-RPC requests by method"),
+                &["method"],
+            )?,
+            registry,
+        };
+        metrics.registry.register(Box::new(metrics.jobs.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.verification_seconds.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.gas_used.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.gas_spent.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.fee_revenue.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.wallet_balance.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.rpc_requests.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.rpc_errors.clone()))?;
+        Ok(metrics)
+    }
+
+    pub fn job(&self, status: JobStatus) {
+        self.jobs.with_label_values(&[status.as_str()]).inc();
+    }
+
+    /// Render all metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding does not fail");
+        String::from_utf8(buffer).expect("text encoding is utf-8")
+    }
+}
+
+/// Layer counting JSON-RPC requests and errors by method.
+#[derive(Clone)]
+pub struct RpcMetricsLayer(pub Arc<Metrics>);
+
+impl<S> Layer<S> for RpcMetricsLayer {
+    type Service = RpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcMetricsService {
+            inner,
+            metrics: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcMetricsService<S> {
+    inner: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S> Service<RequestPacket> for RpcMetricsService<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Send
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let method = match &request {
+            RequestPacket::Single(request) => request.method().to_string(),
+            RequestPacket::Batch(_) => "batch".to_string(),
+        };
+        let metrics = self.metrics.clone();
+        metrics.rpc_requests.with_label_values(&[&method]).inc();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let response = future.await;
+            let failed = match &response {
+                Ok(ResponsePacket::Single(response)) => response.is_error(),
+                Ok(ResponsePacket::Batch(responses)) => responses.iter().any(|r| r.is_error()),
+                Err(_) => true,
+            };
+            if failed {
+                metrics.rpc_errors.with_label_values(&[&method]).inc();
+            }
+            response
+        })
+    }
+}
@@ -0,0 +1,1007 @@
+pub mod gas;
+pub mod health;
+pub mod jobs;
+pub mod keys;
+pub mod metrics;
+pub mod policy;
+pub mod screening;
+pub mod spending;
+pub mod txmanager;
+pub mod verify;
+
+use crate::{
+    api::ApiError,
+    pool::Pool,
+    prove::{run_blocking_until, ProofMode, ProverArgs},
+    simulate::simulate_withdrawal,
+    submit::Withdrawal,
+    webhook::{Event, Notifier},
+};
+use alloy::{
+    network::Ethereum,
+    primitives::{keccak256, Address, B256, U256},
+    providers::{Provider, RootProvider},
+    rpc::client::ClientBuilder,
+    sol_types::SolValue,
+    transports::http::reqwest::Url,
+};
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::StatusCode,
+    response::sse::{self, KeepAlive, Sse},
+    routing::{get, post},
+    Json, Router,
+};
+use eyre::{ensure, eyre, Result};
+use futures::{stream, Stream, StreamExt};
+use health::Readiness;
+use jobs::JobStore;
+use keys::KeyManager;
+use metrics::{eth, gwei, Metrics, RpcMetricsLayer};
+use policy::{Policy, RateLimiter, RateLimits, Rejection};
+use pool_lib::{decode_any_public_values, process_any_withdrawal, Variant, WithdrawalData};
+use pool_relayer_client::{
+    Job, JobEvent, JobStage, JobStatus, Quote, WithdrawBody, WithdrawRequest, WithdrawResponse,
+};
+use serde::Deserialize;
+use spending::{SpendingLimiter, SpendingLimits};
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::{broadcast, watch},
+    task::JoinSet,
+};
+use tracing::{debug, info, instrument, warn};
+use txmanager::{Mined, TxConfig, TxManager};
+use verify::verify_proof;
+
+/// Operator settings of a relayer.
+#[derive(Debug, Clone)]
+pub struct RelayerConfig {
+    pub rpc_url: Url,
+    /// Keys quotes are signed and withdrawals sent with.
+    pub keys: Arc<KeyManager>,
+    pub chain_id: u64,
+    /// Fee charged per withdrawal, in wei.
+    pub fee: U256,
+    /// Seconds a quote stays valid.
+    pub quote_ttl: u64,
+    /// Rules checked on accepting a withdrawal and again before sending it.
+    pub policy: Policy,
+    pub limits: RateLimits,
+    /// Verifying key of the program proofs are checked against before anything is sent.
+    pub vkey: B256,
+    /// Prover for withdrawals submitted as raw inputs. Such requests are rejected if not set.
+    pub prover: Option<ProverArgs>,
+    /// Attempts made at a job before it is marked failed.
+    pub max_attempts: u32,
+    /// Blocks on top of a mined withdrawal before the relayer stops watching it for reorgs.
+    pub confirmations: u64,
+    /// When withdrawals are sent after being verified.
+    pub schedule: Schedule,
+    /// Webhooks told when jobs are mined or fail.
+    pub notifier: Option<Notifier>,
+    pub tx: TxConfig,
+    /// Wallet balance, in wei, below which the relayer reports itself not ready.
+    pub min_balance: U256,
+    /// Pending jobs above which the relayer reports itself not ready.
+    pub max_queue_depth: Option<u64>,
+    /// Caps on the gas the wallet spends.
+    pub spending: SpendingLimits,
+}
+
+/// Delays between verifying a withdrawal and sending it, so the time a proof reaches the
+/// relayer does not reveal which on-chain withdrawal it became.
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    /// Upper bound of the random delay added to every submission.
+    pub jitter: Duration,
+    /// If set, withdrawals are held until the next multiple of this interval, so those verified
+    /// within the same window are sent together.
+    pub batch_window: Option<Duration>,
+}
+
+impl Schedule {
+    /// Delay to wait, from `now`, before sending a withdrawal.
+    pub fn delay(&self, now: Duration) -> Duration {
+        let batch = match self.batch_window {
+            Some(window) if !window.is_zero() => {
+                let window = window.as_millis();
+                Duration::from_millis((window - now.as_millis() % window) as u64)
+            }
+            _ => Duration::ZERO,
+        };
+        let jitter = rand::random_range(0..=self.jitter.as_millis() as u64);
+        batch + Duration::from_millis(jitter)
+    }
+}
+
+/// Delay before retrying a failed attempt, doubled on every attempt.
+const RETRY_BACKOFF: Duration = Duration::from_secs(15);
+
+/// How often a mined withdrawal is checked until it settles.
+const SETTLE_INTERVAL: Duration = Duration::from_secs(12);
+
+/// Job events buffered for slow `GET /jobs/:id/events` streams before they miss some.
+const EVENT_CAPACITY: usize = 1024;
+
+/// A relayer: quotes fees, validates withdrawals and submits them from its wallet.
+pub struct Relayer {
+    pub config: RelayerConfig,
+    provider: RootProvider<Ethereum>,
+    jobs: JobStore,
+    txs: TxManager,
+    ip_limiter: Option<RateLimiter>,
+    nullifier_limiter: Option<RateLimiter>,
+    spending: SpendingLimiter,
+    pub metrics: Arc<Metrics>,
+    /// Progress of every job, for `GET /jobs/:id/events`.
+    events: broadcast::Sender<JobEvent>,
+    /// Set once shutdown starts.
+    shutdown: watch::Sender<bool>,
+    /// Background work on jobs, awaited on shutdown.
+    tasks: Mutex<JoinSet<()>>,
+}
+
+/// A job left for the next run because the relayer is shutting down. Not counted as a failed
+/// attempt.
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "relayer is shutting down")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+impl From<Rejection> for ApiError {
+    fn from(rejection: Rejection) -> Self {
+        let status = match rejection {
+            Rejection::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Rejection::SpendingCapReached { .. } | Rejection::ScreeningUnavailable { .. } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            _ => StatusCode::BAD_REQUEST,
+        };
+        Self {
+            status,
+            message: rejection.to_string(),
+            code: Some(rejection.code()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobsQuery {
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+fn default_limit() -> u32 {
+    50
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteQuery {
+    pub chain_id: u64,
+    pub pool: Address,
+    /// ERC-20 token to quote the fee in, instead of wei.
+    pub fee_token: Option<Address>,
+}
+
+impl Relayer {
+    pub fn new(config: RelayerConfig, jobs: JobStore) -> Result<Self> {
+        let metrics = Arc::new(Metrics::new()?);
+        let provider = RootProvider::new(
+            ClientBuilder::default()
+                .layer(RpcMetricsLayer(metrics.clone()))
+                .http(config.rpc_url.clone()),
+        );
+        let txs = TxManager::new(
+            provider.clone(),
+            config.keys.wallet(),
+            config.chain_id,
+            config.tx.clone(),
+        );
+        let limiter =
+            |limit: Option<u32>| limit.map(|limit| RateLimiter::new(limit, config.limits.window));
+        Ok(Self {
+            ip_limiter: limiter(config.limits.per_ip),
+            nullifier_limiter: limiter(config.limits.per_nullifier),
+            spending: SpendingLimiter::new(config.spending.clone()),
+            provider,
+            config,
+            jobs,
+            txs,
+            metrics,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            shutdown: watch::channel(false).0,
+            tasks: Mutex::new(JoinSet::new()),
+        })
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Stop accepting withdrawals and starting new work on queued jobs. Transactions already
+    /// being sent are seen through until mined.
+    pub fn begin_shutdown(&self) {
+        if !self.shutdown.send_replace(true) {
+            info!("shutting down, no longer accepting withdrawals");
+        }
+    }
+
+    /// Fail with [`Interrupted`] if shutdown has started.
+    fn check_running(&self) -> Result<()> {
+        if self.is_shutting_down() {
+            return Err(Interrupted.into());
+        }
+        Ok(())
+    }
+
+    /// Resolves once shutdown starts.
+    async fn shutdown_started(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+        // The sender lives as long as the relayer, so this only returns once it is set.
+        let _ = shutdown.wait_for(|shutting_down| *shutting_down).await;
+    }
+
+    /// Run a blocking prover call, failing with [`Interrupted`] once shutdown starts so the job
+    /// is left for the next run. Ctrl-C is the relayer's to handle, by draining, so the call does
+    /// not race it.
+    async fn run_blocking<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let interrupted = async {
+            self.shutdown_started().await;
+            Interrupted.into()
+        };
+        run_blocking_until(f, None, interrupted).await
+    }
+
+    /// Wait up to `timeout` for in-flight jobs to reach a point they can be resumed from, then
+    /// flush the job database. Jobs still running after that are resumed on the next start.
+    pub async fn drain(&self, timeout: Duration) -> Result<()> {
+        self.begin_shutdown();
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        info!(jobs = tasks.len(), "draining in-flight jobs");
+        let drained = tokio::time::timeout(timeout, async {
+            while tasks.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                jobs = tasks.len(),
+                "jobs still running at the shutdown timeout, they resume on the next start"
+            );
+            tasks.abort_all();
+        }
+        self.jobs.checkpoint()?;
+        info!("relayer state saved");
+        Ok(())
+    }
+
+    /// Restart every job left unfinished by a previous run.
+    pub fn resume(self: &Arc<Self>) -> Result<()> {
+        for pending in self.jobs.pending()? {
+            info!(job_id = pending.job.id, status = ?pending.job.status, "resuming job");
+            self.spawn(pending.job.id, pending.job.pool, pending.body);
+        }
+        Ok(())
+    }
+
+    /// Address of the key quotes are signed with now.
+    pub fn address(&self) -> Address {
+        self.config
+            .keys
+            .current(now().unwrap_or_default())
+            .address()
+    }
+
+    /// Tell event streams following job `id` that it reached `stage`.
+    fn publish(&self, job_id: &str, stage: JobStage) {
+        let event = JobEvent {
+            job_id: job_id.to_string(),
+            stage,
+            at: now().unwrap_or_default(),
+        };
+        // Sending only fails when nobody is listening.
+        let _ = self.events.send(event);
+    }
+
+    /// Events of every job from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
+    }
+
+    fn check_pool(&self, chain_id: u64, pool: Address) -> Result<(), ApiError> {
+        if chain_id != self.config.chain_id {
+            return Err(ApiError::bad_request(format!(
+                "relayer serves chain {}, not {chain_id}",
+                self.config.chain_id
+            )));
+        }
+        Ok(self.config.policy.check_pool(pool)?)
+    }
+
+    /// Sign a quote for withdrawing from `pool` at the configured fee, in wei or in
+    /// `fee_token` at its price feed's rate.
+    pub async fn quote(
+        &self,
+        chain_id: u64,
+        pool: Address,
+        fee_token: Option<Address>,
+    ) -> Result<Quote, ApiError> {
+        self.check_pool(chain_id, pool)?;
+        let fee = match fee_token {
+            Some(token) => self
+                .config
+                .policy
+                .fee_token_price(&self.provider, token)
+                .await??
+                .from_wei(self.config.fee),
+            None => self.config.fee,
+        };
+        let now = now()?;
+        Ok(Quote::sign(
+            self.config.keys.current(now),
+            chain_id,
+            pool,
+            fee,
+            now + self.config.quote_ttl,
+            fee_token,
+        )
+        .await?)
+    }
+
+    /// What the fee `quote` offers is worth in wei, at the current price for a token fee.
+    async fn quote_value(&self, quote: &Quote) -> Result<U256> {
+        let Some(token) = quote.fee_token else {
+            return Ok(quote.fee);
+        };
+        let price = self
+            .config
+            .policy
+            .fee_token_price(&self.provider, token)
+            .await??;
+        Ok(price.to_wei(quote.fee))
+    }
+
+    /// Check the committed withdrawal takes a quote this relayer signed and that has not expired.
+    fn check_terms(
+        &self,
+        pool: Address,
+        quote: &Quote,
+        data: &WithdrawalData,
+    ) -> Result<(), ApiError> {
+        if data.contractAddress != pool || quote.pool != pool {
+            return Err(ApiError::bad_request("proof is for a different pool"));
+        }
+        if !self.config.keys.accepts(quote.relayer, now()?) {
+            return Err(ApiError::bad_request(
+                "quote was not issued by this relayer",
+            ));
+        }
+        quote
+            .check(data, now()?)
+            .map_err(|e| ApiError::bad_request(e.to_string()))
+    }
+
+    /// Check a submitted proof is for the pinned program, which the pool also verifies, and that
+    /// it verifies locally, before any gas is spent on it.
+    async fn check_proof(&self, pool: Address, request: &WithdrawRequest) -> Result<(), ApiError> {
+        let pool_vkey = Pool::new(pool, &self.provider)
+            .programVkey()
+            .call()
+            .await
+            .map_err(|e| eyre!("failed to read the pool's program: {e}"))?
+            ._0;
+        if pool_vkey != self.config.vkey {
+            return Err(Rejection::ProgramMismatch {
+                pool_vkey,
+                vkey: self.config.vkey,
+            }
+            .into());
+        }
+
+        let vkey = self.config.vkey;
+        let public_values = request.public_values.clone();
+        let proof = request.proof.clone();
+        tokio::task::spawn_blocking(move || verify_proof(vkey, &public_values, &proof))
+            .await
+            .map_err(|e| eyre!("proof verification panicked: {e}"))??;
+        Ok(())
+    }
+
+    /// Validate the terms of a withdrawal from `client` and queue it, returning the job id.
+    pub async fn accept(
+        self: &Arc<Self>,
+        client: IpAddr,
+        body: WithdrawBody,
+    ) -> Result<String, ApiError> {
+        if self.is_shutting_down() {
+            return Err(ApiError::unavailable("relayer is shutting down"));
+        }
+        if let Some(limiter) = &self.ip_limiter {
+            limiter.check(&client.to_string())?;
+        }
+        let pool = body.pool();
+        self.check_pool(body.chain_id(), pool)?;
+        let (data, bridge) = match &body {
+            WithdrawBody::Proof(request) => {
+                let data = Withdrawal {
+                    public_values: request.public_values.clone(),
+                    proof: request.proof.clone(),
+                    bridge: None,
+                    splits: Vec::new(),
+                    token_fee: None,
+                }
+                .with_bridge(request.bridge.clone())
+                .and_then(|withdrawal| relayed_data(&withdrawal))
+                .map_err(|e| ApiError::bad_request(format!("invalid public values: {e}")))?;
+                (data, request.bridge.as_ref())
+            }
+            WithdrawBody::Input(request) => {
+                if self.config.prover.is_none() {
+                    return Err(ApiError::bad_request("relayer does not prove withdrawals"));
+                }
+                let data = process_any_withdrawal(&request.input)
+                    .map_err(|e| ApiError::bad_request(format!("invalid input: {e}")))?
+                    .withdrawal;
+                (data, request.input.bridge.as_ref())
+            }
+        };
+        if !RELAYED_VARIANTS
+            .iter()
+            .any(|&variant| variant as u8 == data.variant)
+        {
+            return Err(Rejection::VariantNotRelayed {
+                variant: data.variant,
+            }
+            .into());
+        }
+        // The pool checks the consent too, but only once the relayer has paid the gas.
+        if !data.feeToken.is_zero() {
+            if data.variant != Variant::Base as u8 {
+                return Err(ApiError::bad_request(
+                    "only plain withdrawals pay their relayer in a token",
+                ));
+            }
+            body.token_fee()
+                .ok_or_else(|| {
+                    ApiError::bad_request("a fee in a token needs the recipient's consent")
+                })?
+                .verify(body.chain_id(), &data)
+                .map_err(|e| ApiError::bad_request(e.to_string()))?;
+        }
+        self.check_terms(pool, body.quote(), &data)?;
+        if let Some(limiter) = &self.nullifier_limiter {
+            let prefix = &data.nullifier[..self.config.limits.nullifier_prefix.min(32)];
+            limiter.check(&hex::encode(prefix))?;
+        }
+        if let WithdrawBody::Proof(request) = &body {
+            self.check_proof(pool, request).await?;
+        }
+        self.config.policy.check(&self.provider, &data).await??;
+        // The recipient of a withdrawal to an L2 address is the bridge, so the funds' owner is
+        // screened as well.
+        if let Some(message) = bridge {
+            self.config.policy.screen(message.l2_recipient).await?;
+        }
+        self.spending.check(&self.jobs)??;
+
+        let id = job_id(self.config.chain_id, pool, &data);
+        match self.jobs.get(&id)? {
+            Some(job) if job.status != JobStatus::Failed => return Ok(id),
+            Some(_) => self.jobs.requeue(&id, &body)?,
+            None => {
+                self.jobs.insert(&id, pool, &body)?;
+            }
+        }
+        self.metrics.job(JobStatus::Queued);
+        self.publish(&id, JobStage::Queued);
+        self.spawn(id.clone(), pool, body);
+        Ok(id)
+    }
+
+    /// Work on job `id` in the background, retrying failed attempts with backoff until
+    /// `max_attempts` is reached.
+    fn spawn(self: &Arc<Self>, id: String, pool: Address, body: WithdrawBody) {
+        let relayer = self.clone();
+        let mut tasks = self.tasks.lock().unwrap();
+        // Reap finished tasks so the set only holds running ones.
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(async move {
+            loop {
+                let result = relayer.process(&id, pool, body.clone()).await;
+                let error = match result {
+                    Ok(()) => return,
+                    Err(e) if e.is::<Interrupted>() => {
+                        info!(job_id = id, "job left for the next run");
+                        return;
+                    }
+                    Err(e) => e,
+                };
+                // A withdrawal the policy refuses fails with the rejection instead of being retried
+                // until the rule happens to pass. The client can requeue it.
+                let rejected = error.is::<Rejection>();
+                let error = error.to_string();
+                let attempts = match relayer.jobs.add_attempt(&id) {
+                    Ok(attempts) => attempts,
+                    Err(e) => {
+                        warn!(job_id = id, "failed to update job: {e}");
+                        return;
+                    }
+                };
+                let terminal = rejected || attempts >= relayer.config.max_attempts;
+                warn!(
+                    job_id = id,
+                    attempts, terminal, "relay attempt failed: {error}"
+                );
+                if let Err(e) = relayer.jobs.record_error(&id, &error, terminal) {
+                    warn!(job_id = id, "failed to update job: {e}");
+                    return;
+                }
+                if terminal {
+                    relayer.metrics.job(JobStatus::Failed);
+                    relayer.publish(
+                        &id,
+                        JobStage::Failed {
+                            error: error.clone(),
+                        },
+                    );
+                    if let Some(notifier) = &relayer.config.notifier {
+                        let tx_hash = relayer
+                            .jobs
+                            .get(&id)
+                            .ok()
+                            .flatten()
+                            .and_then(|job| job.tx_hash);
+                        notifier.notify(Event::JobFailed {
+                            job_id: id.clone(),
+                            error,
+                            tx_hash,
+                        });
+                    }
+                    return;
+                }
+                relayer.publish(&id, JobStage::Retrying { attempts, error });
+                tokio::select! {
+                    _ = tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempts - 1)) => {}
+                    _ = relayer.shutdown_started() => return,
+                }
+            }
+        });
+    }
+
+    /// Prove if needed, check the proof against the pool's verifier, submit, and wait for the
+    /// withdrawal to settle.
+    #[instrument(skip(self, body))]
+    async fn process(&self, job_id: &str, pool: Address, body: WithdrawBody) -> Result<()> {
+        let fee = self.quote_value(body.quote()).await?;
+        // A transaction sent by an earlier attempt, or any replacement of it, may have been
+        // mined since.
+        for tx_hash in self.jobs.tx_hashes(job_id)? {
+            if let Some(mined) = self.txs.receipt(tx_hash).await? {
+                return self.settle(job_id, mined, fee).await;
+            }
+        }
+
+        self.check_running()?;
+        self.jobs.set_status(job_id, JobStatus::Verifying)?;
+        self.metrics.job(JobStatus::Verifying);
+        self.publish(job_id, JobStage::Verifying);
+        let withdrawal = match body {
+            WithdrawBody::Proof(request) => Withdrawal {
+                public_values: request.public_values,
+                proof: request.proof,
+                bridge: None,
+                splits: Vec::new(),
+                token_fee: None,
+            }
+            .with_bridge(request.bridge)?
+            .with_token_fee(request.token_fee)?,
+            WithdrawBody::Input(request) => {
+                let backend = self
+                    .config
+                    .prover
+                    .as_ref()
+                    .ok_or_else(|| eyre!("relayer does not prove withdrawals"))?
+                    .backend()?;
+                // Executing first is quick next to proving, and tells clients what to expect.
+                let (executor, input) = (backend.clone(), request.input.clone());
+                let execution = self.run_blocking(move || executor.execute(&input)).await?;
+                self.publish(
+                    job_id,
+                    JobStage::Executed {
+                        cycles: execution.cycles,
+                    },
+                );
+                self.publish(job_id, JobStage::Proving);
+                let input = request.input;
+                let bridge = input.bridge.clone();
+                let proof = self
+                    .run_blocking(move || backend.prove(&input, ProofMode::Groth16))
+                    .await?;
+                self.publish(job_id, JobStage::Proved);
+                Withdrawal {
+                    public_values: proof.public_values,
+                    proof: proof.proof,
+                    bridge: None,
+                    splits: Vec::new(),
+                    token_fee: None,
+                }
+                .with_bridge(bridge)?
+                .with_token_fee(request.token_fee)?
+            }
+        };
+
+        // Running the withdrawal against the chain also checks the proof with the verifier. It
+        // is sent from the key the proof commits to, which may have been rotated away from
+        // since the quote.
+        let data = relayed_data(&withdrawal)?;
+        ensure!(
+            self.config.keys.holds(data.relayer),
+            "withdrawal pays relayer {} whose key is not loaded",
+            data.relayer
+        );
+        let start = Instant::now();
+        simulate_withdrawal(&self.provider, pool, data.relayer, &withdrawal).await?;
+        self.metrics
+            .verification_seconds
+            .observe(start.elapsed().as_secs_f64());
+        self.publish(job_id, JobStage::Verified);
+        let delay = self
+            .config
+            .schedule
+            .delay(SystemTime::now().duration_since(UNIX_EPOCH)?);
+        if !delay.is_zero() {
+            debug!(delay = ?delay, "delaying submission");
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.shutdown_started() => return Err(Interrupted.into()),
+            }
+        }
+
+        // Gas prices and the proof's age change while a job waits, so the policy is checked
+        // again right before spending gas.
+        self.config.policy.check(&self.provider, &data).await??;
+        self.check_running()?;
+        let max_cost = self
+            .config
+            .tx
+            .max_cost_percent
+            .map(|percent| fee * U256::from(percent) / U256::from(100));
+        let broadcasting = AtomicBool::new(false);
+        let mined = self
+            .txs
+            .send(
+                data.relayer,
+                pool,
+                withdrawal.calldata(),
+                max_cost,
+                |cost| self.reserve(job_id, cost),
+                |tx_hash| {
+                    if let Err(e) = self.jobs.add_tx_hash(job_id, tx_hash) {
+                        warn!(job_id, "failed to update job: {e}");
+                    }
+                    // The job is broadcasting once the node has taken its first transaction,
+                    // not while the send can still fail before anything is out.
+                    if !broadcasting.swap(true, Ordering::Relaxed) {
+                        if let Err(e) = self.jobs.set_status(job_id, JobStatus::Broadcasting) {
+                            warn!(job_id, "failed to update job: {e}");
+                        }
+                        self.metrics.job(JobStatus::Broadcasting);
+                        self.publish(job_id, JobStage::Broadcasting);
+                    }
+                    self.publish(job_id, JobStage::Sent { tx_hash });
+                },
+            )
+            .await?;
+        self.settle(job_id, mined, fee).await
+    }
+
+    /// Follow a mined withdrawal until it is `confirmations` blocks deep. If a reorg drops
+    /// it, the job is `reorged` until the transaction is mined again, and the attempt fails if
+    /// that does not happen within `stuck_after`.
+    async fn settle(&self, job_id: &str, mut mined: Mined, fee: U256) -> Result<()> {
+        info!(
+            tx_hash = %mined.tx_hash,
+            block_number = mined.block_number,
+            gas_used = mined.gas_used,
+            cost = %mined.cost,
+            "withdrawal mined"
+        );
+        self.record_mined(job_id, &mined)?;
+
+        let mut reorged_at = None;
+        loop {
+            let head = self.provider.get_block_number().await?;
+            match self.txs.receipt(mined.tx_hash).await? {
+                Some(current) if reorged_at.is_some() || current.block_hash != mined.block_hash => {
+                    info!(
+                        block_number = current.block_number,
+                        "withdrawal mined in a new block"
+                    );
+                    mined = current;
+                    reorged_at = None;
+                    self.record_mined(job_id, &mined)?;
+                }
+                Some(_) if head >= mined.block_number + self.config.confirmations => break,
+                Some(_) => {}
+                None => match reorged_at {
+                    None => {
+                        warn!(tx_hash = %mined.tx_hash, "withdrawal dropped by a reorg");
+                        self.jobs.set_status(job_id, JobStatus::Reorged)?;
+                        self.metrics.job(JobStatus::Reorged);
+                        self.publish(job_id, JobStage::Reorged);
+                        if let Some(notifier) = &self.config.notifier {
+                            notifier.notify(Event::JobReorged {
+                                job_id: job_id.to_string(),
+                                tx_hash: mined.tx_hash,
+                            });
+                        }
+                        reorged_at = Some(Instant::now());
+                    }
+                    Some(at) => ensure!(
+                        at.elapsed() < self.config.tx.stuck_after,
+                        "transaction {} was dropped by a reorg and not mined again",
+                        mined.tx_hash
+                    ),
+                },
+            }
+            // The mined transaction is recorded, so watching it can resume on the next start.
+            tokio::select! {
+                _ = tokio::time::sleep(SETTLE_INTERVAL) => {}
+                _ = self.shutdown_started() => return Err(Interrupted.into()),
+            }
+        }
+
+        self.jobs.settle(job_id)?;
+        self.publish(job_id, JobStage::Settled);
+        self.metrics.gas_used.inc_by(mined.gas_used);
+        self.metrics.gas_spent.inc_by(gwei(mined.cost));
+        self.metrics.fee_revenue.inc_by(gwei(fee));
+        Ok(())
+    }
+
+    /// Reserve `cost` for job `id` under the spending caps, alerting the webhooks when a cap
+    /// holds it back.
+    fn reserve(&self, job_id: &str, cost: U256) -> Result<()> {
+        let result = self.spending.reserve(&self.jobs, job_id, cost);
+        if let Some(Rejection::SpendingCapReached { window, cap }) =
+            result.as_ref().err().and_then(|e| e.downcast_ref())
+        {
+            warn!(job_id, window, %cap, "spending cap reached");
+            if let Some(notifier) = &self.config.notifier {
+                notifier.notify(Event::SpendingCapReached {
+                    window: window.to_string(),
+                    cap: *cap,
+                });
+            }
+        }
+        result
+    }
+
+    fn record_mined(&self, job_id: &str, mined: &Mined) -> Result<()> {
+        self.jobs.mined(job_id, mined)?;
+        self.jobs.settle_spend(job_id, mined.cost)?;
+        self.metrics.job(JobStatus::Mined);
+        self.publish(
+            job_id,
+            JobStage::Mined {
+                tx_hash: mined.tx_hash,
+                block_number: mined.block_number,
+            },
+        );
+        if let Some(notifier) = &self.config.notifier {
+            notifier.notify(Event::JobMined {
+                job_id: job_id.to_string(),
+                tx_hash: mined.tx_hash,
+                block_number: mined.block_number,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn job(&self, id: &str) -> Result<Option<Job>> {
+        self.jobs.get(id)
+    }
+
+    pub fn jobs(&self, limit: u32, offset: u32) -> Result<Vec<Job>> {
+        self.jobs.list(limit, offset)
+    }
+}
+
+/// Variants the relayer takes: those a pool pays out given only the proof and, for withdrawals to
+/// an L2 address, the bridge message a request carries. Split withdrawals also need their
+/// recipients, and no pool pays out the others.
+const RELAYED_VARIANTS: [Variant; 6] = [
+    Variant::Base,
+    Variant::ProtocolFee,
+    Variant::Bridge,
+    Variant::Registry,
+    Variant::Oracle,
+    Variant::Association,
+];
+
+/// Public values of a withdrawal, whichever variant they are.
+fn relayed_data(withdrawal: &Withdrawal) -> Result<WithdrawalData> {
+    Ok(decode_any_public_values(&withdrawal.public_values)?.withdrawal)
+}
+
+/// Job id of a withdrawal: the first 16 bytes of the hash of the chain, pool and nullifier.
+fn job_id(chain_id: u64, pool: Address, data: &WithdrawalData) -> String {
+    let hash = keccak256((U256::from(chain_id), pool, data.nullifier).abi_encode());
+    hex::encode(&hash[..16])
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// HTTP API of `relayer`.
+pub fn router(relayer: Arc<Relayer>) -> Router {
+    Router::new()
+        .route("/quote", get(quote))
+        .route("/withdraw", post(withdraw))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:id", get(job))
+        .route("/jobs/:id/events", get(job_events))
+        .route("/metrics", get(render_metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(relayer)
+}
+
+/// Resolves on SIGTERM or Ctrl-C.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("failed to listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+async fn readyz(State(relayer): State<Arc<Relayer>>) -> (StatusCode, Json<Readiness>) {
+    let readiness = relayer.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+async fn quote(
+    State(relayer): State<Arc<Relayer>>,
+    Query(query): Query<QuoteQuery>,
+) -> Result<Json<Quote>, ApiError> {
+    Ok(Json(
+        relayer
+            .quote(query.chain_id, query.pool, query.fee_token)
+            .await?,
+    ))
+}
+
+async fn withdraw(
+    State(relayer): State<Arc<Relayer>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Json(body): Json<WithdrawBody>,
+) -> Result<Json<WithdrawResponse>, ApiError> {
+    let job_id = relayer.accept(client.ip(), body).await?;
+    Ok(Json(WithdrawResponse { job_id }))
+}
+
+async fn job(
+    State(relayer): State<Arc<Relayer>>,
+    Path(id): Path<String>,
+) -> Result<Json<Job>, ApiError> {
+    relayer
+        .job(&id)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no job {id}")))
+}
+
+fn status_event(job: &Job) -> Result<sse::Event, axum::Error> {
+    sse::Event::default().event("status").json_data(job)
+}
+
+/// Stream the events of job `id` as they happen, starting with a `status` event holding the job
+/// as `GET /jobs/:id` returns it. The stream ends after the job's final event, or at once if the
+/// job is already final.
+async fn job_events(
+    State(relayer): State<Arc<Relayer>>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, axum::Error>>>, ApiError> {
+    // Subscribe before reading the job so no event falls between the two.
+    let events = relayer.subscribe();
+    let job = relayer
+        .job(&id)?
+        .ok_or_else(|| ApiError::not_found(format!("no job {id}")))?;
+    let first = status_event(&job);
+    let updates = stream::unfold((events, job.is_final()), move |(mut events, done)| {
+        let (relayer, id) = (relayer.clone(), id.clone());
+        async move {
+            if done {
+                return None;
+            }
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = relayer.shutdown_started() => return None,
+                };
+                match event {
+                    Ok(event) if event.job_id == id => {
+                        let done = event.stage.is_final();
+                        let sse = sse::Event::default()
+                            .event(event.stage.name())
+                            .json_data(&event);
+                        return Some((sse, (events, done)));
+                    }
+                    Ok(_) => {}
+                    // Events were missed, so send where the job is now instead.
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let job = relayer.job(&id).ok().flatten()?;
+                        let done = job.is_final();
+                        return Some((status_event(&job), (events, done)));
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Ok(Sse::new(stream::once(async { first }).chain(updates)).keep_alive(KeepAlive::default()))
+}
+
+async fn render_metrics(State(relayer): State<Arc<Relayer>>) -> String {
+    match relayer.provider.get_balance(relayer.address()).await {
+        Ok(balance) => relayer.metrics.wallet_balance.set(eth(balance)),
+        Err(e) => warn!("failed to fetch wallet balance: {e}"),
+    }
+    relayer.metrics.render()
+}
+
+async fn list_jobs(
+    State(relayer): State<Arc<Relayer>>,
+    Query(query): Query<JobsQuery>,
+) -> Result<Json<Vec<Job>>, ApiError> {
+    Ok(Json(relayer.jobs(query.limit.min(500), query.offset)?))
+}
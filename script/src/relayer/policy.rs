@@ -0,0 +1,433 @@
+use super::screening::Screener;
+use crate::{
+    pool::Pool,
+    price::{PricedToken, TokenPrice},
+};
+use alloy::{
+    primitives::{Address, B256, U256},
+    providers::Provider,
+};
+use eyre::Result;
+use pool_lib::{association::AllowListBundle, WithdrawalData};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// Rules a withdrawal must satisfy before the relayer spends gas on it.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// Pools served. Any pool if empty.
+    pub pools: Vec<Address>,
+    /// Minimum fee in wei.
+    pub min_fee: U256,
+    /// Minimum fee in basis points of the pool denomination.
+    pub min_fee_bps: Option<u64>,
+    /// Highest gas price, in wei, the relayer submits at.
+    pub max_gas_price: Option<u128>,
+    /// Most blocks allowed between the proof's block and the current block. The pool itself
+    /// rejects proofs more than 256 blocks old.
+    pub max_proof_age: Option<u64>,
+    /// Trusted allow-lists. If any are set, a withdrawal's `exclusionSetRoot` must be the
+    /// root of one for its pool that is valid now.
+    pub allow_lists: Vec<AllowListBundle>,
+    /// Service each recipient is screened with.
+    pub screener: Option<Screener>,
+    /// ERC-20 tokens fees may be paid in, with the feeds pricing them in ETH. Token fees are
+    /// held to the same minimums at the feed's price.
+    pub fee_tokens: Vec<PricedToken>,
+    /// Seconds after which a price feed is too stale to value a fee with.
+    pub max_price_age: u64,
+}
+
+/// Why a withdrawal was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    PoolNotServed { pool: Address },
+    FeeTooLow { fee: U256, min: U256 },
+    GasPriceTooHigh { gas_price: u128, max: u128 },
+    ProofTooOld { age: u64, max: u64 },
+    RateLimited { retry_after: Duration },
+    InvalidProof { reason: String },
+    ProgramMismatch { pool_vkey: B256, vkey: B256 },
+    UntrustedAllowList { root: B256 },
+    SpendingCapReached { window: &'static str, cap: U256 },
+    RecipientBlocked { recipient: Address, reason: String },
+    ScreeningUnavailable { reason: String },
+    FeeTokenNotAccepted { token: Address },
+    VariantNotRelayed { variant: u8 },
+}
+
+impl Rejection {
+    /// Stable identifier returned to clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::PoolNotServed { .. } => "pool_not_served",
+            Self::FeeTooLow { .. } => "fee_too_low",
+            Self::GasPriceTooHigh { .. } => "gas_price_too_high",
+            Self::ProofTooOld { .. } => "proof_too_old",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::InvalidProof { .. } => "invalid_proof",
+            Self::ProgramMismatch { .. } => "program_mismatch",
+            Self::UntrustedAllowList { .. } => "untrusted_allow_list",
+            Self::SpendingCapReached { .. } => "spending_cap_reached",
+            Self::RecipientBlocked { .. } => "recipient_blocked",
+            Self::ScreeningUnavailable { .. } => "screening_unavailable",
+            Self::FeeTokenNotAccepted { .. } => "fee_token_not_accepted",
+            Self::VariantNotRelayed { .. } => "variant_not_relayed",
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PoolNotServed { pool } => write!(f, "pool {pool} is not served"),
+            Self::FeeTooLow { fee, min } => write!(f, "fee {fee} is below the minimum {min}"),
+            Self::GasPriceTooHigh { gas_price, max } => {
+                write!(f, "gas price {gas_price} is above the maximum {max}")
+            }
+            Self::ProofTooOld { age, max } => {
+                write!(f, "proof is {age} blocks old, the maximum is {max}")
+            }
+            Self::RateLimited { retry_after } => {
+                write!(f, "rate limited, retry in {}s", retry_after.as_secs() + 1)
+            }
+            Self::InvalidProof { reason } => write!(f, "proof does not verify: {reason}"),
+            Self::ProgramMismatch { pool_vkey, vkey } => write!(
+                f,
+                "pool verifies program {pool_vkey}, the relayer only submits {vkey}"
+            ),
+            Self::UntrustedAllowList { root } => {
+                write!(f, "allow-list {root} is not one the relayer trusts")
+            }
+            Self::SpendingCapReached { window, cap } => {
+                write!(
+                    f,
+                    "relayer has spent its {cap} wei of gas for this {window}"
+                )
+            }
+            Self::RecipientBlocked { recipient, reason } => {
+                write!(f, "recipient {recipient} did not pass screening: {reason}")
+            }
+            Self::ScreeningUnavailable { reason } => {
+                write!(f, "recipient could not be screened: {reason}")
+            }
+            Self::FeeTokenNotAccepted { token } => {
+                write!(f, "fees are not accepted in token {token}")
+            }
+            Self::VariantNotRelayed { variant } => {
+                write!(f, "withdrawals of variant {variant} are not relayed")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+impl Policy {
+    pub fn check_pool(&self, pool: Address) -> Result<(), Rejection> {
+        if !self.pools.is_empty() && !self.pools.contains(&pool) {
+            return Err(Rejection::PoolNotServed { pool });
+        }
+        Ok(())
+    }
+
+    /// Evaluate every rule against `data` and the current chain state. The outer error is a
+    /// failure to read the chain, the inner one a rejection.
+    pub async fn check<P: Provider>(
+        &self,
+        provider: &P,
+        data: &WithdrawalData,
+    ) -> Result<Result<(), Rejection>> {
+        if let Err(rejection) = self.check_pool(data.contractAddress) {
+            return Ok(Err(rejection));
+        }
+
+        let mut min = self.min_fee;
+        if let Some(bps) = self.min_fee_bps {
+            let amount = Pool::new(data.contractAddress, provider)
+                .amount()
+                .call()
+                .await?
+                ._0;
+            min = min.max(amount * U256::from(bps) / U256::from(10_000));
+        }
+        let fee = match self.fee_value(provider, data).await? {
+            Ok(fee) => fee,
+            Err(rejection) => return Ok(Err(rejection)),
+        };
+        if fee < min {
+            return Ok(Err(Rejection::FeeTooLow { fee, min }));
+        }
+
+        if let Some(max) = self.max_gas_price {
+            let gas_price = provider.get_gas_price().await?;
+            if gas_price > max {
+                return Ok(Err(Rejection::GasPriceTooHigh { gas_price, max }));
+            }
+        }
+
+        if !self.allow_lists.is_empty() {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let trusted = self.allow_lists.iter().any(|bundle| {
+                bundle.pool == data.contractAddress
+                    && bundle.allow_list.root == data.exclusionSetRoot
+                    && bundle.is_valid_at(now)
+            });
+            if !trusted {
+                return Ok(Err(Rejection::UntrustedAllowList {
+                    root: data.exclusionSetRoot,
+                }));
+            }
+        }
+
+        if let Err(rejection) = self.screen(data.recipient).await {
+            return Ok(Err(rejection));
+        }
+
+        if let Some(max) = self.max_proof_age {
+            let age = provider
+                .get_block_number()
+                .await?
+                .saturating_sub(data.blockNumber);
+            if age > max {
+                return Ok(Err(Rejection::ProofTooOld { age, max }));
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    /// The price of `token`, if fees are accepted in it.
+    pub async fn fee_token_price<P: Provider>(
+        &self,
+        provider: &P,
+        token: Address,
+    ) -> Result<Result<TokenPrice, Rejection>> {
+        let Some(priced) = self.fee_tokens.iter().find(|priced| priced.token == token) else {
+            return Ok(Err(Rejection::FeeTokenNotAccepted { token }));
+        };
+        Ok(Ok(priced.price(provider, self.max_price_age).await?))
+    }
+
+    /// What the fee `data` pays its relayer is worth in wei.
+    pub async fn fee_value<P: Provider>(
+        &self,
+        provider: &P,
+        data: &WithdrawalData,
+    ) -> Result<Result<U256, Rejection>> {
+        if data.feeToken.is_zero() {
+            return Ok(Ok(data.relayerFee));
+        }
+        Ok(self
+            .fee_token_price(provider, data.feeToken)
+            .await?
+            .map(|price| price.to_wei(data.feeTokenAmount)))
+    }
+
+    /// Screen `recipient` with the screening service, if there is one.
+    pub async fn screen(&self, recipient: Address) -> Result<(), Rejection> {
+        let Some(screener) = &self.screener else {
+            return Ok(());
+        };
+        match screener.screen(recipient).await {
+            Ok(None) => Ok(()),
+            Ok(Some(reason)) => Err(Rejection::RecipientBlocked { recipient, reason }),
+            Err(e) if screener.fail_open => {
+                warn!(%recipient, "screening failed, accepting: {e}");
+                Ok(())
+            }
+            Err(e) => Err(Rejection::ScreeningUnavailable {
+                reason: e.to_string(),
+            }),
+        }
+    }
+}
+
+/// Request limits applied when a withdrawal is accepted.
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    /// Withdrawals accepted per client IP every `window`.
+    pub per_ip: Option<u32>,
+    /// Withdrawals accepted per nullifier prefix every `window`. Retries of the same note, or
+    /// of a few notes sharing a prefix, are held back without tracking every nullifier.
+    pub per_nullifier: Option<u32>,
+    /// Bytes of the nullifier that make up the prefix.
+    pub nullifier_prefix: usize,
+    pub window: Duration,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            per_ip: None,
+            per_nullifier: None,
+            nullifier_prefix: 4,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Fixed-window request counter per key.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Allow `limit` requests per key every `window`.
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request for `key`, rejecting it if the key is over its limit.
+    pub fn check(&self, key: &str) -> Result<(), Rejection> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        windows.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        let (start, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if *count >= self.limit {
+            return Err(Rejection::RateLimited {
+                retry_after: self.window - now.duration_since(*start),
+            });
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::{Cassette, ReplayTransport};
+    use alloy::{network::Ethereum, providers::RootProvider, rpc::client::ClientBuilder};
+    use pool_lib::fixture::{withdrawal_data as withdrawal, POOL};
+
+    /// A provider answering `method` with `result`, and nothing else.
+    fn provider(method: &str, result: &str) -> RootProvider<Ethereum> {
+        let cassette: Cassette = serde_json::from_str(&format!(
+            r#"{{"interactions": [{{"method": "{method}", "params": null,
+                "response": {{"jsonrpc": "2.0", "id": 0, "result": "{result}"}}}}]}}"#
+        ))
+        .unwrap();
+        RootProvider::new(ClientBuilder::default().transport(ReplayTransport::new(cassette), true))
+    }
+
+    #[test]
+    fn serves_listed_pools() {
+        assert_eq!(Policy::default().check_pool(POOL), Ok(()));
+        let policy = Policy {
+            pools: vec![POOL],
+            ..Default::default()
+        };
+        assert_eq!(policy.check_pool(POOL), Ok(()));
+        let other = Address::with_last_byte(4);
+        assert_eq!(
+            policy.check_pool(other),
+            Err(Rejection::PoolNotServed { pool: other })
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_with_no_rules() {
+        let provider = provider("eth_chainId", "0x1");
+        let result = Policy::default().check(&provider, &withdrawal()).await;
+        assert_eq!(result.unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn rejects_low_fees() {
+        let provider = provider("eth_chainId", "0x1");
+        let policy = Policy {
+            min_fee: U256::from(2000),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check(&provider, &withdrawal()).await.unwrap(),
+            Err(Rejection::FeeTooLow {
+                fee: U256::from(1000),
+                min: U256::from(2000)
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_fees_in_unlisted_tokens() {
+        let provider = provider("eth_chainId", "0x1");
+        let token = Address::with_last_byte(7);
+        let mut data = withdrawal();
+        data.feeToken = token;
+        data.feeTokenAmount = U256::from(1000);
+        assert_eq!(
+            Policy::default().check(&provider, &data).await.unwrap(),
+            Err(Rejection::FeeTokenNotAccepted { token })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_high_gas_prices() {
+        let provider = provider("eth_gasPrice", "0x3b9aca00");
+        let policy = Policy {
+            max_gas_price: Some(1_000_000_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check(&provider, &withdrawal()).await.unwrap(),
+            Ok(())
+        );
+        let policy = Policy {
+            max_gas_price: Some(999_999_999),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check(&provider, &withdrawal()).await.unwrap(),
+            Err(Rejection::GasPriceTooHigh {
+                gas_price: 1_000_000_000,
+                max: 999_999_999
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_old_proofs() {
+        let provider = provider("eth_blockNumber", "0x100");
+        let policy = Policy {
+            max_proof_age: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check(&provider, &withdrawal()).await.unwrap(),
+            Err(Rejection::ProofTooOld { age: 240, max: 100 })
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_chain_cannot_be_read() {
+        // The cassette has no gas price, as if the endpoint were down.
+        let provider = provider("eth_chainId", "0x1");
+        let policy = Policy {
+            max_gas_price: Some(1),
+            ..Default::default()
+        };
+        assert!(policy.check(&provider, &withdrawal()).await.is_err());
+    }
+
+    #[test]
+    fn rate_limits_each_key() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        assert_eq!(limiter.check("a"), Ok(()));
+        assert_eq!(limiter.check("a"), Ok(()));
+        let rejection = limiter.check("a").unwrap_err();
+        assert_eq!(rejection.code(), "rate_limited");
+        assert_eq!(limiter.check("b"), Ok(()));
+    }
+}
@@ -0,0 +1,74 @@
+use alloy::primitives::Address;
+use eyre::{eyre, Result};
+use reqwest::Url;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// An address screening service the relayer asks about each recipient before paying it.
+///
+/// `GET <url>/<address>` is answered in the format of the Chainalysis sanctions API,
+/// `{"identifications": [{"category": .., "name": .., "description": ..}]}`, and any
+/// identification blocks the address.
+#[derive(Debug, Clone)]
+pub struct Screener {
+    url: Url,
+    api_key: Option<String>,
+    client: reqwest::Client,
+    /// Accept withdrawals when the service cannot be reached, instead of rejecting them.
+    pub fail_open: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Screening {
+    #[serde(default)]
+    identifications: Vec<Identification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identification {
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    name: String,
+}
+
+impl Screener {
+    pub fn new(
+        url: Url,
+        api_key: Option<String>,
+        timeout: Duration,
+        fail_open: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            url,
+            api_key,
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+            fail_open,
+        })
+    }
+
+    /// Why `address` is blocked, or `None` if the service has nothing on it.
+    pub async fn screen(&self, address: Address) -> Result<Option<String>> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| eyre!("invalid screening URL {}", self.url))?
+            .pop_if_empty()
+            .push(&address.to_string());
+        let mut request = self.client.get(url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("X-API-Key", api_key);
+        }
+        let screening: Screening = request.send().await?.error_for_status()?.json().await?;
+        Ok(screening.identifications.first().map(|identification| {
+            match (
+                identification.category.is_empty(),
+                identification.name.is_empty(),
+            ) {
+                (false, false) => format!("{}: {}", identification.category, identification.name),
+                (false, true) => identification.category.clone(),
+                (true, false) => identification.name.clone(),
+                (true, true) => "identified by the screening service".to_string(),
+            }
+        }))
+    }
+}
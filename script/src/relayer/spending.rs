@@ -0,0 +1,184 @@
+use super::{jobs::JobStore, policy::Rejection, Relayer};
+use crate::webhook::Event;
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::U256,
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use eyre::{ensure, Result};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, instrument, warn};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+
+/// Caps on the gas the hot wallet spends, so a compromised or misbehaving relayer can only burn
+/// so much. Transactions count at their worst-case cost until mined.
+#[derive(Debug, Clone, Default)]
+pub struct SpendingLimits {
+    /// Most wei spent in any rolling hour.
+    pub per_hour: Option<U256>,
+    /// Most wei spent in any rolling day.
+    pub per_day: Option<U256>,
+}
+
+/// Checks reservations against [`SpendingLimits`] one at a time, so concurrent jobs cannot
+/// both fit under a cap only one of them fits.
+pub struct SpendingLimiter {
+    limits: SpendingLimits,
+    lock: Mutex<()>,
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+impl SpendingLimiter {
+    pub fn new(limits: SpendingLimits) -> Self {
+        Self {
+            limits,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn windows(&self) -> impl Iterator<Item = (&'static str, u64, U256)> + '_ {
+        [
+            ("hour", HOUR, self.limits.per_hour),
+            ("day", DAY, self.limits.per_day),
+        ]
+        .into_iter()
+        .filter_map(|(name, length, cap)| cap.map(|cap| (name, length, cap)))
+    }
+
+    /// Reject if a cap is already used up, before a withdrawal is queued.
+    pub fn check(&self, jobs: &JobStore) -> Result<Result<(), Rejection>> {
+        let now = now()?;
+        for (window, length, cap) in self.windows() {
+            let spent = jobs.spent_since(now.saturating_sub(length), "")?;
+            if spent >= cap {
+                return Ok(Err(Rejection::SpendingCapReached { window, cap }));
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    /// Reserve `amount` for job `id`, failing if that would take any window over its cap.
+    pub fn reserve(&self, jobs: &JobStore, id: &str, amount: U256) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let now = now()?;
+        for (window, length, cap) in self.windows() {
+            let spent = jobs.spent_since(now.saturating_sub(length), id)?;
+            if spent + amount > cap {
+                return Err(Rejection::SpendingCapReached { window, cap }.into());
+            }
+        }
+        jobs.reserve_spend(id, amount)
+    }
+}
+
+/// Keeps the hot wallet funded from a separate funding wallet. The hot wallet only ever holds
+/// about `target` wei, and the funding wallet sends at most `max_per_day` to it.
+#[derive(Debug, Clone)]
+pub struct FundingConfig {
+    pub signer: PrivateKeySigner,
+    /// Balance below which the hot wallet is topped up.
+    pub threshold: U256,
+    /// Balance the hot wallet is topped up to.
+    pub target: U256,
+    /// Most wei sent to the hot wallet in any rolling day.
+    pub max_per_day: U256,
+    pub interval: Duration,
+}
+
+impl Relayer {
+    /// Top up the hot wallet from the funding wallet whenever its balance drops below the
+    /// threshold, until the relayer shuts down. Top-ups that cannot be made, because the daily
+    /// allowance or the funding wallet is exhausted, are sent to the webhooks as `balance_low`.
+    pub fn spawn_funder(self: &Arc<Self>, config: FundingConfig) {
+        info!(
+            funder = %config.signer.address(),
+            threshold = %config.threshold,
+            target = %config.target,
+            "funding the hot wallet"
+        );
+        let relayer = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = relayer.top_up(&config).await {
+                    warn!("failed to top up the hot wallet: {e}");
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(config.interval) => {}
+                    _ = relayer.shutdown_started() => return,
+                }
+            }
+        });
+    }
+
+    #[instrument(skip_all)]
+    async fn top_up(&self, config: &FundingConfig) -> Result<()> {
+        let hot = self.address();
+        let funder = config.signer.address();
+        let balance = self.provider.get_balance(hot).await?;
+        if balance >= config.threshold {
+            return Ok(());
+        }
+
+        let alert = |reason: String| {
+            warn!(%balance, "hot wallet is low and cannot be topped up: {reason}");
+            if let Some(notifier) = &self.config.notifier {
+                notifier.notify(Event::BalanceLow {
+                    address: hot,
+                    balance,
+                    threshold: config.threshold,
+                    reason,
+                });
+            }
+        };
+        let sent_today = self.jobs.topped_up_since(now()?.saturating_sub(DAY))?;
+        let allowance = config.max_per_day.saturating_sub(sent_today);
+        let amount = config.target.saturating_sub(balance).min(allowance);
+        if amount.is_zero() {
+            alert(format!(
+                "daily top-up allowance of {} wei used",
+                config.max_per_day
+            ));
+            return Ok(());
+        }
+        let funds = self.provider.get_balance(funder).await?;
+        if funds < amount {
+            alert(format!("funding wallet {funder} holds only {funds} wei"));
+            return Ok(());
+        }
+
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(config.signer.clone()))
+            .on_http(self.config.rpc_url.clone());
+        let tx = TransactionRequest::default()
+            .with_from(funder)
+            .with_to(hot)
+            .with_value(amount);
+        let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+        ensure!(
+            receipt.status(),
+            "top-up reverted in {}",
+            receipt.transaction_hash
+        );
+        self.jobs.record_top_up(receipt.transaction_hash, amount)?;
+        info!(%amount, tx_hash = %receipt.transaction_hash, "topped up the hot wallet");
+        if let Some(notifier) = &self.config.notifier {
+            notifier.notify(Event::WalletToppedUp {
+                from: funder,
+                to: hot,
+                amount,
+                tx_hash: receipt.transaction_hash,
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,470 @@
+use super::gas::{next_base_fee, GasStrategy};
+use alloy::{
+    eips::eip2718::Encodable2718,
+    network::{Ethereum, EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::TransactionRequest,
+};
+use eyre::{bail, ensure, Result};
+use std::{collections::HashMap, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::{debug, info, instrument, warn};
+
+/// How often pending transactions are checked for a receipt.
+const POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Replacement and fee settings of a [`TxManager`].
+#[derive(Debug, Clone)]
+pub struct TxConfig {
+    /// Time without a receipt after which a transaction is replaced with higher fees.
+    pub stuck_after: Duration,
+    /// Percentage the fees are raised by on each replacement. Nodes require at least 10.
+    pub bump_percent: u64,
+    /// Replacements made before the fees are left as they are. The last transaction is then
+    /// rebroadcast until something is mined at its nonce or [`TxConfig::deadline`] passes.
+    pub max_bumps: u32,
+    /// Time after the first broadcast by which a send fails if nothing it sent is mined.
+    pub deadline: Duration,
+    pub gas: GasStrategy,
+    /// Most a withdrawal may cost in gas, as a percentage of the relayer fee it collects.
+    pub max_cost_percent: Option<u64>,
+    /// Highest fee per gas a transaction or any of its replacements is sent with, the policy's
+    /// highest gas price.
+    pub max_fee_per_gas: Option<u128>,
+}
+
+impl Default for TxConfig {
+    fn default() -> Self {
+        Self {
+            stuck_after: Duration::from_secs(60),
+            bump_percent: 15,
+            max_bumps: 5,
+            deadline: Duration::from_secs(30 * 60),
+            gas: GasStrategy::Estimate,
+            max_cost_percent: None,
+            max_fee_per_gas: None,
+        }
+    }
+}
+
+/// A mined transaction and what it cost.
+#[derive(Debug, Clone, Copy)]
+pub struct Mined {
+    pub tx_hash: B256,
+    pub block_hash: B256,
+    pub block_number: u64,
+    pub gas_used: u64,
+    /// `gas_used` times the effective gas price, in wei.
+    pub cost: U256,
+}
+
+/// Sends transactions from the relayer wallet, from whichever of its keys a job calls for.
+/// Nonces are allocated one transaction at a time per key so concurrent jobs never collide, and
+/// transactions that are not mined in time are replaced at the same nonce with higher fees.
+pub struct TxManager {
+    provider: RootProvider<Ethereum>,
+    wallet: EthereumWallet,
+    chain_id: u64,
+    config: TxConfig,
+    /// Next nonce to use from each key, once known.
+    nonces: Mutex<HashMap<Address, u64>>,
+}
+
+impl TxManager {
+    pub fn new(
+        provider: RootProvider<Ethereum>,
+        wallet: EthereumWallet,
+        chain_id: u64,
+        config: TxConfig,
+    ) -> Self {
+        Self {
+            provider,
+            wallet,
+            chain_id,
+            config,
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sign `tx` and broadcast it, returning its hash and the signed transaction.
+    async fn broadcast(&self, tx: TransactionRequest) -> Result<(B256, Vec<u8>)> {
+        let envelope = tx.build(&self.wallet).await?;
+        let tx_hash = *envelope.tx_hash();
+        let signed = envelope.encoded_2718();
+        self.provider.send_raw_transaction(&signed).await?;
+        Ok((tx_hash, signed))
+    }
+
+    /// The first of `hashes` that is mined.
+    async fn mined(&self, hashes: &[B256]) -> Result<Option<Mined>> {
+        for tx_hash in hashes {
+            if let Some(mined) = self.receipt(*tx_hash).await? {
+                return Ok(Some(mined));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Receipt of `tx_hash` if it is mined. A reverted transaction is an error.
+    pub async fn receipt(&self, tx_hash: B256) -> Result<Option<Mined>> {
+        let Some(receipt) = self.provider.get_transaction_receipt(tx_hash).await? else {
+            return Ok(None);
+        };
+        ensure!(receipt.status(), "transaction {tx_hash} reverted");
+        Ok(Some(Mined {
+            tx_hash,
+            block_hash: receipt.block_hash.unwrap_or_default(),
+            block_number: receipt.block_number.unwrap_or_default(),
+            gas_used: receipt.gas_used,
+            cost: U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price),
+        }))
+    }
+
+    /// Send a call to `to` with `input` from `from`, one of the wallet's keys, and wait for it to
+    /// be mined, replacing it if it gets stuck. Fees are capped so the transaction never costs
+    /// more than `max_cost` nor pays more per gas than [`TxConfig::max_fee_per_gas`]. `reserve`
+    /// is called with the most the transaction can cost before it and every replacement is sent,
+    /// and stops the send by failing. `on_sent` is called with the hash of the transaction and of
+    /// every replacement. A transaction that cannot be replaced is rebroadcast as it was last
+    /// signed, and the send fails once [`TxConfig::deadline`] passes without one being mined.
+    #[instrument(skip_all, fields(%from, %to))]
+    pub async fn send(
+        &self,
+        from: Address,
+        to: Address,
+        input: Bytes,
+        max_cost: Option<U256>,
+        reserve: impl Fn(U256) -> Result<()>,
+        on_sent: impl Fn(B256),
+    ) -> Result<Mined> {
+        let mut tx = TransactionRequest::default()
+            .with_from(from)
+            .with_to(to)
+            .with_input(input)
+            .with_chain_id(self.chain_id);
+        let gas_limit = self.provider.estimate_gas(&tx).await?;
+        tx.set_gas_limit(gas_limit);
+        let fee_cap = max_cost
+            .map(|max_cost| (max_cost / U256::from(gas_limit)).saturating_to::<u128>())
+            .unwrap_or(u128::MAX)
+            .min(self.config.max_fee_per_gas.unwrap_or(u128::MAX));
+        let base_fee = next_base_fee(&self.provider).await?;
+        ensure!(
+            fee_cap > base_fee,
+            "gas too expensive: base fee is {base_fee} but at most {fee_cap} per gas is paid"
+        );
+        let fees = self.config.gas.fees(&self.provider).await?;
+        let max_fee = fees.max_fee_per_gas.min(fee_cap);
+        tx.set_max_fee_per_gas(max_fee);
+        tx.set_max_priority_fee_per_gas(
+            fees.max_priority_fee_per_gas
+                .min(max_fee.saturating_sub(base_fee)),
+        );
+
+        reserve(U256::from(gas_limit) * U256::from(max_fee))?;
+
+        // Hold the nonce until the transaction is accepted by the node, so a failed broadcast
+        // does not leave a gap.
+        let mut hashes = Vec::new();
+        let (nonce, mut signed) = {
+            let mut nonces = self.nonces.lock().await;
+            let pending = self.provider.get_transaction_count(from).pending().await?;
+            let nonce = nonces
+                .get(&from)
+                .map_or(pending, |next| (*next).max(pending));
+            tx.set_nonce(nonce);
+            let (tx_hash, signed) = self.broadcast(tx.clone()).await?;
+            hashes.push(tx_hash);
+            nonces.insert(from, nonce + 1);
+            info!(nonce, %tx_hash, "sent transaction");
+            (nonce, signed)
+        };
+        on_sent(hashes[0]);
+
+        let deadline = Instant::now() + self.config.deadline;
+        let mut bumps = 0;
+        let mut sent_at = Instant::now();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if let Some(mined) = self.mined(&hashes).await? {
+                return Ok(mined);
+            }
+            // Once the nonce is used, none of ours that is not mined yet ever will be. Only a
+            // transaction sent from the key outside the manager can use it without one of ours
+            // landing, so the receipts are checked again in case one landed since.
+            let mined_nonce = self.provider.get_transaction_count(from).latest().await?;
+            if mined_nonce > nonce {
+                if let Some(mined) = self.mined(&hashes).await? {
+                    return Ok(mined);
+                }
+                bail!("nonce {nonce} was used by a transaction the relayer did not send");
+            }
+            ensure!(
+                Instant::now() < deadline,
+                "transaction at nonce {nonce} not mined within {:?}, its last replacement {} may \
+                 still be",
+                self.config.deadline,
+                hashes[hashes.len() - 1]
+            );
+            if sent_at.elapsed() < self.config.stuck_after {
+                continue;
+            }
+            sent_at = Instant::now();
+
+            let replacement = if bumps >= self.config.max_bumps {
+                warn!(
+                    nonce,
+                    bumps, "transaction still not mined after its last replacement"
+                );
+                None
+            } else {
+                self.replacement_fees(&tx, fee_cap, gas_limit, &reserve)
+                    .await?
+            };
+            let Some((max_fee, priority_fee)) = replacement else {
+                // The node may have dropped it from its pool, which leaves the nonce unused.
+                if let Err(e) = self.provider.send_raw_transaction(&signed).await {
+                    debug!("rebroadcast of the last transaction not taken: {e}");
+                }
+                continue;
+            };
+            tx.set_max_fee_per_gas(max_fee);
+            tx.set_max_priority_fee_per_gas(priority_fee);
+            bumps += 1;
+            match self.broadcast(tx.clone()).await {
+                Ok((tx_hash, replacement)) => {
+                    warn!(%tx_hash, bumps, max_fee, priority_fee, "replaced stuck transaction");
+                    hashes.push(tx_hash);
+                    signed = replacement;
+                    on_sent(tx_hash);
+                }
+                // An earlier transaction may have been mined in the meantime, which the next
+                // receipt check picks up.
+                Err(e) => warn!("failed to replace transaction: {e}"),
+            }
+        }
+    }
+
+    /// Fees to replace `tx` with, its own raised by [`TxConfig::bump_percent`] or the current ones
+    /// if higher, or `None` if they pass `fee_cap` or `reserve` refuses what they can cost.
+    async fn replacement_fees(
+        &self,
+        tx: &TransactionRequest,
+        fee_cap: u128,
+        gas_limit: u64,
+        reserve: &impl Fn(U256) -> Result<()>,
+    ) -> Result<Option<(u128, u128)>> {
+        let bump = |fee: u128| fee + fee * self.config.bump_percent as u128 / 100 + 1;
+        let fees = self.config.gas.fees(&self.provider).await?;
+        let max_fee = bump(tx.max_fee_per_gas.unwrap_or_default()).max(fees.max_fee_per_gas);
+        let priority_fee = bump(tx.max_priority_fee_per_gas.unwrap_or_default())
+            .max(fees.max_priority_fee_per_gas);
+        if max_fee > fee_cap {
+            // A replacement must raise both fees, which the cap does not leave room for.
+            warn!(
+                fee_cap,
+                "stuck transaction is at the fee cap, not replacing it"
+            );
+            return Ok(None);
+        }
+        if let Err(e) = reserve(U256::from(gas_limit) * U256::from(max_fee)) {
+            warn!("not replacing stuck transaction: {e}");
+            return Ok(None);
+        }
+        Ok(Some((max_fee, priority_fee)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        primitives::keccak256,
+        rpc::{
+            client::ClientBuilder,
+            json_rpc::{RequestPacket, Response, ResponsePacket, ResponsePayload},
+        },
+        signers::local::PrivateKeySigner,
+        transports::{TransportError, TransportFut},
+    };
+    use serde_json::{json, value::RawValue, Value};
+    use std::{
+        sync::{Arc, Mutex as StdMutex},
+        task::{Context, Poll},
+    };
+    use tower::Service;
+
+    /// Base fee of every block of a [`FakeNode`].
+    const BASE_FEE: u128 = 100;
+
+    /// What a [`FakeNode`] has seen and been told to mine.
+    #[derive(Default)]
+    struct Chain {
+        /// Nonce of the key's next transaction, counting the ones in the node's pool.
+        pending_nonce: u64,
+        /// Nonce of the key's next transaction to be mined.
+        latest_nonce: u64,
+        /// Raw transactions in the order the node was sent them.
+        sent: Vec<Bytes>,
+        /// Index in `sent` of the transaction that is mined once it is sent.
+        mines: Option<usize>,
+    }
+
+    /// Node answering the requests of a send from a scripted [`Chain`].
+    #[derive(Clone, Default)]
+    struct FakeNode(Arc<StdMutex<Chain>>);
+
+    impl FakeNode {
+        fn answer(&self, method: &str, params: &Value) -> Value {
+            let mut chain = self.0.lock().unwrap();
+            match method {
+                "eth_estimateGas" => json!("0x5208"),
+                "eth_feeHistory" => json!({
+                    "oldestBlock": "0x1",
+                    "baseFeePerGas": [format!("{BASE_FEE:#x}"), format!("{BASE_FEE:#x}")],
+                    "gasUsedRatio": [0.5],
+                }),
+                "eth_getTransactionCount" => match params[1].as_str() {
+                    Some("pending") => json!(format!("{:#x}", chain.pending_nonce)),
+                    _ => json!(format!("{:#x}", chain.latest_nonce)),
+                },
+                "eth_sendRawTransaction" => {
+                    let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+                    chain.sent.push(raw.clone());
+                    json!(keccak256(&raw))
+                }
+                "eth_getTransactionReceipt" => {
+                    let tx_hash: B256 = serde_json::from_value(params[0].clone()).unwrap();
+                    let mined = chain.mines.and_then(|i| chain.sent.get(i)).map(keccak256);
+                    if mined == Some(tx_hash) {
+                        receipt(tx_hash)
+                    } else {
+                        Value::Null
+                    }
+                }
+                _ => panic!("unexpected request {method}"),
+            }
+        }
+
+        fn sent(&self) -> Vec<Bytes> {
+            self.0.lock().unwrap().sent.clone()
+        }
+    }
+
+    impl Service<RequestPacket> for FakeNode {
+        type Response = ResponsePacket;
+        type Error = TransportError;
+        type Future = TransportFut<'static>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: RequestPacket) -> Self::Future {
+            let RequestPacket::Single(request) = request else {
+                panic!("unexpected batch request");
+            };
+            let params = request.params().map_or(Value::Null, |params| {
+                serde_json::from_str(params.get()).unwrap()
+            });
+            let result = self.answer(request.method(), &params).to_string();
+            let response = Response {
+                id: request.id().clone(),
+                payload: ResponsePayload::Success(RawValue::from_string(result).unwrap()),
+            };
+            Box::pin(async move { Ok(ResponsePacket::Single(response)) })
+        }
+    }
+
+    fn receipt(tx_hash: B256) -> Value {
+        json!({
+            "type": "0x2",
+            "status": "0x1",
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockHash": B256::with_last_byte(1),
+            "blockNumber": "0x2",
+            "from": Address::ZERO,
+            "to": Address::ZERO,
+            "contractAddress": null,
+            "gasUsed": "0x5208",
+            "cumulativeGasUsed": "0x5208",
+            "effectiveGasPrice": format!("{BASE_FEE:#x}"),
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+        })
+    }
+
+    fn config() -> TxConfig {
+        TxConfig {
+            gas: GasStrategy::FixedTip { tip: 1 },
+            max_bumps: 2,
+            deadline: Duration::from_secs(10 * 60),
+            ..TxConfig::default()
+        }
+    }
+
+    async fn send(node: &FakeNode, config: TxConfig) -> Result<Mined> {
+        let signer = PrivateKeySigner::random();
+        let from = signer.address();
+        let provider = RootProvider::new(ClientBuilder::default().transport(node.clone(), true));
+        let txs = TxManager::new(provider, EthereumWallet::from(signer), 1, config);
+        txs.send(
+            from,
+            Address::with_last_byte(1),
+            Bytes::new(),
+            None,
+            |_| Ok(()),
+            |_| {},
+        )
+        .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replaces_a_stuck_transaction_until_one_is_mined() {
+        let node = FakeNode::default();
+        node.0.lock().unwrap().mines = Some(1);
+        let mined = send(&node, config()).await.unwrap();
+        let sent = node.sent();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(mined.tx_hash, keccak256(&sent[1]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rebroadcasts_the_last_replacement_until_the_deadline() {
+        let node = FakeNode::default();
+        let error = send(&node, config()).await.unwrap_err();
+        assert!(error.to_string().contains("not mined within"), "{error}");
+        let sent = node.sent();
+        // The transaction and its two replacements, then the last one again and again.
+        assert_ne!(sent[0], sent[1]);
+        assert_ne!(sent[1], sent[2]);
+        assert!(sent.len() > 3);
+        assert!(sent[3..].iter().all(|raw| *raw == sent[2]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rebroadcasts_a_transaction_at_the_fee_cap() {
+        let node = FakeNode::default();
+        // Exactly the first max fee, twice the base fee and the tip, which leaves no room to bump.
+        let config = TxConfig {
+            max_fee_per_gas: Some(2 * BASE_FEE + 1),
+            ..config()
+        };
+        let error = send(&node, config).await.unwrap_err();
+        assert!(error.to_string().contains("not mined within"), "{error}");
+        let sent = node.sent();
+        assert!(sent.len() > 1);
+        assert!(sent.iter().all(|raw| *raw == sent[0]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fails_once_another_transaction_takes_the_nonce() {
+        let node = FakeNode::default();
+        node.0.lock().unwrap().latest_nonce = 1;
+        let error = send(&node, config()).await.unwrap_err();
+        assert!(error.to_string().contains("did not send"), "{error}");
+        assert_eq!(node.sent().len(), 1);
+    }
+}
@@ -0,0 +1,29 @@
+use super::policy::Rejection;
+use alloy::primitives::B256;
+use sha2::{Digest, Sha256};
+use sp1_verifier::{Groth16Verifier, PlonkVerifier, GROTH16_VK_BYTES, PLONK_VK_BYTES};
+
+/// Whether `proof` starts with the selector of the verifier key `vk`, the first 4 bytes of its
+/// sha256, as the SP1 gateway routes proofs.
+fn has_selector(proof: &[u8], vk: &[u8]) -> bool {
+    proof.len() >= 4 && proof[..4] == Sha256::digest(vk)[..4]
+}
+
+/// Check an onchain-encoded groth16 or plonk `proof` of `public_values` against the program
+/// `vkey`, as the pool's verifier would.
+///
+/// The verifier hashes `public_values` into the digest the proof commits to, so public values
+/// edited after proving fail here rather than onchain.
+pub fn verify_proof(vkey: B256, public_values: &[u8], proof: &[u8]) -> Result<(), Rejection> {
+    let vkey_hash = vkey.to_string();
+    let result = if has_selector(proof, &GROTH16_VK_BYTES) {
+        Groth16Verifier::verify(proof, public_values, &vkey_hash, &GROTH16_VK_BYTES)
+            .map_err(|e| e.to_string())
+    } else if has_selector(proof, &PLONK_VK_BYTES) {
+        PlonkVerifier::verify(proof, public_values, &vkey_hash, &PLONK_VK_BYTES)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("not a groth16 or plonk proof for this SP1 version".to_string())
+    };
+    result.map_err(|reason| Rejection::InvalidProof { reason })
+}
@@ -0,0 +1,290 @@
+//! Withdrawals proposed to a Safe multisig instead of broadcast. The withdrawal call is wrapped
+//! in a Safe transaction signed by one owner and posted to the Safe transaction service, where
+//! the other owners confirm it until the Safe's threshold is met and one of them executes it.
+//! Treasuries and other multisig-managed recipients can then withdraw from their own Safe, with
+//! no owner's account calling the pool.
+
+use crate::submit::Withdrawal;
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, RootProvider},
+    signers::{local::PrivateKeySigner, SignerSync},
+    sol,
+    sol_types::{eip712_domain, SolStruct},
+};
+use eyre::{bail, ensure, OptionExt, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+use tokio::time::Instant;
+use tracing::{info, instrument};
+
+sol! {
+    /// The transaction a Safe's owners sign, as its EIP-712 type.
+    struct SafeTx {
+        address to;
+        uint256 value;
+        bytes data;
+        uint8 operation;
+        uint256 safeTxGas;
+        uint256 baseGas;
+        uint256 gasPrice;
+        address gasToken;
+        address refundReceiver;
+        uint256 nonce;
+    }
+
+    #[sol(rpc)]
+    contract Safe {
+        function nonce() external view returns (uint256);
+        function getOwners() external view returns (address[] memory);
+    }
+}
+
+/// Safe transaction service hosted by Safe for `chain_id`.
+pub fn hosted_service(chain_id: u64) -> Result<Url> {
+    let network = match chain_id {
+        1 => "mainnet",
+        10 => "optimism",
+        100 => "gnosis-chain",
+        137 => "polygon",
+        8453 => "base",
+        42161 => "arbitrum",
+        11155111 => "sepolia",
+        _ => bail!(
+            "no hosted Safe transaction service for chain {chain_id}, pass --safe-tx-service-url"
+        ),
+    };
+    Ok(format!("https://safe-transaction-{network}.safe.global").parse()?)
+}
+
+/// A proposal as the Safe transaction service takes it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Proposal {
+    to: Address,
+    value: String,
+    data: Bytes,
+    operation: u8,
+    safe_tx_gas: String,
+    base_gas: String,
+    gas_price: String,
+    gas_token: Address,
+    refund_receiver: Address,
+    nonce: u64,
+    contract_transaction_hash: B256,
+    sender: Address,
+    signature: Bytes,
+    origin: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    results: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Queued {
+    nonce: u64,
+}
+
+/// An owner's signature of a proposed Safe transaction.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Confirmation {
+    pub owner: Address,
+}
+
+/// Where a proposed Safe transaction stands.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTxStatus {
+    pub safe: Address,
+    pub nonce: u64,
+    /// Confirmations needed to execute it, the Safe's threshold.
+    pub confirmations_required: u64,
+    #[serde(default)]
+    pub confirmations: Vec<Confirmation>,
+    pub is_executed: bool,
+    pub is_successful: Option<bool>,
+    pub transaction_hash: Option<B256>,
+}
+
+/// Proposes withdrawals to a Safe through a Safe transaction service.
+pub struct SafeProposer {
+    pub provider: RootProvider<Ethereum>,
+    pub service: Url,
+    pub safe: Address,
+    /// Owner of the Safe that signs the proposal.
+    pub owner: PrivateKeySigner,
+    pub http: reqwest::Client,
+}
+
+impl SafeProposer {
+    /// Nonce for a new Safe transaction: the Safe's, or the one after the last transaction queued
+    /// on the service if others are waiting for confirmations.
+    async fn next_nonce(&self) -> Result<u64> {
+        let nonce: u64 = Safe::new(self.safe, &self.provider)
+            .nonce()
+            .call()
+            .await?
+            ._0
+            .try_into()?;
+        let url = self.service.join(&format!(
+            "api/v1/safes/{}/multisig-transactions/",
+            self.safe
+        ))?;
+        let queued: Page<Queued> = self
+            .http
+            .get(url)
+            .query(&[
+                ("executed", "false".to_string()),
+                ("nonce__gte", nonce.to_string()),
+                ("ordering", "-nonce".to_string()),
+                ("limit", "1".to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(queued
+            .results
+            .first()
+            .map_or(nonce, |queued| queued.nonce + 1)
+            .max(nonce))
+    }
+
+    /// Sign a Safe transaction calling `Pool.withdraw` on `pool` and post it to the service, at
+    /// `nonce` or the next free one. Returns the Safe transaction hash the other owners confirm.
+    #[instrument(skip_all, fields(safe = %self.safe, %pool))]
+    pub async fn propose(
+        &self,
+        pool: Address,
+        withdrawal: &Withdrawal,
+        nonce: Option<u64>,
+    ) -> Result<B256> {
+        let safe = Safe::new(self.safe, &self.provider);
+        let owners = safe.getOwners().call().await?._0;
+        ensure!(
+            owners.contains(&self.owner.address()),
+            "{} is not an owner of the Safe {}",
+            self.owner.address(),
+            self.safe
+        );
+        let chain_id = self.provider.get_chain_id().await?;
+        let nonce = match nonce {
+            Some(nonce) => nonce,
+            None => self.next_nonce().await?,
+        };
+
+        let tx = SafeTx {
+            to: pool,
+            value: U256::ZERO,
+            data: withdrawal.calldata(),
+            operation: 0,
+            safeTxGas: U256::ZERO,
+            baseGas: U256::ZERO,
+            gasPrice: U256::ZERO,
+            gasToken: Address::ZERO,
+            refundReceiver: Address::ZERO,
+            nonce: U256::from(nonce),
+        };
+        let domain = eip712_domain! {
+            chain_id: chain_id,
+            verifying_contract: self.safe,
+        };
+        let hash = tx.eip712_signing_hash(&domain);
+        let signature = self.owner.sign_hash_sync(&hash)?;
+
+        let proposal = Proposal {
+            to: tx.to,
+            value: tx.value.to_string(),
+            data: tx.data,
+            operation: tx.operation,
+            safe_tx_gas: tx.safeTxGas.to_string(),
+            base_gas: tx.baseGas.to_string(),
+            gas_price: tx.gasPrice.to_string(),
+            gas_token: tx.gasToken,
+            refund_receiver: tx.refundReceiver,
+            nonce,
+            contract_transaction_hash: hash,
+            sender: self.owner.address(),
+            signature: Bytes::copy_from_slice(&signature.as_bytes()),
+            origin: "pool".to_string(),
+        };
+        let url = self.service.join(&format!(
+            "api/v1/safes/{}/multisig-transactions/",
+            self.safe
+        ))?;
+        let response = self.http.post(url).json(&proposal).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("Safe transaction service rejected the proposal ({status}): {body}");
+        }
+        info!(safe_tx_hash = %hash, nonce, "proposed withdrawal to the Safe");
+        Ok(hash)
+    }
+}
+
+/// Look up a proposed Safe transaction on the service at `service`.
+pub async fn safe_tx_status(
+    http: &reqwest::Client,
+    service: &Url,
+    safe_tx_hash: B256,
+) -> Result<SafeTxStatus> {
+    let url = service.join(&format!("api/v1/multisig-transactions/{safe_tx_hash}/"))?;
+    Ok(http
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Follow a proposed Safe transaction until it is executed, logging each owner who confirms it,
+/// and return the hash of the transaction that executed it. Gives up after `timeout`, leaving the
+/// proposal for the owners to execute later.
+#[instrument(skip(http, service))]
+pub async fn wait_for_execution(
+    http: &reqwest::Client,
+    service: &Url,
+    safe_tx_hash: B256,
+    timeout: Duration,
+) -> Result<B256> {
+    let deadline = Instant::now() + timeout;
+    let mut seen = HashSet::new();
+    loop {
+        let status = safe_tx_status(http, service, safe_tx_hash).await?;
+        for confirmation in &status.confirmations {
+            if seen.insert(confirmation.owner) {
+                info!(
+                    owner = %confirmation.owner,
+                    confirmations = status.confirmations.len(),
+                    required = status.confirmations_required,
+                    "confirmed by owner"
+                );
+            }
+        }
+        if status.is_executed {
+            let tx_hash = status
+                .transaction_hash
+                .ok_or_eyre("executed Safe transaction has no transaction hash")?;
+            ensure!(
+                status.is_successful != Some(false),
+                "Safe transaction {safe_tx_hash} reverted in {tx_hash}"
+            );
+            return Ok(tx_hash);
+        }
+        ensure!(
+            Instant::now() < deadline,
+            "Safe transaction {safe_tx_hash} not executed after {timeout:?}, {} of {} \
+             confirmations, follow it with `pool safe-status --wait`",
+            status.confirmations.len(),
+            status.confirmations_required
+        );
+        tokio::time::sleep(Duration::from_secs(15)).await;
+    }
+}
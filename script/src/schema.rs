@@ -0,0 +1,22 @@
+use crate::{aggregate::Opening, bundle::ProofBundle, snapshot::Snapshot};
+use pool_lib::{InclusionBranches, WithdrawalInput};
+use pool_relayer_client::{ErrorResponse, Job, JobEvent, Quote, WithdrawBody, WithdrawResponse};
+use schemars::{schema::RootSchema, schema_for};
+
+/// JSON Schemas of every type other software reads or writes, by the name `pool schema` knows
+/// them by.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("withdrawal-input", schema_for!(WithdrawalInput)),
+        ("inclusion-branches", schema_for!(InclusionBranches)),
+        ("aggregation-opening", schema_for!(Vec<Opening>)),
+        ("proof-bundle", schema_for!(ProofBundle)),
+        ("snapshot", schema_for!(Snapshot)),
+        ("relayer-quote", schema_for!(Quote)),
+        ("relayer-withdraw-request", schema_for!(WithdrawBody)),
+        ("relayer-withdraw-response", schema_for!(WithdrawResponse)),
+        ("relayer-job", schema_for!(Job)),
+        ("relayer-job-event", schema_for!(JobEvent)),
+        ("relayer-error", schema_for!(ErrorResponse)),
+    ]
+}
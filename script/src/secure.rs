@@ -0,0 +1,158 @@
+//! Keeping note secrets and keys out of core dumps, swap, freed memory and error messages.
+
+use alloy::primitives::B256;
+use clap::{builder::TypedValueParser, error::ErrorKind};
+use eyre::{eyre, Result};
+use std::{ffi::OsStr, fmt, marker::PhantomData, mem::size_of, ops::Deref, str::FromStr};
+use tracing::{debug, warn};
+
+/// Stop the process from writing core dumps, which would hold any secret it had in memory when
+/// it crashed, and on Linux from being read through ptrace or `/proc/<pid>/mem` by other
+/// processes of the same user.
+pub fn harden() {
+    #[cfg(unix)]
+    {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: `limit` is a valid rlimit for the duration of the call.
+        if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+            warn!(
+                "failed to disable core dumps: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: PR_SET_DUMPABLE takes no pointers.
+        if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+            warn!(
+                "failed to mark the process undumpable: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Lock every page the process has and will map into memory, so nothing it holds is swapped
+/// out. Fails if the memory lock limit (`ulimit -l`) is too low.
+pub fn lock_all() -> Result<()> {
+    #[cfg(unix)]
+    {
+        // SAFETY: mlockall takes no pointers.
+        if unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } != 0 {
+            return Err(eyre!(
+                "failed to lock memory: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    Err(eyre!("locking memory is only supported on unix"))
+}
+
+/// A value in its own allocation, locked into memory so it is never swapped out and zeroed when
+/// dropped. Copies taken out of it are not covered.
+pub struct Locked<T: Copy> {
+    value: Box<T>,
+}
+
+impl<T: Copy> Locked<T> {
+    pub fn new(value: T) -> Self {
+        let mut value = Box::new(value);
+        // SAFETY: the pointer is to `size_of::<T>()` bytes owned by `value`.
+        if !unsafe { memsec::mlock(&mut *value as *mut T as *mut u8, size_of::<T>()) } {
+            debug!("failed to lock secret in memory");
+        }
+        Self { value }
+    }
+}
+
+impl<T: Copy> Deref for Locked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Copy> Drop for Locked<T> {
+    fn drop(&mut self) {
+        let ptr = &mut *self.value as *mut T as *mut u8;
+        // SAFETY: the pointer is to `size_of::<T>()` bytes owned by `value`, which is not read
+        // again: `T: Copy` has no drop glue to see the zeroed bytes.
+        unsafe {
+            memsec::memzero(ptr, size_of::<T>());
+            memsec::munlock(ptr, size_of::<T>());
+        }
+    }
+}
+
+impl<T: Copy> fmt::Debug for Locked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Locked(..)")
+    }
+}
+
+/// Overwrite `bytes` with zeros in a way the compiler does not optimize out.
+pub fn scrub(bytes: &mut [u8]) {
+    // SAFETY: the pointer is to `bytes.len()` bytes borrowed mutably.
+    unsafe { memsec::memzero(bytes.as_mut_ptr(), bytes.len()) }
+}
+
+/// Overwrite a secret with zeros.
+pub fn scrub_secret(secret: &mut B256) {
+    scrub(secret.as_mut_slice())
+}
+
+/// Parses a note, secret or key argument without echoing it in the error, as clap does with
+/// values that fail to parse.
+#[derive(Debug)]
+pub struct SecretParser<T>(PhantomData<fn() -> T>);
+
+impl<T> SecretParser<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for SecretParser<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for SecretParser<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TypedValueParser for SecretParser<T>
+where
+    T: FromStr + Clone + Send + Sync + 'static,
+    T::Err: fmt::Display,
+{
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<T, clap::Error> {
+        let name = arg.map_or_else(|| "value".to_string(), |arg| arg.to_string());
+        let error = |reason: &dyn fmt::Display| {
+            clap::Error::raw(
+                ErrorKind::ValueValidation,
+                format!("invalid {name}: {reason}\n"),
+            )
+            .with_cmd(cmd)
+        };
+        let value = value.to_str().ok_or_else(|| error(&"not UTF-8"))?;
+        value.parse().map_err(|e: T::Err| error(&e))
+    }
+}
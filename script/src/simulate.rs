@@ -0,0 +1,374 @@
+use crate::{pool::Pool, submit::Withdrawal};
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::Ethereum,
+    primitives::{keccak256, Address, Bytes, TxKind, B256, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::BlockTransactionsKind,
+    sol,
+    sol_types::{decode_revert_reason, SolEvent, SolInterface, SolValue},
+};
+use eyre::{eyre, OptionExt, Result};
+use pool_lib::WithdrawalData;
+use revm::{
+    db::{CacheDB, DatabaseRef},
+    primitives::{AccountInfo, Bytecode, ExecutionResult, ResultAndState},
+    Evm,
+};
+use std::{
+    fmt,
+    future::{Future, IntoFuture},
+};
+use tokio::runtime::Handle;
+use tracing::{info, instrument};
+
+sol! {
+    /// Errors raised by the SP1 verifier gateway and verifiers.
+    #[derive(Debug)]
+    interface SP1VerifierErrors {
+        error RouteNotFound(bytes4 selector);
+        error RouteIsFrozen(bytes4 selector);
+        error WrongVerifierSelector(bytes4 received, bytes4 expected);
+        error InvalidProof();
+    }
+}
+
+/// Gas limit of the simulated withdrawal transaction.
+const SIMULATION_GAS_LIMIT: u64 = 5_000_000;
+
+#[derive(Debug)]
+pub struct ForkError(String);
+
+impl fmt::Display for ForkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fork database: {}", self.0)
+    }
+}
+
+impl std::error::Error for ForkError {}
+
+/// Read-only chain state at a fixed block, fetched lazily over RPC. Blocks on the runtime for each
+/// lookup, so it must be used from a blocking context.
+pub struct ForkDb {
+    provider: RootProvider<Ethereum>,
+    block_number: u64,
+    handle: Handle,
+}
+
+impl ForkDb {
+    pub fn new(provider: RootProvider<Ethereum>, block_number: u64) -> Self {
+        Self {
+            provider,
+            block_number,
+            handle: Handle::current(),
+        }
+    }
+
+    fn block_on<T, E: fmt::Display>(
+        &self,
+        f: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, ForkError> {
+        self.handle
+            .block_on(f)
+            .map_err(|e| ForkError(e.to_string()))
+    }
+}
+
+impl DatabaseRef for ForkDb {
+    type Error = ForkError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let balance = self.block_on(
+            self.provider
+                .get_balance(address)
+                .number(self.block_number)
+                .into_future(),
+        )?;
+        let nonce = self.block_on(
+            self.provider
+                .get_transaction_count(address)
+                .number(self.block_number)
+                .into_future(),
+        )?;
+        let code = self.block_on(
+            self.provider
+                .get_code_at(address)
+                .number(self.block_number)
+                .into_future(),
+        )?;
+        Ok(Some(AccountInfo::new(
+            balance,
+            nonce,
+            keccak256(&code),
+            Bytecode::new_raw(code),
+        )))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // Code is always returned with the account in `basic_ref`.
+        Err(ForkError(format!("code {code_hash} not loaded")))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.block_on(
+            self.provider
+                .get_storage_at(address, index)
+                .number(self.block_number)
+                .into_future(),
+        )
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        let block = self.block_on(
+            self.provider
+                .get_block_by_number(number.into(), BlockTransactionsKind::Hashes),
+        )?;
+        block
+            .map(|block| block.header.hash)
+            .ok_or_else(|| ForkError(format!("block {number} not found")))
+    }
+}
+
+/// Explain a revert from `Pool.withdraw` in terms of the check that failed.
+pub fn explain_revert(output: &Bytes) -> String {
+    if let Ok(error) = SP1VerifierErrors::SP1VerifierErrorsErrors::abi_decode(output, true) {
+        return match error {
+            SP1VerifierErrors::SP1VerifierErrorsErrors::InvalidProof(_) => {
+                "proof rejected by the verifier: the proof does not match the pool's program vkey \
+                 or public values"
+                    .to_string()
+            }
+            SP1VerifierErrors::SP1VerifierErrorsErrors::WrongVerifierSelector(e) => format!(
+                "proof was made for verifier {} but the pool's verifier is {}",
+                e.received, e.expected
+            ),
+            SP1VerifierErrors::SP1VerifierErrorsErrors::RouteNotFound(e) => format!(
+                "verifier gateway has no route for proof selector {}, is this a groth16/plonk proof \
+                 from a supported SP1 version?",
+                e.selector
+            ),
+            SP1VerifierErrors::SP1VerifierErrorsErrors::RouteIsFrozen(e) => {
+                format!("verifier route {} is frozen", e.selector)
+            }
+        };
+    }
+
+    match decode_revert_reason(output).as_deref() {
+        Some("Already withdrawn") => "nullifier already spent".to_string(),
+        Some("Invalid block hash") => {
+            "block hash check failed: the proof block is more than 256 blocks old or was reorged"
+                .to_string()
+        }
+        Some("Invalid contract address") => "proof was generated for a different pool".to_string(),
+        Some("Failed to send withdrawal") => "recipient rejected the ETH transfer".to_string(),
+        Some("Failed to send relayer fee") => "relayer rejected the fee transfer".to_string(),
+        Some(reason) => reason.to_string(),
+        None => format!("reverted with {output}"),
+    }
+}
+
+/// Execute `Pool.withdraw` in a local EVM on top of the state at `block`, as if in the block
+/// after it. With `mock_verifier`, the pool's verifier is replaced by a contract that accepts
+/// every proof.
+async fn execute_withdraw(
+    provider: &RootProvider<Ethereum>,
+    block: BlockNumberOrTag,
+    pool: Address,
+    sender: Address,
+    withdrawal: &Withdrawal,
+    mock_verifier: bool,
+) -> Result<ResultAndState> {
+    let chain_id = provider.get_chain_id().await?;
+    let fork = provider
+        .get_block_by_number(block, BlockTransactionsKind::Hashes)
+        .await?
+        .ok_or_eyre("fork block not found")?;
+
+    let mut db = CacheDB::new(ForkDb::new(provider.clone(), fork.header.number));
+    if mock_verifier {
+        let verifier = Pool::new(pool, provider)
+            .verifier()
+            .block(fork.header.number.into())
+            .call()
+            .await?
+            ._0;
+        // A single STOP succeeds with empty return data, which is all `verifyProof` returns.
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x00]));
+        db.insert_account_info(
+            verifier,
+            AccountInfo::new(U256::ZERO, 1, code.hash_slow(), code),
+        );
+    }
+
+    let calldata = withdrawal.calldata();
+    let mut evm = Evm::builder()
+        .with_db(db)
+        .modify_cfg_env(|cfg| cfg.chain_id = chain_id)
+        .modify_block_env(|block| {
+            block.number = U256::from(fork.header.number + 1);
+            block.timestamp = U256::from(fork.header.timestamp + 12);
+            block.basefee = U256::ZERO;
+        })
+        .modify_tx_env(|tx| {
+            tx.caller = sender;
+            tx.transact_to = TxKind::Call(pool);
+            tx.data = calldata;
+            tx.gas_limit = SIMULATION_GAS_LIMIT;
+            tx.gas_price = U256::ZERO;
+            tx.nonce = None;
+        })
+        .build();
+
+    tokio::task::block_in_place(|| evm.transact()).map_err(|e| eyre!("simulation failed: {e:?}"))
+}
+
+/// Execute `Pool.withdraw` in a local EVM on top of the latest state and report whether it would
+/// succeed, with the failing check when it would not.
+#[instrument(skip_all, fields(%pool))]
+pub async fn simulate_withdrawal(
+    provider: &RootProvider<Ethereum>,
+    pool: Address,
+    sender: Address,
+    withdrawal: &Withdrawal,
+) -> Result<u64> {
+    let result = execute_withdraw(
+        provider,
+        BlockNumberOrTag::Latest,
+        pool,
+        sender,
+        withdrawal,
+        false,
+    )
+    .await?
+    .result;
+    match result {
+        ExecutionResult::Success { gas_used, .. } => {
+            info!(gas_used, "simulated withdrawal succeeded");
+            Ok(gas_used)
+        }
+        ExecutionResult::Revert { output, .. } => Err(eyre!(
+            "withdrawal would revert: {}",
+            explain_revert(&output)
+        )),
+        ExecutionResult::Halt { reason, .. } => Err(eyre!("withdrawal would halt: {reason:?}")),
+    }
+}
+
+/// A field where the contract's interpretation of the public values differs from pool-lib's.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub field: &'static str,
+    pub circuit: String,
+    pub contract: String,
+}
+
+/// Run the public values of `data` through the pool contract with a mocked verifier and compare
+/// what the contract does with them, field by field, against what pool-lib committed.
+#[instrument(skip_all, fields(%pool))]
+pub async fn check_public_values(
+    provider: &RootProvider<Ethereum>,
+    pool: Address,
+    data: &WithdrawalData,
+) -> Result<Vec<Mismatch>> {
+    let withdrawal = Withdrawal {
+        public_values: data.abi_encode().into(),
+        proof: Bytes::new(),
+        bridge: None,
+        splits: Vec::new(),
+        token_fee: None,
+    };
+    // The transfers are measured against the state the withdrawal ran on, which the latest block
+    // moves past while it runs.
+    let block = provider.get_block_number().await?;
+    let ResultAndState { result, state } = execute_withdraw(
+        provider,
+        block.into(),
+        pool,
+        Address::ZERO,
+        &withdrawal,
+        true,
+    )
+    .await?;
+    let logs = match result {
+        ExecutionResult::Success { logs, .. } => logs,
+        ExecutionResult::Revert { output, .. } => {
+            return Err(eyre!(
+                "contract rejected public values: {}",
+                explain_revert(&output)
+            ))
+        }
+        ExecutionResult::Halt { reason, .. } => return Err(eyre!("withdrawal halted: {reason:?}")),
+    };
+
+    let event = logs
+        .iter()
+        .filter(|log| log.address == pool)
+        .find_map(|log| Pool::Withdrawal::decode_log_data(&log.data, true).ok())
+        .ok_or_eyre("no Withdrawal event emitted")?;
+
+    let mut mismatches = Vec::new();
+    let mut compare = |field, circuit: String, contract: String| {
+        if circuit != contract {
+            mismatches.push(Mismatch {
+                field,
+                circuit,
+                contract,
+            });
+        }
+    };
+    compare(
+        "nullifier",
+        data.nullifier.to_string(),
+        event.nullifier.to_string(),
+    );
+    compare(
+        "exclusionSetRoot",
+        data.exclusionSetRoot.to_string(),
+        event.exclusionSetRoot.to_string(),
+    );
+    compare(
+        "recipient",
+        data.recipient.to_string(),
+        event.recipient.to_string(),
+    );
+    compare(
+        "relayer",
+        data.relayer.to_string(),
+        event.relayer.to_string(),
+    );
+    compare(
+        "relayerFee",
+        data.relayerFee.to_string(),
+        event.relayerFee.to_string(),
+    );
+
+    // The transfers must match the committed fee split.
+    let amount = Pool::new(pool, provider)
+        .amount()
+        .block(block.into())
+        .call()
+        .await?
+        ._0;
+    let balance_change = |address: Address| async move {
+        let before = provider.get_balance(address).block_id(block.into()).await?;
+        let after = state
+            .get(&address)
+            .map(|account| account.info.balance)
+            .unwrap_or(before);
+        Ok::<_, eyre::Report>(after.saturating_sub(before))
+    };
+    if data.recipient != data.relayer {
+        compare(
+            "recipient transfer",
+            (amount - data.relayerFee).to_string(),
+            balance_change(data.recipient).await?.to_string(),
+        );
+        compare(
+            "relayer transfer",
+            data.relayerFee.to_string(),
+            balance_change(data.relayer).await?.to_string(),
+        );
+    }
+
+    Ok(mismatches)
+}
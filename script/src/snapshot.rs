@@ -0,0 +1,242 @@
+use alloy::primitives::{keccak256, Address, B256};
+use eyre::{bail, ensure, Result};
+use pool_lib::{compute_merkle_root, schema};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// First bytes of every snapshot file.
+const MAGIC: &[u8; 8] = b"POOLSNAP";
+
+/// Version written by [`Snapshot::write`]. Readers reject versions they do not know.
+pub const VERSION: u16 = 1;
+
+/// Leaves per chunk unless told otherwise.
+pub const DEFAULT_CHUNK_SIZE: u32 = 4096;
+
+/// A pool's deposit tree at a block, for bootstrapping clients without replaying history.
+///
+/// The file is big-endian: the magic, version, chain id, pool, block number and hash, leaf count,
+/// Merkle root, then the frontier (the roots of the complete subtrees the leaves decompose into,
+/// largest first), then the leaves in chunks. Each chunk is its length, its leaves and the
+/// keccak256 of those leaves, so a corrupt download is caught at the chunk it happened in. Its
+/// JSON form, described by `pool schema snapshot`, holds the same fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Snapshot {
+    pub chain_id: u64,
+    #[schemars(schema_with = "schema::address")]
+    pub pool: Address,
+    pub block_number: u64,
+    #[schemars(schema_with = "schema::bytes32")]
+    pub block_hash: B256,
+    #[schemars(schema_with = "schema::bytes32_array")]
+    pub leaves: Vec<B256>,
+}
+
+/// Roots of the complete subtrees `leaves` decomposes into, largest first: one for each set bit
+/// of the leaf count.
+pub fn frontier(leaves: &[B256]) -> Vec<B256> {
+    let mut nodes = Vec::new();
+    let mut start = 0;
+    for bit in (0..usize::BITS).rev() {
+        let size = 1 << bit;
+        if leaves.len() & size != 0 {
+            nodes.push(compute_merkle_root(&leaves[start..start + size]));
+            start += size;
+        }
+    }
+    nodes
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+impl Snapshot {
+    pub fn root(&self) -> B256 {
+        compute_merkle_root(&self.leaves)
+    }
+
+    pub fn write(&self, writer: &mut impl Write, chunk_size: u32) -> Result<()> {
+        ensure!(chunk_size > 0, "chunk size must be positive");
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_be_bytes())?;
+        writer.write_all(&self.chain_id.to_be_bytes())?;
+        writer.write_all(self.pool.as_slice())?;
+        writer.write_all(&self.block_number.to_be_bytes())?;
+        writer.write_all(self.block_hash.as_slice())?;
+        writer.write_all(&(self.leaves.len() as u64).to_be_bytes())?;
+        writer.write_all(self.root().as_slice())?;
+
+        let frontier = frontier(&self.leaves);
+        writer.write_all(&[frontier.len() as u8])?;
+        for node in &frontier {
+            writer.write_all(node.as_slice())?;
+        }
+
+        for chunk in self.leaves.chunks(chunk_size as usize) {
+            let bytes: Vec<u8> = chunk.iter().flat_map(|leaf| leaf.0).collect();
+            writer.write_all(&(chunk.len() as u32).to_be_bytes())?;
+            writer.write_all(&bytes)?;
+            writer.write_all(keccak256(&bytes).as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Read a snapshot, checking every chunk checksum, the frontier and the root.
+    pub fn read(reader: &mut impl Read) -> Result<Self> {
+        ensure!(&read_array::<8>(reader)? == MAGIC, "not a pool snapshot");
+        let version = u16::from_be_bytes(read_array(reader)?);
+        if version != VERSION {
+            bail!("unsupported snapshot version {version}, expected {VERSION}");
+        }
+        let chain_id = u64::from_be_bytes(read_array(reader)?);
+        let pool = Address::from(read_array::<20>(reader)?);
+        let block_number = u64::from_be_bytes(read_array(reader)?);
+        let block_hash = B256::from(read_array::<32>(reader)?);
+        let leaf_count = u64::from_be_bytes(read_array(reader)?);
+        let root = B256::from(read_array::<32>(reader)?);
+        let frontier_len = read_array::<1>(reader)?[0];
+        let expected_frontier = (0..frontier_len)
+            .map(|_| Ok(B256::from(read_array::<32>(reader)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The leaf count and chunk lengths are only trusted as far as the file backs them, so
+        // nothing is allocated ahead of the bytes actually read.
+        let mut leaves = Vec::new();
+        while (leaves.len() as u64) < leaf_count {
+            let len = u32::from_be_bytes(read_array(reader)?) as u64;
+            ensure!(
+                len > 0 && leaves.len() as u64 + len <= leaf_count,
+                "chunk at leaf {} has invalid length {len}",
+                leaves.len()
+            );
+            let mut bytes = Vec::new();
+            reader.by_ref().take(len * 32).read_to_end(&mut bytes)?;
+            ensure!(
+                bytes.len() as u64 == len * 32,
+                "snapshot ends in the chunk at leaf {}",
+                leaves.len()
+            );
+            let checksum = B256::from(read_array::<32>(reader)?);
+            ensure!(
+                keccak256(&bytes) == checksum,
+                "checksum mismatch in chunk at leaf {}",
+                leaves.len()
+            );
+            leaves.extend(bytes.chunks(32).map(B256::from_slice));
+        }
+
+        ensure!(
+            frontier(&leaves) == expected_frontier,
+            "snapshot frontier does not match its leaves"
+        );
+        let snapshot = Self {
+            chain_id,
+            pool,
+            block_number,
+            block_hash,
+            leaves,
+        };
+        ensure!(
+            snapshot.root() == root,
+            "snapshot root does not match its leaves"
+        );
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes before the frontier: magic, version, chain id, pool, block number and hash, leaf
+    /// count and root.
+    const HEADER_LEN: usize = 8 + 2 + 8 + 20 + 8 + 32 + 8 + 32;
+
+    fn snapshot(leaves: u64) -> Snapshot {
+        Snapshot {
+            chain_id: 1,
+            pool: Address::with_last_byte(1),
+            block_number: 20_000_000,
+            block_hash: B256::with_last_byte(2),
+            leaves: (0..leaves).map(|i| keccak256(i.to_be_bytes())).collect(),
+        }
+    }
+
+    fn encode(snapshot: &Snapshot, chunk_size: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        snapshot.write(&mut bytes, chunk_size).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trips() {
+        for (leaves, chunk_size) in [(0, 4), (1, 4), (7, 3), (8, 8), (13, 1), (13, 4096)] {
+            let snapshot = snapshot(leaves);
+            let read = Snapshot::read(&mut encode(&snapshot, chunk_size).as_slice()).unwrap();
+            assert_eq!(read, snapshot);
+        }
+    }
+
+    #[test]
+    fn frontier_has_one_root_per_set_bit() {
+        let snapshot = snapshot(13);
+        let frontier = frontier(&snapshot.leaves);
+        assert_eq!(frontier.len(), 3);
+        assert_eq!(frontier[0], compute_merkle_root(&snapshot.leaves[..8]));
+        assert_eq!(frontier[2], snapshot.leaves[12]);
+        assert!(super::frontier(&[]).is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_version() {
+        let mut bytes = encode(&snapshot(3), 2);
+        bytes[0] ^= 1;
+        assert!(Snapshot::read(&mut bytes.as_slice()).is_err());
+
+        let mut bytes = encode(&snapshot(3), 2);
+        bytes[9] = 2;
+        let error = Snapshot::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("unsupported snapshot version 2"));
+    }
+
+    #[test]
+    fn rejects_corrupt_chunks() {
+        // 3 leaves have a frontier of 2 roots, then the first chunk's length and leaves.
+        let first_leaf = HEADER_LEN + 1 + 2 * 32 + 4;
+        let mut bytes = encode(&snapshot(3), 2);
+        bytes[first_leaf] ^= 1;
+        let error = Snapshot::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("checksum mismatch in chunk at leaf 0"));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = encode(&snapshot(3), 2);
+        for len in [HEADER_LEN, bytes.len() - 33, bytes.len() - 1] {
+            assert!(Snapshot::read(&mut &bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_chunks_past_the_leaf_count() {
+        let chunk_len = HEADER_LEN + 1 + 2 * 32;
+        let mut bytes = encode(&snapshot(3), 2);
+        bytes[chunk_len..chunk_len + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let error = Snapshot::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("invalid length"));
+    }
+
+    #[test]
+    fn rejects_wrong_root() {
+        let mut bytes = encode(&snapshot(3), 2);
+        bytes[HEADER_LEN - 1] ^= 1;
+        let error = Snapshot::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("root does not match"));
+    }
+}
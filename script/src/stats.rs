@@ -0,0 +1,319 @@
+//! Pool activity from an indexer's GraphQL API, and what it says about when to withdraw: how
+//! the anonymity set grew, how busy the pool is and how exposed the user's own notes are. The
+//! user's notes are matched against the indexer's data locally and never sent to it.
+
+use crate::notes::{NoteRecord, WatchedNote};
+use alloy::primitives::{Address, B256};
+use eyre::{eyre, Result};
+use pool_lib::compute_commitment;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, instrument};
+
+/// Rows requested per page, the most the indexer returns.
+const PAGE_SIZE: u32 = 1000;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+#[derive(Debug, Deserialize)]
+struct GraphqlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+async fn query<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &Url,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<T> {
+    let response: GraphqlResponse<T> = client
+        .post(url.clone())
+        .json(&json!({ "query": query, "variables": variables }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if let Some(error) = response.errors.first() {
+        return Err(eyre!("indexer query failed: {}", error.message));
+    }
+    response
+        .data
+        .ok_or_else(|| eyre!("indexer returned no data"))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexedDeposit {
+    commitment: B256,
+    block_number: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexedWithdrawal {
+    nullifier: B256,
+    block_number: u64,
+}
+
+/// Every deposit and withdrawal of a pool the indexer has seen.
+#[derive(Debug, Clone)]
+pub struct Activity {
+    /// Last block the indexer has indexed the pool to.
+    pub block_number: u64,
+    /// Commitment and block of each deposit, in leaf order.
+    pub deposits: Vec<(B256, u64)>,
+    /// Nullifier and block of each withdrawal, in the order they were made.
+    pub withdrawals: Vec<(B256, u64)>,
+}
+
+/// Fetch the activity of `pool` from the indexer's `POST /graphql` at `url`.
+#[instrument(skip(client))]
+pub async fn fetch_activity(
+    client: &reqwest::Client,
+    url: &Url,
+    pool: Address,
+) -> Result<Activity> {
+    #[derive(Deserialize)]
+    struct PoolData {
+        pool: PoolBlock,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct PoolBlock {
+        block_number: Option<u64>,
+    }
+    #[derive(Deserialize)]
+    struct Deposits {
+        deposits: Vec<IndexedDeposit>,
+    }
+    #[derive(Deserialize)]
+    struct Withdrawals {
+        withdrawals: Vec<IndexedWithdrawal>,
+    }
+
+    let pool_data: PoolData = query(
+        client,
+        url,
+        "query($pool: String!) { pool(address: $pool) { blockNumber } }",
+        json!({ "pool": pool.to_string() }),
+    )
+    .await?;
+    let block_number = pool_data
+        .pool
+        .block_number
+        .ok_or_else(|| eyre!("indexer does not index pool {pool}"))?;
+
+    let mut deposits = Vec::new();
+    loop {
+        let page: Deposits = query(
+            client,
+            url,
+            "query($pool: String!, $from: Int!, $limit: Int!) {
+                deposits(pool: $pool, from: $from, limit: $limit) { commitment blockNumber }
+            }",
+            json!({ "pool": pool.to_string(), "from": deposits.len(), "limit": PAGE_SIZE }),
+        )
+        .await?;
+        let done = page.deposits.len() < PAGE_SIZE as usize;
+        deposits.extend(
+            page.deposits
+                .into_iter()
+                .map(|deposit| (deposit.commitment, deposit.block_number)),
+        );
+        if done {
+            break;
+        }
+    }
+
+    let mut withdrawals = Vec::new();
+    loop {
+        let page: Withdrawals = query(
+            client,
+            url,
+            "query($pool: String!, $from: Int!, $limit: Int!) {
+                withdrawals(pool: $pool, from: $from, limit: $limit) { nullifier blockNumber }
+            }",
+            json!({ "pool": pool.to_string(), "from": withdrawals.len(), "limit": PAGE_SIZE }),
+        )
+        .await?;
+        let done = page.withdrawals.len() < PAGE_SIZE as usize;
+        withdrawals.extend(
+            page.withdrawals
+                .into_iter()
+                .map(|withdrawal| (withdrawal.nullifier, withdrawal.block_number)),
+        );
+        if done {
+            break;
+        }
+    }
+    debug!(
+        deposits = deposits.len(),
+        withdrawals = withdrawals.len(),
+        "fetched pool activity"
+    );
+
+    Ok(Activity {
+        block_number,
+        deposits,
+        withdrawals,
+    })
+}
+
+/// Deposits and withdrawals made by the end of a block.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnonymitySetPoint {
+    pub block_number: u64,
+    pub deposits: u64,
+    pub withdrawals: u64,
+    /// Deposits not yet withdrawn, the anonymity set a withdrawal then hid in.
+    pub unspent: u64,
+}
+
+/// How exposed one of the user's notes is.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteExposure {
+    pub commitment: B256,
+    pub leaf_index: u64,
+    pub block_number: u64,
+    /// Deposits that landed after this one. Withdrawing when few have links the withdrawal to
+    /// the deposit by timing.
+    pub deposits_after: u64,
+    pub spent: bool,
+    /// Whether the note is watched without its secret.
+    pub watch_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    pub pool: Address,
+    pub block_number: u64,
+    pub deposits: u64,
+    pub withdrawals: u64,
+    pub unspent: u64,
+    pub anonymity_set: Vec<AnonymitySetPoint>,
+    /// Blocks the rates are measured over, ending at `block_number`.
+    pub window_blocks: u64,
+    pub deposits_per_day: f64,
+    pub withdrawals_per_day: f64,
+    /// Median blocks between a deposit and its withdrawal, estimated by pairing withdrawals
+    /// with deposits in order since the pool does not link them. `None` before any withdrawal.
+    pub median_blocks_in_pool: Option<u64>,
+    pub notes: Vec<NoteExposure>,
+}
+
+impl PoolStats {
+    /// Summarize `activity`, with `points` anonymity set points from the first deposit on, rates
+    /// over the last `window_blocks` blocks of `block_time` seconds, and the exposure of those of
+    /// `notes` and `watched` deposited into the pool.
+    pub fn compute(
+        pool: Address,
+        activity: &Activity,
+        notes: &[NoteRecord],
+        watched: &[WatchedNote],
+        points: u64,
+        window_blocks: u64,
+        block_time: u64,
+    ) -> Self {
+        let deposit_blocks: Vec<u64> = activity.deposits.iter().map(|(_, block)| *block).collect();
+        let mut withdrawal_blocks: Vec<u64> = activity
+            .withdrawals
+            .iter()
+            .map(|(_, block)| *block)
+            .collect();
+        withdrawal_blocks.sort_unstable();
+        let count = |blocks: &[u64], by: u64| blocks.partition_point(|&block| block <= by) as u64;
+
+        let end = activity.block_number;
+        let start = deposit_blocks.first().copied().unwrap_or(end).min(end);
+        let anonymity_set = (0..points.max(1))
+            .map(|i| match points {
+                0 | 1 => end,
+                _ => start + (end - start) * i / (points - 1),
+            })
+            .map(|block_number| {
+                let deposits = count(&deposit_blocks, block_number);
+                let withdrawals = count(&withdrawal_blocks, block_number);
+                AnonymitySetPoint {
+                    block_number,
+                    deposits,
+                    withdrawals,
+                    unspent: deposits.saturating_sub(withdrawals),
+                }
+            })
+            .collect();
+
+        let window_start = end.saturating_sub(window_blocks);
+        let days = (end - window_start) as f64 * block_time as f64 / SECONDS_PER_DAY;
+        let rate = |blocks: &[u64]| {
+            if days > 0.0 {
+                (count(blocks, end) - count(blocks, window_start)) as f64 / days
+            } else {
+                0.0
+            }
+        };
+
+        let mut in_pool: Vec<u64> = deposit_blocks
+            .iter()
+            .zip(&withdrawal_blocks)
+            .map(|(deposit, withdrawal)| withdrawal.saturating_sub(*deposit))
+            .collect();
+        in_pool.sort_unstable();
+        let median_blocks_in_pool = (!in_pool.is_empty()).then(|| in_pool[in_pool.len() / 2]);
+
+        let total = activity.deposits.len() as u64;
+        let held = notes
+            .iter()
+            .filter(|record| record.note.pool == pool)
+            .map(|record| {
+                let nullifier = compute_commitment(&record.note.secret).1;
+                (record.commitment, nullifier, false)
+            });
+        let watched = watched
+            .iter()
+            .filter(|watched| watched.key.pool == pool)
+            .map(|watched| (watched.key.commitment, watched.key.nullifier, true));
+        let notes = held
+            .chain(watched)
+            .filter_map(|(commitment, nullifier, watch_only)| {
+                let leaf_index = activity
+                    .deposits
+                    .iter()
+                    .position(|(deposited, _)| *deposited == commitment)?;
+                Some(NoteExposure {
+                    commitment,
+                    leaf_index: leaf_index as u64,
+                    block_number: activity.deposits[leaf_index].1,
+                    deposits_after: total - leaf_index as u64 - 1,
+                    spent: activity
+                        .withdrawals
+                        .iter()
+                        .any(|(spent, _)| *spent == nullifier),
+                    watch_only,
+                })
+            })
+            .collect();
+
+        Self {
+            pool,
+            block_number: end,
+            deposits: total,
+            withdrawals: activity.withdrawals.len() as u64,
+            unspent: total.saturating_sub(activity.withdrawals.len() as u64),
+            anonymity_set,
+            window_blocks: end - window_start,
+            deposits_per_day: rate(&deposit_blocks),
+            withdrawals_per_day: rate(&withdrawal_blocks),
+            median_blocks_in_pool,
+            notes,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::pool::Pool;
+use alloy::{
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::{BlockTransactionsKind, Filter},
+    sol_types::SolEvent,
+};
+use eyre::{OptionExt, Result};
+use pool_lib::note::Note;
+use std::str::FromStr;
+
+/// A CLI argument that is either a full note or a raw secret.
+#[derive(Debug, Clone)]
+pub enum NoteOrSecret {
+    Note(Note),
+    Secret(B256),
+}
+
+impl FromStr for NoteOrSecret {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Note::from_str(s) {
+            Ok(note) => Ok(Self::Note(note)),
+            Err(_) => Ok(Self::Secret(B256::from_str(s)?)),
+        }
+    }
+}
+
+impl NoteOrSecret {
+    pub fn secret(&self) -> B256 {
+        match self {
+            Self::Note(note) => note.secret,
+            Self::Secret(secret) => *secret,
+        }
+    }
+}
+
+/// Where and when a nullifier was spent.
+#[derive(Debug, Clone)]
+pub struct Spend {
+    pub block_number: u64,
+    pub block_timestamp: Option<u64>,
+    pub tx_hash: B256,
+    pub recipient: Address,
+    pub relayer: Address,
+    pub relayer_fee: U256,
+}
+
+/// Spent status of a nullifier in one pool.
+#[derive(Debug, Clone)]
+pub struct NullifierStatus {
+    pub spent: bool,
+    /// The withdrawal that spent the nullifier, if it happened within the searched range.
+    pub spend: Option<Spend>,
+}
+
+/// Check the pool's nullifier mapping and search the last `lookback` blocks for the withdrawal
+/// that spent it.
+pub async fn nullifier_status<P: Provider>(
+    provider: &P,
+    pool: Address,
+    nullifier: B256,
+    lookback: u64,
+) -> Result<NullifierStatus> {
+    let spent = Pool::new(pool, provider)
+        .nullifiers(nullifier)
+        .call()
+        .await?
+        ._0;
+    if !spent {
+        return Ok(NullifierStatus { spent, spend: None });
+    }
+
+    let latest = provider.get_block_number().await?;
+    let filter = Filter::new()
+        .address(pool)
+        .event_signature(Pool::Withdrawal::SIGNATURE_HASH)
+        .topic1(nullifier)
+        .from_block(latest.saturating_sub(lookback))
+        .to_block(latest);
+    let spend = match provider.get_logs(&filter).await?.into_iter().next() {
+        Some(log) => {
+            let event = log.log_decode::<Pool::Withdrawal>()?;
+            let block_number = log.block_number.ok_or_eyre("log missing block number")?;
+            let block_timestamp = match log.block_timestamp {
+                Some(timestamp) => Some(timestamp),
+                None => provider
+                    .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+                    .await?
+                    .map(|block| block.header.timestamp),
+            };
+            Some(Spend {
+                block_number,
+                block_timestamp,
+                tx_hash: log
+                    .transaction_hash
+                    .ok_or_eyre("log missing transaction hash")?,
+                recipient: event.inner.data.recipient,
+                relayer: event.inner.data.relayer,
+                relayer_fee: event.inner.data.relayerFee,
+            })
+        }
+        None => None,
+    };
+
+    Ok(NullifierStatus { spent, spend })
+}
+
+/// The transaction that deposited a commitment.
+#[derive(Debug, Clone)]
+pub struct DepositOrigin {
+    pub block_number: u64,
+    pub block_timestamp: Option<u64>,
+    pub tx_hash: B256,
+    /// Sender of the deposit transaction.
+    pub depositor: Address,
+    pub leaf_index: u64,
+}
+
+/// Search the last `lookback` blocks for the deposit of `commitment` into `pool`.
+pub async fn deposit_origin<P: Provider>(
+    provider: &P,
+    pool: Address,
+    commitment: B256,
+    lookback: u64,
+) -> Result<Option<DepositOrigin>> {
+    let latest = provider.get_block_number().await?;
+    let filter = Filter::new()
+        .address(pool)
+        .from_block(latest.saturating_sub(lookback))
+        .to_block(latest);
+    let single = filter
+        .clone()
+        .event_signature(Pool::Deposit::SIGNATURE_HASH)
+        .topic1(commitment);
+    let (log, leaf_index) = match provider.get_logs(&single).await?.into_iter().next() {
+        Some(log) => {
+            let leaf_index = log.log_decode::<Pool::Deposit>()?.inner.data.leafIndex.to();
+            (log, leaf_index)
+        }
+        // Batched deposits do not index their commitments, so every batch is searched.
+        None => {
+            let batches = filter.event_signature(Pool::DepositMany::SIGNATURE_HASH);
+            let found = provider
+                .get_logs(&batches)
+                .await?
+                .into_iter()
+                .find_map(|log| {
+                    let event = log.log_decode::<Pool::DepositMany>().ok()?.inner.data;
+                    let position = event.commitments.iter().position(|c| *c == commitment)?;
+                    Some((log, event.firstLeafIndex.to::<u64>() + position as u64))
+                });
+            let Some(found) = found else {
+                return Ok(None);
+            };
+            found
+        }
+    };
+    let block_number = log.block_number.ok_or_eyre("log missing block number")?;
+    let tx_hash = log
+        .transaction_hash
+        .ok_or_eyre("log missing transaction hash")?;
+    let block_timestamp = match log.block_timestamp {
+        Some(timestamp) => Some(timestamp),
+        None => provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await?
+            .map(|block| block.header.timestamp),
+    };
+    let depositor = provider
+        .get_transaction_by_hash(tx_hash)
+        .await?
+        .ok_or_eyre("deposit transaction not found")?
+        .from;
+    Ok(Some(DepositOrigin {
+        block_number,
+        block_timestamp,
+        tx_hash,
+        depositor,
+        leaf_index,
+    }))
+}
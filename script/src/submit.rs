@@ -0,0 +1,295 @@
+use crate::{
+    bundle::{is_bundle, ProofBundle},
+    pool::Pool,
+};
+use alloy::{
+    eips::eip2718::Encodable2718,
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol_types::{SolCall, SolValue},
+    transports::http::reqwest::Url,
+};
+use eyre::{bail, ensure, Result};
+use pool_lib::{
+    bridge::BridgeMessage, compute_distribution_hash, split::Split, token_fee::TokenFeeConsent,
+    BridgeWithdrawalData, SplitWithdrawalData, Variant, WithdrawalData,
+};
+use sp1_sdk::SP1ProofWithPublicValues;
+use std::{path::Path, time::Duration};
+use tracing::{info, instrument, warn};
+
+/// The recipients of `splits` and their amounts, as `Pool.withdrawSplit` takes them.
+fn split_columns(splits: &[Split]) -> (Vec<Address>, Vec<U256>) {
+    splits
+        .iter()
+        .map(|split| (split.recipient, split.amount))
+        .unzip()
+}
+
+/// Flashbots Protect RPC, which keeps transactions out of the public mempool.
+pub const FLASHBOTS_PROTECT_RPC: &str = "https://rpc.flashbots.net/fast";
+
+/// A proven withdrawal ready to be sent to the pool contract.
+#[derive(Debug, Clone)]
+pub struct Withdrawal {
+    pub public_values: Bytes,
+    pub proof: Bytes,
+    /// The bridge deposit its public values commit to, for withdrawals to an L2 address.
+    pub bridge: Option<BridgeMessage>,
+    /// The recipients and amounts its public values commit to, for split withdrawals.
+    pub splits: Vec<Split>,
+    /// The recipient's consent to the fee, for withdrawals paying their relayer in a token.
+    pub token_fee: Option<TokenFeeConsent>,
+}
+
+impl Withdrawal {
+    /// Load a withdrawal from a bincode-serialized proof file written by `withdraw --prove`, or
+    /// a [`ProofBundle`] written by `pool export-proof` or other SP1 tooling.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if is_bundle(&bytes) {
+            let bundle: ProofBundle = serde_json::from_slice(&bytes)?;
+            return Ok(bundle.into());
+        }
+        let proof: SP1ProofWithPublicValues = bincode::deserialize(&bytes)?;
+        Ok(Self::from_proof(&proof))
+    }
+
+    pub fn from_proof(proof: &SP1ProofWithPublicValues) -> Self {
+        Self {
+            public_values: proof.public_values.to_vec().into(),
+            proof: proof.bytes().into(),
+            bridge: None,
+            splits: Vec::new(),
+            token_fee: None,
+        }
+    }
+
+    /// Attach the bridge deposit of a withdrawal to an L2 address, checking its public values
+    /// commit to it. Without one, the withdrawal must not be to an L2 address.
+    pub fn with_bridge(mut self, bridge: Option<BridgeMessage>) -> Result<Self> {
+        let data = BridgeWithdrawalData::abi_decode(&self.public_values, true)
+            .ok()
+            .filter(|data| data.withdrawal.variant == Variant::Bridge as u8);
+        match (&bridge, data) {
+            (Some(message), Some(data)) => message.check(&data)?,
+            (Some(_), None) => bail!("withdrawal is not to an L2 address"),
+            (None, Some(data)) => bail!(
+                "withdrawal is to {} on chain {}, and needs its bridge message",
+                data.l2Recipient,
+                data.l2ChainId
+            ),
+            (None, None) => {}
+        }
+        self.bridge = bridge;
+        Ok(self)
+    }
+
+    /// Attach the recipients of a split withdrawal, checking its public values commit to them.
+    /// Without any, the withdrawal must not be split.
+    pub fn with_splits(mut self, splits: Vec<Split>) -> Result<Self> {
+        let data = SplitWithdrawalData::abi_decode(&self.public_values, true)
+            .ok()
+            .filter(|data| data.withdrawal.variant == Variant::Split as u8);
+        match (splits.is_empty(), data) {
+            (false, Some(data)) => {
+                let (recipients, amounts) = split_columns(&splits);
+                ensure!(
+                    compute_distribution_hash(&recipients, &amounts) == data.distributionHash,
+                    "withdrawal commits to a different distribution"
+                );
+            }
+            (false, None) => bail!("withdrawal is not split between recipients"),
+            (true, Some(_)) => bail!("withdrawal is split, and needs its --split recipients"),
+            (true, None) => {}
+        }
+        self.splits = splits;
+        Ok(self)
+    }
+
+    /// Attach the recipient's consent to the fee of a withdrawal paying its relayer in a token.
+    /// Without one, the withdrawal must pay its relayer in ETH. The pool checks the consent.
+    pub fn with_token_fee(mut self, consent: Option<TokenFeeConsent>) -> Result<Self> {
+        let data = WithdrawalData::abi_decode(&self.public_values, false)?;
+        match (&consent, data.feeToken.is_zero()) {
+            (Some(_), true) => bail!("withdrawal pays no fee in a token"),
+            (None, false) => bail!(
+                "withdrawal pays its relayer in token {}, and needs the recipient's consent",
+                data.feeToken
+            ),
+            _ => {}
+        }
+        self.token_fee = consent;
+        Ok(self)
+    }
+
+    /// The variant the public values are tagged with, if they decode.
+    pub fn variant(&self) -> Option<Variant> {
+        let data = WithdrawalData::abi_decode(&self.public_values, false).ok()?;
+        Variant::from_tag(data.variant)
+    }
+
+    /// Calldata for `Pool.withdraw`, `Pool.withdrawWithTokenFee` for withdrawals paying their
+    /// relayer in a token, `BridgePool.withdrawToL2` for withdrawals to an L2 address,
+    /// `Pool.withdrawSplit` for split withdrawals, `FeePool.withdrawWithProtocolFee` for
+    /// withdrawals paying a protocol fee, `RegistryPool.withdrawRegistered` for withdrawals
+    /// proving a registry entry, `OraclePool.withdrawWithOracle` for withdrawals anchored
+    /// through a block hash oracle, or `AssociationPool.withdrawAssociated` for withdrawals from
+    /// an association set.
+    pub fn calldata(&self) -> Bytes {
+        if let Some(consent) = &self.token_fee {
+            let permit = consent.permit.as_ref();
+            let signature = permit.map(|permit| permit.signature);
+            return Pool::withdrawWithTokenFeeCall {
+                _publicValues: self.public_values.clone(),
+                _proofBytes: self.proof.clone(),
+                _fee: Pool::TokenFeePermit {
+                    authorization: consent.authorization.as_bytes().into(),
+                    deadline: permit.map_or(U256::ZERO, |permit| permit.deadline),
+                    v: signature.map_or(0, |signature| 27 + signature.v() as u8),
+                    r: signature.map_or(B256::ZERO, |signature| signature.r().to_be_bytes().into()),
+                    s: signature.map_or(B256::ZERO, |signature| signature.s().to_be_bytes().into()),
+                },
+            }
+            .abi_encode()
+            .into();
+        }
+        match &self.bridge {
+            None if !self.splits.is_empty() => {
+                let (recipients, amounts) = split_columns(&self.splits);
+                Pool::withdrawSplitCall {
+                    _publicValues: self.public_values.clone(),
+                    _proofBytes: self.proof.clone(),
+                    _recipients: recipients,
+                    _amounts: amounts,
+                }
+                .abi_encode()
+            }
+            None if self.variant() == Some(Variant::ProtocolFee) => {
+                Pool::withdrawWithProtocolFeeCall {
+                    _publicValues: self.public_values.clone(),
+                    _proofBytes: self.proof.clone(),
+                }
+                .abi_encode()
+            }
+            None if self.variant() == Some(Variant::Registry) => Pool::withdrawRegisteredCall {
+                _publicValues: self.public_values.clone(),
+                _proofBytes: self.proof.clone(),
+            }
+            .abi_encode(),
+            None if self.variant() == Some(Variant::Oracle) => Pool::withdrawWithOracleCall {
+                _publicValues: self.public_values.clone(),
+                _proofBytes: self.proof.clone(),
+            }
+            .abi_encode(),
+            None if self.variant() == Some(Variant::Association) => Pool::withdrawAssociatedCall {
+                _publicValues: self.public_values.clone(),
+                _proofBytes: self.proof.clone(),
+            }
+            .abi_encode(),
+            Some(message) => Pool::withdrawToL2Call {
+                _publicValues: self.public_values.clone(),
+                _proofBytes: self.proof.clone(),
+                _minGasLimit: message.min_gas_limit,
+                _extraData: message.extra_data.clone(),
+            }
+            .abi_encode(),
+            None => Pool::withdrawCall {
+                _publicValues: self.public_values.clone(),
+                _proofBytes: self.proof.clone(),
+            }
+            .abi_encode(),
+        }
+        .into()
+    }
+}
+
+/// Send the withdrawal to `pool` from `signer`'s account and wait for the receipt.
+#[instrument(skip_all, fields(%pool))]
+pub async fn submit_direct(
+    rpc_url: Url,
+    signer: PrivateKeySigner,
+    pool: Address,
+    withdrawal: &Withdrawal,
+) -> Result<B256> {
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .on_http(rpc_url);
+    let tx = TransactionRequest::default()
+        .with_to(pool)
+        .with_input(withdrawal.calldata());
+    let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+    ensure!(
+        receipt.status(),
+        "withdrawal reverted in {}",
+        receipt.transaction_hash
+    );
+    Ok(receipt.transaction_hash)
+}
+
+/// Send the withdrawal through a private transaction RPC, falling back to broadcasting the same
+/// signed transaction publicly if it is not included within `fallback_blocks` blocks, and giving
+/// up once it is not included within `max_blocks`.
+#[instrument(skip_all, fields(%pool))]
+pub async fn submit_private(
+    rpc_url: Url,
+    private_rpc_url: Url,
+    signer: PrivateKeySigner,
+    pool: Address,
+    withdrawal: &Withdrawal,
+    fallback_blocks: u64,
+    max_blocks: u64,
+) -> Result<B256> {
+    ensure!(
+        max_blocks > fallback_blocks,
+        "--max-blocks must be more than --fallback-blocks"
+    );
+    let public = RootProvider::new_http(rpc_url);
+    let private = RootProvider::new_http(private_rpc_url);
+
+    let from = signer.address();
+    let mut tx = TransactionRequest::default()
+        .with_from(from)
+        .with_to(pool)
+        .with_input(withdrawal.calldata())
+        .with_chain_id(public.get_chain_id().await?)
+        .with_nonce(public.get_transaction_count(from).pending().await?);
+    tx.set_gas_limit(public.estimate_gas(&tx).await?);
+    let fees = public.estimate_eip1559_fees(None).await?;
+    tx.set_max_fee_per_gas(fees.max_fee_per_gas);
+    tx.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+
+    let envelope = tx.build(&EthereumWallet::from(signer)).await?;
+    let tx_hash = *envelope.tx_hash();
+    let raw = envelope.encoded_2718();
+
+    private.send_raw_transaction(&raw).await?;
+    info!(%tx_hash, "sent private transaction");
+
+    let start_block = public.get_block_number().await?;
+    let mut fallback_sent = false;
+    loop {
+        if let Some(receipt) = public.get_transaction_receipt(tx_hash).await? {
+            ensure!(receipt.status(), "withdrawal reverted in {}", tx_hash);
+            return Ok(tx_hash);
+        }
+
+        let block = public.get_block_number().await?;
+        if !fallback_sent && block >= start_block + fallback_blocks {
+            warn!(
+                fallback_blocks,
+                "not included privately, broadcasting publicly"
+            );
+            public.send_raw_transaction(&raw).await?;
+            fallback_sent = true;
+        }
+        ensure!(
+            block < start_block + max_blocks,
+            "withdrawal {tx_hash} not included within {max_blocks} blocks"
+        );
+        tokio::time::sleep(Duration::from_secs(4)).await;
+    }
+}
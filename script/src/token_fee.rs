@@ -0,0 +1,157 @@
+//! Signing a recipient's consent to the token fee of a withdrawal, which
+//! `Pool.withdrawWithTokenFee` needs before it takes the fee from them.
+
+use alloy::{
+    primitives::{keccak256, PrimitiveSignature, B256, U256},
+    providers::Provider,
+    signers::{local::PrivateKeySigner, SignerSync},
+    sol,
+    sol_types::{SolStruct, SolValue},
+};
+use eyre::{ensure, Result};
+use pool_lib::{
+    intent::intent_domain,
+    token_fee::{TokenFeeAuthorization, TokenFeeConsent, TokenPermit},
+    WithdrawalData,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20Permit {
+        function DOMAIN_SEPARATOR() external view returns (bytes32);
+
+        function nonces(address owner) external view returns (uint256);
+    }
+}
+
+/// Seconds a permit of a token fee stays valid once signed.
+pub const PERMIT_TTL: u64 = 24 * 60 * 60;
+
+/// Sign the authorization of the token fee `data` pays, as its recipient `signer`, for a pool on
+/// chain `chain_id`.
+pub fn authorize(
+    signer: &PrivateKeySigner,
+    chain_id: u64,
+    data: &WithdrawalData,
+) -> Result<PrimitiveSignature> {
+    ensure!(
+        signer.address() == data.recipient,
+        "the token fee is paid by the recipient {}, not {}",
+        data.recipient,
+        signer.address()
+    );
+    let domain = intent_domain(chain_id, data.contractAddress);
+    Ok(signer.sign_hash_sync(&TokenFeeAuthorization::new(data).eip712_signing_hash(&domain))?)
+}
+
+/// Sign the recipient's consent to the token fee `data` pays: its authorization and, if the token
+/// takes EIP-2612 permits, a permit of the fee to the pool.
+pub async fn sign_token_fee<P: Provider>(
+    provider: &P,
+    signer: &PrivateKeySigner,
+    data: &WithdrawalData,
+) -> Result<TokenFeeConsent> {
+    let authorization = authorize(signer, provider.get_chain_id().await?, data)?;
+    let token = IERC20Permit::new(data.feeToken, provider);
+    let permit = match tokio::try_join!(
+        token.DOMAIN_SEPARATOR().call(),
+        token.nonces(data.recipient).call()
+    ) {
+        Ok((separator, nonce)) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let deadline = U256::from(now + PERMIT_TTL);
+            let hash = permit_hash(separator._0, data, nonce._0, deadline);
+            Some(TokenPermit {
+                deadline,
+                signature: signer.sign_hash_sync(&hash)?,
+            })
+        }
+        Err(e) => {
+            warn!(
+                token = %data.feeToken,
+                "token takes no permits, so the pool must be approved for the fee, which links \
+                 the recipient to it: {e}"
+            );
+            None
+        }
+    };
+    Ok(TokenFeeConsent {
+        authorization,
+        permit,
+    })
+}
+
+/// EIP-2612 digest of a permit to the pool of `data` for its token fee, from its recipient.
+fn permit_hash(domain_separator: B256, data: &WithdrawalData, nonce: U256, deadline: U256) -> B256 {
+    let typehash = keccak256(
+        "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)",
+    );
+    let struct_hash = keccak256(
+        (
+            typehash,
+            data.recipient,
+            data.contractAddress,
+            data.feeTokenAmount,
+            nonce,
+            deadline,
+        )
+            .abi_encode(),
+    );
+    keccak256([&[0x19, 0x01][..], &domain_separator[..], &struct_hash[..]].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Address;
+    use pool_lib::fixture::withdrawal_data;
+
+    fn token_fee(recipient: Address) -> WithdrawalData {
+        WithdrawalData {
+            relayerFee: U256::ZERO,
+            recipient,
+            feeToken: Address::with_last_byte(7),
+            feeTokenAmount: U256::from(1000),
+            ..withdrawal_data()
+        }
+    }
+
+    #[test]
+    fn recipient_authorizes_its_token_fee() {
+        let recipient = PrivateKeySigner::random();
+        let data = token_fee(recipient.address());
+        let consent = TokenFeeConsent {
+            authorization: authorize(&recipient, 1, &data).unwrap(),
+            permit: None,
+        };
+        consent.verify(1, &data).unwrap();
+        // The authorization is for one chain, relayer and amount.
+        assert!(consent.verify(2, &data).is_err());
+        let mut other = data.clone();
+        other.relayer = Address::with_last_byte(8);
+        assert!(consent.verify(1, &other).is_err());
+        let mut other = data.clone();
+        other.feeTokenAmount = U256::from(1001);
+        assert!(consent.verify(1, &other).is_err());
+    }
+
+    #[test]
+    fn only_the_recipient_authorizes() {
+        // What an attacker proving a withdrawal of their own note to a victim would try.
+        let victim = Address::with_last_byte(9);
+        let attacker = PrivateKeySigner::random();
+        let data = token_fee(victim);
+        assert!(authorize(&attacker, 1, &data).is_err());
+
+        let mut own = token_fee(attacker.address());
+        let authorization = authorize(&attacker, 1, &own).unwrap();
+        own.recipient = victim;
+        let consent = TokenFeeConsent {
+            authorization,
+            permit: None,
+        };
+        assert!(consent.verify(1, &own).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::submit::Withdrawal;
+use alloy::{
+    network::Ethereum,
+    primitives::{address, Address, Bytes, B256, U256},
+    providers::{Provider, RootProvider},
+    signers::{local::PrivateKeySigner, SignerSync},
+    sol,
+    sol_types::SolCall,
+};
+use eyre::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, instrument};
+
+/// The ERC-4337 v0.7 EntryPoint.
+pub const ENTRY_POINT_V07: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+
+sol! {
+    struct PackedUserOperation {
+        address sender;
+        uint256 nonce;
+        bytes initCode;
+        bytes callData;
+        bytes32 accountGasLimits;
+        uint256 preVerificationGas;
+        bytes32 gasFees;
+        bytes paymasterAndData;
+        bytes signature;
+    }
+
+    #[sol(rpc)]
+    contract EntryPoint {
+        function getNonce(address sender, uint192 key) external view returns (uint256 nonce);
+        function getUserOpHash(PackedUserOperation calldata userOp) external view returns (bytes32);
+    }
+
+    /// The `execute` entrypoint shared by SimpleAccount-style smart accounts.
+    contract SmartAccount {
+        function execute(address dest, uint256 value, bytes calldata func) external;
+    }
+}
+
+/// A v0.7 user operation in the unpacked form used by bundler RPCs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_verification_gas_limit: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_post_op_gas_limit: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_data: Option<Bytes>,
+    pub signature: Bytes,
+}
+
+/// Gas limits returned by `eth_estimateUserOperationGas`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GasEstimate {
+    call_gas_limit: U256,
+    verification_gas_limit: U256,
+    pre_verification_gas: U256,
+    paymaster_verification_gas_limit: Option<U256>,
+    paymaster_post_op_gas_limit: Option<U256>,
+}
+
+/// Paymaster sponsoring the user operation.
+#[derive(Debug, Clone)]
+pub struct Paymaster {
+    pub address: Address,
+    pub data: Bytes,
+}
+
+/// Pack two 128-bit values into one word, high half first.
+fn pack_u128s(high: U256, low: U256) -> B256 {
+    ((high << 128) | low).into()
+}
+
+impl UserOperation {
+    /// The packed form hashed by the EntryPoint.
+    pub fn pack(&self) -> PackedUserOperation {
+        let paymaster_and_data = match self.paymaster {
+            Some(paymaster) => {
+                let mut out = paymaster.to_vec();
+                out.extend_from_slice(
+                    &self
+                        .paymaster_verification_gas_limit
+                        .unwrap_or_default()
+                        .to_be_bytes::<32>()[16..],
+                );
+                out.extend_from_slice(
+                    &self
+                        .paymaster_post_op_gas_limit
+                        .unwrap_or_default()
+                        .to_be_bytes::<32>()[16..],
+                );
+                out.extend_from_slice(self.paymaster_data.as_deref().unwrap_or_default());
+                out.into()
+            }
+            None => Bytes::new(),
+        };
+        PackedUserOperation {
+            sender: self.sender,
+            nonce: self.nonce,
+            initCode: Bytes::new(),
+            callData: self.call_data.clone(),
+            accountGasLimits: pack_u128s(self.verification_gas_limit, self.call_gas_limit),
+            preVerificationGas: self.pre_verification_gas,
+            gasFees: pack_u128s(self.max_priority_fee_per_gas, self.max_fee_per_gas),
+            paymasterAndData: paymaster_and_data,
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+/// Submits withdrawals as ERC-4337 user operations from a smart account.
+pub struct UserOpSubmitter {
+    pub provider: RootProvider<Ethereum>,
+    pub bundler: RootProvider<Ethereum>,
+    pub entry_point: Address,
+    pub account: Address,
+    pub owner: PrivateKeySigner,
+    pub paymaster: Option<Paymaster>,
+}
+
+impl UserOpSubmitter {
+    /// Build and sign a user operation calling `Pool.withdraw` on `pool` from the account.
+    pub async fn build(&self, pool: Address, withdrawal: &Withdrawal) -> Result<UserOperation> {
+        let entry_point = EntryPoint::new(self.entry_point, &self.provider);
+        let nonce = entry_point
+            .getNonce(self.account, Default::default())
+            .call()
+            .await?
+            .nonce;
+        let fees = self.provider.estimate_eip1559_fees(None).await?;
+
+        let call_data = SmartAccount::executeCall {
+            dest: pool,
+            value: U256::ZERO,
+            func: withdrawal.calldata(),
+        }
+        .abi_encode();
+
+        let mut op = UserOperation {
+            sender: self.account,
+            nonce,
+            call_data: call_data.into(),
+            max_fee_per_gas: U256::from(fees.max_fee_per_gas),
+            max_priority_fee_per_gas: U256::from(fees.max_priority_fee_per_gas),
+            paymaster: self.paymaster.as_ref().map(|p| p.address),
+            paymaster_data: self.paymaster.as_ref().map(|p| p.data.clone()),
+            // Estimation needs a signature of the right shape, not a valid one.
+            signature: vec![0xff; 65].into(),
+            ..Default::default()
+        };
+
+        let estimate: GasEstimate = self
+            .bundler
+            .raw_request(
+                "eth_estimateUserOperationGas".into(),
+                (&op, self.entry_point),
+            )
+            .await?;
+        op.call_gas_limit = estimate.call_gas_limit;
+        op.verification_gas_limit = estimate.verification_gas_limit;
+        op.pre_verification_gas = estimate.pre_verification_gas;
+        if op.paymaster.is_some() {
+            op.paymaster_verification_gas_limit = estimate.paymaster_verification_gas_limit;
+            op.paymaster_post_op_gas_limit = estimate.paymaster_post_op_gas_limit;
+        }
+
+        let hash = entry_point.getUserOpHash(op.pack()).call().await?._0;
+        let signature = self.owner.sign_message_sync(hash.as_slice())?;
+        op.signature = Bytes::copy_from_slice(&signature.as_bytes());
+        Ok(op)
+    }
+
+    /// Send a signed user operation to the bundler and wait up to `timeout` for it to be
+    /// included.
+    #[instrument(skip_all, fields(sender = %op.sender))]
+    pub async fn send(&self, op: &UserOperation, timeout: Duration) -> Result<B256> {
+        let op_hash: B256 = self
+            .bundler
+            .raw_request("eth_sendUserOperation".into(), (op, self.entry_point))
+            .await?;
+        info!(%op_hash, "sent user operation");
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let receipt: Option<serde_json::Value> = self
+                .bundler
+                .raw_request("eth_getUserOperationReceipt".into(), (op_hash,))
+                .await?;
+            if let Some(receipt) = receipt {
+                ensure!(
+                    receipt["success"].as_bool() == Some(true),
+                    "user operation {op_hash} reverted"
+                );
+                let tx_hash =
+                    serde_json::from_value(receipt["receipt"]["transactionHash"].clone())?;
+                return Ok(tx_hash);
+            }
+            ensure!(
+                Instant::now() < deadline,
+                "user operation {op_hash} not included after {timeout:?}, the bundler may still \
+                 include it"
+            );
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{aggregate::AGGREGATION_ELF, disclosure::DISCLOSURE_ELF, pool::Pool, prove::ELF};
+use alloy::{
+    primitives::{keccak256, Address, B256},
+    providers::Provider,
+};
+use eyre::{bail, ensure, eyre, Result};
+use sp1_sdk::{HashableKey, Prover, ProverClient};
+use std::{path::Path, process::Command, sync::OnceLock};
+use tracing::{error, info, instrument};
+
+/// Docker image tag of the SP1 toolchain the guest is built with, matching the `sp1-sdk` version.
+pub const SP1_DOCKER_TAG: &str = "v4.0.0";
+
+/// Name the guest ELF is built under.
+pub const ELF_NAME: &str = "pool-program";
+
+/// Rebuild the guest program in `program_dir` reproducibly inside the pinned SP1 docker image
+/// and return the ELF.
+#[instrument(skip_all, fields(program_dir = %program_dir.display(), tag))]
+pub fn rebuild(program_dir: &Path, tag: &str) -> Result<Vec<u8>> {
+    let out_dir = std::env::temp_dir().join(format!("pool-verify-build-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir)?;
+    info!("rebuilding guest in docker");
+    let status = Command::new("cargo")
+        .current_dir(program_dir)
+        .args(["prove", "build", "--docker", "--locked", "--tag", tag])
+        .args(["--elf-name", ELF_NAME])
+        .arg("--output-directory")
+        .arg(&out_dir)
+        .status()
+        .map_err(|e| {
+            eyre::eyre!("failed to run cargo prove: {e}, is the SP1 toolchain installed?")
+        })?;
+    ensure!(status.success(), "docker build failed with {status}");
+    let elf = std::fs::read(out_dir.join(ELF_NAME))?;
+    std::fs::remove_dir_all(&out_dir)?;
+    Ok(elf)
+}
+
+/// Verifying key hash of `elf`, as the pool contract stores it in `programVkey`.
+pub fn vkey(elf: &[u8]) -> Result<B256> {
+    let (_, vk) = ProverClient::builder().mock().build().setup(elf);
+    Ok(vk.bytes32().parse()?)
+}
+
+/// Identity of a guest build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildId {
+    pub elf_hash: B256,
+    pub vkey: B256,
+}
+
+impl BuildId {
+    pub fn of(elf: &[u8]) -> Result<Self> {
+        Ok(Self {
+            elf_hash: keccak256(elf),
+            vkey: vkey(elf)?,
+        })
+    }
+}
+
+/// An embedded guest program and the vkey hash it was pinned to when this binary was built, from
+/// the `POOL_PROGRAM_VKEY`, `POOL_AGGREGATION_VKEY` or `POOL_DISCLOSURE_VKEY` variable.
+pub struct Program {
+    pub name: &'static str,
+    pub elf: &'static [u8],
+    pub pinned: Option<&'static str>,
+    vkey: OnceLock<B256>,
+}
+
+pub static WITHDRAWAL_PROGRAM: Program = Program {
+    name: ELF_NAME,
+    elf: ELF,
+    pinned: option_env!("POOL_PROGRAM_VKEY"),
+    vkey: OnceLock::new(),
+};
+
+pub static AGGREGATION_PROGRAM: Program = Program {
+    name: "pool-aggregation",
+    elf: AGGREGATION_ELF,
+    pinned: option_env!("POOL_AGGREGATION_VKEY"),
+    vkey: OnceLock::new(),
+};
+
+pub static DISCLOSURE_PROGRAM: Program = Program {
+    name: "pool-disclosure",
+    elf: DISCLOSURE_ELF,
+    pinned: option_env!("POOL_DISCLOSURE_VKEY"),
+    vkey: OnceLock::new(),
+};
+
+impl Program {
+    /// Vkey hash of the embedded ELF, recomputed once per process and checked against the one
+    /// pinned at build time. A mismatch means the ELF is not the program the binary was released
+    /// for, and nothing should be proved with it.
+    pub fn checked_vkey(&self) -> Result<B256> {
+        if let Some(vkey) = self.vkey.get() {
+            return Ok(*vkey);
+        }
+        let vkey = vkey(self.elf)?;
+        match self.pinned {
+            Some(pinned) => {
+                let pinned: B256 = pinned
+                    .parse()
+                    .map_err(|_| eyre!("invalid pinned vkey for {}", self.name))?;
+                if vkey != pinned {
+                    error!(
+                        program = self.name,
+                        %vkey,
+                        %pinned,
+                        "embedded program is not the pinned one"
+                    );
+                    bail!(
+                        "embedded {} has vkey {vkey} but this binary was pinned to {pinned}",
+                        self.name
+                    );
+                }
+                info!(program = self.name, %vkey, "embedded program matches its pinned vkey");
+            }
+            None => info!(program = self.name, %vkey, "no vkey pinned at build time"),
+        }
+        Ok(*self.vkey.get_or_init(|| vkey))
+    }
+}
+
+/// Fail unless `pool` verifies proofs of the program with `vkey`, before any proof for it is
+/// generated or submitted.
+pub async fn check_pool_vkey<P: Provider>(provider: &P, pool: Address, vkey: B256) -> Result<()> {
+    let deployed = Pool::new(pool, provider)
+        .programVkey()
+        .call()
+        .await
+        .map_err(|e| eyre!("failed to read the program of pool {pool}: {e}"))?
+        ._0;
+    if deployed != vkey {
+        error!(%pool, %deployed, %vkey, "pool verifies a different program");
+        bail!("pool {pool} verifies program {deployed}, but this binary proves {vkey}");
+    }
+    Ok(())
+}
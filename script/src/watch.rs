@@ -0,0 +1,77 @@
+//! Watch-only notes followed through an indexer database. A watched note's commitment confirms
+//! its deposit, the pool's later deposits and withdrawals measure how its anonymity set grew, and
+//! its nullifier shows up in a `Withdrawal` event once it is spent. None of it needs the secret,
+//! and nothing here can withdraw the note.
+
+use crate::{indexer::filter::find_deposits, notes::WatchedNote};
+use eyre::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use tracing::instrument;
+
+/// Where a watched note stands according to the indexer.
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchStatus {
+    /// Last block of the note's pool the indexer has seen, `None` if it does not follow the pool.
+    pub indexed_to: Option<u64>,
+    /// Deposits that landed after the note's. Withdrawing when few have links the withdrawal to
+    /// the deposit by timing.
+    pub deposits_after: u64,
+    /// Deposits into the pool not yet withdrawn, the anonymity set a withdrawal would hide in.
+    pub unspent: u64,
+}
+
+/// Bring `watched` up to date with the indexer database at `db`, recording its deposit and
+/// withdrawal as the indexer has them, which forgets either if it was reorged out.
+#[instrument(skip_all, fields(commitment = %watched.key.commitment))]
+pub fn sync_watched(
+    conn: &Connection,
+    db: &Path,
+    watched: &mut WatchedNote,
+) -> Result<WatchStatus> {
+    let key = &watched.key;
+    let pool = key.pool.to_string();
+    let indexed_to: Option<u64> = conn
+        .query_row(
+            "SELECT block_number FROM indexed_pools WHERE chain_id = ?1 AND pool = ?2",
+            params![key.chain_id, pool],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let count = |sql: &str| -> Result<u64> {
+        Ok(conn.query_row(sql, params![key.chain_id, pool], |row| row.get(0))?)
+    };
+    let deposits = count("SELECT COUNT(*) FROM deposits WHERE chain_id = ?1 AND pool = ?2")?;
+    let withdrawals = count("SELECT COUNT(*) FROM withdrawals WHERE chain_id = ?1 AND pool = ?2")?;
+
+    let leaf_index = find_deposits(conn, db, key.chain_id, key.pool, &[key.commitment])?
+        .get(&key.commitment)
+        .copied();
+    let deposit = match leaf_index {
+        Some(leaf_index) => Some(conn.query_row(
+            "SELECT block_number, tx_hash FROM deposits
+             WHERE chain_id = ?1 AND pool = ?2 AND leaf_index = ?3",
+            params![key.chain_id, pool, leaf_index],
+            |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?)),
+        )?),
+        None => None,
+    };
+    let spent: Option<String> = conn
+        .query_row(
+            "SELECT tx_hash FROM withdrawals WHERE chain_id = ?1 AND pool = ?2 AND nullifier = ?3",
+            params![key.chain_id, pool, key.nullifier.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    watched.leaf_index = leaf_index;
+    watched.block_number = deposit.as_ref().map(|(block_number, _)| *block_number);
+    watched.tx_hash = deposit.map(|(_, tx_hash)| tx_hash.parse()).transpose()?;
+    watched.spent_tx_hash = spent.map(|tx_hash| tx_hash.parse()).transpose()?;
+    Ok(WatchStatus {
+        indexed_to,
+        deposits_after: leaf_index.map_or(0, |leaf_index| deposits - leaf_index - 1),
+        unspent: deposits.saturating_sub(withdrawals),
+    })
+}
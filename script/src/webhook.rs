@@ -0,0 +1,344 @@
+use crate::chat::{Discord, Telegram};
+use alloy::primitives::{Address, B256, U256};
+use clap::Args;
+use eyre::Result;
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde::Serialize;
+use sha2::Sha256;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, warn};
+
+/// Deliveries made per target before giving up on an event.
+const MAX_DELIVERIES: u32 = 4;
+
+/// Delay before the first redelivery, doubled on every attempt.
+const REDELIVERY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Webhook and chat alert options shared by the relayer and indexer.
+#[derive(Args, Debug, Clone)]
+pub struct WebhookArgs {
+    /// URLs every event is POSTed to.
+    #[clap(long = "webhook-url", env = "WEBHOOK_URLS", value_delimiter = ',')]
+    pub urls: Vec<Url>,
+
+    /// Key payloads are signed with. Receivers check the `X-Pool-Signature` header with it.
+    #[clap(long, env = "WEBHOOK_SECRET", requires = "urls")]
+    pub webhook_secret: Option<String>,
+
+    /// Telegram bot alerts are sent from.
+    #[clap(long, env = "TELEGRAM_BOT_TOKEN", requires = "telegram_chat_id")]
+    pub telegram_bot_token: Option<String>,
+
+    /// Telegram chat alerts are sent to.
+    #[clap(long, env = "TELEGRAM_CHAT_ID", requires = "telegram_bot_token")]
+    pub telegram_chat_id: Option<String>,
+
+    /// Discord webhook alerts are posted to.
+    #[clap(long, env = "DISCORD_WEBHOOK_URL")]
+    pub discord_webhook_url: Option<Url>,
+}
+
+impl WebhookArgs {
+    /// The notifier for these options, if any target is set.
+    pub fn notifier(&self) -> Option<Notifier> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("client builds");
+        let mut sinks: Vec<Arc<dyn Sink>> = Vec::new();
+        if !self.urls.is_empty() && self.webhook_secret.is_none() {
+            warn!("webhook payloads are not signed, set --webhook-secret");
+        }
+        for url in &self.urls {
+            sinks.push(Arc::new(Webhook {
+                url: url.clone(),
+                secret: self.webhook_secret.clone().map(String::into_bytes),
+                http: http.clone(),
+            }));
+        }
+        if let (Some(token), Some(chat_id)) = (&self.telegram_bot_token, &self.telegram_chat_id) {
+            sinks.push(Arc::new(Telegram::new(token, chat_id, http.clone())));
+        }
+        if let Some(url) = &self.discord_webhook_url {
+            sinks.push(Arc::new(Discord::new(url.clone(), http)));
+        }
+        (!sinks.is_empty()).then(|| Notifier::new(sinks))
+    }
+}
+
+/// An event delivered to webhooks, as a JSON object tagged with `type`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A watched commitment was deposited.
+    DepositConfirmed {
+        chain_id: u64,
+        pool: Address,
+        commitment: B256,
+        leaf_index: u64,
+        block_number: u64,
+        tx_hash: B256,
+    },
+    /// A watched nullifier was withdrawn.
+    NoteSpent {
+        chain_id: u64,
+        pool: Address,
+        nullifier: B256,
+        recipient: Address,
+        block_number: u64,
+        tx_hash: B256,
+    },
+    /// A relay job's withdrawal was mined.
+    JobMined {
+        job_id: String,
+        tx_hash: B256,
+        block_number: u64,
+    },
+    /// A relay job ran out of attempts.
+    JobFailed {
+        job_id: String,
+        error: String,
+        tx_hash: Option<B256>,
+    },
+    /// A relay job's mined withdrawal was dropped by a reorg.
+    JobReorged { job_id: String, tx_hash: B256 },
+    /// The relayer's hot wallet is below its threshold and could not be topped up.
+    BalanceLow {
+        address: Address,
+        balance: U256,
+        threshold: U256,
+        reason: String,
+    },
+    /// The funding wallet topped up the relayer's hot wallet.
+    WalletToppedUp {
+        from: Address,
+        to: Address,
+        amount: U256,
+        tx_hash: B256,
+    },
+    /// A withdrawal was held back because the relayer reached a spending cap.
+    SpendingCapReached { window: String, cap: U256 },
+    /// Indexed blocks were reorged out and are being indexed again.
+    Reorg {
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    },
+    /// A withdrawal from a pool whose denomination is above the indexer's alert threshold.
+    LargeWithdrawal {
+        chain_id: u64,
+        pool: Address,
+        nullifier: B256,
+        amount: U256,
+        recipient: Address,
+        tx_hash: B256,
+    },
+}
+
+impl Event {
+    /// Whether an operator should be told about the event, rather than it being routine.
+    pub fn is_alert(&self) -> bool {
+        !matches!(
+            self,
+            Self::DepositConfirmed { .. }
+                | Self::NoteSpent { .. }
+                | Self::JobMined { .. }
+                | Self::WalletToppedUp { .. }
+        )
+    }
+}
+
+/// One line describing the event, for chat messages.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DepositConfirmed {
+                pool,
+                commitment,
+                leaf_index,
+                ..
+            } => write!(
+                f,
+                "Deposit {commitment} confirmed in {pool} at leaf {leaf_index}"
+            ),
+            Self::NoteSpent {
+                pool,
+                nullifier,
+                tx_hash,
+                ..
+            } => write!(
+                f,
+                "Nullifier {nullifier} withdrawn from {pool} in {tx_hash}"
+            ),
+            Self::JobMined {
+                job_id, tx_hash, ..
+            } => write!(f, "Relay job {job_id} mined in {tx_hash}"),
+            Self::JobFailed { job_id, error, .. } => {
+                write!(f, "Relay job {job_id} failed: {error}")
+            }
+            Self::JobReorged { job_id, tx_hash } => write!(
+                f,
+                "Relay job {job_id}: transaction {tx_hash} was dropped by a reorg"
+            ),
+            Self::BalanceLow {
+                address,
+                balance,
+                threshold,
+                reason,
+            } => write!(
+                f,
+                "Relayer wallet {address} holds {balance} wei, below {threshold}: {reason}"
+            ),
+            Self::WalletToppedUp { to, amount, .. } => {
+                write!(f, "Topped up relayer wallet {to} with {amount} wei")
+            }
+            Self::SpendingCapReached { window, cap } => {
+                write!(f, "Relayer reached its {window} spending cap of {cap} wei")
+            }
+            Self::Reorg {
+                chain_id,
+                from_block,
+                to_block,
+            } => write!(
+                f,
+                "Blocks {from_block} to {to_block} on chain {chain_id} were reorged"
+            ),
+            Self::LargeWithdrawal {
+                pool,
+                amount,
+                recipient,
+                tx_hash,
+                ..
+            } => write!(
+                f,
+                "Withdrawal of {amount} wei from {pool} to {recipient} in {tx_hash}"
+            ),
+        }
+    }
+}
+
+/// Somewhere events are delivered.
+pub trait Sink: fmt::Debug + Send + Sync {
+    /// Where the sink delivers to, for logs. Must not contain credentials.
+    fn target(&self) -> String;
+
+    /// Whether the sink takes `event`.
+    fn accepts(&self, _event: &Event) -> bool {
+        true
+    }
+
+    /// Deliver `event`, whose JSON encoding is `body`, once. `delivery` is the same across
+    /// redeliveries of the event.
+    fn deliver<'a>(
+        &'a self,
+        delivery: &'a str,
+        event: &'a Event,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+/// POSTs events to a URL.
+///
+/// Each request carries `X-Pool-Delivery`, a random id that stays the same across redeliveries,
+/// `X-Pool-Timestamp`, and `X-Pool-Signature: sha256=<hex>`, the HMAC-SHA256 of
+/// `<timestamp>.<body>` with the shared secret.
+#[derive(Debug)]
+pub struct Webhook {
+    url: Url,
+    secret: Option<Vec<u8>>,
+    http: reqwest::Client,
+}
+
+impl Sink for Webhook {
+    fn target(&self) -> String {
+        self.url.to_string()
+    }
+
+    fn deliver<'a>(
+        &'a self,
+        delivery: &'a str,
+        _event: &'a Event,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let mut request = self
+                .http
+                .post(self.url.clone())
+                .header("Content-Type", "application/json")
+                .header("X-Pool-Delivery", delivery)
+                .header("X-Pool-Timestamp", timestamp.to_string())
+                .body(body.to_vec());
+            if let Some(secret) = &self.secret {
+                request = request.header("X-Pool-Signature", sign(secret, timestamp, body));
+            }
+            request.send().await?.error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+/// Sends events to every [`Sink`] in the background.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    sinks: Vec<Arc<dyn Sink>>,
+}
+
+/// The `X-Pool-Signature` value of `body` sent at `timestamp`.
+pub fn sign(secret: &[u8], timestamp: u64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+impl Notifier {
+    pub fn new(sinks: Vec<Arc<dyn Sink>>) -> Self {
+        Self { sinks }
+    }
+
+    /// Deliver `event` to every sink that takes it, retrying failed deliveries with backoff.
+    pub fn notify(&self, event: Event) {
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("failed to encode webhook event: {e}");
+                return;
+            }
+        };
+        let delivery = hex::encode(rand::random::<[u8; 16]>());
+        let event = Arc::new(event);
+        for sink in &self.sinks {
+            if !sink.accepts(&event) {
+                continue;
+            }
+            let sink = sink.clone();
+            let event = event.clone();
+            let body = body.clone();
+            let delivery = delivery.clone();
+            tokio::spawn(async move {
+                let target = sink.target();
+                for attempt in 0..MAX_DELIVERIES {
+                    match sink.deliver(&delivery, &event, &body).await {
+                        Ok(()) => {
+                            debug!(sink = target, delivery, "delivered event");
+                            return;
+                        }
+                        Err(e) => warn!(
+                            sink = target,
+                            delivery, attempt, "event delivery failed: {e}"
+                        ),
+                    }
+                    tokio::time::sleep(REDELIVERY_BACKOFF * 2u32.pow(attempt)).await;
+                }
+            });
+        }
+    }
+}
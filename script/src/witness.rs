@@ -0,0 +1,769 @@
+use crate::{
+    allow_list,
+    asp::AspBranch,
+    beacon::finalized_execution_block,
+    bytecode::verify_pool_code,
+    cassette::{Cassette, RecordLayer, ReplayTransport},
+    ens::AddressOrName,
+    helios::{HeliosArgs, HeliosNode, KeepAliveLayer},
+    pool::{Pool, DEPOSITS_SLOT},
+    secure::SecretParser,
+};
+use alloy::{
+    consensus::{BlockHeader, Header as ConsensusHeader},
+    eips::BlockNumberOrTag,
+    network::Ethereum,
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, RootProvider},
+    rpc::{
+        client::ClientBuilder,
+        types::{BlockTransactionsKind, EIP1186AccountProofResponse, Header},
+    },
+    transports::http::reqwest::Url,
+};
+use clap::Args;
+use eyre::{ensure, eyre, OptionExt, Result, WrapErr};
+use futures::{join, stream, try_join, StreamExt, TryStreamExt};
+use pool_lib::{
+    association::SignedRoot,
+    bridge::{BridgeMessage, DEFAULT_L2_GAS_LIMIT},
+    compact::prune_proof,
+    compute_commitment, compute_inclusion_root, compute_nft_commitment, compute_storage_keys,
+    hash_block_header,
+    nft::NftToken,
+    oracle::{block_hash_entry_key, OracleAnchor},
+    protocol_fee::fee_key,
+    split::{Distribution, Split},
+    token_fee::TokenFee,
+    withdrawal_public_values, InclusionBranches, WithdrawalInput,
+};
+use std::{
+    future::IntoFuture,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, instrument, warn};
+
+/// `deposits(i)` calls in flight at once while searching for a deposit.
+const SEARCH_CONCURRENCY: usize = 16;
+
+/// Arguments for building a withdrawal witness from an RPC endpoint.
+#[derive(Args, Debug, Clone)]
+pub struct WitnessArgs {
+    #[clap(
+        long,
+        env = "RPC_URL",
+        required_unless_present_any = ["replay", "header_file"]
+    )]
+    pub rpc_url: Option<Url>,
+
+    /// Record all RPC traffic to this cassette file.
+    #[clap(long, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer all RPC requests from this cassette file instead of the network.
+    #[clap(long)]
+    pub replay: Option<PathBuf>,
+
+    /// Build the witness offline from this block header JSON, as returned by
+    /// `eth_getBlockByNumber`.
+    #[clap(long, requires_all = ["proof_file", "deposits_file"], conflicts_with_all = ["record", "replay"])]
+    pub header_file: Option<PathBuf>,
+
+    /// `eth_getProof` response JSON for the offline witness.
+    #[clap(long, requires = "header_file")]
+    pub proof_file: Option<PathBuf>,
+
+    /// JSON array of the pool's deposits at the header's block, for the offline witness.
+    #[clap(long, requires = "header_file")]
+    pub deposits_file: Option<PathBuf>,
+
+    /// Pool address, ENS name or `registry:<registry>/<denomination in wei>/<version>`.
+    pub address: AddressOrName,
+
+    /// Prove the pool's registry entry at the proof block and commit to it in the public values.
+    #[clap(
+        long,
+        conflicts_with_all = ["header_file", "collection", "protocol_fee", "l2_recipient"]
+    )]
+    pub commit_registry: bool,
+
+    /// Block hash oracle holding the hash of `--proof-block`, to prove the deposit at a block
+    /// older than `BLOCKHASH` reaches and anchor it at the finalized block.
+    #[clap(
+        long,
+        requires = "proof_block",
+        conflicts_with_all = [
+            "header_file",
+            "collection",
+            "protocol_fee",
+            "l2_recipient",
+            "commit_registry",
+        ]
+    )]
+    pub blockhash_oracle: Option<Address>,
+
+    /// Storage slot of the oracle's `mapping(uint256 => bytes32) blockHashes`.
+    #[clap(long, default_value_t = U256::ZERO)]
+    pub oracle_slot: U256,
+
+    /// Block to prove the deposit at through `--blockhash-oracle`, instead of the finalized one.
+    #[clap(long, requires = "blockhash_oracle")]
+    pub proof_block: Option<u64>,
+
+    #[clap(value_parser = SecretParser::<B256>::new())]
+    pub secret: B256,
+
+    /// Collection of the token the note deposited, for withdrawals from ERC-721 pools.
+    #[clap(long, requires = "token_id", conflicts_with = "l2_recipient")]
+    pub collection: Option<Address>,
+
+    /// Id of the token the note deposited.
+    #[clap(long, requires = "collection")]
+    pub token_id: Option<U256>,
+
+    /// Prove the protocol fee at the proof block, for withdrawals from pools whose owner can
+    /// change it.
+    #[clap(long, conflicts_with_all = ["collection", "l2_recipient"])]
+    pub protocol_fee: bool,
+
+    /// Recipient address or ENS name.
+    #[clap(long, env = "POOL_RECIPIENT", default_value_t = AddressOrName::Address(Address::ZERO))]
+    pub recipient: AddressOrName,
+
+    #[clap(flatten)]
+    pub bridge: BridgeArgs,
+
+    /// Split the withdrawal between recipients instead of paying `--recipient`, as
+    /// `<address>=<wei>` once per recipient. The amounts and the relayer fee must add up to the
+    /// pool's deposit amount.
+    #[clap(
+        long = "split",
+        value_name = "ADDRESS=WEI",
+        value_parser = parse_split,
+        conflicts_with_all = [
+            "header_file",
+            "collection",
+            "protocol_fee",
+            "l2_recipient",
+            "commit_registry",
+            "blockhash_oracle",
+        ]
+    )]
+    pub splits: Vec<Split>,
+
+    /// Relayer address or ENS name.
+    #[clap(long, env = "POOL_RELAYER", default_value_t = AddressOrName::Address(Address::ZERO))]
+    pub relayer: AddressOrName,
+
+    /// Index of the deposit in the pool, if known, to skip searching for it.
+    #[clap(long)]
+    pub leaf_index: Option<u64>,
+
+    /// Fee paid to the relayer, in wei.
+    #[clap(long, default_value_t = U256::ZERO)]
+    pub relayer_fee: U256,
+
+    /// ERC-20 token to pay the relayer in instead of ETH, in place of `--relayer-fee`.
+    #[clap(long, requires = "token_fee_amount", conflicts_with = "relayer_fee")]
+    pub token_fee: Option<Address>,
+
+    /// Amount of `--token-fee` paid to the relayer, in the token's smallest unit.
+    #[clap(long, requires = "token_fee")]
+    pub token_fee_amount: Option<U256>,
+
+    /// Consensus-layer Beacon API used to confirm the proof block is finalized, instead of trusting
+    /// the execution RPC's finalized tag.
+    #[clap(long, env = "BEACON_URL")]
+    pub beacon_url: Option<Url>,
+
+    /// Expected keccak256 hash of the pool's runtime bytecode, for deployments not in the
+    /// built-in allow-list.
+    #[clap(long)]
+    pub expected_code_hash: Option<B256>,
+
+    /// Build a witness for a pool whose bytecode is neither in the built-in allow-list nor
+    /// `--expected-code-hash`, only warning about it.
+    #[clap(long, conflicts_with = "expected_code_hash")]
+    pub allow_unknown_pool: bool,
+
+    /// Refuse to build a witness when fewer than this many deposits were made after ours by the
+    /// proof block, since withdrawing from a nearly empty set links the withdrawal to the deposit.
+    #[clap(long, env = "POOL_MIN_ANONYMITY_SET", default_value_t = 10)]
+    pub min_anonymity_set: u64,
+
+    /// Only warn when the anonymity set is below `--min-anonymity-set`.
+    #[clap(long)]
+    pub force: bool,
+
+    /// Signed allow-list bundle whose set the withdrawal proves its deposit is in, committed as
+    /// its `exclusionSetRoot`.
+    #[clap(long, requires = "allow_list_issuers")]
+    pub allow_list: Option<PathBuf>,
+
+    /// Issuers the `--allow-list` bundle is trusted from.
+    #[clap(
+        long = "allow-list-issuer",
+        env = "POOL_ALLOW_LIST_ISSUERS",
+        value_delimiter = ','
+    )]
+    pub allow_list_issuers: Vec<Address>,
+
+    /// Branch of the deposit into an association set, as `asp` serves it at
+    /// `/sets/<name>/branch`. The withdrawal commits to the set's root as its `exclusionSetRoot`
+    /// and to the provider that signed it.
+    #[clap(long, requires = "asp_providers", conflicts_with = "allow_list")]
+    pub association: Option<PathBuf>,
+
+    /// Providers the `--association` root is trusted from.
+    #[clap(
+        long = "asp-provider",
+        env = "POOL_ASP_PROVIDERS",
+        value_delimiter = ','
+    )]
+    pub asp_providers: Vec<Address>,
+
+    #[clap(flatten)]
+    pub helios: HeliosArgs,
+}
+
+/// Arguments for withdrawing to an L2 address, through the canonical bridge given as the
+/// `--recipient`.
+#[derive(Args, Debug, Clone)]
+pub struct BridgeArgs {
+    /// Address on L2 to withdraw to.
+    #[clap(long, requires = "l2_chain_id")]
+    pub l2_recipient: Option<Address>,
+
+    /// Chain id of the L2 the bridge deposits on.
+    #[clap(long, requires = "l2_recipient")]
+    pub l2_chain_id: Option<u64>,
+
+    /// Gas limit of the deposit's execution on L2.
+    #[clap(long, default_value_t = DEFAULT_L2_GAS_LIMIT)]
+    pub l2_gas_limit: u32,
+
+    /// Data passed along with the deposit to the L2 recipient.
+    #[clap(long, default_value_t = Bytes::new())]
+    pub l2_extra_data: Bytes,
+}
+
+impl BridgeArgs {
+    /// The bridge deposit to make, if withdrawing to an L2 address.
+    pub fn message(&self) -> Option<BridgeMessage> {
+        Some(BridgeMessage {
+            l2_chain_id: self.l2_chain_id?,
+            l2_recipient: self.l2_recipient?,
+            min_gas_limit: self.l2_gas_limit,
+            extra_data: self.l2_extra_data.clone(),
+        })
+    }
+}
+
+impl WitnessArgs {
+    /// Connect to the RPC endpoint, or to the replayed cassette. With `--helios`, requests go
+    /// through a local light client proxying the RPC endpoint, which runs until the provider is
+    /// dropped.
+    pub async fn connect(&self) -> Result<RootProvider<Ethereum>> {
+        let client = match (&self.replay, &self.record) {
+            (Some(replay), _) => {
+                let transport = ReplayTransport::new(Cassette::load(replay)?);
+                ClientBuilder::default().transport(transport, true)
+            }
+            (None, record) => {
+                let rpc_url = self.rpc_url.clone().ok_or_eyre("--rpc-url is required")?;
+                if self.helios.helios {
+                    let node = HeliosNode::spawn(&self.helios, &rpc_url).await?;
+                    let url = node.url().clone();
+                    let client = ClientBuilder::default().layer(KeepAliveLayer(Arc::new(node)));
+                    let client = match record {
+                        Some(record) => client.layer(RecordLayer::new(record.clone())).http(url),
+                        None => client.http(url),
+                    };
+                    return Ok(RootProvider::new(client));
+                }
+                match record {
+                    Some(record) => ClientBuilder::default()
+                        .layer(RecordLayer::new(record.clone()))
+                        .http(rpc_url),
+                    None => ClientBuilder::default().http(rpc_url),
+                }
+            }
+        };
+        Ok(RootProvider::new(client))
+    }
+
+    /// Branches of `commitment` in the `--allow-list` set, after checking the bundle is for `pool`
+    /// (and `chain_id`, when known), authentic, trusted and currently valid.
+    pub fn inclusion_branches(
+        &self,
+        chain_id: Option<u64>,
+        pool: Address,
+        commitment: B256,
+    ) -> Result<Option<InclusionBranches>> {
+        let Some(path) = &self.allow_list else {
+            return Ok(None);
+        };
+        let bundle = allow_list::load(path)?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        bundle.verify(&self.allow_list_issuers, now)?;
+        ensure!(
+            bundle.pool == pool,
+            "allow-list is for pool {}",
+            bundle.pool
+        );
+        if let Some(chain_id) = chain_id {
+            ensure!(
+                bundle.chain_id == chain_id,
+                "allow-list is for chain {}",
+                bundle.chain_id
+            );
+        }
+        let branches = bundle.branch(commitment)?;
+        info!(root = %bundle.allow_list.root, issuer = %bundle.issuer, "using allow-list");
+        Ok(Some(branches))
+    }
+
+    /// The `--association` branch of `commitment`, after checking its root is for `pool` (and
+    /// `chain_id`, when known), signed by a trusted provider and reached by the branch.
+    pub fn association(
+        &self,
+        chain_id: Option<u64>,
+        pool: Address,
+        commitment: B256,
+    ) -> Result<Option<AspBranch>> {
+        let Some(path) = &self.association else {
+            return Ok(None);
+        };
+        let branch: AspBranch = serde_json::from_slice(&std::fs::read(path)?)
+            .wrap_err_with(|| format!("reading association branch {}", path.display()))?;
+        let signer = branch.root.signer()?;
+        ensure!(
+            self.asp_providers.contains(&signer),
+            "association root signer {signer} is not trusted"
+        );
+        ensure!(
+            branch.root.pool == pool,
+            "association set is for pool {}",
+            branch.root.pool
+        );
+        if let Some(chain_id) = chain_id {
+            ensure!(
+                branch.root.chain_id == chain_id,
+                "association set is for chain {}",
+                branch.root.chain_id
+            );
+        }
+        ensure!(
+            compute_inclusion_root(&commitment, &branch.branches) == branch.root.root.root,
+            "deposit is not in the association set"
+        );
+        info!(root = %branch.root.root.root, provider = %signer, "using association set");
+        Ok(Some(branch))
+    }
+
+    /// Branches of `commitment` into the set the withdrawal proves its deposit is in, from
+    /// `--association` or `--allow-list`, with the signed root of an association set.
+    pub fn inclusion_set(
+        &self,
+        chain_id: Option<u64>,
+        pool: Address,
+        commitment: B256,
+    ) -> Result<(Option<InclusionBranches>, Option<SignedRoot>)> {
+        match self.association(chain_id, pool, commitment)? {
+            Some(AspBranch { root, branches }) => Ok((Some(branches), Some(root))),
+            None => Ok((self.inclusion_branches(chain_id, pool, commitment)?, None)),
+        }
+    }
+
+    /// The token the note deposited, if it is for an ERC-721 pool.
+    pub fn nft(&self) -> Option<NftToken> {
+        Some(NftToken::new(self.collection?, self.token_id?))
+    }
+
+    /// The relayer fee in a token, if it is not paid in ETH.
+    pub fn token_fee(&self) -> Option<TokenFee> {
+        Some(TokenFee {
+            token: self.token_fee?,
+            amount: self.token_fee_amount?,
+        })
+    }
+
+    /// Commitment of the note, which binds its token in ERC-721 pools.
+    pub fn commitment(&self) -> B256 {
+        match self.nft() {
+            Some(nft) => compute_nft_commitment(&self.secret, &nft.collection, &nft.token_id).0,
+            None => compute_commitment(&self.secret).0,
+        }
+    }
+
+    /// Storage keys proven for the deposit at `index`: the deposits length, the deposit, in
+    /// ERC-721 pools the token's escrow entry and in pools with a protocol fee the fee slot.
+    pub fn storage_keys(&self, index: u64) -> Vec<B256> {
+        let keys = compute_storage_keys(U256::from(DEPOSITS_SLOT), U256::from(index));
+        let mut keys = vec![keys.0, keys.1];
+        keys.extend(self.nft().map(|nft| nft.escrow_key()));
+        if self.protocol_fee {
+            keys.push(fee_key());
+        }
+        keys
+    }
+
+    /// Build the witness from fixture files if given, otherwise from the RPC endpoint.
+    pub async fn build(&self) -> Result<WithdrawalInput> {
+        match &self.header_file {
+            Some(_) => self.build_offline(),
+            None => build_witness(&self.connect().await?, self).await,
+        }
+    }
+
+    /// Assemble and validate the witness from `--header-file`, `--proof-file` and
+    /// `--deposits-file` without any RPC access. ENS names and registry entries cannot be
+    /// resolved and the pool bytecode cannot be checked offline.
+    pub fn build_offline(&self) -> Result<WithdrawalInput> {
+        let read = |path: &Option<PathBuf>| -> Result<Vec<u8>> {
+            Ok(std::fs::read(
+                path.as_ref().ok_or_eyre("missing fixture file")?,
+            )?)
+        };
+        let header: Header = serde_json::from_slice(&read(&self.header_file)?)?;
+        let proof: EIP1186AccountProofResponse = serde_json::from_slice(&read(&self.proof_file)?)?;
+        let deposits: Vec<B256> = serde_json::from_slice(&read(&self.deposits_file)?)?;
+
+        let raw_address = |name: &AddressOrName| match name {
+            AddressOrName::Address(address) => Ok(*address),
+            AddressOrName::Name(name) => Err(eyre!("cannot resolve ENS name {name} offline")),
+            AddressOrName::Registry(entry) => {
+                Err(eyre!("cannot resolve registry entry {entry} offline"))
+            }
+        };
+        let address = raw_address(&self.address)?;
+        let recipient = raw_address(&self.recipient)?;
+        let relayer = raw_address(&self.relayer)?;
+        ensure!(
+            proof.address == address,
+            "proof is for {}, not the pool",
+            proof.address
+        );
+        warn!("building witness offline, pool bytecode is not checked");
+
+        let commitment = self.commitment();
+        let index = deposits
+            .iter()
+            .position(|deposit| *deposit == commitment)
+            .ok_or_eyre("commitment not found in deposits file")?;
+        info!(index, block_number = header.number, "found deposit");
+
+        let proof_keys: Vec<B256> = proof
+            .storage_proof
+            .iter()
+            .map(|slot| slot.key.as_b256())
+            .collect();
+        ensure!(
+            proof_keys == self.storage_keys(index as u64),
+            "proof file does not prove the storage keys for deposit {index}"
+        );
+
+        let mut proof = proof;
+        prune_proof(&mut proof, header.state_root);
+        let (inclusion_set_branches, association) =
+            self.inclusion_set(None, address, commitment)?;
+        let input = WithdrawalInput {
+            secret: self.secret,
+            account_proof: proof,
+            array_index: U256::from(index),
+            block_header: header.inner,
+            inclusion_set_branches,
+            contract_address: address,
+            array_slot: U256::from(DEPOSITS_SLOT),
+            relayer_fee: self.relayer_fee,
+            recipient,
+            relayer,
+            nft: self.nft(),
+            protocol_fee: self.protocol_fee,
+            bridge: self.bridge.message(),
+            registry: None,
+            oracle: None,
+            split: None,
+            token_fee: self.token_fee(),
+            association,
+        };
+        withdrawal_public_values(&input)?;
+        Ok(input)
+    }
+}
+
+/// Parse a `--split` of `<address>=<wei>`.
+pub fn parse_split(s: &str) -> Result<Split, String> {
+    let (recipient, amount) = s
+        .split_once('=')
+        .ok_or_else(|| "expected <address>=<wei>".to_string())?;
+    Ok(Split {
+        recipient: recipient
+            .parse()
+            .map_err(|e| format!("invalid recipient: {e}"))?,
+        amount: amount.parse().map_err(|e| format!("invalid amount: {e}"))?,
+    })
+}
+
+/// Build the withdrawal witness for `args.secret` against the latest finalized block.
+#[instrument(skip_all, fields(pool = %args.address))]
+pub async fn build_witness<P: Provider>(
+    provider: &P,
+    args: &WitnessArgs,
+) -> Result<WithdrawalInput> {
+    let finalized = match &args.beacon_url {
+        Some(beacon_url) => {
+            let finalized = finalized_execution_block(beacon_url).await?;
+            if finalized.execution_optimistic {
+                warn!(
+                    slot = finalized.slot,
+                    "beacon node has only optimistically imported the finalized block"
+                );
+            }
+            let block = provider
+                .get_block_by_number(finalized.block_number.into(), BlockTransactionsKind::Hashes)
+                .await?
+                .ok_or_eyre("finalized block not found")?;
+            // Hash the header ourselves so the RPC cannot substitute a different one.
+            ensure!(
+                block.header.inner.hash_slow() == finalized.block_hash,
+                "execution RPC header for block {} does not match the finalized beacon block",
+                finalized.block_number
+            );
+            info!(slot = finalized.slot, "confirmed finality with beacon node");
+            block
+        }
+        None => provider
+            .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_eyre("finalized block not found")?,
+    };
+    info!(
+        block_number = finalized.header.number(),
+        "fetched finalized block"
+    );
+    let (header, anchor) = match args.proof_block {
+        Some(proof_block) => {
+            ensure!(
+                proof_block < finalized.header.number(),
+                "proof block {proof_block} is not before the finalized block {}",
+                finalized.header.number()
+            );
+            let block = provider
+                .get_block_by_number(proof_block.into(), BlockTransactionsKind::Hashes)
+                .await?
+                .ok_or_eyre("proof block not found")?;
+            (block, Some(finalized))
+        }
+        None => (finalized, None),
+    };
+    let block_number = header.header.number();
+
+    let pool = async {
+        match (&args.address, args.commit_registry) {
+            (AddressOrName::Registry(entry), true) => {
+                let (address, entry) = entry.entry(provider, block_number).await?;
+                Ok::<_, eyre::Report>((address, Some(entry)))
+            }
+            (_, true) => Err(eyre!(
+                "--commit-registry needs the pool as registry:<registry>/<wei>/<version>"
+            )),
+            (address, false) => Ok((address.resolve(provider, block_number).await?, None)),
+        }
+    };
+    let ((address, registry), recipient, relayer) = try_join!(
+        pool,
+        args.recipient.resolve(provider, block_number),
+        args.relayer.resolve(provider, block_number),
+    )?;
+    info!(%address, %recipient, %relayer, "resolved addresses");
+
+    let contract = Pool::new(address, provider);
+    let target_commitment = args.commitment();
+    debug!(commitment = %target_commitment, "derived commitment");
+    // The deposit at the given leaf index and its proof are fetched alongside the other reads,
+    // and only refetched if the deposit turns out to be elsewhere.
+    let hint = async {
+        let Some(i) = args.leaf_index else {
+            return Ok::<_, eyre::Report>(None);
+        };
+        let deposit = contract.deposits(U256::from(i)).block(block_number.into());
+        let (commitment, proof) = join!(
+            deposit.call().into_future(),
+            provider
+                .get_proof(address, args.storage_keys(i))
+                .number(block_number)
+                .into_future(),
+        );
+        // Past the end of the array the call reverts.
+        match commitment {
+            Ok(commitment) => Ok(Some((i, commitment._0, proof?))),
+            Err(_) => Ok(None),
+        }
+    };
+    let (code_hash, length, chain_id, hint) = try_join!(
+        verify_pool_code(
+            provider,
+            address,
+            block_number,
+            args.expected_code_hash,
+            args.allow_unknown_pool
+        ),
+        async {
+            Ok::<_, eyre::Report>(
+                provider
+                    .get_storage_at(address, U256::from(DEPOSITS_SLOT))
+                    .number(block_number)
+                    .await?,
+            )
+        },
+        async { Ok::<_, eyre::Report>(provider.get_chain_id().await?) },
+        hint,
+    )?;
+    info!(%code_hash, "verified pool bytecode");
+    info!(%length, "read deposits length");
+
+    let mut found = None;
+    if let Some((i, commitment, proof)) = hint.filter(|(i, ..)| U256::from(*i) < length) {
+        if commitment == target_commitment {
+            found = Some((i, Some(proof)));
+        } else {
+            warn!(
+                index = i,
+                "deposit is not at the given leaf index, searching"
+            );
+        }
+    }
+    if found.is_none() {
+        let mut deposits = stream::iter(0..length.to::<u64>())
+            .map(|i| {
+                let call = contract.deposits(U256::from(i)).block(block_number.into());
+                async move { Ok::<_, eyre::Report>((i, call.call().await?._0)) }
+            })
+            .buffered(SEARCH_CONCURRENCY);
+        while let Some((i, commitment)) = deposits.try_next().await? {
+            if commitment == target_commitment {
+                found = Some((i, None));
+                break;
+            }
+        }
+    }
+    let (found_index, proof) = found.ok_or_eyre("commitment not found")?;
+    info!(index = found_index, "found deposit");
+    check_anonymity_set(found_index, length.to(), args.min_anonymity_set, args.force)?;
+
+    let mut proof = match proof {
+        Some(proof) => proof,
+        None => {
+            let keys = args.storage_keys(found_index);
+            debug!(?keys, "computed storage keys");
+            provider
+                .get_proof(address, keys)
+                .number(block_number)
+                .await?
+        }
+    };
+    prune_proof(&mut proof, header.header.state_root);
+
+    let split = if args.splits.is_empty() {
+        None
+    } else {
+        let distribution = Distribution {
+            note_value: contract
+                .amount()
+                .block(block_number.into())
+                .call()
+                .await?
+                ._0,
+            splits: args.splits.clone(),
+        };
+        distribution.verify(&args.relayer_fee)?;
+        Some(distribution)
+    };
+
+    let (inclusion_set_branches, association) =
+        args.inclusion_set(Some(chain_id), address, target_commitment)?;
+
+    let oracle = match (args.blockhash_oracle, anchor) {
+        (Some(oracle), Some(anchor)) => Some(
+            oracle_anchor(
+                provider,
+                oracle,
+                args.oracle_slot,
+                anchor.header.inner,
+                &header.header.inner,
+            )
+            .await?,
+        ),
+        _ => None,
+    };
+
+    Ok(WithdrawalInput {
+        secret: args.secret,
+        account_proof: proof,
+        array_index: U256::from(found_index),
+        block_header: header.header.inner,
+        inclusion_set_branches,
+        contract_address: address,
+        array_slot: U256::from(DEPOSITS_SLOT),
+        relayer_fee: args.relayer_fee,
+        recipient,
+        relayer,
+        nft: args.nft(),
+        protocol_fee: args.protocol_fee,
+        bridge: args.bridge.message(),
+        registry,
+        oracle,
+        split,
+        token_fee: args.token_fee(),
+        association,
+    })
+}
+
+/// Prove that `oracle` holds the hash of `block` in the state of the `anchor` block.
+async fn oracle_anchor<P: Provider>(
+    provider: &P,
+    oracle: Address,
+    slot: U256,
+    anchor: ConsensusHeader,
+    block: &ConsensusHeader,
+) -> Result<OracleAnchor> {
+    let mut proof = provider
+        .get_proof(oracle, vec![block_hash_entry_key(slot, block.number)])
+        .number(anchor.number)
+        .await?;
+    prune_proof(&mut proof, anchor.state_root);
+    let anchor = OracleAnchor {
+        oracle,
+        slot,
+        header: anchor,
+        proof,
+    };
+    let anchor_block = anchor
+        .verify(&hash_block_header(block)?)
+        .map_err(|e| eyre!("block hash oracle {oracle} cannot anchor the proof block: {e}"))?;
+    info!(%oracle, anchor = anchor_block.number, "anchored proof block through oracle");
+    Ok(anchor)
+}
+
+/// Check that at least `min` deposits were made after the one at `index`, out of `length`
+/// deposits at the proof block. With `force`, a small set is only warned about.
+pub fn check_anonymity_set(index: u64, length: u64, min: u64, force: bool) -> Result<()> {
+    let later = length.saturating_sub(index + 1);
+    if later >= min {
+        info!(later, "anonymity set");
+        return Ok(());
+    }
+    ensure!(
+        force,
+        "only {later} deposits were made after yours, below the minimum of {min}; wait for more \
+         deposits or pass --force to withdraw anyway"
+    );
+    warn!(
+        later,
+        min, "anonymity set is below the minimum, the withdrawal may be linked to the deposit"
+    );
+    Ok(())
+}
@@ -0,0 +1,486 @@
+//! Types and encodings shared by everything that handles pool notes and withdrawal outputs:
+//! the note format, commitment derivation and the public values proofs commit to. pool-lib
+//! re-exports all of it alongside withdrawal verification.
+
+pub mod note;
+pub mod redact;
+pub mod view;
+pub mod watch;
+
+use alloy::{
+    primitives::{keccak256, Address, Bytes, B256, U256},
+    sol,
+    sol_types::SolValue,
+};
+
+sol! {
+    #[derive(Debug)]
+    struct WithdrawalData {
+        /// [`Variant`] of the withdrawal, which every pool checks is one it pays out.
+        uint8 variant;
+        bytes32 nullifier;
+        bytes32 blockHash;
+        bytes32 exclusionSetRoot;
+        uint256 relayerFee;
+        address recipient;
+        address relayer;
+        address contractAddress;
+        uint64 blockNumber;
+        /// ERC-20 token the relayer is paid in instead of ETH, or zero for a fee in ETH.
+        address feeToken;
+        /// Amount of `feeToken` the relayer is paid, in its smallest unit.
+        uint256 feeTokenAmount;
+    }
+
+    /// Public values of a withdrawal from an ERC-721 pool: a [`WithdrawalData`] of the
+    /// [`Variant::Nft`], followed by the token the note deposited.
+    #[derive(Debug)]
+    struct NftWithdrawalData {
+        WithdrawalData withdrawal;
+        address collection;
+        uint256 tokenId;
+    }
+
+    /// Public values of a withdrawal from a pool with a protocol fee: a [`WithdrawalData`] of
+    /// the [`Variant::ProtocolFee`], followed by the fee in force at the proof block.
+    #[derive(Debug)]
+    struct FeeWithdrawalData {
+        WithdrawalData withdrawal;
+        uint256 protocolFee;
+    }
+
+    /// Public values of a withdrawal to an L2 address: a [`WithdrawalData`] of the
+    /// [`Variant::Bridge`], whose recipient is a canonical bridge, followed by the deposit the
+    /// bridge is to make.
+    #[derive(Debug)]
+    struct BridgeWithdrawalData {
+        WithdrawalData withdrawal;
+        uint256 l2ChainId;
+        address l2Recipient;
+        /// Hash of the bridge deposit message, as [`compute_bridge_message_hash`].
+        bytes32 bridgeMessageHash;
+    }
+
+    /// Public values of a withdrawal from a pool resolved through a registry: a
+    /// [`WithdrawalData`] of the [`Variant::Registry`], followed by the registry entry the pool
+    /// is at.
+    #[derive(Debug)]
+    struct RegistryWithdrawalData {
+        WithdrawalData withdrawal;
+        address registry;
+        uint256 chainId;
+        uint256 denomination;
+        uint256 version;
+    }
+
+    /// Public values of a withdrawal split between several recipients: a [`WithdrawalData`] of
+    /// the [`Variant::Split`] with no recipient, followed by the distribution and the note
+    /// value its amounts and the relayer fee add up to.
+    #[derive(Debug)]
+    struct SplitWithdrawalData {
+        WithdrawalData withdrawal;
+        /// Hash of the recipients and their amounts, as [`compute_distribution_hash`].
+        bytes32 distributionHash;
+        uint256 noteValue;
+    }
+
+    /// Public values of a withdrawal proven against a block older than `BLOCKHASH` reaches: a
+    /// [`WithdrawalData`] of the [`Variant::Oracle`] for the recent anchor block, followed by
+    /// the block hash oracle that holds the hash of the proof block in the anchor's state, and
+    /// that block.
+    #[derive(Debug)]
+    struct OracleWithdrawalData {
+        WithdrawalData withdrawal;
+        address blockHashOracle;
+        /// Storage slot of the oracle's `mapping(uint256 => bytes32) blockHashes`.
+        uint256 oracleSlot;
+        /// keccak256 of the oracle's runtime bytecode at the anchor block.
+        bytes32 oracleCodeHash;
+        bytes32 provenBlockHash;
+        uint64 provenBlockNumber;
+    }
+
+    /// Public values of a withdrawal whose deposit is in an association set: a
+    /// [`WithdrawalData`] of the [`Variant::Association`], whose `exclusionSetRoot` is the set's
+    /// root, followed by the provider that signed the root and what it signed with it.
+    #[derive(Debug)]
+    struct AssociationWithdrawalData {
+        WithdrawalData withdrawal;
+        /// Address that signed the root as an EIP-712 `AssociationRoot` for the pool.
+        address aspSigner;
+        bytes32 setId;
+        uint64 leafCount;
+        uint64 issuedAt;
+        /// Chain id of the domain the root was signed under.
+        uint256 chainId;
+    }
+
+    /// Public values of an aggregated proof over many withdrawal proofs.
+    #[derive(Debug)]
+    struct AggregateData {
+        /// SP1 vkey digest of the child program, as big-endian words.
+        bytes32 childVkeyDigest;
+        /// Merkle root of the keccak256 hashes of each child's public values.
+        bytes32 publicValuesRoot;
+        uint256 count;
+    }
+}
+
+/// Which kind of withdrawal public values are for, committed as their first word. The kinds
+/// share the [`WithdrawalData`] fields and only add words after them, so a pool checks the tag
+/// and the length of the public values before it reads them, or a proof of one kind could be
+/// paid out as another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Variant {
+    Base = 0,
+    Nft = 1,
+    ProtocolFee = 2,
+    Bridge = 3,
+    Registry = 4,
+    Split = 5,
+    Oracle = 6,
+    Association = 7,
+}
+
+impl Variant {
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        Some(match tag {
+            0 => Self::Base,
+            1 => Self::Nft,
+            2 => Self::ProtocolFee,
+            3 => Self::Bridge,
+            4 => Self::Registry,
+            5 => Self::Split,
+            6 => Self::Oracle,
+            7 => Self::Association,
+            _ => return None,
+        })
+    }
+}
+
+/// Compute commitment and nullifier from secret.
+pub fn compute_commitment(secret: &B256) -> (B256, B256) {
+    let u256 = U256::from_be_slice(&secret.0);
+    let commitment = keccak256(u256.to_be_bytes::<32>());
+    let nullifier = keccak256(u256.wrapping_add(U256::from(1)).to_be_bytes::<32>());
+    (commitment, nullifier)
+}
+
+/// Compute commitment and nullifier from secret for a note of an ERC-721 pool, whose
+/// commitment binds the token it deposited. The nullifier is the same as for other notes.
+pub fn compute_nft_commitment(
+    secret: &B256,
+    collection: &Address,
+    token_id: &U256,
+) -> (B256, B256) {
+    let commitment = keccak256((*secret, *collection, *token_id).abi_encode());
+    (commitment, compute_commitment(secret).1)
+}
+
+/// Hash of the message of a bridge deposit to `l2_recipient` on chain `l2_chain_id`,
+/// `keccak256(abi.encode(l2ChainId, l2Recipient, minGasLimit, extraData))`.
+pub fn compute_bridge_message_hash(
+    l2_chain_id: u64,
+    l2_recipient: &Address,
+    min_gas_limit: u32,
+    extra_data: &[u8],
+) -> B256 {
+    keccak256(
+        (
+            U256::from(l2_chain_id),
+            *l2_recipient,
+            min_gas_limit,
+            Bytes::copy_from_slice(extra_data),
+        )
+            .abi_encode_params(),
+    )
+}
+
+/// Hash of a withdrawal's distribution to `recipients` of `amounts`,
+/// `keccak256(abi.encode(recipients, amounts))`.
+pub fn compute_distribution_hash(recipients: &[Address], amounts: &[U256]) -> B256 {
+    keccak256((recipients.to_vec(), amounts.to_vec()).abi_encode_params())
+}
+
+/// Public values of a withdrawal proof from any pool.
+#[derive(Debug)]
+pub struct PublicValues {
+    pub withdrawal: WithdrawalData,
+    /// Collection and id of the token, for withdrawals from ERC-721 pools.
+    pub nft: Option<(Address, U256)>,
+    /// Fee in force at the proof block, for pools with a protocol fee.
+    pub protocol_fee: Option<U256>,
+    /// L2 chain id, L2 recipient and bridge message hash, for withdrawals to an L2 address.
+    pub bridge: Option<(U256, Address, B256)>,
+    /// Registry, denomination and version of the entry the pool is at, for withdrawals that
+    /// commit to it.
+    pub registry: Option<(Address, U256, U256)>,
+    /// Oracle, oracle code hash, and number and hash of the proof block, for withdrawals
+    /// anchored through a block hash oracle.
+    pub oracle: Option<(Address, B256, u64, B256)>,
+    /// Distribution hash and note value, for withdrawals split between several recipients.
+    pub split: Option<(B256, U256)>,
+    /// Provider that signed the root, set id and when the root was issued, for withdrawals from
+    /// an association set.
+    pub association: Option<(Address, B256, u64)>,
+}
+
+/// ABI-encode `data` as the public values of a withdrawal proof.
+pub fn encode_public_values(data: &WithdrawalData) -> Vec<u8> {
+    data.abi_encode()
+}
+
+/// Decode the public values of a withdrawal proof, rejecting non-canonical encodings and those
+/// of other variants.
+pub fn decode_public_values(public_values: &[u8]) -> alloy::sol_types::Result<WithdrawalData> {
+    let data = WithdrawalData::abi_decode(public_values, true)?;
+    if data.variant != Variant::Base as u8 {
+        return Err(alloy::sol_types::Error::custom(
+            "public values are not of a base withdrawal",
+        ));
+    }
+    Ok(data)
+}
+
+/// Decode the public values of a withdrawal proof from any pool, as the variant they are tagged
+/// with.
+pub fn decode_any_public_values(public_values: &[u8]) -> alloy::sol_types::Result<PublicValues> {
+    // Read the tag from the shared prefix, then decode the whole encoding as its variant.
+    let prefix = WithdrawalData::abi_decode(public_values, false)?;
+    let variant = Variant::from_tag(prefix.variant)
+        .ok_or_else(|| alloy::sol_types::Error::custom("unknown withdrawal variant"))?;
+    let mut values = PublicValues {
+        withdrawal: prefix,
+        nft: None,
+        protocol_fee: None,
+        bridge: None,
+        registry: None,
+        oracle: None,
+        split: None,
+        association: None,
+    };
+    values.withdrawal = match variant {
+        Variant::Base => decode_public_values(public_values)?,
+        Variant::Nft => {
+            let data = NftWithdrawalData::abi_decode(public_values, true)?;
+            values.nft = Some((data.collection, data.tokenId));
+            data.withdrawal
+        }
+        Variant::ProtocolFee => {
+            let data = FeeWithdrawalData::abi_decode(public_values, true)?;
+            values.protocol_fee = Some(data.protocolFee);
+            data.withdrawal
+        }
+        Variant::Bridge => {
+            let data = BridgeWithdrawalData::abi_decode(public_values, true)?;
+            values.bridge = Some((data.l2ChainId, data.l2Recipient, data.bridgeMessageHash));
+            data.withdrawal
+        }
+        Variant::Registry => {
+            let data = RegistryWithdrawalData::abi_decode(public_values, true)?;
+            values.registry = Some((data.registry, data.denomination, data.version));
+            data.withdrawal
+        }
+        Variant::Split => {
+            let data = SplitWithdrawalData::abi_decode(public_values, true)?;
+            values.split = Some((data.distributionHash, data.noteValue));
+            data.withdrawal
+        }
+        Variant::Oracle => {
+            let data = OracleWithdrawalData::abi_decode(public_values, true)?;
+            values.oracle = Some((
+                data.blockHashOracle,
+                data.oracleCodeHash,
+                data.provenBlockNumber,
+                data.provenBlockHash,
+            ));
+            data.withdrawal
+        }
+        Variant::Association => {
+            let data = AssociationWithdrawalData::abi_decode(public_values, true)?;
+            values.association = Some((data.aspSigner, data.setId, data.issuedAt));
+            data.withdrawal
+        }
+    };
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256};
+
+    /// Length of the public values of a base withdrawal, 11 words.
+    const BASE_LENGTH: usize = 11 * 32;
+
+    fn withdrawal(variant: Variant) -> WithdrawalData {
+        WithdrawalData {
+            variant: variant as u8,
+            nullifier: b256!("1111111111111111111111111111111111111111111111111111111111111111"),
+            blockHash: b256!("2222222222222222222222222222222222222222222222222222222222222222"),
+            exclusionSetRoot: B256::ZERO,
+            relayerFee: U256::from(1000),
+            recipient: address!("0000000000000000000000000000000000000001"),
+            relayer: address!("0000000000000000000000000000000000000002"),
+            contractAddress: address!("0000000000000000000000000000000000000003"),
+            blockNumber: 20_000_000,
+            feeToken: Address::ZERO,
+            feeTokenAmount: U256::ZERO,
+        }
+    }
+
+    #[test]
+    fn variant_tags() {
+        for tag in 0..=7 {
+            assert_eq!(Variant::from_tag(tag).unwrap() as u8, tag);
+        }
+        assert_eq!(Variant::from_tag(8), None);
+        assert_eq!(Variant::Association as u8, 7);
+    }
+
+    #[test]
+    fn base_round_trip() {
+        let encoded = encode_public_values(&withdrawal(Variant::Base));
+        assert_eq!(encoded.len(), BASE_LENGTH);
+        let decoded = decode_public_values(&encoded).unwrap();
+        assert_eq!(decoded.nullifier, withdrawal(Variant::Base).nullifier);
+        assert_eq!(decoded.blockNumber, 20_000_000);
+        assert_eq!(encode_public_values(&decoded), encoded);
+    }
+
+    #[test]
+    fn base_decode_rejects_other_variants() {
+        let encoded = encode_public_values(&withdrawal(Variant::Nft));
+        assert!(decode_public_values(&encoded).is_err());
+    }
+
+    #[test]
+    fn decode_any_base() {
+        let encoded = encode_public_values(&withdrawal(Variant::Base));
+        let values = decode_any_public_values(&encoded).unwrap();
+        assert_eq!(values.withdrawal.variant, Variant::Base as u8);
+        assert!(values.nft.is_none() && values.split.is_none() && values.association.is_none());
+    }
+
+    #[test]
+    fn decode_any_variants() {
+        let token = address!("00000000000000000000000000000000000000aa");
+        let encoded = NftWithdrawalData {
+            withdrawal: withdrawal(Variant::Nft),
+            collection: token,
+            tokenId: U256::from(7),
+        }
+        .abi_encode();
+        assert_eq!(encoded.len(), BASE_LENGTH + 2 * 32);
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().nft,
+            Some((token, U256::from(7)))
+        );
+
+        let encoded = FeeWithdrawalData {
+            withdrawal: withdrawal(Variant::ProtocolFee),
+            protocolFee: U256::from(50),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().protocol_fee,
+            Some(U256::from(50))
+        );
+
+        let message = compute_bridge_message_hash(10, &token, 200_000, &[]);
+        let encoded = BridgeWithdrawalData {
+            withdrawal: withdrawal(Variant::Bridge),
+            l2ChainId: U256::from(10),
+            l2Recipient: token,
+            bridgeMessageHash: message,
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().bridge,
+            Some((U256::from(10), token, message))
+        );
+
+        let encoded = RegistryWithdrawalData {
+            withdrawal: withdrawal(Variant::Registry),
+            registry: token,
+            chainId: U256::from(1),
+            denomination: U256::from(100),
+            version: U256::from(2),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().registry,
+            Some((token, U256::from(100), U256::from(2)))
+        );
+
+        let distribution = compute_distribution_hash(&[token], &[U256::from(99)]);
+        let encoded = SplitWithdrawalData {
+            withdrawal: withdrawal(Variant::Split),
+            distributionHash: distribution,
+            noteValue: U256::from(100),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().split,
+            Some((distribution, U256::from(100)))
+        );
+
+        let proven = b256!("3333333333333333333333333333333333333333333333333333333333333333");
+        let code_hash = b256!("4444444444444444444444444444444444444444444444444444444444444444");
+        let encoded = OracleWithdrawalData {
+            withdrawal: withdrawal(Variant::Oracle),
+            blockHashOracle: token,
+            oracleSlot: U256::ZERO,
+            oracleCodeHash: code_hash,
+            provenBlockHash: proven,
+            provenBlockNumber: 100,
+        }
+        .abi_encode();
+        assert_eq!(encoded.len(), BASE_LENGTH + 5 * 32);
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().oracle,
+            Some((token, code_hash, 100, proven))
+        );
+
+        let set_id = b256!("5555555555555555555555555555555555555555555555555555555555555555");
+        let encoded = AssociationWithdrawalData {
+            withdrawal: withdrawal(Variant::Association),
+            aspSigner: token,
+            setId: set_id,
+            leafCount: 16,
+            issuedAt: 1_700_000_000,
+            chainId: U256::from(1),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_any_public_values(&encoded).unwrap().association,
+            Some((token, set_id, 1_700_000_000))
+        );
+    }
+
+    #[test]
+    fn decode_any_rejects_unknown_tags() {
+        let mut data = withdrawal(Variant::Base);
+        data.variant = 8;
+        assert!(decode_any_public_values(&data.abi_encode()).is_err());
+    }
+
+    #[test]
+    fn decode_any_rejects_mistagged_lengths() {
+        // A base withdrawal tagged as an NFT one is too short to be decoded as one.
+        let encoded = encode_public_values(&withdrawal(Variant::Nft));
+        assert!(decode_any_public_values(&encoded).is_err());
+
+        // An NFT withdrawal tagged as a base one is too long.
+        let mut encoded = NftWithdrawalData {
+            withdrawal: withdrawal(Variant::Base),
+            collection: Address::ZERO,
+            tokenId: U256::ZERO,
+        }
+        .abi_encode();
+        assert!(decode_any_public_values(&encoded).is_err());
+        encoded.truncate(BASE_LENGTH);
+        assert!(decode_any_public_values(&encoded).is_ok());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::redact::Redacted;
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Prefix of the string encoding of a [`Note`].
+pub const NOTE_PREFIX: &str = "pool-note-v1";
+
+/// Everything a depositor needs to keep to withdraw later: the secret and where it was deposited.
+///
+/// Encoded as `pool-note-v1-<chain id>-<pool address>-<secret>`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    pub chain_id: u64,
+    pub pool: Address,
+    pub secret: B256,
+}
+
+/// Why a string is not a [`Note`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteError {
+    /// The string does not start with [`NOTE_PREFIX`].
+    Prefix,
+    /// The string does not have a chain id, pool and secret.
+    Format,
+    /// One of the parts does not parse.
+    Field(&'static str),
+}
+
+impl fmt::Display for NoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Prefix => write!(f, "not a {NOTE_PREFIX} note"),
+            Self::Format => write!(f, "invalid note"),
+            Self::Field(name) => write!(f, "invalid note {name}"),
+        }
+    }
+}
+
+impl std::error::Error for NoteError {}
+
+impl fmt::Debug for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Note")
+            .field("chain_id", &self.chain_id)
+            .field("pool", &self.pool)
+            .field("secret", &Redacted(self.secret))
+            .finish()
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{NOTE_PREFIX}-{}-{:#x}-{:#x}",
+            self.chain_id, self.pool, self.secret
+        )
+    }
+}
+
+impl FromStr for Note {
+    type Err = NoteError;
+
+    fn from_str(s: &str) -> Result<Self, NoteError> {
+        let rest = s
+            .strip_prefix(NOTE_PREFIX)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .ok_or(NoteError::Prefix)?;
+        let parts: Vec<&str> = rest.split('-').collect();
+        if parts.len() != 3 {
+            return Err(NoteError::Format);
+        }
+        Ok(Self {
+            chain_id: parts[0].parse().map_err(|_| NoteError::Field("chain id"))?,
+            pool: parts[1].parse().map_err(|_| NoteError::Field("pool"))?,
+            secret: parts[2].parse().map_err(|_| NoteError::Field("secret"))?,
+        })
+    }
+}
@@ -0,0 +1,44 @@
+//! Keeping secrets out of logs and error messages. Types holding a note secret show
+//! it as `<redacted>` in their `Debug` output, unless revealing secrets was turned on for local
+//! development.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static REVEAL: AtomicBool = AtomicBool::new(false);
+
+/// Show secrets in `Debug` output from now on. Only for debugging locally.
+pub fn reveal_secrets() {
+    REVEAL.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`reveal_secrets`] was called.
+pub fn secrets_revealed() -> bool {
+    REVEAL.load(Ordering::Relaxed)
+}
+
+/// Formats as `<redacted>` unless secrets are revealed.
+#[derive(Clone, Copy)]
+pub struct Redacted<T>(pub T);
+
+impl<T: fmt::Debug> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if secrets_revealed() {
+            self.0.fmt(f)
+        } else {
+            f.write_str("<redacted>")
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if secrets_revealed() {
+            self.0.fmt(f)
+        } else {
+            f.write_str("<redacted>")
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{compute_commitment, note::Note};
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Prefix of the string encoding of a [`ViewKey`].
+pub const VIEW_KEY_PREFIX: &str = "pool-view-v1";
+
+/// What a note's holder can hand an auditor so they can find its deposit, without being able to
+/// withdraw it or recognize its withdrawal.
+///
+/// The commitment is `keccak256(secret)`, so anything that recomputes it can also compute the
+/// nullifier `keccak256(secret + 1)`. The view key carries the commitment itself instead, from
+/// which the nullifier does not follow.
+///
+/// Encoded as `pool-view-v1-<chain id>-<pool address>-<commitment>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewKey {
+    pub chain_id: u64,
+    pub pool: Address,
+    pub commitment: B256,
+}
+
+impl Note {
+    /// The view key of this note.
+    pub fn view_key(&self) -> ViewKey {
+        let (commitment, _) = compute_commitment(&self.secret);
+        ViewKey {
+            chain_id: self.chain_id,
+            pool: self.pool,
+            commitment,
+        }
+    }
+}
+
+/// Why a string is not a [`ViewKey`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewKeyError {
+    /// The string does not start with [`VIEW_KEY_PREFIX`].
+    Prefix,
+    /// The string does not have a chain id, pool and commitment.
+    Format,
+    /// One of the parts does not parse.
+    Field(&'static str),
+}
+
+impl fmt::Display for ViewKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Prefix => write!(f, "not a {VIEW_KEY_PREFIX} view key"),
+            Self::Format => write!(f, "invalid view key"),
+            Self::Field(name) => write!(f, "invalid view key {name}"),
+        }
+    }
+}
+
+impl std::error::Error for ViewKeyError {}
+
+impl fmt::Display for ViewKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{VIEW_KEY_PREFIX}-{}-{:#x}-{:#x}",
+            self.chain_id, self.pool, self.commitment
+        )
+    }
+}
+
+impl FromStr for ViewKey {
+    type Err = ViewKeyError;
+
+    fn from_str(s: &str) -> Result<Self, ViewKeyError> {
+        let rest = s
+            .strip_prefix(VIEW_KEY_PREFIX)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .ok_or(ViewKeyError::Prefix)?;
+        let parts: Vec<&str> = rest.split('-').collect();
+        if parts.len() != 3 {
+            return Err(ViewKeyError::Format);
+        }
+        Ok(Self {
+            chain_id: parts[0]
+                .parse()
+                .map_err(|_| ViewKeyError::Field("chain id"))?,
+            pool: parts[1].parse().map_err(|_| ViewKeyError::Field("pool"))?,
+            commitment: parts[2]
+                .parse()
+                .map_err(|_| ViewKeyError::Field("commitment"))?,
+        })
+    }
+}
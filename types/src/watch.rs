@@ -0,0 +1,94 @@
+use crate::{compute_commitment, note::Note};
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Prefix of the string encoding of a [`WatchKey`].
+pub const WATCH_KEY_PREFIX: &str = "pool-watch-v1";
+
+/// What a wallet needs to follow a note it cannot spend: the commitment its deposit emits and
+/// the nullifier its withdrawal reveals. Both are hashes of the secret, so neither gives it away,
+/// but together they link the deposit to its withdrawal, and a watch key should be kept as
+/// privately as the note itself.
+///
+/// Encoded as `pool-watch-v1-<chain id>-<pool address>-<commitment>-<nullifier>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchKey {
+    pub chain_id: u64,
+    pub pool: Address,
+    pub commitment: B256,
+    pub nullifier: B256,
+}
+
+impl Note {
+    /// The watch key of this note.
+    pub fn watch_key(&self) -> WatchKey {
+        let (commitment, nullifier) = compute_commitment(&self.secret);
+        WatchKey {
+            chain_id: self.chain_id,
+            pool: self.pool,
+            commitment,
+            nullifier,
+        }
+    }
+}
+
+/// Why a string is not a [`WatchKey`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchKeyError {
+    /// The string does not start with [`WATCH_KEY_PREFIX`].
+    Prefix,
+    /// The string does not have a chain id, pool, commitment and nullifier.
+    Format,
+    /// One of the parts does not parse.
+    Field(&'static str),
+}
+
+impl fmt::Display for WatchKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Prefix => write!(f, "not a {WATCH_KEY_PREFIX} watch key"),
+            Self::Format => write!(f, "invalid watch key"),
+            Self::Field(name) => write!(f, "invalid watch key {name}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchKeyError {}
+
+impl fmt::Display for WatchKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{WATCH_KEY_PREFIX}-{}-{:#x}-{:#x}-{:#x}",
+            self.chain_id, self.pool, self.commitment, self.nullifier
+        )
+    }
+}
+
+impl FromStr for WatchKey {
+    type Err = WatchKeyError;
+
+    fn from_str(s: &str) -> Result<Self, WatchKeyError> {
+        let rest = s
+            .strip_prefix(WATCH_KEY_PREFIX)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .ok_or(WatchKeyError::Prefix)?;
+        let parts: Vec<&str> = rest.split('-').collect();
+        if parts.len() != 4 {
+            return Err(WatchKeyError::Format);
+        }
+        Ok(Self {
+            chain_id: parts[0]
+                .parse()
+                .map_err(|_| WatchKeyError::Field("chain id"))?,
+            pool: parts[1].parse().map_err(|_| WatchKeyError::Field("pool"))?,
+            commitment: parts[2]
+                .parse()
+                .map_err(|_| WatchKeyError::Field("commitment"))?,
+            nullifier: parts[3]
+                .parse()
+                .map_err(|_| WatchKeyError::Field("nullifier"))?,
+        })
+    }
+}
@@ -0,0 +1,161 @@
+//! `wasm-bindgen` bindings to pool-lib, so browser wallets can derive notes and assemble
+//! withdrawal inputs without sending secrets anywhere.
+//!
+//! Hashes, addresses and amounts cross the boundary as `0x`-prefixed hex strings, and structs as
+//! plain objects with the same field names as in pool-lib. Errors are thrown as strings.
+//!
+//! Build with `wasm-pack build wasm --target web`.
+
+use alloy::primitives::{Address, B256, U256};
+use pool_lib::{
+    compute_commitment, compute_inclusion_root, compute_merkle_branch, compute_merkle_root,
+    compute_storage_keys, note::Note, process_any_withdrawal, InclusionBranches, WithdrawalInput,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+fn error(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, JsError> {
+    value
+        .parse()
+        .map_err(|_| JsError::new(&format!("invalid {name} {value}")))
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(error)
+}
+
+fn from_js<T: DeserializeOwned>(value: JsValue) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value).map_err(error)
+}
+
+/// [`pool_lib::WithdrawalData`], the public values of a withdrawal.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublicValues {
+    nullifier: B256,
+    block_hash: B256,
+    exclusion_set_root: B256,
+    relayer_fee: U256,
+    recipient: Address,
+    relayer: Address,
+    contract_address: Address,
+    block_number: u64,
+    fee_token: Address,
+    fee_token_amount: U256,
+}
+
+#[derive(Serialize)]
+struct Commitment {
+    commitment: B256,
+    nullifier: B256,
+}
+
+/// The commitment to deposit and the nullifier revealed on withdrawal for `secret`, as
+/// `{ commitment, nullifier }`.
+#[wasm_bindgen(js_name = computeCommitment)]
+pub fn commitment(secret: &str) -> Result<JsValue, JsError> {
+    let (commitment, nullifier) = compute_commitment(&parse("secret", secret)?);
+    to_js(&Commitment {
+        commitment,
+        nullifier,
+    })
+}
+
+/// Encode a note for `secret` deposited into `pool` on `chainId`.
+#[wasm_bindgen(js_name = encodeNote)]
+pub fn encode_note(chain_id: u64, pool: &str, secret: &str) -> Result<String, JsError> {
+    Ok(Note {
+        chain_id,
+        pool: parse::<Address>("pool", pool)?,
+        secret: parse("secret", secret)?,
+    }
+    .to_string())
+}
+
+/// Decode a note into `{ chain_id, pool, secret }`.
+#[wasm_bindgen(js_name = decodeNote)]
+pub fn decode_note(note: &str) -> Result<JsValue, JsError> {
+    to_js(&note.parse::<Note>().map_err(error)?)
+}
+
+/// Storage keys of the deposits array's length and of element `arrayIndex`, for
+/// `eth_getProof`.
+#[wasm_bindgen(js_name = computeStorageKeys)]
+pub fn storage_keys(array_slot: &str, array_index: &str) -> Result<Vec<String>, JsError> {
+    let (length, element) = compute_storage_keys(
+        parse::<U256>("array slot", array_slot)?,
+        parse::<U256>("array index", array_index)?,
+    );
+    Ok(vec![length.to_string(), element.to_string()])
+}
+
+/// Root of `leaves`, an array of commitments.
+#[wasm_bindgen(js_name = computeMerkleRoot)]
+pub fn merkle_root(leaves: JsValue) -> Result<String, JsError> {
+    let leaves: Vec<B256> = from_js(leaves)?;
+    Ok(compute_merkle_root(&leaves).to_string())
+}
+
+/// Branches proving `leaves[index]` against [`merkle_root`] of `leaves`, as
+/// `{ index, proof }`.
+#[wasm_bindgen(js_name = computeMerkleBranch)]
+pub fn merkle_branch(leaves: JsValue, index: usize) -> Result<JsValue, JsError> {
+    let leaves: Vec<B256> = from_js(leaves)?;
+    if index >= leaves.len() {
+        return Err(JsError::new(&format!(
+            "index {index} is out of bounds for {} leaves",
+            leaves.len()
+        )));
+    }
+    to_js(&compute_merkle_branch(&leaves, index))
+}
+
+/// Root `commitment` proves membership of with `branches`, as committed in a withdrawal's
+/// `exclusionSetRoot`.
+#[wasm_bindgen(js_name = computeInclusionRoot)]
+pub fn inclusion_root(commitment: &str, branches: JsValue) -> Result<String, JsError> {
+    let branches: InclusionBranches = from_js(branches)?;
+    Ok(compute_inclusion_root(&parse("commitment", commitment)?, &branches).to_string())
+}
+
+/// Check `input` the way the withdrawal program does, returning the public values it would
+/// commit. Proving an input that fails here would fail too.
+#[wasm_bindgen(js_name = validateWithdrawalInput)]
+pub fn validate_withdrawal_input(input: JsValue) -> Result<JsValue, JsError> {
+    let input: WithdrawalInput = from_js(input)?;
+    let data = process_any_withdrawal(&input).map_err(error)?.withdrawal;
+    to_js(&PublicValues {
+        nullifier: data.nullifier,
+        block_hash: data.blockHash,
+        exclusion_set_root: data.exclusionSetRoot,
+        relayer_fee: data.relayerFee,
+        recipient: data.recipient,
+        relayer: data.relayer,
+        contract_address: data.contractAddress,
+        block_number: data.blockNumber,
+        fee_token: data.feeToken,
+        fee_token_amount: data.feeTokenAmount,
+    })
+}
+
+/// Encode `input` as the CBOR the withdrawal program reads, after validating it.
+#[wasm_bindgen(js_name = serializeWithdrawalInput)]
+pub fn serialize_withdrawal_input(input: JsValue) -> Result<Vec<u8>, JsError> {
+    let input: WithdrawalInput = from_js(input)?;
+    process_any_withdrawal(&input).map_err(error)?;
+    serde_cbor::to_vec(&input).map_err(error)
+}
+
+/// Decode CBOR written by [`serialize_withdrawal_input`] back into an input object.
+#[wasm_bindgen(js_name = deserializeWithdrawalInput)]
+pub fn deserialize_withdrawal_input(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let input: WithdrawalInput = serde_cbor::from_slice(bytes).map_err(error)?;
+    to_js(&input)
+}